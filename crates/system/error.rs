@@ -2,13 +2,28 @@ use core::{fmt, result};
 
 pub struct Error {
     pub errno: isize,
+    /// Why the error happened, for debugging the kernel
+    ///
+    /// This is never passed to userspace, which only sees `errno`.
+    pub cause: Option<&'static str>,
 }
 
 pub type Result<T> = result::Result<T, Error>;
 
 impl Error {
     pub fn new(errno: isize) -> Error {
-        Error { errno: errno }
+        Error {
+            errno: errno,
+            cause: None,
+        }
+    }
+
+    /// Create an error with the reason it happened, such as "tracee not stopped"
+    pub fn with_cause(errno: isize, cause: &'static str) -> Error {
+        Error {
+            errno: errno,
+            cause: Some(cause),
+        }
     }
 
     pub fn mux(result: Result<usize>) -> usize {
@@ -36,9 +51,13 @@ impl Error {
     }
 }
 
+/// Shows the cause after the text, if there is one
 impl fmt::Debug for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> result::Result<(), fmt::Error> {
-        f.write_str(self.text())
+        match self.cause {
+            Some(cause) => write!(f, "{}: {}", self.text(), cause),
+            None => f.write_str(self.text()),
+        }
     }
 }
 
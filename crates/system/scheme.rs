@@ -5,14 +5,32 @@ use super::error::*;
 use super::syscall::*;
 use super::c_string_to_str;
 
-#[derive(Copy, Clone, Debug, Default)]
+/// The magic of a `Packet`, which changes whenever its layout does
+pub const PACKET_MAGIC: usize = 0x5041_4B31;
+
+#[derive(Copy, Clone, Debug)]
 #[repr(packed)]
 pub struct Packet {
     pub id: usize,
     pub a: usize,
     pub b: usize,
     pub c: usize,
-    pub d: usize
+    pub d: usize,
+    /// Always `PACKET_MAGIC`
+    pub magic: usize
+}
+
+impl Default for Packet {
+    fn default() -> Packet {
+        Packet {
+            id: 0,
+            a: 0,
+            b: 0,
+            c: 0,
+            d: 0,
+            magic: PACKET_MAGIC
+        }
+    }
 }
 
 impl Deref for Packet {
@@ -42,6 +60,7 @@ pub trait Scheme {
             SYS_UNLINK => self.unlink(c_string_to_str(packet.b as *const u8)),
 
             SYS_DUP => self.dup(packet.b),
+            SYS_FCNTL => self.fcntl(packet.b, packet.c, packet.d),
             SYS_READ => self.read(packet.b, unsafe { slice::from_raw_parts_mut(packet.c as *mut u8, packet.d) }),
             SYS_WRITE => self.write(packet.b, unsafe { slice::from_raw_parts(packet.c as *const u8, packet.d) }),
             SYS_LSEEK => self.seek(packet.b, packet.c, packet.d),
@@ -88,6 +107,11 @@ pub trait Scheme {
         Err(Error::new(EBADF))
     }
 
+    #[allow(unused_variables)]
+    fn fcntl(&mut self, id: usize, cmd: usize, arg: usize) -> Result<usize> {
+        Err(Error::new(EBADF))
+    }
+
     #[allow(unused_variables)]
     fn read(&mut self, id: usize, buf: &mut [u8]) -> Result<usize> {
         Err(Error::new(EBADF))
@@ -1,7 +1,235 @@
-use syscall::arch::syscall1;
+use syscall::arch::{syscall1, syscall3};
 use error::Result;
+use syscall::unix::TimeSpec;
 
 pub const SYS_SUPERVISE: usize = 1638; // loominatzi confirmed
+pub const SYS_SUPERVISE_OBSERVE: usize = 1639;
+pub const SYS_CONTROL: usize = 1640;
+pub const SYS_FMAP: usize = 1641;
+
+/// Skip the stopped syscall, returning `a` to the tracee
+pub const SUPERVISE_RETURN: usize = 1;
+/// Read the registers of the stopped tracee
+pub const SUPERVISE_GETREGS: usize = 2;
+/// Replace the registers of the stopped tracee
+pub const SUPERVISE_SETREGS: usize = 3;
+/// Read `b` bytes of memory at `a` from the stopped tracee
+pub const SUPERVISE_PEEK: usize = 4;
+/// Write memory at `a` in the stopped tracee
+pub const SUPERVISE_POKE: usize = 5;
+/// Resume the stopped tracee for a single instruction
+pub const SUPERVISE_STEP: usize = 6;
+/// Resume the stopped tracee, running the syscall, until the next event
+pub const SUPERVISE_CONTINUE: usize = 7;
+/// Stop supervising the tracee, resuming it if it is stopped
+pub const SUPERVISE_DETACH: usize = 8;
+/// Resume the stopped tracee like `SUPERVISE_CONTINUE`, delivering the signal in `a`
+pub const SUPERVISE_SIGNAL: usize = 9;
+/// Make reads return EAGAIN instead of blocking if `a` is not 0, or block again if it is
+pub const SUPERVISE_NONBLOCK: usize = 10;
+/// Only stop the tracee for the classes of events in `a`, a mask of `SUPERVISE_MASK_*`
+pub const SUPERVISE_MASK: usize = 11;
+/// Replace the arguments of the stopped syscall with `a`, `b`, and `c`
+pub const SUPERVISE_SETARGS: usize = 12;
+/// Replace the syscall filter with the rules following the packet, using the action `a` and the
+/// errno `b` for syscalls without a rule
+pub const SUPERVISE_FILTER: usize = 13;
+/// Take writes shorter than a packet as the value of `SUPERVISE_RETURN` if `a` is not 0, or reject
+/// them again if it is. This is deprecated, and only kept for old supervisors
+pub const SUPERVISE_LEGACY_WRITE: usize = 14;
+/// Make the read blocked on the descriptor by the context `a` return EINTR, or every blocked read
+/// if `a` is 0
+pub const SUPERVISE_INTERRUPT: usize = 15;
+/// Decide what happens to the tracee when it exits, with one of `SUPERVISE_EXIT_*` in `a`
+pub const SUPERVISE_EXIT_POLICY: usize = 16;
+/// Tear down the tracee `a`, or the current tracee if `a` is 0, at once, whatever it is doing
+pub const SUPERVISE_FORCEKILL: usize = 17;
+/// Read the FPU and SSE registers of the stopped tracee, in the layout saved by `fxsave`
+pub const SUPERVISE_GETFPREGS: usize = 18;
+/// Replace the FPU and SSE registers of the stopped tracee, in the layout loaded by `fxrstor`
+pub const SUPERVISE_SETFPREGS: usize = 19;
+
+/// Keep an exited tracee as a zombie, stopped at its exit event, until the supervisor resumes it
+pub const SUPERVISE_EXIT_ZOMBIE: usize = 0;
+/// Tear an exited tracee down at once, only queueing its exit event
+pub const SUPERVISE_EXIT_REAP: usize = 1;
+
+/// Stop the tracee at the syscall, as long as `SUPERVISE_MASK_SYSCALL` is set
+pub const SUPERVISE_FILTER_TRAP: usize = 0;
+/// Run the syscall without stopping the tracee
+pub const SUPERVISE_FILTER_ALLOW: usize = 1;
+/// Fail the syscall with the errno of the rule, without stopping the tracee
+pub const SUPERVISE_FILTER_DENY: usize = 2;
+
+/// A rule of a syscall filter, see `SUPERVISE_FILTER`
+#[derive(Copy, Clone, Debug, Default)]
+#[repr(packed)]
+pub struct SuperviseRule {
+    /// The syscall number
+    pub syscall: usize,
+    /// One of `SUPERVISE_FILTER_*`
+    pub action: usize,
+    /// The errno returned by `SUPERVISE_FILTER_DENY`
+    pub errno: usize,
+}
+
+/// Stop the tracee when it makes a syscall
+pub const SUPERVISE_MASK_SYSCALL: usize = 1;
+/// Stop the tracee when a syscall returns
+pub const SUPERVISE_MASK_SYSCALL_EXIT: usize = 2;
+/// Stop the tracee before a signal is delivered to it
+pub const SUPERVISE_MASK_SIGNAL: usize = 4;
+/// Stop the tracee when it has loaded a new executable
+pub const SUPERVISE_MASK_EXEC: usize = 8;
+/// Every class of events, which is what a tracee stops for by default
+pub const SUPERVISE_MASK_ALL: usize = 0xF;
+
+/// The tracee stopped after a single instruction, with its instruction pointer in `b`
+pub const SUPERVISE_EVENT_STEP: usize = 0x10000001;
+/// The syscall `b` of the tracee returned `c`
+pub const SUPERVISE_EVENT_SYSCALL_EXIT: usize = 0x10000002;
+/// The signal `b` is about to be delivered to the tracee
+pub const SUPERVISE_EVENT_SIGNAL: usize = 0x10000003;
+/// The tracee loaded a new executable, with its entry point in `b`
+pub const SUPERVISE_EVENT_EXEC: usize = 0x10000004;
+/// The tracee exited with the status `b`, see `SUPERVISE_EXIT_POLICY`
+pub const SUPERVISE_EVENT_EXIT: usize = 0x10000005;
+
+/// Make a mapping writeable
+pub const MAP_WRITE: usize = 1;
+
+/// Report that a read from the handle would not block
+pub const EVENT_READ: usize = 1;
+/// Report that a write to the handle would not block
+pub const EVENT_WRITE: usize = 2;
+
+/// Make reads of a `console:` handle return every byte as it is typed if `a` is not 0, or whole
+/// lines, edited with backspace, if it is
+pub const CONSOLE_RAW: usize = 1;
+/// Echo what is typed to the display if `a` is not 0
+pub const CONSOLE_ECHO: usize = 2;
+/// Return the size of the console window in characters, as `rows << 16 | columns`
+pub const CONSOLE_GETWINSZ: usize = 3;
+/// Set the size of the console window in characters to `a`, in the layout of `CONSOLE_GETWINSZ`,
+/// sending SIGWINCH to the foreground context, which is the last to open `console:`
+pub const CONSOLE_SETWINSZ: usize = 4;
+/// Return the size of the console window in pixels, as `height << 16 | width`
+pub const CONSOLE_GETPIXELS: usize = 5;
+/// Set the size of the console window in pixels to `a`, in the layout of `CONSOLE_GETPIXELS`,
+/// sending SIGWINCH like `CONSOLE_SETWINSZ`
+pub const CONSOLE_SETPIXELS: usize = 6;
+
+/// Keep an `shm:` segment after its last handle is closed if `a` is not 0, until it is unlinked
+pub const SHM_PERSIST: usize = 1;
+
+/// Answer the next request written to the server handle of a user scheme with a duplicate of the
+/// descriptor `a` of the provider, instead of a file id, if the request is an open or a dup
+pub const SCHEME_PASS_DUP: usize = 1;
+/// Like `SCHEME_PASS_DUP`, handing over the descriptor `a` itself, which the provider no longer has
+pub const SCHEME_PASS_MOVE: usize = 2;
+
+/// Return 1 if the handle is a terminal, such as `console:` or the slave end of a `pty:`, or 0 if
+/// it is not, like `isatty`. Every handle accepts this command, whatever other commands it has
+pub const CONTROL_ISATTY: usize = 0x5401;
+
+/// A handle registered with, or read from, the `event:` scheme
+///
+/// Writing events to an `event:` handle registers the file descriptors in `id`, waiting for the
+/// `EVENT_READ` and `EVENT_WRITE` bits in `flags`, or unregisters them if `flags` is 0. Reading
+/// blocks until at least one of them is ready, and returns an event for each ready descriptor,
+/// with the bits which are ready in `flags`.
+#[derive(Copy, Clone, Debug, Default)]
+#[repr(packed)]
+pub struct Event {
+    pub id: usize,
+    pub flags: usize,
+}
+
+/// Not a class of events: `a` events were dropped before this record, as the buffer was full
+pub const TRACE_DROPPED: usize = 0;
+/// Record context switches, with the PID switched from in `a` and the PID switched to in `b`
+pub const TRACE_SWITCH: usize = 1;
+/// Record syscalls, with the PID in `a` and the syscall number in `b`
+pub const TRACE_SYSCALL: usize = 2;
+/// Record IRQs, with the IRQ in `a`
+pub const TRACE_IRQ: usize = 4;
+/// Every class of events
+pub const TRACE_ALL: usize = 7;
+
+/// The memory usage, read from `memory:?format=binary`
+#[derive(Copy, Clone, Debug, Default)]
+#[repr(packed)]
+pub struct MemoryInfo {
+    /// The bytes of memory in total
+    pub total: usize,
+    /// The bytes in use
+    pub used: usize,
+    /// The bytes which are free
+    pub free: usize,
+}
+
+/// The context runs in userspace, rather than only in the kernel
+pub const CONTEXT_INFO_USER: usize = 1;
+/// The context is blocked
+pub const CONTEXT_INFO_BLOCKED: usize = 2;
+/// The context exited
+pub const CONTEXT_INFO_EXITED: usize = 4;
+/// The context is a vfork child, which its parent waits for
+pub const CONTEXT_INFO_VFORK: usize = 8;
+/// The context is sleeping until a time
+pub const CONTEXT_INFO_SLEEPING: usize = 16;
+/// The context is supervised
+pub const CONTEXT_INFO_SUPERVISED: usize = 32;
+
+/// A context, one of those read from `context:?format=binary`
+///
+/// Every context is read in one snapshot, so the records agree with each other.
+#[derive(Copy, Clone, Debug, Default)]
+#[repr(packed)]
+pub struct ContextInfo {
+    pub pid: usize,
+    pub ppid: usize,
+    /// The `CONTEXT_INFO_*` flags of the context
+    pub flags: usize,
+    /// The number of times the context was switched to
+    pub switch: usize,
+    /// The number of time slices used
+    pub time: usize,
+    /// The bytes of memory used
+    pub memory: usize,
+    /// The number of open files
+    pub files: usize,
+    pub iopl: usize,
+    pub priority: usize,
+    /// The start of the name, padded with NULs
+    pub name: [u8; 32],
+}
+
+/// The uptime, read from `uptime:?format=binary`
+#[derive(Copy, Clone, Debug, Default)]
+#[repr(packed)]
+pub struct UptimeInfo {
+    /// The time since boot, from the monotonic clock
+    pub uptime: TimeSpec,
+    /// The real time at boot
+    pub boot: TimeSpec,
+}
+
+/// A kernel event, read from the `trace:` scheme
+///
+/// Writing a mask of `TRACE_*` classes to a `trace:` handle, as a decimal number, records the
+/// events of those classes from then on, and writing 0 stops recording. Reading removes as many
+/// whole records as fit, and returns 0 once none are left.
+#[derive(Copy, Clone, Debug, Default)]
+#[repr(packed)]
+pub struct TraceRecord {
+    /// The timestamp counter of the processor when the event happened
+    pub time: u64,
+    /// The class of the event, one of `TRACE_*`
+    pub event: usize,
+    pub a: usize,
+    pub b: usize,
+}
 
 /// <!-- @MANSTART{supervise} -->
 /// Supervise a given child process' system calls.
@@ -11,22 +239,138 @@ pub const SYS_SUPERVISE: usize = 1638; // loominatzi confirmed
 /// logging, and debugging.
 ///
 /// SUPERVISE takes a PID specifing the process to be supervised. This PID must be a child process
-/// of the invoker. If not, EACCES will be returned.
+/// of the invoker. If not, EACCES will be returned. A process can not supervise itself, which
+/// returns EINVAL.
 ///
 /// A process can only have one supervisor at a time. If SUPERVISE is called on a process, which
 /// already have a supervisor EPERM will be returned.
 ///
+/// Passing a PID of 0 supervises every child of the invoker which does not already have a
+/// supervisor, and every process they create afterwards, through a single file descriptor.
+///
 /// The process identified by the given PID will be restricted in such a way, that every syscall
 /// made will mark the process as blocked and store the syscall until it is handled by the parrent.
 ///
 /// The return value (if successful) is a file descriptor, from which syscalls can be read and written:
-/// the syscalls are read in `Packet` sized packages, containing the respective blocking syscall. If
-/// no syscall is blocking (or the last blocking syscall have been handled), 0 bytes will be read to
-/// the buffer.
+/// the syscalls are read in `Packet` sized packages, containing the respective blocking syscall,
+/// with the PID of the process as the `id`. Reading blocks until a process stops at a syscall. A
+/// process stopped after `SUPERVISE_STEP` is read as a package with `a` set to
+/// `SUPERVISE_EVENT_STEP`.
+///
+/// A syscall package carries the syscall number in `a`, and its arguments in `b`, `c`, and `d`,
+/// so the syscall can be inspected without `SUPERVISE_GETREGS`. These are the registers the
+/// syscall was made with: EAX, EBX, ECX, and EDX on x86, or RAX, RBX, RCX, and RDX on x86_64.
 ///
-/// Writing pointer sized integers to this file handle will set the EAX register of the particular
-/// process, after which the process is unblocked and the syscall buffer is emptied. The behavior of
-/// writing packages of unexpected size is unspecified.
+/// At most 256 packages are queued: further tracees wait until the supervisor reads some.
+///
+/// A read larger than a `Packet` also returns the packages which are already queued, as long as
+/// whole packages fit, without waiting for more. A read smaller than a `Packet` returns EINVAL.
+///
+/// Writing a `Packet` to this file handle sends a command, given by the `id` of the packet, to the
+/// process of the last package read. If the process is not stopped, EBUSY is returned:
+///
+/// - `SUPERVISE_RETURN` skips the syscall, setting the EAX register to `a`, after which the process
+///   is unblocked. If the process stopped for any other event, it is simply unblocked.
+/// - `SUPERVISE_STEP` runs the syscall, if any, and then sets the trap flag, so the process stops
+///   again after a single instruction. The trap flag is cleared by `SUPERVISE_RETURN` and
+///   `SUPERVISE_CONTINUE`.
+/// - `SUPERVISE_CONTINUE` runs the syscall, if any, and lets the process run until its next syscall.
+/// - `SUPERVISE_DETACH` lets the process run unsupervised, as if SUPERVISE was never called on it.
+///   It may be called whether or not the process is stopped, and succeeds even if the process was
+///   already detached. Afterwards, the parent may supervise the process again with SUPERVISE.
+///   Closing the file descriptor detaches the process as well.
+/// - `SUPERVISE_SIGNAL` resumes the process like `SUPERVISE_CONTINUE`, with the signal `a` pending,
+///   which is delivered when the syscall returns. Signals outside of `1..NSIG` return EINVAL.
+/// - `SUPERVISE_SETARGS` replaces the arguments of the syscall with `a`, `b`, and `c`, in the same
+///   order as they were read, before the syscall is resumed. It is ignored if the process did not
+///   stop at a syscall.
+/// - `SUPERVISE_GETREGS` makes the next read return the registers of the process. That read must
+///   be exactly the size of the kernel's `Regs`, or EINVAL is returned.
+/// - `SUPERVISE_SETREGS` must be followed by the registers, in the same layout, in the same write.
+///   All of the registers are replaced at once. Changing the segment registers or the privileged
+///   flags, such as IF or IOPL, returns EPERM, and leaves the registers as they were.
+/// - `SUPERVISE_PEEK` makes the following reads return `b` bytes of memory at `a`, in chunks of at
+///   most 4096 bytes. A read shorter than a chunk leaves the rest of it for the next read.
+///   Replies to `SUPERVISE_GETREGS` and `SUPERVISE_PEEK` are read before packages from other
+///   processes.
+/// - `SUPERVISE_POKE` must be followed by the data, in the same write, which is written to the
+///   memory at `a`.
+///
+/// Memory accesses outside of the memory of the process, or writes to read only memory, return
+/// EFAULT.
+///
+/// `SUPERVISE_MASK` changes which events the process stops for, and may be called whether or not
+/// the process is stopped. `a` is a mask of:
+///
+/// - `SUPERVISE_MASK_SYSCALL`: stop at every syscall, as described above.
+/// - `SUPERVISE_MASK_SYSCALL_EXIT`: stop when a syscall returns, with `a` set to
+///   `SUPERVISE_EVENT_SYSCALL_EXIT`, the syscall in `b`, and its return value in `c`. The return
+///   value can be changed with `SUPERVISE_SETREGS`.
+/// - `SUPERVISE_MASK_SIGNAL`: stop before a signal is delivered, with `a` set to
+///   `SUPERVISE_EVENT_SIGNAL`, and the signal in `b`. `SUPERVISE_RETURN` discards the signal.
+/// - `SUPERVISE_MASK_EXEC`: stop once a new executable is loaded, before it runs, with `a` set to
+///   `SUPERVISE_EVENT_EXEC`, and the entry point in `b`. The registers of this stop can not be
+///   changed.
+///
+/// `SUPERVISE_FILTER` installs a syscall filter on the process, which may also be done whether
+/// or not it is stopped. The packet is followed by `SuperviseRule`s, in the same write, which
+/// decide what happens when the process makes a syscall, before it would stop:
+///
+/// - `SUPERVISE_FILTER_TRAP`: stop the process, as if there was no filter.
+/// - `SUPERVISE_FILTER_ALLOW`: run the syscall without notifying the supervisor.
+/// - `SUPERVISE_FILTER_DENY`: fail the syscall with the `errno` of the rule, without notifying the
+///   supervisor.
+///
+/// Syscalls without a rule use the action in `a`, with the errno in `b`. Writing no rules with an
+/// `a` of `SUPERVISE_FILTER_TRAP` removes the filter. Children supervised along with the process
+/// inherit its filter. Unknown actions, or a deny without an errno, return EINVAL.
+///
+/// When a process exits, the supervisor reads a package with `a` set to `SUPERVISE_EVENT_EXIT` and
+/// the exit status in `b`, which is `128 + signal` if it was terminated by a signal, whatever the
+/// mask is. What happens next is set with `SUPERVISE_EXIT_POLICY`, whether or not the process is
+/// stopped, and inherited by children supervised along with it:
+///
+/// - `SUPERVISE_EXIT_ZOMBIE`: the process stops at the event, before its files are closed and its
+///   memory is freed, so its final state can be read with `SUPERVISE_GETREGS` and
+///   `SUPERVISE_PEEK`. Any command which resumes it, such as `SUPERVISE_CONTINUE`, acknowledges
+///   the exit, after which the process is torn down and its parent gets its status. This is the
+///   default, so that the exit event is never missed.
+/// - `SUPERVISE_EXIT_REAP`: the process is torn down at once, without stopping, and can not be sent
+///   commands afterwards.
+///
+/// Unknown policies return EINVAL.
+///
+/// `SUPERVISE_FORCEKILL` tears down a process which may never run again, such as one stuck in the
+/// kernel, without waiting for it: its files are closed, its memory is freed, and the supervisor
+/// reads its exit event, with a status of `128 + SIGKILL`, as if it was reaped. `a` is the PID of
+/// the process, or 0 for the one commands are sent to. Only the parent of the process, or a
+/// context with an IOPL of 3, may do this, others get EPERM. Processes of other supervisors return
+/// ESRCH, and EBUSY is returned while the supervisor has too many unread events to take the exit
+/// event, in which case the process is left alone.
+///
+/// Events outside of the mask let the process continue without notifying the supervisor. Steps
+/// always stop. The mask starts as `SUPERVISE_MASK_ALL`, and children supervised along with the
+/// process inherit it. Masks with unknown bits return EINVAL.
+///
+/// `SUPERVISE_NONBLOCK` is not sent to a process, it changes the file descriptor itself. If `a` is
+/// not 0, reads return EAGAIN when there is nothing to read, instead of blocking. The same flag is
+/// available as `O_NONBLOCK` through `sys_fcntl`. Once every process is gone and its events are
+/// read, reads return ESRCH instead of blocking forever.
+///
+/// `SUPERVISE_INTERRUPT` is not sent to a process either. It cancels a read blocked on the file
+/// descriptor, or a duplicate of it, such as one in another thread, which returns EINTR. `a` is the
+/// PID of the thread blocked in the read, or 0 to cancel every blocked read. Returns the number of
+/// reads canceled. It may also be used on an observer.
+///
+/// Every packet carries `PACKET_MAGIC` in its `magic` field, which `Packet::default()` fills in.
+/// Packets with any other magic were built for a different layout, and return EINVAL.
+///
+/// Unknown commands, or packets of unexpected size, return EINVAL.
+///
+/// Writing fewer bytes than a `Packet` returns EINVAL. Registers are replaced whole, with
+/// `SUPERVISE_SETREGS`. Old supervisors can send `SUPERVISE_LEGACY_WRITE` with an `a` of 1 to have
+/// such writes taken as a big endian, pointer sized integer, which is treated like
+/// `SUPERVISE_RETURN`, as they used to be.
 ///
 /// Note that a process blocked by a syscall will have its potential sleep cleared (i.e., it will
 /// not wake up after the sleep is finished).
@@ -38,3 +382,35 @@ pub const SYS_SUPERVISE: usize = 1638; // loominatzi confirmed
 pub fn sys_supervise(pid: usize) -> Result<usize> {
     unsafe { syscall1(SYS_SUPERVISE, pid) }
 }
+
+/// Observe a given child process, without supervising it.
+///
+/// The returned file descriptor reads the same packages as one returned by `sys_supervise`, for
+/// every syscall, syscall return, signal, exec, and exit of the process, but the process never
+/// stops for them. Every write returns EACCES. If 256 packages are unread, further ones are
+/// dropped, so an observer can not slow the process down.
+///
+/// Any number of observers, and a supervisor, may be attached to the process at once. Children of
+/// the process are not observed. The PID must be a child of the invoker, or EACCES is returned,
+/// and can not be the invoker itself, which returns EINVAL.
+pub fn sys_supervise_observe(pid: usize) -> Result<usize> {
+    unsafe { syscall1(SYS_SUPERVISE_OBSERVE, pid) }
+}
+
+/// Run the resource specific command `cmd` on a file descriptor, with the argument `arg`.
+///
+/// This is an out-of-band channel, separate from the data read and written. A supervisor file
+/// descriptor accepts the `SUPERVISE_*` commands which take at most one argument, with `arg` in
+/// place of `a`. `CONTROL_ISATTY` works on every file descriptor. Resources without commands, or
+/// unknown commands, return EINVAL.
+pub fn sys_control(fd: usize, cmd: usize, arg: usize) -> Result<usize> {
+    unsafe { syscall3(SYS_CONTROL, fd, cmd, arg) }
+}
+
+/// Map the first `len` bytes of the resource referenced by a file descriptor into memory.
+///
+/// The mapping is writeable if `flags` has `MAP_WRITE`. Returns the address of the mapping, or
+/// ENODEV if the resource can not be mapped.
+pub fn sys_fmap(fd: usize, len: usize, flags: usize) -> Result<usize> {
+    unsafe { syscall3(SYS_FMAP, fd, len, flags) }
+}
@@ -24,17 +24,28 @@ pub const SYS_CLOCK_GETTIME: usize = 265;
 pub const SYS_DUP: usize = 41;
 pub const SYS_EXECVE: usize = 11;
 pub const SYS_EXIT: usize = 1;
+pub const SYS_FCNTL: usize = 55;
+    pub const F_GETFD: usize = 1;
+    pub const F_SETFD: usize = 2;
+    pub const F_GETFL: usize = 3;
+    pub const F_SETFL: usize = 4;
+    pub const FD_CLOEXEC: usize = 1;
 pub const SYS_FPATH: usize = 928;
 pub const SYS_FSTAT: usize = 28;
 pub const SYS_FSYNC: usize = 118;
 pub const SYS_FTRUNCATE: usize = 93;
 pub const SYS_GETPID: usize = 20;
+pub const SYS_GETRANDOM: usize = 318;
+    pub const GRND_NONBLOCK: usize = 1;
+    pub const GRND_RANDOM: usize = 2;
 pub const SYS_IOPL: usize = 110;
 pub const SYS_LINK: usize = 9;
 pub const SYS_LSEEK: usize = 19;
     pub const SEEK_SET: usize = 0;
     pub const SEEK_CUR: usize = 1;
     pub const SEEK_END: usize = 2;
+    pub const SEEK_DATA: usize = 3;
+    pub const SEEK_HOLE: usize = 4;
 pub const SYS_MKDIR: usize = 39;
 pub const SYS_NANOSLEEP: usize = 162;
 pub const SYS_OPEN: usize = 5;
@@ -50,6 +61,7 @@ pub const SYS_OPEN: usize = 5;
     pub const O_CREAT: usize = 0x200;
     pub const O_TRUNC: usize = 0x400;
     pub const O_EXCL: usize = 0x800;
+    pub const O_CLOEXEC: usize = 0x1000;
 pub const SYS_PIPE2: usize = 331;
 pub const SYS_READ: usize = 3;
 pub const SYS_RMDIR: usize = 84;
@@ -62,6 +74,41 @@ pub const SYS_WAITPID: usize = 7;
 pub const SYS_WRITE: usize = 4;
 pub const SYS_YIELD: usize = 158;
 
+// Signals
+pub const SIGHUP: usize = 1;
+pub const SIGINT: usize = 2;
+pub const SIGQUIT: usize = 3;
+pub const SIGILL: usize = 4;
+pub const SIGTRAP: usize = 5;
+pub const SIGABRT: usize = 6;
+pub const SIGBUS: usize = 7;
+pub const SIGFPE: usize = 8;
+pub const SIGKILL: usize = 9;
+pub const SIGUSR1: usize = 10;
+pub const SIGSEGV: usize = 11;
+pub const SIGUSR2: usize = 12;
+pub const SIGPIPE: usize = 13;
+pub const SIGALRM: usize = 14;
+pub const SIGTERM: usize = 15;
+pub const SIGSTKFLT: usize = 16;
+pub const SIGCHLD: usize = 17;
+pub const SIGCONT: usize = 18;
+pub const SIGSTOP: usize = 19;
+pub const SIGTSTP: usize = 20;
+pub const SIGTTIN: usize = 21;
+pub const SIGTTOU: usize = 22;
+pub const SIGURG: usize = 23;
+pub const SIGXCPU: usize = 24;
+pub const SIGXFSZ: usize = 25;
+pub const SIGVTALRM: usize = 26;
+pub const SIGPROF: usize = 27;
+pub const SIGWINCH: usize = 28;
+pub const SIGIO: usize = 29;
+pub const SIGPWR: usize = 30;
+pub const SIGSYS: usize = 31;
+/// The number of signals, valid signals are in `1..NSIG`
+pub const NSIG: usize = 32;
+
 #[derive(Copy, Clone, Debug, Default)]
 #[repr(packed)]
 pub struct Stat {
@@ -117,6 +164,10 @@ pub fn sys_exit(status: usize) -> Result<usize> {
     unsafe { syscall1(SYS_EXIT, status) }
 }
 
+pub fn sys_fcntl(fd: usize, cmd: usize, arg: usize) -> Result<usize> {
+    unsafe { syscall3(SYS_FCNTL, fd, cmd, arg) }
+}
+
 pub fn sys_fpath(fd: usize, buf: &mut [u8]) -> Result<usize> {
     unsafe { syscall3(SYS_FPATH, fd, buf.as_mut_ptr() as usize, buf.len()) }
 }
@@ -137,6 +188,10 @@ pub fn sys_getpid() -> Result<usize> {
     unsafe { syscall0(SYS_GETPID) }
 }
 
+pub fn sys_getrandom(buf: &mut [u8], flags: usize) -> Result<usize> {
+    unsafe { syscall3(SYS_GETRANDOM, buf.as_mut_ptr() as usize, buf.len(), flags) }
+}
+
 pub unsafe fn sys_iopl(level: usize) -> Result<usize> {
     syscall1(SYS_IOPL, level)
 }
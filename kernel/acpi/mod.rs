@@ -1,10 +1,14 @@
 use alloc::boxed::Box;
 
-use core::str;
+use collections::borrow::Cow;
+use collections::string::String;
+use collections::vec::Vec;
 
-use fs::{KScheme, Resource, Url};
-use system::error::{Error, Result, ENOENT};
-use system::syscall::O_CREAT;
+use core::{cmp, slice, str};
+
+use fs::{KScheme, Resource, ResourceSeek, Url};
+use system::error::{Error, Result, EINVAL, ENOENT};
+use system::syscall::{MODE_FILE, O_CREAT, Stat};
 pub use self::dsdt::DSDT;
 pub use self::fadt::FADT;
 pub use self::madt::MADT;
@@ -20,6 +24,10 @@ pub mod rsdt;
 pub mod sdt;
 pub mod ssdt;
 
+/// The ACPI scheme
+///
+/// `acpi:` lists the names of the tables found at boot, one per line, and `acpi:NAME`, such as
+/// `acpi:APIC`, reads the raw bytes of a table. Both are read only.
 #[derive(Clone, Debug, Default)]
 pub struct Acpi {
     rsdt: RSDT,
@@ -27,9 +35,95 @@ pub struct Acpi {
     dsdt: Option<DSDT>,
     ssdt: Option<SSDT>,
     madt: Option<MADT>,
+    /// The tables found at boot, by name, see `name_tables`
+    tables: Vec<(String, &'static SDTHeader)>,
+}
+
+/// Name tables by their signature, numbering those which share one from 1, like `SSDT1`
+pub fn name_tables(headers: &[&'static SDTHeader]) -> Vec<(String, &'static SDTHeader)> {
+    let mut tables = Vec::new();
+    for header in headers.iter() {
+        let signature = String::from_utf8_lossy(&header.signature).into_owned();
+        let shared = headers.iter().filter(|other| other.signature == header.signature).count();
+        let name = if shared > 1 {
+            let number = tables.iter()
+                               .filter(|table: &&(String, &SDTHeader)| {
+                                   table.1.signature == header.signature
+                               })
+                               .count() + 1;
+            format!("{}{}", signature, number)
+        } else {
+            signature
+        };
+        tables.push((name, *header));
+    }
+    tables
+}
+
+/// A read only ACPI resource, holding a table or the list of tables
+pub struct AcpiResource {
+    path: String,
+    data: Cow<'static, [u8]>,
+    seek: usize,
+}
+
+impl Resource for AcpiResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box AcpiResource {
+            path: self.path.clone(),
+            data: self.data.clone(),
+            seek: self.seek,
+        })
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        for (b, p) in buf.iter_mut().zip(self.path.bytes()) {
+            *b = p;
+        }
+
+        Ok(cmp::min(buf.len(), self.path.len()))
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let mut i = 0;
+        for (b, d) in buf.iter_mut().zip(self.data.iter().skip(self.seek)) {
+            *b = *d;
+            i += 1;
+        }
+        self.seek += i;
+
+        Ok(i)
+    }
+
+    fn seek(&mut self, pos: ResourceSeek) -> Result<usize> {
+        let len = self.data.len();
+        self.seek = match pos {
+            ResourceSeek::Start(offset) => cmp::min(len, offset),
+            ResourceSeek::Current(offset) =>
+                cmp::max(0, cmp::min(len as isize, self.seek as isize + offset)) as usize,
+            ResourceSeek::End(offset) =>
+                cmp::max(0, cmp::min(len as isize, len as isize + offset)) as usize,
+            ResourceSeek::Data(_) | ResourceSeek::Hole(_) => return Err(Error::new(EINVAL)),
+        };
+        Ok(self.seek)
+    }
+
+    fn stat(&self, stat: &mut Stat) -> Result<usize> {
+        stat.st_mode = MODE_FILE;
+        stat.st_size = self.data.len() as u32;
+        Ok(0)
+    }
 }
 
 impl Acpi {
+    /// An ACPI scheme serving `tables`, without any parsed tables
+    pub fn with_tables(tables: &[&'static SDTHeader]) -> Acpi {
+        Acpi {
+            tables: name_tables(tables),
+            ..Acpi::default()
+        }
+    }
+
     pub fn new() -> Option<Box<Self>> {
         match RSDT::new() {
             Ok(rsdt) => {
@@ -41,16 +135,21 @@ impl Acpi {
                     dsdt: None,
                     ssdt: None,
                     madt: None,
+                    tables: Vec::new(),
                 };
 
+                let mut headers = Vec::new();
                 for addr in acpi.rsdt.addrs.iter() {
                     let header = unsafe { &*(*addr as *const SDTHeader) };
+                    headers.push(header);
                     if let Some(fadt) = FADT::new(header) {
                         //Can't do it debugln!("{:#?}", fadt);
-                        if let Some(dsdt) = DSDT::new(unsafe { &*(fadt.dsdt as *const SDTHeader) }) {
+                        let dsdt_header = unsafe { &*(fadt.dsdt as *const SDTHeader) };
+                        if let Some(dsdt) = DSDT::new(dsdt_header) {
                             syslog_debug!("DSDT:");
                             aml::parse(dsdt.data);
                             acpi.dsdt = Some(dsdt);
+                            headers.push(dsdt_header);
                         }
                         acpi.fadt = Some(fadt);
                     } else if let Some(ssdt) = SSDT::new(header) {
@@ -64,6 +163,7 @@ impl Acpi {
                         syslog_debug!("{}: Unknown Table", unsafe { str::from_utf8_unchecked(&header.signature) });
                     }
                 }
+                acpi.tables = name_tables(&headers);
 
                 Some(acpi)
             }
@@ -95,6 +195,33 @@ impl KScheme for Acpi {
             }
         }
 
-        Err(Error::new(ENOENT))
+        let name = url.reference().trim_matches('/');
+        if name.is_empty() {
+            let mut list = String::new();
+            for &(ref name, _) in self.tables.iter() {
+                list.push_str(name);
+                list.push('\n');
+            }
+            return Ok(box AcpiResource {
+                path: String::from("acpi:"),
+                data: Cow::Owned(list.into_bytes()),
+                seek: 0,
+            });
+        }
+
+        match self.tables.iter().find(|&&(ref table, _)| table == name) {
+            Some(&(_, header)) => {
+                let data = unsafe {
+                    slice::from_raw_parts(header as *const SDTHeader as *const u8,
+                                          header.length as usize)
+                };
+                Ok(box AcpiResource {
+                    path: format!("acpi:{}", name),
+                    data: Cow::Borrowed(data),
+                    seek: 0,
+                })
+            },
+            None => Err(Error::new(ENOENT)),
+        }
     }
 }
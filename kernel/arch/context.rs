@@ -1,11 +1,12 @@
+use common::random;
 use common::slice::GetSlice;
 
 use alloc::arc::Arc;
 use alloc::boxed::{Box, FnBox};
 
-use arch::memory;
+use arch::memory::{self, Memory};
 use arch::paging::Page;
-use arch::regs::Regs;
+use arch::regs::{FpRegs, Regs};
 
 use collections::borrow::Cow;
 use collections::string::{String, ToString};
@@ -15,16 +16,20 @@ use common::time::Duration;
 
 use core::cell::UnsafeCell;
 use core::slice::{self, Iter, IterMut};
-use core::{mem, ptr};
+use core::{cmp, mem, ptr};
 use core::ops::DerefMut;
 
 use fs::Resource;
+use fs::supervisor_resource::{Observer, SupervisedResource};
+
+use schemes::trace;
 
 use syscall;
 
-use system::error::{Error, Result, EBADF, EFAULT, ENOMEM, ESRCH, ENOENT, EINVAL};
+use system::error::{Error, Result, EBADF, EFAULT, EMFILE, ENOMEM, ESRCH, ENOENT, EINVAL};
+use system::syscall::{SIGKILL, SIGSTOP};
 
-use sync::WaitMap;
+use sync::{WaitCondition, WaitMap};
 
 pub const CONTEXT_IMAGE_ADDR: usize = 0x8048000;
 pub const CONTEXT_IMAGE_SIZE: usize = 0x10000000;
@@ -38,6 +43,21 @@ pub const CONTEXT_MMAP_SIZE: usize = 0x20000000;
 pub const CONTEXT_STACK_ADDR: usize = CONTEXT_MMAP_ADDR + CONTEXT_MMAP_SIZE + memory::CLUSTER_SIZE;
 pub const CONTEXT_STACK_SIZE: usize = 0x100000;
 
+/// The default limit on the number of open files of a context, until `kparam:file_limit` is set
+pub const CONTEXT_FILE_LIMIT: usize = 1024;
+
+/// The default scheduling priority of a context, which gets one time slice per round
+pub const CONTEXT_PRIORITY_DEFAULT: usize = 8;
+/// The highest scheduling priority of a context
+pub const CONTEXT_PRIORITY_MAX: usize = 4 * CONTEXT_PRIORITY_DEFAULT;
+
+/// The number of CPUs contexts can be scheduled on, as the other processors are not started
+pub const CONTEXT_CPUS: usize = 1;
+/// The CPU the scheduler runs on
+pub const CONTEXT_CPU: usize = 0;
+/// The affinity of a context which may run on every CPU, the default
+pub const CONTEXT_AFFINITY_ALL: usize = (1 << CONTEXT_CPUS) - 1;
+
 pub struct ContextManager {
     pub inner: Vec<Box<Context>>,
     pub enabled: bool,
@@ -152,8 +172,11 @@ pub unsafe fn context_switch() {
                                 break 'searching;
                             }
                         }
-                    } else {
-                        break 'searching;
+                    } else if next.affinity & 1 << CONTEXT_CPU == 1 << CONTEXT_CPU {
+                        let priority = next.priority;
+                        if next.take_credit(priority) {
+                            break 'searching;
+                        }
                     }
                 }
                 if contexts.i == current_i {
@@ -188,6 +211,7 @@ pub unsafe fn context_switch() {
     }
 
     if current_ptr as usize > 0 && next_ptr as usize > 0 {
+        trace::record(syscall::TRACE_SWITCH, (*current_ptr).pid, (*next_ptr).pid);
         (*current_ptr).switch_to(&mut *next_ptr);
     }
 }
@@ -200,6 +224,9 @@ pub unsafe fn context_clone(regs: &Regs) -> Result<usize> {
     if kernel_stack > 0 {
         let clone_pid = Context::next_pid();
 
+        // The parent and the child must not see the same random stream
+        random::fork(clone_pid);
+
         let context = {
             let mut parent = try!(contexts.current_mut());
 
@@ -234,6 +261,7 @@ pub unsafe fn context_clone(regs: &Regs) -> Result<usize> {
                         virtual_size: entry.virtual_size,
                         writeable: entry.writeable,
                         allocated: true,
+                        shared: None,
                     })
                 } else {
                     None
@@ -285,20 +313,15 @@ pub unsafe fn context_clone(regs: &Regs) -> Result<usize> {
             } else {
                 let files: Vec<ContextFile> = (*parent.files.get())
                     .iter()
-                    .filter_map(|file| {
-                        if let Ok(resource) = file.resource.dup() {
-                            Some(ContextFile {
-                                fd: file.fd,
-                                resource: resource,
-                            })
-                        } else {
-                            None
-                        }
-                    })
+                    .filter_map(|file| file.dup().ok())
                     .collect();
                 Arc::new(UnsafeCell::new(files))
             };
 
+            let supervised_resource = parent.supervised_resource.as_ref().and_then(|resource| {
+                resource.inherit(clone_pid)
+            });
+
             // Must be last, so blocking does not cause a deadlock
             let vfork = if flags & syscall::CLONE_VFORK == syscall::CLONE_VFORK {
                 parent.block("context_clone vfork");
@@ -311,17 +334,29 @@ pub unsafe fn context_clone(regs: &Regs) -> Result<usize> {
                 pid: clone_pid,
                 ppid: parent.pid,
                 name: parent.name.clone(),
+                args: parent.args.clone(),
                 iopl: parent.iopl,
                 blocked: 0,
                 exited: false,
                 switch: 0,
                 time: 0,
+                user_time: 0,
+                priority: parent.priority,
+                affinity: parent.affinity,
+                credit: 0,
                 vfork: vfork,
                 wake: None,
-
-                supervised: flags & syscall::CLONE_SUPERVISE == syscall::CLONE_SUPERVISE,
+                waiting: None,
+                pending_signals: 0,
+                blocked_signals: parent.blocked_signals,
+
+                supervised: flags & syscall::CLONE_SUPERVISE == syscall::CLONE_SUPERVISE ||
+                            supervised_resource.is_some(),
+                supervised_resource: supervised_resource,
+                observers: Vec::new(),
                 blocked_syscall: false,
                 current_syscall: None,
+                stopped_regs: None,
 
                 kernel_stack: kernel_stack,
                 regs: kernel_regs,
@@ -335,6 +370,7 @@ pub unsafe fn context_clone(regs: &Regs) -> Result<usize> {
                 env_vars: env_vars,
                 cwd: cwd,
                 files: files,
+                file_limit: parent.file_limit,
 
                 statuses: WaitMap::new(),
             }
@@ -393,7 +429,7 @@ unsafe extern "cdecl" fn context_box(box_fn_ptr: usize) {
     let box_fn = ptr::read(box_fn_ptr as *mut Box<FnBox()>);
     memory::unalloc(box_fn_ptr);
     box_fn();
-    syscall::process::exit(0);
+    syscall::process::exit(0, &Regs::default());
 }
 
 /// Reads a Boxed function and executes it
@@ -405,7 +441,7 @@ unsafe extern "cdecl" fn context_box(/*Throw away extra params from ABI*/ _rdi:
     let box_fn = ptr::read(box_fn_ptr as *mut Box<FnBox()>);
     memory::unalloc(box_fn_ptr);
     box_fn();
-    syscall::process::exit(0);
+    syscall::process::exit(0, &Regs::default());
 }
 
 pub struct ContextMemory {
@@ -414,6 +450,8 @@ pub struct ContextMemory {
     pub virtual_size: usize,
     pub writeable: bool,
     pub allocated: bool,
+    /// Shared memory which is kept alive while it is mapped, such as the pages of an `shm:` segment
+    pub shared: Option<Arc<Memory<u8>>>,
 }
 
 impl ContextMemory {
@@ -448,6 +486,22 @@ impl Drop for ContextMemory {
 pub struct ContextFile {
     pub fd: usize,
     pub resource: Box<Resource>,
+    /// Whether the file is closed when a new image is executed, set with `FD_CLOEXEC`
+    pub cloexec: bool,
+}
+
+impl ContextFile {
+    /// Duplicate the file for a new process, keeping its descriptor and flags
+    ///
+    /// The duplicate of an in-memory file, such as a `VecResource`, shares the data until either
+    /// of them writes, so forking does not copy it up front.
+    pub fn dup(&self) -> Result<ContextFile> {
+        Ok(ContextFile {
+            fd: self.fd,
+            resource: try!(self.resource.dup()),
+            cloexec: self.cloexec,
+        })
+    }
 }
 
 pub struct ContextZone {
@@ -468,6 +522,19 @@ impl ContextZone {
     pub fn dup(&self) -> ContextZone {
         let mut mem: Vec<ContextMemory> = Vec::new();
         for entry in self.memory.iter() {
+            // Shared memory stays shared with the copy
+            if let Some(ref shared) = entry.shared {
+                mem.push(ContextMemory {
+                    physical_address: entry.physical_address,
+                    virtual_address: entry.virtual_address,
+                    virtual_size: entry.virtual_size,
+                    writeable: entry.writeable,
+                    allocated: false,
+                    shared: Some(shared.clone()),
+                });
+                continue;
+            }
+
             let physical_address = unsafe { memory::alloc(entry.virtual_size) };
             if physical_address > 0 {
                 //TODO: Remap pages during memcpy
@@ -485,6 +552,7 @@ impl ContextZone {
                     virtual_size: entry.virtual_size,
                     writeable: entry.writeable,
                     allocated: true,
+                    shared: None,
                 });
             } else {
                 //debugln!("{}: {}: failed to dup memory {:X}:{:X} for {}", parent.pid, parent.name, entry.virtual_address, entry.virtual_address + entry.virtual_size, clone_pid);
@@ -517,6 +585,7 @@ impl ContextZone {
                             virtual_size: size,
                             writeable: writeable,
                             allocated: allocated,
+                            shared: None,
                         });
 
                         return Ok(virtual_address);
@@ -537,6 +606,7 @@ impl ContextZone {
                     virtual_size: size,
                     writeable: writeable,
                     allocated: allocated,
+                    shared: None,
                 });
 
                 return Ok(virtual_address);
@@ -653,6 +723,8 @@ pub struct Context {
     pub ppid: usize,
     /// The name of the context
     pub name: Cow<'static, str>,
+    /// The arguments of the executed image, which are empty for kernel threads
+    pub args: Vec<String>,
     /// The I/O privilege level
     pub iopl: usize,
     /// Indicates that the context is blocked, and should not be switched to
@@ -663,23 +735,50 @@ pub struct Context {
     pub switch: usize,
     /// The number of time slices used
     pub time: usize,
+    /// The number of those time slices in which the PIT interrupted userspace, see `tick`
+    pub user_time: usize,
+    /// The scheduling priority, from 1 to `CONTEXT_PRIORITY_MAX`, inherited by children
+    pub priority: usize,
+    /// The CPUs the context may run on, where bit `n` is set for CPU `n`, inherited by children
+    pub affinity: usize,
+    /// Scheduling credit, earned by `priority` and spent by `CONTEXT_PRIORITY_DEFAULT` per slice
+    pub credit: usize,
     /// Indicates that the context needs to unblock parent
     pub vfork: Option<*mut Context>,
     /// When to wake up
     pub wake: Option<Duration>,
+    /// The wait condition which may hold the context, from the start of a wait until its end
+    ///
+    /// Tracked so that a context killed while it waits can be removed from it, see `force_exit`.
+    pub waiting: Option<*const WaitCondition>,
+    /// Pending signals, where bit `n` is set if signal `n` is pending
+    pub pending_signals: usize,
+    /// Blocked signals, which stay pending instead of being taken, inherited by children
+    ///
+    /// SIGKILL and SIGSTOP are taken even if they are blocked.
+    pub blocked_signals: usize,
     // }
 
     /// Is this process supervised?
     ///
     /// i.e., will the syscalls made by this process block the process until handled by
     /// a supervisor?
+    ///
+    /// This is set by `CLONE_SUPERVISE`, or when a supervisor attaches, and cleared only when the
+    /// supervisor detaches or is closed, along with `supervised_resource`.
     pub supervised: bool,
+    /// The tracee end of the supervisor, if one is attached
+    pub supervised_resource: Option<SupervisedResource>,
+    /// The event queues of observe only supervisors
+    pub observers: Vec<Observer>,
     /// Is this process currently blocked by a syscall?
     ///
     /// This means that the process is waiting for the superviser to handle the syscall.
     pub blocked_syscall: bool,
     /// The current syscall
     pub current_syscall: Option<(usize, usize, usize, usize, usize)>,
+    /// The registers of the context while it is stopped by its supervisor
+    pub stopped_regs: Option<Regs>,
 
     // These members control the stack and registers and are unique to each context {
     // The kernel stack
@@ -711,6 +810,9 @@ pub struct Context {
     pub files: Arc<UnsafeCell<Vec<ContextFile>>>,
     // }
 
+    /// The number of open files past which opening more returns `EMFILE`, inherited by children
+    pub file_limit: usize,
+
     /// Exit statuses of children
     pub statuses: WaitMap<usize, usize>,
 }
@@ -752,17 +854,28 @@ impl Context {
             pid: Context::next_pid(),
             ppid: 0,
             name: "kidle".into(),
+            args: Vec::new(),
             iopl: 3,
             blocked: 0,
             exited: false,
             switch: 0,
             time: 0,
+            user_time: 0,
+            priority: CONTEXT_PRIORITY_DEFAULT,
+            affinity: CONTEXT_AFFINITY_ALL,
+            credit: 0,
             vfork: None,
             wake: None,
+            waiting: None,
+            pending_signals: 0,
+            blocked_signals: 0,
 
             supervised: false,
+            supervised_resource: None,
+            observers: Vec::new(),
             blocked_syscall: false,
             current_syscall: None,
+            stopped_regs: None,
 
             kernel_stack: 0,
             regs: Regs::default(),
@@ -777,6 +890,7 @@ impl Context {
 
             cwd: Arc::new(UnsafeCell::new(String::new())),
             files: Arc::new(UnsafeCell::new(Vec::new())),
+            file_limit: unsafe { *::env().file_limit.get() },
 
             statuses: WaitMap::new(),
         }
@@ -794,17 +908,28 @@ impl Context {
             pid: Context::next_pid(),
             ppid: 0,
             name: name,
+            args: Vec::new(),
             iopl: 3,
             blocked: 0,
             exited: false,
             switch: 0,
             time: 0,
+            user_time: 0,
+            priority: CONTEXT_PRIORITY_DEFAULT,
+            affinity: CONTEXT_AFFINITY_ALL,
+            credit: 0,
             vfork: None,
             wake: None,
+            waiting: None,
+            pending_signals: 0,
+            blocked_signals: 0,
 
             supervised: false,
+            supervised_resource: None,
+            observers: Vec::new(),
             blocked_syscall: false,
             current_syscall: None,
+            stopped_regs: None,
 
             kernel_stack: kernel_stack,
             regs: regs,
@@ -819,6 +944,7 @@ impl Context {
 
             cwd: Arc::new(UnsafeCell::new(String::new())),
             files: Arc::new(UnsafeCell::new(Vec::new())),
+            file_limit: unsafe { *::env().file_limit.get() },
 
             statuses: WaitMap::new(),
         };
@@ -865,12 +991,77 @@ impl Context {
         }
     }
 
+    /// Make `signal` pending
+    ///
+    /// If the signal is not blocked, this also ends the wait the context is blocked in, so an
+    /// interruptible wait, such as that of `Futexes::wait`, can return `EINTR`. Other waits wait
+    /// again.
+    pub fn signal(&mut self, signal: usize) {
+        self.pending_signals |= 1 << signal;
+        if self.deliverable_signals() & 1 << signal == 1 << signal {
+            if let Some(condition) = self.waiting {
+                if unsafe { (*condition).remove(self) } {
+                    self.unblock("Context::signal");
+                }
+            }
+        }
+    }
+
+    /// The pending signals which are taken the next time the context returns from a syscall
+    ///
+    /// These are the pending signals which are not blocked, but SIGKILL and SIGSTOP are always
+    /// taken.
+    pub fn deliverable_signals(&self) -> usize {
+        let blocked = self.blocked_signals & !(1 << SIGKILL | 1 << SIGSTOP);
+        self.pending_signals & !blocked
+    }
+
     pub fn exit(&mut self) {
         // debugln!("    EXIT {}: {}", self.pid, self.name);
-        self.files = Arc::new(UnsafeCell::new(Vec::new()));
+        ::env().futexes.forget(self.pid);
+        self.close_files();
         self.exited = true;
     }
 
+    /// Let go of the file table, closing its files if no other context shares it
+    ///
+    /// A table shared with `CLONE_FILES` is closed by whichever context lets go of it last, either
+    /// when it exits or when it is dropped.
+    fn close_files(&mut self) {
+        let files = mem::replace(&mut self.files, Arc::new(UnsafeCell::new(Vec::new())));
+        // Nobody is left to report teardown errors to
+        if let Ok(files) = Arc::try_unwrap(files) {
+            for mut file in unsafe { files.into_inner() } {
+                let _ = file.resource.close();
+            }
+        }
+    }
+
+    /// Tear down a context which is not running, without switching to it
+    ///
+    /// Like `exit`, this closes the files, and it also frees the memory and the kernel stack at
+    /// once, as the context never runs again. Whatever the kernel code it was in holds is leaked.
+    /// The context is first removed from the wait condition it is blocked on, if any, so it can be
+    /// freed afterwards.
+    pub unsafe fn kill(&mut self) {
+        if let Some(condition) = self.waiting.take() {
+            (*condition).forget(self);
+        }
+        self.exit();
+        if let Some(vfork) = self.vfork.take() {
+            (*vfork).unblock("Context::kill vfork");
+        }
+
+        self.stack = None;
+        self.image = Arc::new(UnsafeCell::new(ContextZone::new(0, 0)));
+        self.heap = Arc::new(UnsafeCell::new(ContextZone::new(0, 0)));
+        self.mmap = Arc::new(UnsafeCell::new(ContextZone::new(0, 0)));
+        if self.kernel_stack > 0 {
+            memory::unalloc(self.kernel_stack);
+            self.kernel_stack = 0;
+        }
+    }
+
     pub fn canonicalize(&self, path: &str) -> String {
         // TODO my eyes burn, rewrite this.
         if path.find(':').is_none() {
@@ -918,6 +1109,114 @@ impl Context {
         return next_fd;
     }
 
+    /// Account a tick of the PIT to the context, which it interrupted with the registers `regs`
+    ///
+    /// The tick is user time if `regs` are those of ring 3, and system time otherwise.
+    pub fn tick(&mut self, regs: &Regs) {
+        self.time += 1;
+        if regs.cs & 3 == 3 {
+            self.user_time += 1;
+        }
+    }
+
+    /// Earn `earned` scheduling credit, then spend the credit of a time slice if there is enough
+    ///
+    /// Returns `true` if the context may run for another time slice. A context with the default
+    /// priority always may, one with a lower priority is skipped by some rounds of the scheduler,
+    /// and one with a higher priority runs for more than one slice in a row. Credit is capped at
+    /// `CONTEXT_PRIORITY_MAX`, so a context can not save it up while it is not scheduled.
+    pub fn take_credit(&mut self, earned: usize) -> bool {
+        self.credit = cmp::min(self.credit + earned, CONTEXT_PRIORITY_MAX);
+        if self.credit >= CONTEXT_PRIORITY_DEFAULT {
+            self.credit -= CONTEXT_PRIORITY_DEFAULT;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Check that `count` more files can be opened without going over `file_limit`
+    ///
+    /// Returns `EMFILE` if they can not.
+    pub fn reserve_files(&self, count: usize) -> Result<()> {
+        if unsafe { (*self.files.get()).len() } + count > self.file_limit {
+            Err(Error::new(EMFILE))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Map `size` bytes of fresh memory into the mmap zone, starting with a copy of `data`
+    ///
+    /// The rest of the mapping is zeroed, and it is mapped right away if this is the current
+    /// context. Returns the virtual address of the mapping, `EINVAL` if `size` is 0, and `ENOMEM`
+    /// if there is no memory or no space left in the zone.
+    pub fn map_copy(&self, data: &[u8], size: usize, writeable: bool) -> Result<usize> {
+        if size == 0 {
+            return Err(Error::new(EINVAL));
+        }
+        let size = (size + 4095) / 4096 * 4096;
+
+        let physical_address = unsafe { memory::alloc_aligned(size, 4096) };
+        if physical_address == 0 {
+            return Err(Error::new(ENOMEM));
+        }
+        unsafe {
+            ::memset(physical_address as *mut u8, 0, size);
+            ::memcpy(physical_address as *mut u8, data.as_ptr(), cmp::min(data.len(), size));
+        }
+
+        let mmap = unsafe { &mut *self.mmap.get() };
+        let virtual_address = match mmap.add_mem(physical_address, size, writeable, true) {
+            Ok(virtual_address) => virtual_address,
+            Err(err) => {
+                unsafe { memory::unalloc(physical_address) };
+                return Err(err);
+            }
+        };
+
+        let contexts = unsafe { & *::env().contexts.get() };
+        if contexts.current().ok().map(|current| current.pid) == Some(self.pid) {
+            for memory in mmap.memory.iter_mut() {
+                if memory.virtual_address == virtual_address {
+                    unsafe { memory.map() };
+                    break;
+                }
+            }
+        }
+
+        Ok(virtual_address)
+    }
+
+    /// Map the first `size` bytes of `memory` into the mmap zone, sharing its pages
+    ///
+    /// The mapping keeps `memory` alive, and is mapped right away if this is the current context.
+    /// Returns the virtual address of the mapping, `EINVAL` if `size` is 0 or larger than
+    /// `memory`, and `ENOMEM` if there is no space left in the zone.
+    pub fn map_shared(&self, memory: &Arc<Memory<u8>>, size: usize, writeable: bool) -> Result<usize> {
+        if size == 0 || size > memory.len() {
+            return Err(Error::new(EINVAL));
+        }
+        let size = (size + 4095) / 4096 * 4096;
+
+        let mmap = unsafe { &mut *self.mmap.get() };
+        let virtual_address = try!(mmap.add_mem(memory.address(), size, writeable, false));
+
+        let contexts = unsafe { & *::env().contexts.get() };
+        let current = contexts.current().ok().map(|current| current.pid) == Some(self.pid);
+        for entry in mmap.memory.iter_mut() {
+            if entry.virtual_address == virtual_address {
+                entry.shared = Some(memory.clone());
+                if current {
+                    unsafe { entry.map() };
+                }
+                break;
+            }
+        }
+
+        Ok(virtual_address)
+    }
+
     /// Get a resource from a file descriptor
     pub fn get_file<'a>(&self, fd: usize) -> Result<&'a Box<Resource>> {
         for file in unsafe { (*self.files.get()).iter() } {
@@ -931,6 +1230,32 @@ impl Context {
         Err(Error::new(EBADF))
     }
 
+    /// Get the open file of a file descriptor, along with its descriptor flags
+    pub fn get_context_file_mut<'a>(&mut self, fd: usize) -> Result<&'a mut ContextFile> {
+        for file in unsafe { (*self.files.get()).iter_mut() } {
+            if file.fd == fd {
+                return Ok(file);
+            }
+        }
+
+        Err(Error::new(EBADF))
+    }
+
+    /// Close every file marked with `FD_CLOEXEC`, as when a new image is executed
+    pub fn close_on_exec(&self) {
+        let files = unsafe { &mut *self.files.get() };
+        let mut i = 0;
+        while i < files.len() {
+            if files[i].cloexec {
+                let mut file = files.remove(i);
+                // The new image has no way to learn about teardown errors
+                let _ = file.resource.close();
+            } else {
+                i += 1;
+            }
+        }
+    }
+
     /// Get a mutable resource from a file descriptor
     pub fn get_file_mut<'a>(&mut self, fd: usize) -> Result<&'a mut Box<Resource>> {
         for file in unsafe { (*self.files.get()).iter_mut() } {
@@ -944,6 +1269,15 @@ impl Context {
         Err(Error::new(EBADF))
     }
 
+    /// Remove the file descriptor `fd`, handing over its resource without closing it
+    pub fn take_file(&self, fd: usize) -> Result<Box<Resource>> {
+        let files = unsafe { &mut *self.files.get() };
+        match files.iter().position(|file| file.fd == fd) {
+            Some(i) => Ok(files.remove(i).resource),
+            None => Err(Error::new(EBADF)),
+        }
+    }
+
     pub unsafe fn push(&mut self, data: usize) {
         self.regs.sp -= mem::size_of::<usize>();
         ptr::write(self.regs.sp as *mut usize, data);
@@ -1081,6 +1415,34 @@ impl Context {
         }
     }
 
+    /// Save the FPU and SSE registers of the processor to `fx`
+    ///
+    /// This must only be called on the current context, such as by a stopped tracee, whose
+    /// registers are otherwise only saved when it is switched away from.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    pub unsafe fn save_fx(&mut self) {
+        asm!("fxsave [$0]" : : "r"(self.fx) : "memory" : "intel", "volatile");
+        self.loadable = true;
+    }
+
+    /// Load the FPU and SSE registers of the processor from `fx`
+    ///
+    /// This must only be called on the current context, after `save_fx`.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    pub unsafe fn load_fx(&self) {
+        asm!("fxrstor [$0]" : : "r"(self.fx) : "memory" : "intel", "volatile");
+    }
+
+    /// The FPU and SSE registers, as last saved to `fx`
+    pub fn fp_regs(&self) -> FpRegs {
+        unsafe { ptr::read(self.fx as *const FpRegs) }
+    }
+
+    /// Replace the FPU and SSE registers saved in `fx`, which are loaded by `load_fx`
+    pub fn set_fp_regs(&mut self, fp_regs: &FpRegs) {
+        unsafe { ptr::write(self.fx as *mut FpRegs, *fp_regs) };
+    }
+
     // This function must not push or pop
     #[cfg(target_arch = "x86")]
     #[cold]
@@ -1163,6 +1525,7 @@ impl Drop for Context {
         if let Some(vfork) = self.vfork.take() {
             unsafe { (*vfork).unblock("Context::drop vfork") };
         }
+        self.close_files();
         if self.kernel_stack > 0 {
             unsafe { memory::unalloc(self.kernel_stack) };
         }
@@ -185,6 +185,13 @@ struct MemoryMapEntry {
 
 const MEMORY_MAP: *const MemoryMapEntry = 0x500 as *const MemoryMapEntry;
 
+/// The number of free clusters, kept up to date by `set_cluster`
+static mut CLUSTERS_FREE: usize = 0;
+
+/// The number of free clusters below which an allocation notifies `Environment::memory_pressure`,
+/// or 0 if nobody is waiting, see `watch_pressure`
+static mut PRESSURE_THRESHOLD: usize = 0;
+
 /// Get the data (address) of a given cluster
 pub unsafe fn cluster(number: usize) -> usize {
     if number < CLUSTER_COUNT {
@@ -194,14 +201,27 @@ pub unsafe fn cluster(number: usize) -> usize {
     }
 }
 
-/// Set the address of a cluster
-pub unsafe fn set_cluster(number: usize, address: usize) {
+/// Set the address of a cluster, without counting free clusters
+unsafe fn write_cluster(number: usize, address: usize) {
     if number < CLUSTER_COUNT {
         ptr::write((CLUSTER_ADDRESS + number * mem::size_of::<usize>()) as *mut usize,
                    address);
     }
 }
 
+/// Set the address of a cluster
+pub unsafe fn set_cluster(number: usize, address: usize) {
+    if number < CLUSTER_COUNT {
+        let old = cluster(number);
+        if old == 0 && address != 0 {
+            CLUSTERS_FREE -= 1;
+        } else if old != 0 && address == 0 {
+            CLUSTERS_FREE += 1;
+        }
+        write_cluster(number, address);
+    }
+}
+
 /// Convert an adress to the cluster number
 pub unsafe fn address_to_cluster(address: usize) -> usize {
     if address >= CLUSTER_ADDRESS + CLUSTER_COUNT * mem::size_of::<usize>() {
@@ -219,8 +239,9 @@ pub unsafe fn cluster_to_address(number: usize) -> usize {
 pub unsafe fn cluster_init() {
     // First, set all clusters to the not present value
     for cluster in 0..CLUSTER_COUNT {
-        set_cluster(cluster, 0xFFFFFFFF);
+        write_cluster(cluster, 0xFFFFFFFF);
     }
+    CLUSTERS_FREE = 0;
 
     // Next, set all valid clusters to the free value
     // TODO: Optimize this function
@@ -283,6 +304,11 @@ pub unsafe fn alloc_aligned(size: usize, align: usize) -> usize {
                 page.flush();
             }
 
+            if CLUSTERS_FREE < PRESSURE_THRESHOLD {
+                PRESSURE_THRESHOLD = 0;
+                ::env().memory_pressure.notify("memory::alloc_aligned");
+            }
+
             return address;
         }
     }
@@ -400,6 +426,20 @@ pub fn memory_used() -> usize {
     })
 }
 
+/// The number of free clusters, without scanning them like `memory_free`
+pub fn clusters_free() -> usize {
+    unsafe { CLUSTERS_FREE }
+}
+
+/// Notify `Environment::memory_pressure` once an allocation leaves fewer than `threshold` free
+/// clusters
+///
+/// Every waiter is notified at once, after which they have to watch again. With several waiters,
+/// the highest threshold is watched, so each has to check its own threshold when notified.
+pub fn watch_pressure(threshold: usize) {
+    unsafe { PRESSURE_THRESHOLD = cmp::max(PRESSURE_THRESHOLD, threshold) };
+}
+
 pub fn memory_free() -> usize {
     (0..CLUSTER_COUNT).fold(0usize, |ret, i| unsafe {
         if cluster(i) == 0 {
@@ -1,6 +1,6 @@
 pub use self::arch::*;
 
-use system::scheme::Packet;
+use system::scheme::{Packet, PACKET_MAGIC};
 
 #[cfg(target_arch = "x86")]
 #[path="x86/regs.rs"]
@@ -10,6 +10,7 @@ mod arch;
 #[path="x86_64/regs.rs"]
 mod arch;
 
+/// The packet of a syscall, with the number in `a`, followed by the arguments
 impl Into<Packet> for Regs {
     fn into(self) -> Packet {
         Packet {
@@ -18,6 +19,7 @@ impl Into<Packet> for Regs {
             b: self.bx,
             c: self.cx,
             d: self.dx,
+            magic: PACKET_MAGIC,
         }
     }
 }
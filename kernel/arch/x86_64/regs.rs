@@ -22,3 +22,26 @@ pub struct Regs {
     pub sp: usize,
     pub ss: usize,
 }
+
+/// The FPU and SSE registers, in the layout saved by `fxsave` without a 64-bit operand size
+#[derive(Copy, Clone, Default)]
+#[repr(packed)]
+pub struct FpRegs {
+    pub fcw: u16,
+    pub fsw: u16,
+    pub ftw: u8,
+    pub _reserved0: u8,
+    pub fop: u16,
+    pub fip: u32,
+    pub fcs: u16,
+    pub _reserved1: u16,
+    pub fdp: u32,
+    pub fds: u16,
+    pub _reserved2: u16,
+    pub mxcsr: u32,
+    pub mxcsr_mask: u32,
+    /// The x87 registers, each in the low 10 bytes of its slot
+    pub st: [[u8; 16]; 8],
+    pub xmm: [[u8; 16]; 16],
+    pub _reserved3: [[u8; 16]; 6],
+}
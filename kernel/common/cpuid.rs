@@ -0,0 +1,131 @@
+use collections::Vec;
+
+/// The registers returned by `cpuid`
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CpuId {
+    pub eax: u32,
+    pub ebx: u32,
+    pub ecx: u32,
+    pub edx: u32,
+}
+
+/// Run `cpuid` on `leaf`, with `subleaf` in ECX
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub fn cpuid(leaf: u32, subleaf: u32) -> CpuId {
+    let mut id = CpuId::default();
+    unsafe {
+        asm!("cpuid"
+            : "={eax}"(id.eax), "={ebx}"(id.ebx), "={ecx}"(id.ecx), "={edx}"(id.edx)
+            : "{eax}"(leaf), "{ecx}"(subleaf)
+            :
+            : "volatile")
+    };
+    id
+}
+
+/// Run `cpuid` on `leaf`, which reads as all zeros without x86
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+pub fn cpuid(_leaf: u32, _subleaf: u32) -> CpuId {
+    CpuId::default()
+}
+
+/// A register returned by `cpuid`
+#[derive(Copy, Clone, Debug)]
+pub enum Register {
+    Ebx,
+    Ecx,
+    Edx,
+}
+
+/// A feature flag reported by `cpuid`
+pub struct Feature {
+    pub name: &'static str,
+    pub leaf: u32,
+    pub register: Register,
+    pub bit: u32,
+}
+
+macro_rules! feature {
+    ($name:expr, $leaf:expr, $register:ident, $bit:expr) => (
+        Feature {
+            name: $name,
+            leaf: $leaf,
+            register: Register::$register,
+            bit: $bit,
+        }
+    )
+}
+
+/// The feature flags which are detected
+pub static FEATURES: [Feature; 41] = [
+    feature!("fpu", 1, Edx, 0),
+    feature!("tsc", 1, Edx, 4),
+    feature!("msr", 1, Edx, 5),
+    feature!("pae", 1, Edx, 6),
+    feature!("apic", 1, Edx, 9),
+    feature!("sep", 1, Edx, 11),
+    feature!("mtrr", 1, Edx, 12),
+    feature!("pge", 1, Edx, 13),
+    feature!("cmov", 1, Edx, 15),
+    feature!("pat", 1, Edx, 16),
+    feature!("clflush", 1, Edx, 19),
+    feature!("mmx", 1, Edx, 23),
+    feature!("fxsr", 1, Edx, 24),
+    feature!("sse", 1, Edx, 25),
+    feature!("sse2", 1, Edx, 26),
+    feature!("htt", 1, Edx, 28),
+    feature!("sse3", 1, Ecx, 0),
+    feature!("pclmulqdq", 1, Ecx, 1),
+    feature!("ssse3", 1, Ecx, 9),
+    feature!("fma", 1, Ecx, 12),
+    feature!("cx16", 1, Ecx, 13),
+    feature!("sse4_1", 1, Ecx, 19),
+    feature!("sse4_2", 1, Ecx, 20),
+    feature!("x2apic", 1, Ecx, 21),
+    feature!("movbe", 1, Ecx, 22),
+    feature!("popcnt", 1, Ecx, 23),
+    feature!("aes", 1, Ecx, 25),
+    feature!("xsave", 1, Ecx, 26),
+    feature!("avx", 1, Ecx, 28),
+    feature!("f16c", 1, Ecx, 29),
+    feature!("rdrand", 1, Ecx, 30),
+    feature!("hypervisor", 1, Ecx, 31),
+    feature!("bmi1", 7, Ebx, 3),
+    feature!("avx2", 7, Ebx, 5),
+    feature!("bmi2", 7, Ebx, 8),
+    feature!("rdseed", 7, Ebx, 18),
+    feature!("adx", 7, Ebx, 19),
+    feature!("sha", 7, Ebx, 29),
+    feature!("syscall", 0x80000001, Edx, 11),
+    feature!("nx", 0x80000001, Edx, 20),
+    feature!("lm", 0x80000001, Edx, 29),
+];
+
+impl Feature {
+    /// Check if the processor has this feature
+    pub fn detect(&self) -> bool {
+        // The highest supported leaf of the basic or extended range
+        let max_leaf = cpuid(self.leaf & 0x80000000, 0).eax;
+        if self.leaf > max_leaf {
+            return false;
+        }
+
+        let id = cpuid(self.leaf, 0);
+        let value = match self.register {
+            Register::Ebx => id.ebx,
+            Register::Ecx => id.ecx,
+            Register::Edx => id.edx,
+        };
+        value & 1 << self.bit == 1 << self.bit
+    }
+}
+
+/// Check if the processor has the feature called `name`
+pub fn has_feature(name: &str) -> bool {
+    FEATURES.iter().any(|feature| feature.name == name && feature.detect())
+}
+
+/// The names of the features the processor has
+pub fn features() -> Vec<&'static str> {
+    FEATURES.iter().filter(|feature| feature.detect()).map(|feature| feature.name).collect()
+}
@@ -1,3 +1,5 @@
+/// CPU feature detection
+pub mod cpuid;
 /// Debug
 #[macro_use]
 pub mod debug;
@@ -1,19 +1,584 @@
-static mut seed: u64 = 19940046431; //259261034506304368955239; //1706322144714608529217229883707268827757977089;
+use core::{cmp, mem, usize};
+
+use common::cpuid;
+
+use system::error::{Error, Result, EINVAL};
+
+/// The number of rounds of the ChaCha20 block function
+const CHACHA_ROUNDS: usize = 20;
+
+/// The ChaCha constant, "expand 32-byte k"
+const CHACHA_CONSTANTS: [u32; 4] = [0x61707865, 0x3320646e, 0x79622d32, 0x6b206574];
+
+/// A ChaCha20 based cryptographically secure pseudorandom number generator
+pub struct Csprng {
+    /// The 256-bit key
+    key: [u32; 8],
+    /// The 64-bit nonce
+    nonce: [u32; 2],
+    /// The 64-bit block counter
+    counter: u64,
+    /// The current keystream block
+    block: [u8; 64],
+    /// The number of bytes of `block` that have been handed out
+    used: usize,
+}
+
+impl Csprng {
+    /// Create an unseeded generator
+    pub const fn new() -> Csprng {
+        Csprng {
+            key: [0; 8],
+            nonce: [0; 2],
+            counter: 0,
+            block: [0; 64],
+            used: 64,
+        }
+    }
+
+    /// Create a generator from an explicit key, counter and nonce
+    pub fn from_parts(key: [u32; 8], counter: u64, nonce: [u32; 2]) -> Csprng {
+        Csprng {
+            key: key,
+            nonce: nonce,
+            counter: counter,
+            block: [0; 64],
+            used: 64,
+        }
+    }
+
+    /// Mix seed material into the key
+    ///
+    /// The buffered keystream is discarded and a fresh key is derived, so that the new material
+    /// affects all following output.
+    pub fn seed(&mut self, seed: &[u8]) {
+        for (i, &b) in seed.iter().enumerate() {
+            self.key[(i / 4) % 8] ^= (b as u32) << ((i % 4) * 8);
+        }
+        self.refill();
+        for i in 0..self.key.len() {
+            self.key[i] ^= (self.block[i * 4] as u32) |
+                           (self.block[i * 4 + 1] as u32) << 8 |
+                           (self.block[i * 4 + 2] as u32) << 16 |
+                           (self.block[i * 4 + 3] as u32) << 24;
+        }
+        self.block = [0; 64];
+        self.used = self.block.len();
+    }
+
+    /// Replace the key with the next bytes of the keystream, erasing the buffered keystream
+    ///
+    /// This is fast key erasure: the old key can not be recovered from the new one, so no output
+    /// produced before can be recovered from the state of the generator.
+    pub fn erase(&mut self) {
+        self.refill();
+        for i in 0..self.key.len() {
+            self.key[i] = (self.block[i * 4] as u32) |
+                          (self.block[i * 4 + 1] as u32) << 8 |
+                          (self.block[i * 4 + 2] as u32) << 16 |
+                          (self.block[i * 4 + 3] as u32) << 24;
+        }
+        self.block = [0; 64];
+        self.used = self.block.len();
+    }
+
+    /// Compute the next keystream block
+    fn refill(&mut self) {
+        let mut state = [0u32; 16];
+        state[..4].copy_from_slice(&CHACHA_CONSTANTS);
+        state[4..12].copy_from_slice(&self.key);
+        state[12] = self.counter as u32;
+        state[13] = (self.counter >> 32) as u32;
+        state[14] = self.nonce[0];
+        state[15] = self.nonce[1];
+
+        let mut working = state;
+        for _ in 0..CHACHA_ROUNDS / 2 {
+            quarter_round(&mut working, 0, 4, 8, 12);
+            quarter_round(&mut working, 1, 5, 9, 13);
+            quarter_round(&mut working, 2, 6, 10, 14);
+            quarter_round(&mut working, 3, 7, 11, 15);
+            quarter_round(&mut working, 0, 5, 10, 15);
+            quarter_round(&mut working, 1, 6, 11, 12);
+            quarter_round(&mut working, 2, 7, 8, 13);
+            quarter_round(&mut working, 3, 4, 9, 14);
+        }
+
+        for i in 0..state.len() {
+            let word = working[i].wrapping_add(state[i]);
+            self.block[i * 4] = word as u8;
+            self.block[i * 4 + 1] = (word >> 8) as u8;
+            self.block[i * 4 + 2] = (word >> 16) as u8;
+            self.block[i * 4 + 3] = (word >> 24) as u8;
+        }
+
+        self.counter = self.counter.wrapping_add(1);
+        self.used = 0;
+    }
+
+    /// Fill `buf` with the next bytes of the keystream
+    pub fn next_block(&mut self, buf: &mut [u8]) {
+        for b in buf.iter_mut() {
+            if self.used >= self.block.len() {
+                self.refill();
+            }
+            *b = self.block[self.used];
+            self.used += 1;
+        }
+    }
+
+    /// Get the next pointer sized number
+    pub fn next_usize(&mut self) -> usize {
+        let mut bytes = [0; 8];
+        self.next_block(&mut bytes);
+
+        let mut value = 0;
+        for &b in bytes.iter().take(mem::size_of::<usize>()) {
+            value = value << 8 | b as usize;
+        }
+        value
+    }
+}
+
+fn quarter_round(state: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(16);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(12);
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(8);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(7);
+}
+
+/// The number of bits of entropy that must be credited before `random:` stops blocking
+pub const ENTROPY_THRESHOLD: usize = 128;
+
+/// The number of bytes added to the entropy pool before the CSPRNG is rekeyed
+pub const REKEY_BYTES: usize = 32;
+
+/// The number of requests served before the CSPRNG is rekeyed, unless the pool is told otherwise
+pub const REKEY_REQUESTS: usize = 1024;
+/// The highest number of requests which may be served between rekeys
+pub const REKEY_REQUESTS_MAX: usize = 1 << 20;
+
+/// The most bits of entropy credited to the interrupt samples waiting to be added to the pool
+const ENTROPY_SAMPLES: usize = 64;
+
+/// An entropy pool, feeding a CSPRNG
+///
+/// Entropy is collected in the pool, and only mixed into the CSPRNG when it is rekeyed, after
+/// either `REKEY_BYTES` bytes were added or `rekey_requests` requests were served. Every rekey also
+/// mixes in a number from `hw_random`, if there is one.
+pub struct EntropyPool {
+    /// The generator handing out random bytes
+    csprng: Csprng,
+    /// Entropy waiting to be mixed into the CSPRNG
+    pool: [u8; 32],
+    /// The number of bytes added to `pool` since the last rekey
+    pending: usize,
+    /// The number of bits credited to the bytes in `pool`
+    credit: usize,
+    /// The number of requests served since the last rekey
+    requests: usize,
+    /// The number of requests served before the CSPRNG is rekeyed
+    rekey_requests: usize,
+    /// The number of bits of entropy credited to the CSPRNG
+    entropy: usize,
+    /// The number of times the CSPRNG was rekeyed for a new context
+    generation: usize,
+    /// The number of bytes handed out
+    served: usize,
+    /// The number of times the pool was mixed into the CSPRNG
+    rekeys: usize,
+    /// The number of times the key of the CSPRNG was erased on request, see `erase`
+    erasures: usize,
+}
+
+/// Counters of an entropy pool, see `EntropyPool::stats`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EntropyStats {
+    /// The number of bits of entropy credited to the CSPRNG
+    pub entropy: usize,
+    /// The number of bytes handed out
+    pub served: usize,
+    /// The number of times the pool was mixed into the CSPRNG
+    pub rekeys: usize,
+    /// The number of times the CSPRNG was rekeyed for a new context
+    pub forks: usize,
+    /// The number of times the key of the CSPRNG was erased on request
+    pub erasures: usize,
+}
+
+impl EntropyPool {
+    /// Create an empty pool, with an unseeded generator
+    pub const fn new() -> EntropyPool {
+        EntropyPool {
+            csprng: Csprng::new(),
+            pool: [0; 32],
+            pending: 0,
+            credit: 0,
+            requests: 0,
+            rekey_requests: REKEY_REQUESTS,
+            entropy: 0,
+            generation: 0,
+            served: 0,
+            rekeys: 0,
+            erasures: 0,
+        }
+    }
+
+    /// Add `data` to the pool, crediting it with `credit` bits of entropy
+    pub fn add_entropy(&mut self, data: &[u8], credit: usize) {
+        self.credit = self.credit.saturating_add(credit);
+        for &b in data.iter() {
+            self.pool[self.pending % self.pool.len()] ^= b;
+            self.pending += 1;
+            if self.pending >= REKEY_BYTES {
+                self.rekey();
+            }
+        }
+    }
+
+    /// Fill `buf` with random bytes
+    pub fn fill(&mut self, buf: &mut [u8]) {
+        self.csprng.next_block(buf);
+        self.served = self.served.saturating_add(buf.len());
+        self.requests += 1;
+        if self.requests >= self.rekey_requests {
+            self.rekey();
+        }
+    }
+
+    /// Mix the pool into the CSPRNG
+    pub fn rekey(&mut self) {
+        if let Some(hw) = hw_random() {
+            for j in 0..8 {
+                self.pool[j] ^= (hw >> (j * 8)) as u8;
+            }
+        }
+
+        self.csprng.seed(&self.pool);
+        self.rekeys = self.rekeys.saturating_add(1);
+        self.entropy = self.entropy.saturating_add(self.credit);
+        self.pool = [0; 32];
+        self.pending = 0;
+        self.credit = 0;
+        self.requests = 0;
+    }
+
+    /// Rekey the CSPRNG for a new context, mixing in its PID and the fork generation
+    ///
+    /// The buffered keystream is erased, so no bytes generated before the fork are handed out
+    /// after it, and the stream which follows depends on the PID of the new context.
+    pub fn fork(&mut self, pid: usize) {
+        self.generation = self.generation.wrapping_add(1);
+
+        let mut seed = [0; 16];
+        for i in 0..mem::size_of::<usize>() {
+            seed[i] = (pid >> (i * 8)) as u8;
+            seed[i + 8] = (self.generation >> (i * 8)) as u8;
+        }
+        self.csprng.seed(&seed);
+    }
+
+    /// Rekey the CSPRNG at once, and erase its key
+    ///
+    /// The pending entropy is mixed in, and then the key is replaced with keystream, see
+    /// `Csprng::erase`, so no output produced before can be recovered from the state which
+    /// follows. Returns the number of erasures so far, including this one.
+    pub fn erase(&mut self) -> usize {
+        self.rekey();
+        self.csprng.erase();
+        self.erasures = self.erasures.wrapping_add(1);
+        self.erasures
+    }
+
+    /// The number of bits of entropy credited to the CSPRNG so far
+    pub fn entropy(&self) -> usize {
+        self.entropy
+    }
+
+    /// The number of requests served before the CSPRNG is rekeyed
+    pub fn rekey_requests(&self) -> usize {
+        self.rekey_requests
+    }
+
+    /// Rekey the CSPRNG after every `requests` requests
+    ///
+    /// Returns `EINVAL` if `requests` is 0 or above `REKEY_REQUESTS_MAX`.
+    pub fn set_rekey_requests(&mut self, requests: usize) -> Result<()> {
+        if requests == 0 || requests > REKEY_REQUESTS_MAX {
+            return Err(Error::new(EINVAL));
+        }
+
+        self.rekey_requests = requests;
+        if self.requests >= requests {
+            self.rekey();
+        }
+        Ok(())
+    }
+
+    /// The counters of the pool
+    pub fn stats(&self) -> EntropyStats {
+        EntropyStats {
+            entropy: self.entropy,
+            served: self.served,
+            rekeys: self.rekeys,
+            forks: self.generation,
+            erasures: self.erasures,
+        }
+    }
+}
+
+/// The kernel entropy pool
+///
+/// Like the rest of the kernel state, this is only touched from kernel code with interrupts
+/// disabled.
+static mut POOL: EntropyPool = EntropyPool::new();
+
+/// Interrupt timing samples waiting to be added to the entropy pool
+static mut SAMPLES: [u8; 32] = [0; 32];
+/// The number of samples in `SAMPLES`
+static mut SAMPLE_COUNT: usize = 0;
+/// The number of bits credited to the samples in `SAMPLES`
+static mut SAMPLE_CREDIT: usize = 0;
+/// The timestamp of the last interrupt
+static mut LAST_TSC: u64 = 0;
+/// The time between the last two interrupts
+static mut LAST_DELTA: u64 = 0;
+/// Whether the processor supports `rdrand`, detected in `init`
+static mut RDRAND: bool = false;
+
+/// The number of times to retry `rdrand` before giving up
+const RDRAND_RETRIES: usize = 10;
+
+/// Read the timestamp counter
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub fn rdtsc() -> u64 {
+    let low: u32;
+    let high: u32;
+    unsafe { asm!("rdtsc" : "={eax}"(low), "={edx}"(high) : : : "volatile") };
+    (high as u64) << 32 | low as u64
+}
+
+/// Read the timestamp counter
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+pub fn rdtsc() -> u64 {
+    0
+}
+
+/// Issue `rdrand`, retrying while the carry flag reports that no number was ready
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn rdrand() -> Option<usize> {
+    for _ in 0..RDRAND_RETRIES {
+        let value: usize;
+        let ok: u8;
+        unsafe {
+            asm!("rdrand $0
+                setc $1"
+                : "=r"(value), "=r"(ok) : : "cc" : "intel", "volatile")
+        };
+        if ok != 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// Get a random number from the hardware random number generator
+///
+/// Returns `None` if the processor has no `rdrand`, or it failed to produce a number.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub fn hw_random() -> Option<u64> {
+    if !unsafe { RDRAND } {
+        return None;
+    }
+
+    let mut value = 0;
+    let mut bits = 0;
+    while bits < 64 {
+        if let Some(part) = rdrand() {
+            value |= (part as u64) << bits;
+        } else {
+            return None;
+        }
+        bits += mem::size_of::<usize>() * 8;
+    }
+    Some(value)
+}
+
+/// Get a random number from the hardware random number generator
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+pub fn hw_random() -> Option<u64> {
+    None
+}
+
+/// Seed the kernel CSPRNG
+///
+/// This is called once at boot, after the real time clock has been read. When the processor has
+/// a hardware random number generator, it provides the seed, and is trusted enough to unblock
+/// `random:` immediately. Otherwise, the seed is scraped from the timestamp counter and the
+/// clocks, which is weak, but the best we have this early.
+pub fn init() {
+    unsafe { RDRAND = cpuid::has_feature("rdrand") };
+
+    let realtime = unsafe { *::env().clock_realtime.get() };
+    let monotonic = unsafe { *::env().clock_monotonic.get() };
+
+    let mut words = [rdtsc(), realtime.secs as u64, realtime.nanos as u64, monotonic.nanos as u64];
+    let mut credit = 0;
+    for word in words.iter_mut() {
+        if let Some(hw) = hw_random() {
+            *word ^= hw;
+            credit += 64;
+        }
+    }
+
+    let mut seed = [0; 32];
+    for (i, word) in words.iter().enumerate() {
+        for j in 0..8 {
+            seed[i * 8 + j] = (word >> (j * 8)) as u8;
+        }
+    }
+
+    unsafe { POOL.add_entropy(&seed, credit) };
+}
+
+/// Add the interrupt samples gathered so far to the entropy pool
+unsafe fn fold_samples() {
+    if SAMPLE_COUNT > 0 {
+        let len = cmp::min(SAMPLE_COUNT * 2, SAMPLES.len());
+        POOL.add_entropy(&SAMPLES[..len], SAMPLE_CREDIT);
+        SAMPLES = [0; 32];
+        SAMPLE_COUNT = 0;
+        SAMPLE_CREDIT = 0;
+    }
+}
+
+/// Gather timing jitter from an interrupt
+///
+/// Every interrupt mixes the low bits of the change in time between interrupts into a buffer of
+/// samples, and is credited with at most one bit of entropy, up to `ENTROPY_SAMPLES` bits. Once
+/// the buffer is full, new samples are mixed over the old ones. The samples are only added to the
+/// entropy pool outside of interrupts, when the pool is read or its entropy is checked, so an
+/// interrupt never rekeys the CSPRNG or wakes anyone.
+pub fn add_interrupt_entropy(interrupt: usize) {
+    unsafe {
+        let tsc = rdtsc();
+        let delta = tsc.wrapping_sub(LAST_TSC);
+        let jitter = delta ^ LAST_DELTA;
+        LAST_TSC = tsc;
+        LAST_DELTA = delta;
+
+        let i = (SAMPLE_COUNT * 2) % SAMPLES.len();
+        SAMPLES[i] ^= jitter as u8 ^ interrupt as u8;
+        SAMPLES[i + 1] ^= (jitter >> 8) as u8;
+        SAMPLE_COUNT = SAMPLE_COUNT.wrapping_add(1);
+        if jitter != 0 && SAMPLE_CREDIT < ENTROPY_SAMPLES {
+            SAMPLE_CREDIT += 1;
+        }
+    }
+}
+
+/// Add `data` to the kernel entropy pool
+///
+/// No entropy is credited, as the source of the data is not trusted.
+pub fn add_entropy(data: &[u8]) {
+    unsafe { POOL.add_entropy(data, 0) };
+}
+
+/// The number of bits of entropy credited to the kernel CSPRNG so far
+///
+/// The interrupt samples gathered since the last fill are folded into the pool first.
+pub fn entropy() -> usize {
+    unsafe {
+        fold_samples();
+        POOL.entropy()
+    }
+}
+
+/// Whether `ENTROPY_THRESHOLD` bits of entropy have been credited to the kernel CSPRNG
+pub fn ready() -> bool {
+    entropy() >= ENTROPY_THRESHOLD
+}
+
+/// The counters of the kernel entropy pool
+pub fn stats() -> EntropyStats {
+    unsafe { POOL.stats() }
+}
+
+/// The number of requests the kernel CSPRNG serves before it is rekeyed
+pub fn rekey_requests() -> usize {
+    unsafe { POOL.rekey_requests() }
+}
+
+/// Rekey the kernel CSPRNG after every `requests` requests, see `EntropyPool::set_rekey_requests`
+pub fn set_rekey_requests(requests: usize) -> Result<()> {
+    unsafe { POOL.set_rekey_requests(requests) }
+}
+
+/// Rekey the kernel CSPRNG and erase its key, see `EntropyPool::erase`
+///
+/// The interrupt samples gathered since the last fill are folded into the pool first. Returns the
+/// number of erasures so far.
+pub fn erase() -> usize {
+    unsafe {
+        fold_samples();
+        POOL.erase()
+    }
+}
+
+/// Rekey the kernel CSPRNG when the context `pid` is created, see `EntropyPool::fork`
+pub fn fork(pid: usize) {
+    unsafe { POOL.fork(pid) };
+}
+
+/// Fill `buf` with random bytes from the kernel entropy pool
+///
+/// The interrupt samples gathered since the last fill are folded into the pool first.
+pub fn fill(buf: &mut [u8]) {
+    unsafe {
+        fold_samples();
+        POOL.fill(buf);
+    }
+}
 
 /// Generate pseudo random number
 pub fn rand() -> usize {
-    unsafe {
-        seed ^= seed << 12;
-        seed ^= seed << 25;
-        seed ^= seed << 27;
-        seed = seed * 82724793451 + 12345;
-        seed as usize % ::core::usize::MAX
+    let mut bytes = [0; 8];
+    fill(&mut bytes[..mem::size_of::<usize>()]);
+
+    let mut value = 0;
+    for &b in bytes.iter().take(mem::size_of::<usize>()) {
+        value = value << 8 | b as usize;
     }
+    value
 }
 
-/// Set the seed
+/// Generate a random number uniformly distributed in `[low, high]`
+///
+/// Draws that would bias the result towards the start of the range are rejected and redrawn.
+pub fn rand_range(low: usize, high: usize) -> usize {
+    let range = high.wrapping_sub(low).wrapping_add(1);
+    if range == 0 {
+        return rand();
+    }
+
+    let limit = usize::MAX - (usize::MAX % range + 1) % range;
+    loop {
+        let value = rand();
+        if value <= limit {
+            return low + value % range;
+        }
+    }
+}
+
+/// Add `s` to the kernel entropy pool
 pub fn srand(s: usize) {
-    unsafe {
-        seed = s as u64;
+    let mut seed = [0; 8];
+    for (i, b) in seed.iter_mut().enumerate().take(mem::size_of::<usize>()) {
+        *b = (s >> (i * 8)) as u8;
     }
+    add_entropy(&seed);
 }
@@ -1,12 +1,82 @@
+use collections::{String, Vec};
+
+use core::{char, str};
+
+use system::error::{Error, Result, EINVAL};
+
 /// Public keyboard layouts
 /// The layout can be:
 /// *   English
 /// *   French
 /// *   German
+/// *   A keymap loaded at runtime
 pub enum Layout {
     English,
     French,
     German,
+    Custom(Keymap),
+}
+
+impl Layout {
+    /// The name of the layout
+    pub fn name(&self) -> &str {
+        match *self {
+            Layout::English => "English",
+            Layout::French => "French",
+            Layout::German => "German",
+            Layout::Custom(ref keymap) => &keymap.name,
+        }
+    }
+}
+
+/// The size of the name at the start of a keymap blob
+pub const KEYMAP_NAME_SIZE: usize = 32;
+
+/// The size of a keymap blob
+///
+/// The name is followed by the normal, shift and AltGr characters of each of the 58 scancodes of
+/// a layout, as little endian `u32` code points.
+pub const KEYMAP_SIZE: usize = KEYMAP_NAME_SIZE + 58 * 3 * 4;
+
+/// A keyboard layout loaded at runtime
+pub struct Keymap {
+    name: String,
+    scancodes: Vec<[char; 3]>,
+}
+
+impl Keymap {
+    /// Parse a keymap blob, see `KEYMAP_SIZE`
+    ///
+    /// The name is UTF-8, padded with NULs, and must not be empty. Returns `EINVAL` if the blob has
+    /// the wrong size, or if the name or a character is not valid.
+    pub fn parse(blob: &[u8]) -> Result<Keymap> {
+        if blob.len() != KEYMAP_SIZE {
+            return Err(Error::new(EINVAL));
+        }
+
+        let name = &blob[..KEYMAP_NAME_SIZE];
+        let name_len = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+        let name = try!(str::from_utf8(&name[..name_len]).or(Err(Error::new(EINVAL))));
+        if name.is_empty() {
+            return Err(Error::new(EINVAL));
+        }
+
+        let mut scancodes = Vec::new();
+        for chunk in blob[KEYMAP_NAME_SIZE..].chunks(3 * 4) {
+            let mut characters = ['\0'; 3];
+            for (character, bytes) in characters.iter_mut().zip(chunk.chunks(4)) {
+                let code = bytes[0] as u32 | (bytes[1] as u32) << 8 | (bytes[2] as u32) << 16 |
+                           (bytes[3] as u32) << 24;
+                *character = try!(char::from_u32(code).ok_or(Error::new(EINVAL)));
+            }
+            scancodes.push(characters);
+        }
+
+        Ok(Keymap {
+            name: String::from(name),
+            scancodes: scancodes,
+        })
+    }
 }
 
 /// Function to get the scancode from the current layout
@@ -23,6 +93,7 @@ pub fn get_scancode_from_layout(layout: &Layout, scancode: u8) -> [char; 3] {
         Layout::English => SCANCODES_EN[scancode as usize],
         Layout::French => SCANCODES_FR[scancode as usize],
         Layout::German => SCANCODES_DE[scancode as usize],
+        Layout::Custom(ref keymap) => keymap.scancodes[scancode as usize],
     }
 }
 
@@ -31,6 +102,7 @@ fn get_special_keys_from_layout(layout: &Layout, scancode: u8) -> [char; 3] {
         Layout::English => SCANCODES_EXTRA_EN,
         Layout::French => SCANCODES_EXTRA_FR,
         Layout::German => SCANCODES_EXTRA_DE,
+        Layout::Custom(_) => &[],
     };
     match keys.iter().filter(|&&(code, _)| code == scancode).next() {
         Some(&(_, keys)) => keys,
@@ -2,6 +2,8 @@
 pub mod io;
 /// PCI
 pub mod pci;
+/// PIT
+pub mod pit;
 /// PS2
 pub mod ps2;
 /// RTC
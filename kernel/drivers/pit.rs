@@ -0,0 +1,83 @@
+use common::time::{Duration, NANOS_PER_SEC};
+
+use drivers::io::{Io, Pio};
+
+use system::error::{Error, Result, EINVAL};
+
+/// The frequency of the oscillator driving the PIT, in Hz
+pub const PIT_OSCILLATOR: u64 = 1193182;
+/// The divisor the PIT is set up with at boot, see `asm/initialize.asm`
+pub const PIT_DIVISOR_DEFAULT: u16 = 5370;
+/// The lowest tick frequency which can be set, in Hz
+pub const PIT_HZ_MIN: usize = 20;
+/// The highest tick frequency which can be set, in Hz
+pub const PIT_HZ_MAX: usize = 1000;
+
+/// The PIT (programmable interval timer), which raises interrupt 0x20 on every tick
+pub struct Pit {
+    command: Pio<u8>,
+    data: Pio<u8>,
+    divisor: u16,
+    /// The time between ticks, which is added to the clocks on every tick
+    duration: Duration,
+}
+
+impl Pit {
+    /// The PIT, as set up at boot
+    pub fn new() -> Self {
+        Pit {
+            command: Pio::<u8>::new(0x43),
+            data: Pio::<u8>::new(0x40),
+            divisor: PIT_DIVISOR_DEFAULT,
+            duration: Pit::duration_of(PIT_DIVISOR_DEFAULT),
+        }
+    }
+
+    /// The time between ticks with `divisor`, rounded down to nanoseconds
+    fn duration_of(divisor: u16) -> Duration {
+        let nanos = divisor as u64 * NANOS_PER_SEC as u64 / PIT_OSCILLATOR;
+        Duration::new((nanos / NANOS_PER_SEC as u64) as i64,
+                      (nanos % NANOS_PER_SEC as u64) as i32)
+    }
+
+    /// The time between ticks
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// The divisor of the oscillator
+    pub fn divisor(&self) -> u16 {
+        self.divisor
+    }
+
+    /// The tick frequency, rounded to Hz
+    pub fn frequency(&self) -> usize {
+        ((PIT_OSCILLATOR + self.divisor as u64 / 2) / self.divisor as u64) as usize
+    }
+
+    /// Reprogram the PIT to tick once every `divisor` periods of the oscillator
+    ///
+    /// The clocks advance by the new duration from the next tick on, so they stay consistent.
+    pub fn set_divisor(&mut self, divisor: u16) {
+        // Channel 0, low byte then high byte, as a square wave generator, like the BIOS sets it up
+        self.command.write(0x36);
+        self.data.write(divisor as u8);
+        self.data.write((divisor >> 8) as u8);
+
+        self.divisor = divisor;
+        self.duration = Pit::duration_of(divisor);
+    }
+
+    /// Reprogram the PIT to the divisor closest to `hz`
+    ///
+    /// Returns `EINVAL` unless `hz` is within `PIT_HZ_MIN` and `PIT_HZ_MAX`.
+    pub fn set_frequency(&mut self, hz: usize) -> Result<()> {
+        if hz < PIT_HZ_MIN || hz > PIT_HZ_MAX {
+            return Err(Error::new(EINVAL));
+        }
+
+        let divisor = (PIT_OSCILLATOR + hz as u64 / 2) / hz as u64;
+        self.set_divisor(divisor as u16);
+        Ok(())
+    }
+}
@@ -69,9 +69,6 @@ pub struct Ps2 {
     mouse_x: i32,
     /// Mouse point y
     mouse_y: i32,
-    /// Layout for keyboard
-    /// Default: English
-    layout: layouts::Layout,
 }
 
 impl Ps2 {
@@ -91,7 +88,6 @@ impl Ps2 {
             mouse_i: 0,
             mouse_x: 0,
             mouse_y: 0,
-            layout: layouts::Layout::English,
         };
 
         module.init();
@@ -309,7 +305,10 @@ impl Ps2 {
 
 
         Some(KeyEvent {
-            character: layouts::char_for_scancode(scancode & 0x7F, shift, self.altgr, &self.layout),
+            character: layouts::char_for_scancode(scancode & 0x7F,
+                                                  shift,
+                                                  self.altgr,
+                                                  &unsafe { & *::env().console.get() }.layout),
             scancode: scancode & 0x7F,
             pressed: scancode < 0x80,
         })
@@ -370,7 +369,7 @@ impl Ps2 {
 
     /// Function to change the layout of the keyboard
     pub fn change_layout(&mut self, layout: usize) {
-        self.layout = match layout {
+        unsafe { &mut *::env().console.get() }.layout = match layout {
             0 => layouts::Layout::English,
             1 => layouts::Layout::French,
             2 => layouts::Layout::German,
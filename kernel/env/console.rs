@@ -5,21 +5,187 @@ use alloc::boxed::Box;
 use collections::String;
 
 use common::debug::SerialConsole;
-use common::event::{self, Event, EventOption};
+use collections::Vec;
+
+use common::event::{self, Event, EventOption, KeyEvent};
 
 use core::mem;
 
+use drivers::kb_layouts::layouts::Layout;
+
 use graphics::color::Color;
 use graphics::display::Display;
 
-use sync::WaitQueue;
+use sync::{ByteQueue, WaitQueue};
+
+/// The number of bytes of input which can be queued for `console:`, and the longest line
+pub const TTY_CAPACITY: usize = 4096;
+
+/// The size of the console window, see `CONSOLE_GETWINSZ`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct WinSize {
+    pub rows: usize,
+    pub columns: usize,
+    /// The width in pixels
+    pub width: usize,
+    /// The height in pixels
+    pub height: usize,
+}
+
+impl WinSize {
+    /// The size of a text mode screen, of 80 by 25 characters of 8 by 16 pixels
+    pub fn new() -> WinSize {
+        WinSize {
+            rows: 25,
+            columns: 80,
+            width: 640,
+            height: 400,
+        }
+    }
+
+    /// The size of a display, filled with characters of 8 by 16 pixels
+    pub fn of_display(display: &Display) -> WinSize {
+        WinSize {
+            rows: display.height / 16,
+            columns: display.width / 8,
+            width: display.width,
+            height: display.height,
+        }
+    }
+}
+
+/// The line discipline of `console:`
+pub struct Tty {
+    /// Input ready to be read
+    pub input: ByteQueue,
+    /// The line being edited in canonical mode
+    line: Vec<u8>,
+    /// Whether input is read as it is typed, instead of a line at a time
+    pub raw: bool,
+    /// Whether input is echoed to the display
+    pub echo: bool,
+    /// The number of open `console:` resources
+    pub open: usize,
+    /// The context which last opened `console:`, which is told when the window is resized
+    pub foreground: usize,
+}
+
+impl Tty {
+    pub fn new() -> Tty {
+        Tty {
+            input: ByteQueue::new(TTY_CAPACITY),
+            line: Vec::new(),
+            raw: false,
+            echo: true,
+            open: 0,
+            foreground: 0,
+        }
+    }
+
+    /// Handle typed bytes
+    ///
+    /// In canonical mode, a line is only queued once it ends with a newline, and backspace or
+    /// delete erase the last byte of the line, echoing a backspace, a space and a backspace to wipe
+    /// it from the screen. In raw mode, every byte is queued as it is. Input which does not fit is
+    /// dropped. Returns the bytes to echo.
+    pub fn input(&mut self, bytes: &[u8]) -> Vec<u8> {
+        let mut echo = Vec::new();
+        for &b in bytes.iter() {
+            if self.raw {
+                if self.input.try_write_bytes(&[b]) > 0 {
+                    echo.push(b);
+                }
+            } else if b == 8 || b == 0x7F {
+                if self.line.pop().is_some() {
+                    echo.extend_from_slice(b"\x08 \x08");
+                }
+            } else if b == b'\n' {
+                self.line.push(b);
+                echo.push(b);
+                let line = mem::replace(&mut self.line, Vec::new());
+                self.input.try_write_bytes(&line);
+            } else if self.line.len() < TTY_CAPACITY - 1 {
+                self.line.push(b);
+                echo.push(b);
+            }
+        }
+
+        if self.echo {
+            echo
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Switch between raw and canonical mode
+    ///
+    /// The line being edited is queued as it is when switching to raw mode.
+    pub fn set_raw(&mut self, raw: bool) {
+        if raw && !self.raw {
+            let line = mem::replace(&mut self.line, Vec::new());
+            self.input.try_write_bytes(&line);
+        }
+        self.raw = raw;
+    }
+
+    /// Read queued input, without blocking
+    ///
+    /// In canonical mode, this stops after the end of a line.
+    pub fn try_read(&mut self, buf: &mut [u8]) -> usize {
+        if self.raw {
+            return self.input.try_read_bytes(buf);
+        }
+
+        let mut i = 0;
+        while i < buf.len() {
+            if self.input.try_read_bytes(&mut buf[i..i + 1]) == 0 {
+                break;
+            }
+            i += 1;
+            if buf[i - 1] == b'\n' {
+                break;
+            }
+        }
+        i
+    }
+}
+
+/// The bytes typed with a key, using VT100 escapes for the keys without a character
+fn key_string(key_event: &KeyEvent) -> String {
+    match key_event.scancode {
+        event::K_BKSP => "\x7F".into(),
+        event::K_UP => "\x1B[A".into(),
+        event::K_DOWN => "\x1B[B".into(),
+        event::K_RIGHT => "\x1B[C".into(),
+        event::K_LEFT => "\x1B[D".into(),
+        event::K_HOME => "\x1B[H".into(),
+        event::K_END => "\x1B[F".into(),
+        event::K_DEL => "\x1B[3~".into(),
+        event::K_PGUP => "\x1B[5~".into(),
+        event::K_PGDN => "\x1B[6~".into(),
+        _ => match key_event.character {
+            '\0' => String::new(),
+            c => {
+                let mut string = String::new();
+                string.push(c);
+                string
+            }
+        },
+    }
+}
 
 pub struct Console {
     pub display: Option<Box<Display>>,
     pub inner: Option<ransid::Console>,
     pub draw: bool,
     pub command: String,
-    pub commands: WaitQueue<String>
+    pub commands: WaitQueue<String>,
+    /// Takes the keyboard input while `console:` is open
+    pub tty: Tty,
+    /// The keyboard layout, which can be loaded through `console:keymap`
+    pub layout: Layout,
+    /// The size of the window reported to programs, which they can change through `console:`
+    pub size: WinSize,
 }
 
 impl Console {
@@ -30,19 +196,34 @@ impl Console {
         } else {
             None
         };
+        let size = match display_option {
+            Some(ref display) => WinSize::of_display(display),
+            None => WinSize::new(),
+        };
         Console {
             display: display_option,
             inner: inner_option,
             draw: false,
             command: String::new(),
-            commands: WaitQueue::new()
+            commands: WaitQueue::new(),
+            tty: Tty::new(),
+            layout: Layout::English,
+            size: size,
         }
     }
 
     pub fn event(&mut self, event: Event) {
         match event.to_option() {
             EventOption::Key(key_event) => {
-                if key_event.pressed {
+                if key_event.pressed && self.tty.open > 0 {
+                    let echo = self.tty.input(key_string(&key_event).as_bytes());
+                    if !echo.is_empty() {
+                        if let Some(ref mut inner) = self.inner {
+                            inner.redraw = true;
+                        }
+                        self.write(&echo);
+                    }
+                } else if key_event.pressed {
                     let raw_mode = if let Some(ref inner) = self.inner {
                         inner.raw_mode
                     } else {
@@ -50,24 +231,7 @@ impl Console {
                     };
 
                     if raw_mode {
-                        match key_event.scancode {
-                            event::K_BKSP => self.command.push_str("\x7F"),
-                            event::K_UP => self.command.push_str("\x1B[A"),
-                            event::K_DOWN => self.command.push_str("\x1B[B"),
-                            event::K_RIGHT => self.command.push_str("\x1B[C"),
-                            event::K_LEFT => self.command.push_str("\x1B[D"),
-                            event::K_HOME => self.command.push_str("\x1B[H"),
-                            event::K_END => self.command.push_str("\x1B[F"),
-                            event::K_DEL => self.command.push_str("\x1B[3~"),
-                            event::K_PGUP => self.command.push_str("\x1B[5~"),
-                            event::K_PGDN => self.command.push_str("\x1B[6~"),
-                            _ => match key_event.character {
-                                '\0' => {},
-                                c => {
-                                    self.command.push(c);
-                                }
-                            },
-                        }
+                        self.command.push_str(&key_string(&key_event));
 
                         if ! self.command.is_empty() {
                             let mut command = String::new();
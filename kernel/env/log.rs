@@ -3,7 +3,9 @@ use core::fmt;
 pub struct Log {
     pub data: [u8; 65536],
     pub start: usize,
-    pub end: usize
+    pub end: usize,
+    /// The number of bytes dropped from the start of the log so far
+    pub dropped: usize
 }
 
 impl Log {
@@ -11,12 +13,14 @@ impl Log {
         Log {
             data: [0; 65536],
             start: 0,
-            end: 0
+            end: 0,
+            dropped: 0
         }
     }
 
     fn move_start(&mut self) {
         self.start += 1;
+        self.dropped += 1;
         while self.start >= self.data.len() {
             self.start -= self.data.len();
         }
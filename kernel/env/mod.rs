@@ -1,19 +1,27 @@
+use alloc::arc::Weak;
 use alloc::boxed::Box;
 
 use collections::string::{String, ToString};
 use collections::vec::Vec;
 
 use core::cell::UnsafeCell;
+use core::slice;
 
-use arch::context::ContextManager;
+use arch::context::{ContextManager, CONTEXT_FILE_LIMIT};
 use common::event::Event;
 use common::time::Duration;
 use disk::Disk;
+use drivers::pit::Pit;
 use network::Nic;
+use schemes::faults::Faults;
+use schemes::futex::Futexes;
+use schemes::sysinfo::Load;
+use schemes::trace::Trace;
+use schemes::watchdog::Watchdog;
 use fs::{KScheme, Resource, Scheme, VecResource, Url};
-use sync::WaitQueue;
+use sync::{WaitCondition, WaitQueue};
 
-use system::error::{Error, Result, ENOENT, EEXIST};
+use system::error::{Error, Result, EEXIST, EINVAL, ELOOP, ENODEV, ENOENT};
 use system::syscall::{O_CREAT, Stat};
 
 use self::console::Console;
@@ -34,6 +42,8 @@ pub struct Environment {
     pub clock_realtime: UnsafeCell<Duration>,
     /// Monotonic clock
     pub clock_monotonic: UnsafeCell<Duration>,
+    /// The timer which advances the clocks, see `schemes::timer`
+    pub pit: UnsafeCell<Pit>,
 
     /// Default console
     pub console: UnsafeCell<Console>,
@@ -41,12 +51,40 @@ pub struct Environment {
     pub disks: UnsafeCell<Vec<Box<Disk>>>,
     /// Network interfaces
     pub nics: UnsafeCell<Vec<Box<Nic>>>,
+    /// The limit on open files given to new root contexts, see `schemes::kparam`
+    pub file_limit: UnsafeCell<usize>,
     /// Pending events
     pub events: WaitQueue<Event>,
+    /// Contexts waiting on futexes, see `schemes::futex`
+    pub futexes: Futexes,
+    /// The hostname
+    pub hostname: UnsafeCell<String>,
+    /// Contexts waiting for an IRQ, see `schemes::irq`
+    pub irq: WaitCondition,
     /// Kernel logs
     pub log: UnsafeCell<Log>,
+    /// Contexts waiting for new kernel log messages
+    pub log_written: WaitCondition,
+    /// Contexts waiting for free memory to drop, see `memory::watch_pressure`
+    pub memory_pressure: WaitCondition,
     /// Schemes
     pub schemes: UnsafeCell<Vec<Box<KScheme>>>,
+    /// Names of deregistered schemes
+    pub removed_schemes: UnsafeCell<Vec<String>>,
+    /// Scheme aliases, from the alias to the name it stands for
+    pub scheme_aliases: UnsafeCell<Vec<(String, String)>>,
+    /// Armed watchdogs, checked on every tick of the PIT
+    pub watchdogs: UnsafeCell<Vec<Weak<UnsafeCell<Watchdog>>>>,
+    /// Ticks of the PIT since boot
+    pub ticks: UnsafeCell<u64>,
+    /// Contexts waiting for the next tick of the PIT, such as those of `fs::RateLimited`
+    pub tick: WaitCondition,
+    /// Runnable contexts counted on recent ticks of the PIT, see `schemes::sysinfo`
+    pub load: UnsafeCell<Load>,
+    /// Recorded kernel events, see `schemes::trace`
+    pub trace: UnsafeCell<Trace>,
+    /// Faults which killed a context, see `schemes::faults`
+    pub faults: UnsafeCell<Faults>,
 
     /// Interrupt stats
     pub interrupts: UnsafeCell<[u64; 256]>,
@@ -59,13 +97,28 @@ impl Environment {
 
             clock_realtime: UnsafeCell::new(Duration::new(0, 0)),
             clock_monotonic: UnsafeCell::new(Duration::new(0, 0)),
+            pit: UnsafeCell::new(Pit::new()),
 
             console: UnsafeCell::new(Console::new()),
             disks: UnsafeCell::new(Vec::new()),
             nics: UnsafeCell::new(Vec::new()),
+            file_limit: UnsafeCell::new(CONTEXT_FILE_LIMIT),
             events: WaitQueue::new(),
+            futexes: Futexes::new(),
+            hostname: UnsafeCell::new("redox".to_string()),
+            irq: WaitCondition::new(),
             log: UnsafeCell::new(Log::new()),
+            log_written: WaitCondition::new(),
+            memory_pressure: WaitCondition::new(),
             schemes: UnsafeCell::new(Vec::new()),
+            removed_schemes: UnsafeCell::new(Vec::new()),
+            scheme_aliases: UnsafeCell::new(Vec::new()),
+            watchdogs: UnsafeCell::new(Vec::new()),
+            ticks: UnsafeCell::new(0),
+            tick: WaitCondition::new(),
+            load: UnsafeCell::new(Load::new()),
+            trace: UnsafeCell::new(Trace::new()),
+            faults: UnsafeCell::new(Faults::new()),
 
             interrupts: UnsafeCell::new([0; 256]),
         }
@@ -77,50 +130,159 @@ impl Environment {
         }
     }
 
+    /// The registered schemes
+    pub fn schemes(&self) -> slice::Iter<Box<KScheme>> {
+        unsafe { &*self.schemes.get() }.iter()
+    }
+
+    /// Register a scheme
+    ///
+    /// Returns `EEXIST` if a scheme or an alias with the same name is already registered.
+    pub fn register_scheme(&self, scheme: Box<KScheme>) -> Result<()> {
+        if self.schemes().any(|other| other.scheme() == scheme.scheme()) ||
+           self.aliases().any(|&(ref alias, _)| alias == scheme.scheme()) {
+            return Err(Error::new(EEXIST));
+        }
+
+        unsafe { &mut *self.removed_schemes.get() }.retain(|name| name != scheme.scheme());
+        unsafe { &mut *self.schemes.get() }.push(scheme);
+        Ok(())
+    }
+
+    /// Deregister the scheme named `name`
+    ///
+    /// Opening the scheme afterwards returns `ENODEV`, until it is registered again. Returns
+    /// `ENOENT` if no scheme is registered with that name.
+    pub fn deregister_scheme(&self, name: &str) -> Result<()> {
+        let schemes = unsafe { &mut *self.schemes.get() };
+        if let Some(i) = schemes.iter().position(|scheme| scheme.scheme() == name) {
+            unsafe { &mut *self.removed_schemes.get() }.push(name.to_string());
+            schemes.remove(i);
+            Ok(())
+        } else {
+            Err(Error::new(ENOENT))
+        }
+    }
+
+    /// The scheme aliases, from the alias to the name it stands for
+    pub fn aliases(&self) -> slice::Iter<(String, String)> {
+        unsafe { &*self.scheme_aliases.get() }.iter()
+    }
+
+    /// Make opening the scheme `from` open the scheme `to` instead
+    ///
+    /// `to` may be another alias, or a scheme which is not registered yet. Returns `EINVAL` if
+    /// either name is empty, `EEXIST` if `from` is a registered scheme or alias, and `ELOOP` if
+    /// `to` leads back to `from`.
+    pub fn alias_scheme(&self, from: &str, to: &str) -> Result<()> {
+        if from.is_empty() || to.is_empty() {
+            return Err(Error::new(EINVAL));
+        }
+
+        if self.schemes().any(|scheme| scheme.scheme() == from) ||
+           self.aliases().any(|&(ref alias, _)| alias == from) {
+            return Err(Error::new(EEXIST));
+        }
+
+        let mut name = to;
+        while let Some(&(_, ref target)) = self.aliases().find(|&&(ref alias, _)| alias == name) {
+            name = &target[..];
+        }
+        if name == from {
+            return Err(Error::new(ELOOP));
+        }
+
+        unsafe { &mut *self.scheme_aliases.get() }.push((from.to_string(), to.to_string()));
+        Ok(())
+    }
+
+    /// Remove the alias `from`
+    ///
+    /// Returns `ENOENT` if there is no such alias.
+    pub fn unalias_scheme(&self, from: &str) -> Result<()> {
+        let aliases = unsafe { &mut *self.scheme_aliases.get() };
+        if let Some(i) = aliases.iter().position(|&(ref alias, _)| alias == from) {
+            aliases.remove(i);
+            Ok(())
+        } else {
+            Err(Error::new(ENOENT))
+        }
+    }
+
+    /// Follow the aliases of a scheme name, to the name of the scheme it stands for
+    ///
+    /// Returns `ELOOP` if the aliases do not end.
+    pub fn resolve_scheme<'a>(&'a self, name: &'a str) -> Result<&'a str> {
+        let mut name = name;
+        for _ in 0..self.aliases().len() + 1 {
+            match self.aliases().find(|&&(ref alias, _)| alias == name) {
+                Some(&(_, ref target)) => name = &target[..],
+                None => return Ok(name),
+            }
+        }
+        Err(Error::new(ELOOP))
+    }
+
+    /// The names of the registered schemes, one per line, followed by the aliases as `from -> to`
+    pub fn scheme_list(&self) -> String {
+        let mut list = String::new();
+
+        for scheme in self.schemes() {
+            let scheme_str = scheme.scheme();
+            if !scheme_str.is_empty() {
+                if !list.is_empty() {
+                    list = list + "\n" + scheme_str;
+                } else {
+                    list = scheme_str.to_string();
+                }
+            }
+        }
+
+        for &(ref from, ref to) in self.aliases() {
+            if !list.is_empty() {
+                list.push('\n');
+            }
+            list = list + from + " -> " + to;
+        }
+
+        list
+    }
+
     /// Open a new resource
+    ///
+    /// A scheme alias opens the scheme it stands for, with the URL unchanged. The scheme is only
+    /// opened if its `check_access` allows it.
     pub fn open(&self, url: Url, flags: usize) -> Result<Box<Resource>> {
         let url_scheme = url.scheme();
         if url_scheme.is_empty() {
             let url_path = url.reference();
             if url_path.trim_matches('/').is_empty() {
-                let mut list = String::new();
-
-                for scheme in unsafe { &mut *self.schemes.get() }.iter() {
-                    let scheme_str = scheme.scheme();
-                    if !scheme_str.is_empty() {
-                        if !list.is_empty() {
-                            list = list + "\n" + scheme_str;
-                        } else {
-                            list = scheme_str.to_string();
-                        }
-                    }
-                }
-
-                Ok(box VecResource::new(":".to_string(), list.into_bytes()))
+                Ok(box VecResource::new(":".to_string(), self.scheme_list().into_bytes()))
             } else if flags & O_CREAT == O_CREAT {
-                for scheme in unsafe { &mut *self.schemes.get() }.iter_mut() {
-                    if scheme.scheme() == url_path {
-                        return Err(Error::new(EEXIST));
-                    }
+                if self.schemes().any(|scheme| scheme.scheme() == url_path) {
+                    return Err(Error::new(EEXIST));
                 }
 
-                match Scheme::new(url_path) {
-                    Ok((scheme, server)) => {
-                        unsafe { &mut *self.schemes.get() }.push(scheme);
-                        Ok(server)
-                    },
-                    Err(err) => Err(err)
-                }
+                let (scheme, server) = try!(Scheme::new(url_path));
+                try!(self.register_scheme(scheme));
+                Ok(server)
             } else {
                 Err(Error::new(ENOENT))
             }
         } else {
+            let url_scheme = try!(self.resolve_scheme(url_scheme));
             for mut scheme in unsafe { &mut *self.schemes.get() }.iter_mut() {
                 if scheme.scheme() == url_scheme {
+                    try!(scheme.check_access(&url, flags));
                     return scheme.open(url, flags);
                 }
             }
-            Err(Error::new(ENOENT))
+
+            if unsafe { &*self.removed_schemes.get() }.iter().any(|name| name == url_scheme) {
+                Err(Error::new(ENODEV))
+            } else {
+                Err(Error::new(ENOENT))
+            }
         }
     }
 
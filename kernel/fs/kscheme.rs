@@ -2,8 +2,40 @@ use super::{Resource, Url};
 
 use alloc::boxed::Box;
 
-use system::error::{Error, Result, EPERM};
-use system::syscall::Stat;
+use system::error::{Error, Result, EEXIST, ENOENT, EPERM};
+use system::syscall::{Stat, O_CREAT, O_EXCL, O_RDWR, O_WRONLY};
+
+/// Returns `EPERM` unless the current context has an IOPL of 3
+pub fn check_privileged() -> Result<()> {
+    if try!(unsafe { & *::env().contexts.get() }.current()).iopl != 3 {
+        return Err(Error::new(EPERM));
+    }
+    Ok(())
+}
+
+/// Whether `flags` open for writing, with `O_WRONLY` or `O_RDWR`
+pub fn is_write(flags: usize) -> bool {
+    flags & (O_WRONLY | O_RDWR) != 0
+}
+
+/// Apply `O_CREAT` and `O_EXCL` to the open of a named object, such as an environment variable
+///
+/// Returns whether the object has to be created, which is only if it does not `exist` and
+/// `O_CREAT` is set. Returns `ENOENT` if it does not exist otherwise, and `EEXIST` if it does and
+/// both `O_CREAT` and `O_EXCL` are set.
+pub fn open_named(exists: bool, flags: usize) -> Result<bool> {
+    if exists {
+        if flags & (O_CREAT | O_EXCL) == O_CREAT | O_EXCL {
+            Err(Error::new(EEXIST))
+        } else {
+            Ok(false)
+        }
+    } else if flags & O_CREAT == O_CREAT {
+        Ok(true)
+    } else {
+        Err(Error::new(ENOENT))
+    }
+}
 
 #[allow(unused_variables)]
 pub trait KScheme {
@@ -15,6 +47,18 @@ pub trait KScheme {
         ""
     }
 
+    /// Check whether the current context may open `url` with `flags`
+    ///
+    /// `Environment::open` consults it before `open`, so the access policy of a scheme is kept in
+    /// one place. Returns `EPERM` or `EACCES` to deny the open.
+    fn check_access(&self, url: &Url, flags: usize) -> Result<()> {
+        Ok(())
+    }
+
+    /// Open a resource
+    ///
+    /// Schemes accepting flags in the URL, like `log:?nonblock`, get them with `Url::flags`.
+    /// Schemes of named objects create them with `open_named`.
     fn open(&mut self, path: Url, flags: usize) -> Result<Box<Resource>> {
         Err(Error::new(EPERM))
     }
@@ -1,15 +1,19 @@
-pub use self::kscheme::KScheme;
-pub use self::resource::{Resource, ResourceSeek};
+pub use self::kscheme::{check_privileged, is_write, open_named, KScheme};
+pub use self::rate_limited::RateLimited;
+pub use self::resource::{copy_resource, Resource, ResourceSeek};
 pub use self::scheme::Scheme;
 pub use self::slice_resource::{SliceResource, SliceMutResource};
-pub use self::url::{Url, OwnedUrl};
+pub use self::url::{Format, Url, OwnedUrl};
 pub use self::vec_resource::VecResource;
 pub use self::supervisor_resource::SupervisorResource;
+pub use self::tee::Tee;
 
 /// Kernel schemes
 pub mod kscheme;
 /// Internal resource representation
 pub mod resource;
+/// Rate limited resource
+pub mod rate_limited;
 /// Userspace scheme
 pub mod scheme;
 /// URL
@@ -20,3 +24,5 @@ pub mod slice_resource;
 pub mod vec_resource;
 /// Supervisor resource.
 pub mod supervisor_resource;
+/// Resource writing to two resources
+pub mod tee;
@@ -0,0 +1,150 @@
+use alloc::boxed::Box;
+
+use core::cmp;
+
+use fs::{Resource, ResourceSeek};
+
+use system::error::{Error, Result, EAGAIN, EINVAL};
+use system::syscall::Stat;
+
+/// The number of PIT ticks since boot
+fn ticks() -> u64 {
+    unsafe { *::env().ticks.get() }
+}
+
+/// A resource which limits the bytes read from and written to another resource per tick of the PIT
+///
+/// Reads and writes share a budget of `rate` bytes, which is refilled on every tick. Once it is
+/// used up, they block until the next tick, or return `EAGAIN` if the limiter is nonblocking. A
+/// duplicate has a budget of its own. Mapping is not supported, as it would bypass the limit.
+pub struct RateLimited<R: Resource + ?Sized> {
+    inner: Box<R>,
+    /// The bytes which can be moved per tick
+    rate: usize,
+    /// The bytes left until the next tick
+    budget: usize,
+    /// The tick the budget was last refilled on
+    tick: u64,
+    nonblock: bool,
+}
+
+impl<R: Resource + ?Sized> RateLimited<R> {
+    /// Limit `inner` to `rate` bytes per tick
+    ///
+    /// Returns `EINVAL` if `rate` is 0, as nothing could ever be moved.
+    pub fn new(inner: Box<R>, rate: usize, nonblock: bool) -> Result<RateLimited<R>> {
+        if rate == 0 {
+            return Err(Error::new(EINVAL));
+        }
+
+        Ok(RateLimited {
+            inner: inner,
+            rate: rate,
+            budget: rate,
+            tick: ticks(),
+            nonblock: nonblock,
+        })
+    }
+
+    /// Is any of the budget left, or will it be refilled by the next `reserve`?
+    fn has_budget(&self) -> bool {
+        self.budget > 0 || self.tick != ticks()
+    }
+
+    /// Wait for some of the budget, and return how many of `len` bytes can be moved with it
+    fn reserve(&mut self, len: usize) -> Result<usize> {
+        loop {
+            let tick = ticks();
+            if tick != self.tick {
+                self.tick = tick;
+                self.budget = self.rate;
+            }
+
+            if self.budget > 0 {
+                return Ok(cmp::min(len, self.budget));
+            } else if self.nonblock {
+                return Err(Error::new(EAGAIN));
+            }
+
+            ::env().tick.wait("RateLimited::reserve");
+        }
+    }
+}
+
+impl<R: Resource + ?Sized> Resource for RateLimited<R> {
+    fn close(&mut self) -> Result<()> {
+        self.inner.close()
+    }
+
+    fn control(&mut self, cmd: usize, arg: usize) -> Result<usize> {
+        self.inner.control(cmd, arg)
+    }
+
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box try!(RateLimited::new(try!(self.inner.dup()), self.rate, self.nonblock)))
+    }
+
+    fn fcntl(&mut self, cmd: usize, arg: usize) -> Result<usize> {
+        self.inner.fcntl(cmd, arg)
+    }
+
+    fn flags(&self) -> usize {
+        self.inner.flags()
+    }
+
+    fn is_readable(&self) -> bool {
+        self.has_budget() && self.inner.is_readable()
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.inner.is_terminal()
+    }
+
+    fn is_writable(&self) -> bool {
+        self.has_budget() && self.inner.is_writable()
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        self.inner.path(buf)
+    }
+
+    /// Read at most the bytes left in the budget
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return self.inner.read(buf);
+        }
+
+        let len = try!(self.reserve(buf.len()));
+        let count = try!(self.inner.read(&mut buf[..len]));
+        self.budget -= count;
+        Ok(count)
+    }
+
+    /// Write at most the bytes left in the budget
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return self.inner.write(buf);
+        }
+
+        let len = try!(self.reserve(buf.len()));
+        let count = try!(self.inner.write(&buf[..len]));
+        self.budget -= count;
+        Ok(count)
+    }
+
+    fn seek(&mut self, pos: ResourceSeek) -> Result<usize> {
+        self.inner.seek(pos)
+    }
+
+    fn stat(&self, stat: &mut Stat) -> Result<usize> {
+        self.inner.stat(stat)
+    }
+
+    fn sync(&mut self) -> Result<()> {
+        self.inner.sync()
+    }
+
+    fn truncate(&mut self, len: usize) -> Result<()> {
+        self.inner.truncate(len)
+    }
+}
@@ -1,6 +1,10 @@
 use alloc::boxed::Box;
 
-use system::error::{Error, Result, EPERM, ESPIPE};
+use collections::Vec;
+
+use core::cmp;
+
+use system::error::{Error, Result, EINVAL, EIO, ENODEV, EPERM, ESPIPE};
 use system::syscall::Stat;
 
 /// Resource seek
@@ -12,17 +16,69 @@ pub enum ResourceSeek {
     Current(isize),
     /// End point
     End(isize),
+    /// The next data at or after an offset
+    Data(usize),
+    /// The next hole at or after an offset, where the end counts as a hole
+    Hole(usize),
 }
 
 /// A system resource
 #[allow(unused_variables)]
 pub trait Resource {
+    /// Tear down the resource when its file descriptor is closed, before it is dropped
+    /// Returns `Ok(())` unless the resource has cleanup which can fail.
+    fn close(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Run the resource specific command `cmd`, outside of the data read and written
+    /// Returns `EINVAL` if the command is not supported.
+    fn control(&mut self, cmd: usize, arg: usize) -> Result<usize> {
+        Err(Error::new(EINVAL))
+    }
+
     /// Duplicate the resource
     /// Returns `EPERM` if the operation is not supported.
     fn dup(&self) -> Result<Box<Resource>> {
         Err(Error::new(EPERM))
     }
 
+    /// Get or set the flags of the resource, according to `cmd`
+    /// Returns `EINVAL` if the command is not supported.
+    fn fcntl(&mut self, cmd: usize, arg: usize) -> Result<usize> {
+        Err(Error::new(EINVAL))
+    }
+
+    /// The flags of the resource, as returned by `F_GETFL`
+    /// Returns 0 unless the resource keeps flags.
+    fn flags(&self) -> usize {
+        0
+    }
+
+    /// Would a read return without blocking?
+    /// Returns `true` unless the resource can block.
+    fn is_readable(&self) -> bool {
+        true
+    }
+
+    /// Is the resource a terminal, which programs can be interactive on, see `CONTROL_ISATTY`?
+    /// Returns `false` unless the resource is a terminal.
+    fn is_terminal(&self) -> bool {
+        false
+    }
+
+    /// Would a write return without blocking?
+    /// Returns `true` unless the resource can block.
+    fn is_writable(&self) -> bool {
+        true
+    }
+
+    /// Map the resource into the address space of the current context, with `MAP_*` flags
+    /// Returns the virtual address of the mapping, or `ENODEV` if the operation is not supported.
+    fn mmap(&mut self, len: usize, flags: usize) -> Result<usize> {
+        Err(Error::new(ENODEV))
+    }
+
     /// Return the path of this resource
     /// Returns `EPERM` if the operation is not supported.
     fn path(&self, buf: &mut [u8]) -> Result<usize> {
@@ -35,13 +91,105 @@ pub trait Resource {
         Err(Error::new(EPERM))
     }
 
+    /// Read data until `buf` is full
+    /// Returns `EIO` if the end of the resource comes first, or the first error of `read`.
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
+        let mut i = 0;
+        while i < buf.len() {
+            match try!(self.read(&mut buf[i..])) {
+                0 => return Err(Error::new(EIO)),
+                count => i += count,
+            }
+        }
+        Ok(())
+    }
+
+    /// Read data to buffer, up to and including the next newline
+    /// Returns `EPERM` if the operation is not supported.
+    fn read_line(&mut self, buf: &mut [u8]) -> Result<usize> {
+        Err(Error::new(EPERM))
+    }
+
+    /// Read data until the end of the resource, appending it to `out`
+    /// Returns the number of bytes appended, or the first error of `read`.
+    fn read_to_end(&mut self, out: &mut Vec<u8>) -> Result<usize> {
+        let mut buf = [0; 512];
+        let mut total = 0;
+        loop {
+            let count = try!(self.read(&mut buf));
+            if count == 0 {
+                return Ok(total);
+            }
+            out.extend_from_slice(&buf[..count]);
+            total += count;
+        }
+    }
+
+    /// Read data to each of `bufs` in turn, stopping after the first short read
+    /// Returns the total number of bytes read, or the error of the first read if nothing was read.
+    fn readv(&mut self, bufs: &mut [&mut [u8]]) -> Result<usize> {
+        let mut total = 0;
+        for buf in bufs.iter_mut() {
+            let count = match self.read(buf) {
+                Ok(count) => count,
+                Err(err) => if total == 0 {
+                    return Err(err);
+                } else {
+                    break;
+                },
+            };
+            total += count;
+            if count < buf.len() {
+                break;
+            }
+        }
+        Ok(total)
+    }
+
     /// Write to resource
     /// Returns `EPERM` if the operation is not supported.
     fn write(&mut self, buf: &[u8]) -> Result<usize> {
         Err(Error::new(EPERM))
     }
 
+    /// Write all of `buf`
+    /// Returns `EIO` if a write accepts nothing, or the first error of `write`.
+    fn write_all(&mut self, buf: &[u8]) -> Result<()> {
+        let mut i = 0;
+        while i < buf.len() {
+            match try!(self.write(&buf[i..])) {
+                0 => return Err(Error::new(EIO)),
+                count => i += count,
+            }
+        }
+        Ok(())
+    }
+
+    /// Write each of `bufs` in turn, stopping after the first short write
+    /// Returns the total number of bytes written, or the error of the first write if nothing was
+    /// written.
+    fn writev(&mut self, bufs: &[&[u8]]) -> Result<usize> {
+        let mut total = 0;
+        for buf in bufs.iter() {
+            let count = match self.write(buf) {
+                Ok(count) => count,
+                Err(err) => if total == 0 {
+                    return Err(err);
+                } else {
+                    break;
+                },
+            };
+            total += count;
+            if count < buf.len() {
+                break;
+            }
+        }
+        Ok(total)
+    }
+
     /// Seek to the given offset
+    /// Returns the new offset, so `ResourceSeek::Current(0)` gets the offset without moving it, and
+    /// `ResourceSeek::End(0)` gets the size.
     /// Returns `ESPIPE` if the operation is not supported.
     fn seek(&mut self, pos: ResourceSeek) -> Result<usize> {
         Err(Error::new(ESPIPE))
@@ -65,3 +213,38 @@ pub trait Resource {
         Err(Error::new(EPERM))
     }
 }
+
+/// Copy up to `len` bytes from `src` to `dst`, through a bounce buffer, starting at their cursors
+/// Returns the number of bytes copied, which is less than `len` if `src` reached its end or `dst`
+/// stopped accepting data, or the first error if nothing was copied.
+pub fn copy_resource(src: &mut Resource, dst: &mut Resource, len: usize) -> Result<usize> {
+    let mut buf = [0; 512];
+    let mut total = 0;
+    while total < len {
+        let chunk = cmp::min(buf.len(), len - total);
+        let count = match src.read(&mut buf[..chunk]) {
+            Ok(0) => break,
+            Ok(count) => count,
+            Err(err) => if total == 0 {
+                return Err(err);
+            } else {
+                break;
+            },
+        };
+
+        let mut written = 0;
+        while written < count {
+            match dst.write(&buf[written..count]) {
+                Ok(0) => return Ok(total + written),
+                Ok(n) => written += n,
+                Err(err) => if total + written == 0 {
+                    return Err(err);
+                } else {
+                    return Ok(total + written);
+                },
+            }
+        }
+        total += count;
+    }
+    Ok(total)
+}
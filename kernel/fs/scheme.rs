@@ -1,10 +1,10 @@
 use alloc::arc::{Arc, Weak};
 use alloc::boxed::Box;
 
-use collections::String;
+use collections::{BTreeMap, String};
 use collections::borrow::ToOwned;
 
-use core::cell::Cell;
+use core::cell::{Cell, UnsafeCell};
 use core::mem::size_of;
 use core::ops::DerefMut;
 use core::{ptr, slice};
@@ -13,11 +13,12 @@ use arch::context::Context;
 
 use sync::{WaitMap, WaitQueue};
 
-use system::error::{Error, Result, EFAULT, EINVAL, ENODEV, ESPIPE};
-use system::scheme::Packet;
-use system::syscall::{SYS_CLOSE, SYS_DUP, SYS_FPATH, SYS_FSTAT, SYS_FSYNC, SYS_FTRUNCATE,
-                    SYS_OPEN, SYS_LSEEK, SEEK_SET, SEEK_CUR, SEEK_END, SYS_MKDIR,
-                    SYS_READ, SYS_WRITE, SYS_RMDIR, SYS_STAT, SYS_UNLINK, Stat};
+use system::error::{Error, Result, EBUSY, EFAULT, EINVAL, ENODEV, ESPIPE};
+use system::scheme::{Packet, PACKET_MAGIC};
+use system::syscall::{F_GETFL, SCHEME_PASS_DUP, SCHEME_PASS_MOVE, SYS_CLOSE, SYS_DUP, SYS_FCNTL,
+                    SYS_FPATH, SYS_FSTAT, SYS_FSYNC, SYS_FTRUNCATE, SYS_OPEN, SYS_LSEEK, SEEK_SET,
+                    SEEK_CUR, SEEK_END, SEEK_DATA, SEEK_HOLE, SYS_MKDIR, SYS_READ, SYS_WRITE,
+                    SYS_RMDIR, SYS_STAT, SYS_UNLINK, Stat};
 
 use super::{Resource, ResourceSeek, KScheme, Url};
 
@@ -27,6 +28,8 @@ struct SchemeInner {
     next_id: Cell<usize>,
     todo: WaitQueue<Packet>,
     done: WaitMap<usize, (usize, usize, usize, usize)>,
+    /// Resources passed by the provider with its replies, by request id
+    passed: UnsafeCell<BTreeMap<usize, Box<Resource>>>,
 }
 
 impl SchemeInner {
@@ -37,10 +40,14 @@ impl SchemeInner {
             next_id: Cell::new(1),
             todo: WaitQueue::new(),
             done: WaitMap::new(),
+            passed: UnsafeCell::new(BTreeMap::new()),
         }
     }
 
-    fn call(inner: &Weak<SchemeInner>, a: usize, b: usize, c: usize, d: usize) -> Result<usize> {
+    /// Send a request to the provider, and wait for its reply
+    ///
+    /// Returns the result, along with the resource the provider passed with the reply, if any.
+    fn request(inner: &Weak<SchemeInner>, a: usize, b: usize, c: usize, d: usize) -> Result<(usize, Option<Box<Resource>>)> {
         if let Some(scheme) = inner.upgrade() {
             let id = scheme.next_id.get();
 
@@ -58,17 +65,27 @@ impl SchemeInner {
                 a: a,
                 b: b,
                 c: c,
-                d: d
+                d: d,
+                magic: PACKET_MAGIC
             }, "SchemeInner::call todo");
 
             let res = Error::demux(scheme.done.receive(&id, "SchemeInner::call done").0);
             // debugln!("{} {}: {} {} {:X} {:X} {:X} = {:?}", scheme.name, id, a, ::syscall::name(a), b, c, d, res);
-            res
+            let passed = unsafe { &mut *scheme.passed.get() }.remove(&id);
+            res.map(|value| (value, passed))
         } else {
             Err(Error::new(ENODEV))
         }
     }
 
+    fn call(inner: &Weak<SchemeInner>, a: usize, b: usize, c: usize, d: usize) -> Result<usize> {
+        match try!(SchemeInner::request(inner, a, b, c, d)) {
+            (value, None) => Ok(value),
+            // Only requests which open a resource can be answered with one
+            (_, Some(_)) => Err(Error::new(EINVAL)),
+        }
+    }
+
     fn capture(inner: &Weak<SchemeInner>, mut physical_address: usize, size: usize, writeable: bool) -> Result<usize> {
         if let Some(scheme) = inner.upgrade() {
             if physical_address >= 0x80000000 {
@@ -98,7 +115,7 @@ impl SchemeInner {
 
 impl Drop for SchemeInner {
     fn drop(&mut self) {
-        unsafe { &mut *::env().schemes.get() }.retain(|scheme| scheme.scheme() != self.name);
+        let _ = ::env().deregister_scheme(&self.name);
     }
 }
 
@@ -108,6 +125,10 @@ pub struct SchemeResource {
 }
 
 impl SchemeResource {
+    fn request(&self, a: usize, b: usize, c: usize, d: usize) -> Result<(usize, Option<Box<Resource>>)> {
+        SchemeInner::request(&self.inner, a, b, c, d)
+    }
+
     fn call(&self, a: usize, b: usize, c: usize, d: usize) -> Result<usize> {
         SchemeInner::call(&self.inner, a, b, c, d)
     }
@@ -124,11 +145,23 @@ impl SchemeResource {
 impl Resource for SchemeResource {
     /// Duplicate the resource
     fn dup(&self) -> Result<Box<Resource>> {
-        let file_id = try!(self.call(SYS_DUP, self.file_id, 0, 0));
-        Ok(Box::new(SchemeResource {
-            inner: self.inner.clone(),
-            file_id: file_id
-        }))
+        match try!(self.request(SYS_DUP, self.file_id, 0, 0)) {
+            (_, Some(resource)) => Ok(resource),
+            (file_id, None) => Ok(Box::new(SchemeResource {
+                inner: self.inner.clone(),
+                file_id: file_id
+            })),
+        }
+    }
+
+    /// Get or set the flags of the resource
+    fn fcntl(&mut self, cmd: usize, arg: usize) -> Result<usize> {
+        self.call(SYS_FCNTL, self.file_id, cmd, arg)
+    }
+
+    /// Ask the scheme for the flags of the resource, or 0 if it does not support `F_GETFL`
+    fn flags(&self) -> usize {
+        self.call(SYS_FCNTL, self.file_id, F_GETFL, 0).unwrap_or(0)
     }
 
     /// Return the URL of this resource
@@ -202,7 +235,9 @@ impl Resource for SchemeResource {
         let (whence, offset) = match pos {
             ResourceSeek::Start(offset) => (SEEK_SET, offset as usize),
             ResourceSeek::Current(offset) => (SEEK_CUR, offset as usize),
-            ResourceSeek::End(offset) => (SEEK_END, offset as usize)
+            ResourceSeek::End(offset) => (SEEK_END, offset as usize),
+            ResourceSeek::Data(offset) => (SEEK_DATA, offset),
+            ResourceSeek::Hole(offset) => (SEEK_HOLE, offset),
         };
 
         self.call(SYS_LSEEK, self.file_id, offset, whence)
@@ -248,13 +283,36 @@ impl Drop for SchemeResource {
 
 pub struct SchemeServerResource {
     inner: Arc<SchemeInner>,
+    /// The resource to pass with the next reply, see `SCHEME_PASS_DUP`
+    pending: Option<Box<Resource>>,
 }
 
 impl Resource for SchemeServerResource {
+    /// Pass a descriptor of the provider with the next reply, with `SCHEME_PASS_DUP` or
+    /// `SCHEME_PASS_MOVE`
+    /// Returns `EBADF` if the descriptor is not open, or `EBUSY` if one is already waiting to be
+    /// passed.
+    fn control(&mut self, cmd: usize, arg: usize) -> Result<usize> {
+        if self.pending.is_some() {
+            return Err(Error::new(EBUSY));
+        }
+
+        let contexts = unsafe { & *::env().contexts.get() };
+        let current = try!(contexts.current());
+        let resource = match cmd {
+            SCHEME_PASS_DUP => try!(try!(current.get_file(arg)).dup()),
+            SCHEME_PASS_MOVE => try!(current.take_file(arg)),
+            _ => return Err(Error::new(EINVAL)),
+        };
+        self.pending = Some(resource);
+        Ok(0)
+    }
+
     /// Duplicate the resource
     fn dup(&self) -> Result<Box<Resource>> {
         Ok(box SchemeServerResource {
-            inner: self.inner.clone()
+            inner: self.inner.clone(),
+            pending: None,
         })
     }
 
@@ -309,6 +367,9 @@ impl Resource for SchemeServerResource {
 
             while i <= buf.len() - size_of::<Packet>() {
                 let packet = unsafe { & *(buf.as_ptr().offset(i as isize) as *const Packet) };
+                if let Some(resource) = self.pending.take() {
+                    unsafe { &mut *self.inner.passed.get() }.insert(packet.id, resource);
+                }
                 self.inner.done.send(packet.id, (packet.a, packet.b, packet.c, packet.d), "SchemeServerResource::write done");
                 i += size_of::<Packet>();
             }
@@ -334,6 +395,21 @@ impl Resource for SchemeServerResource {
     }
 }
 
+/// A scheme provided by a userspace process
+///
+/// Opening `:name` with `O_CREAT` registers the scheme and returns its server resource. Every
+/// operation on the scheme is sent to the provider as a `Packet`, with the syscall number in `a`
+/// and its arguments in `b`, `c` and `d`, which the provider reads from the server resource. The
+/// caller blocks until the provider writes back a `Packet` with the same `id` and the result in `a`.
+/// Buffers are mapped into the provider for the duration of the call.
+///
+/// An open is sent as `SYS_OPEN`, with the NUL terminated URL in `b` and the flags in `c`. The
+/// provider answers it with a handle id of its choosing, which is then sent in `b` of every request
+/// on the opened resource, from reads and writes to the `SYS_CLOSE` when it is dropped, so a
+/// provider runs a loop much like accept and recv on a socket. An open or a dup can be
+/// answered with a descriptor of the provider instead of a file id, see `SCHEME_PASS_DUP`, which the
+/// caller gets in place of a resource of the scheme.
+///
 /// Scheme has to be wrapped
 pub struct Scheme {
     name: String,
@@ -345,7 +421,8 @@ impl Scheme {
         let contexts = unsafe { &mut *::env().contexts.get() };
         let mut current = try!(contexts.current_mut());
         let server = box SchemeServerResource {
-            inner: Arc::new(SchemeInner::new(name, current.deref_mut()))
+            inner: Arc::new(SchemeInner::new(name, current.deref_mut())),
+            pending: None,
         };
         let scheme = box Scheme {
             name: name.to_owned(),
@@ -354,6 +431,10 @@ impl Scheme {
         Ok((scheme, server))
     }
 
+    fn request(&self, a: usize, b: usize, c: usize, d: usize) -> Result<(usize, Option<Box<Resource>>)> {
+        SchemeInner::request(&self.inner, a, b, c, d)
+    }
+
     fn call(&self, a: usize, b: usize, c: usize, d: usize) -> Result<usize> {
         SchemeInner::call(&self.inner, a, b, c, d)
     }
@@ -381,12 +462,13 @@ impl KScheme for Scheme {
 
         let virtual_address = try!(self.capture(c_str.as_ptr() as usize, c_str.len(), false));
 
-        let result = self.call(SYS_OPEN, virtual_address, flags, 0);
+        let result = self.request(SYS_OPEN, virtual_address, flags, 0);
 
         self.release(virtual_address);
 
         match result {
-            Ok(file_id) => Ok(box SchemeResource {
+            Ok((_, Some(resource))) => Ok(resource),
+            Ok((file_id, None)) => Ok(box SchemeResource {
                 inner: self.inner.clone(),
                 file_id: file_id,
             }),
@@ -435,6 +517,7 @@ impl KScheme for Scheme {
             let result = self.call(SYS_STAT, c_str_address, virtual_address + offset, buf.len());
 
             self.release(c_str_address);
+            self.release(virtual_address);
 
             result.and(Ok(()))
         } else {
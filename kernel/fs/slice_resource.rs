@@ -5,7 +5,8 @@ use alloc::boxed::Box;
 use core::cmp::{max, min};
 use core::slice;
 
-use system::error::Result;
+use system::error::{Error, Result, EINVAL};
+use system::syscall::{MODE_FILE, Stat};
 
 /// A slice resource
 pub struct SliceResource {
@@ -62,16 +63,24 @@ impl Resource for SliceResource {
         match pos {
             ResourceSeek::Start(offset) => self.seek = min(self.data.len(), offset),
             ResourceSeek::Current(offset) =>
-                self.seek = max(0, min(self.seek as isize, self.seek as isize + offset)) as usize,
+                self.seek = max(0,
+                                min(self.data.len() as isize,
+                                    self.seek as isize + offset)) as usize,
             ResourceSeek::End(offset) =>
                 self.seek = max(0,
-                                min(self.seek as isize,
-                                    self.data.len() as isize +
-                                    offset)) as usize,
+                                min(self.data.len() as isize,
+                                    self.data.len() as isize + offset)) as usize,
+            ResourceSeek::Data(_) | ResourceSeek::Hole(_) => return Err(Error::new(EINVAL)),
         }
         return Ok(self.seek);
     }
 
+    fn stat(&self, stat: &mut Stat) -> Result<usize> {
+        stat.st_mode = MODE_FILE;
+        stat.st_size = self.data.len() as u32;
+        Ok(0)
+    }
+
     fn sync(&mut self) -> Result<()> {
         Ok(())
     }
@@ -142,16 +151,24 @@ impl Resource for SliceMutResource {
         match pos {
             ResourceSeek::Start(offset) => self.seek = min(self.data.len(), offset),
             ResourceSeek::Current(offset) =>
-                self.seek = max(0, min(self.seek as isize, self.seek as isize + offset)) as usize,
+                self.seek = max(0,
+                                min(self.data.len() as isize,
+                                    self.seek as isize + offset)) as usize,
             ResourceSeek::End(offset) =>
                 self.seek = max(0,
-                                min(self.seek as isize,
-                                    self.data.len() as isize +
-                                    offset)) as usize,
+                                min(self.data.len() as isize,
+                                    self.data.len() as isize + offset)) as usize,
+            ResourceSeek::Data(_) | ResourceSeek::Hole(_) => return Err(Error::new(EINVAL)),
         }
         return Ok(self.seek);
     }
 
+    fn stat(&self, stat: &mut Stat) -> Result<usize> {
+        stat.st_mode = MODE_FILE;
+        stat.st_size = self.data.len() as u32;
+        Ok(0)
+    }
+
     fn sync(&mut self) -> Result<()> {
         Ok(())
     }
@@ -1,59 +1,1212 @@
-use core::{cmp, mem};
+use alloc::arc::{Arc, Weak};
+use alloc::boxed::Box;
+
+use collections::Vec;
+
+use arch::context::Context;
+use arch::regs::{FpRegs, Regs};
+
+use core::{cmp, mem, slice};
+
+use sync::{WaitCondition, WaitQueue};
+
 use super::Resource;
-use system::error::Result;
-use system::scheme::Packet;
-use arch::context::{Context, context_switch};
+
+use system::error::{Error, Result, EACCES, EAGAIN, EBUSY, EFAULT, EINTR, EINVAL, EPERM, ESRCH};
+use system::scheme::{Packet, PACKET_MAGIC};
+use system::syscall::{F_GETFL, F_SETFL, NSIG, O_NONBLOCK, SIGKILL, SUPERVISE_CONTINUE,
+                      SUPERVISE_DETACH, SUPERVISE_EVENT_EXIT, SUPERVISE_EVENT_STEP,
+                      SUPERVISE_EXIT_POLICY, SUPERVISE_EXIT_REAP, SUPERVISE_EXIT_ZOMBIE,
+                      SUPERVISE_FILTER, SUPERVISE_FILTER_DENY, SUPERVISE_FORCEKILL,
+                      SUPERVISE_GETFPREGS, SUPERVISE_GETREGS, SUPERVISE_INTERRUPT,
+                      SUPERVISE_LEGACY_WRITE, SUPERVISE_MASK, SUPERVISE_MASK_ALL,
+                      SUPERVISE_NONBLOCK, SUPERVISE_PEEK, SUPERVISE_POKE, SUPERVISE_RETURN,
+                      SUPERVISE_SETARGS, SUPERVISE_SETFPREGS, SUPERVISE_SETREGS, SUPERVISE_SIGNAL,
+                      SUPERVISE_STEP, SuperviseRule};
+
+/// The number of events queued for a supervisor before tracees wait for it to read them
+const SUPERVISE_QUEUE: usize = 256;
+
+/// The flags a supervisor may change with `SUPERVISE_SETREGS`
+///
+/// These are CF, PF, AF, ZF, SF, TF, DF, and OF. The rest, like IF and IOPL, are kept.
+const SUPERVISE_FLAGS: usize = 0xDD5;
+
+/// The trap flag, which raises a debug exception after every instruction
+const TRAP_FLAG: usize = 0x100;
+
+/// The largest amount of memory sent in one event by `SUPERVISE_PEEK`
+const SUPERVISE_CHUNK: usize = 4096;
+
+/// The MXCSR bits a supervisor may set with `SUPERVISE_SETFPREGS`, which every processor with SSE
+/// supports. Setting any other bit would fault when the registers are loaded.
+const SUPERVISE_MXCSR: u32 = 0xFFBF;
+
+/// An event sent from a tracee to its supervisor
+pub enum SupervisorEvent {
+    /// The tracee stopped, at a syscall or after a step
+    Stop(Packet),
+    /// The registers of the tracee, sent in reply to `SUPERVISE_GETREGS`
+    Regs(Regs),
+    /// The FPU and SSE registers of the tracee, sent in reply to `SUPERVISE_GETFPREGS`
+    FpRegs(FpRegs),
+    /// Memory of the tracee, sent in reply to `SUPERVISE_PEEK`, and whether it is the last chunk
+    Memory(Vec<u8>, bool),
+    /// The memory requested by `SUPERVISE_PEEK` could not be read
+    Fault,
+}
+
+impl SupervisorEvent {
+    /// Is this a reply to a command?
+    fn is_reply(&self) -> bool {
+        match *self {
+            SupervisorEvent::Stop(_) => false,
+            _ => true,
+        }
+    }
+}
+
+/// The event queue of an observe only supervisor, which is stored in each of its tracees
+pub type Observer = Weak<WaitQueue<(usize, SupervisorEvent)>>;
+
+/// A command sent from a supervisor to its tracee
+pub enum SupervisorCommand {
+    /// Skip the syscall, returning the given value
+    Return(usize),
+    /// Reply with the registers of the syscall
+    GetRegs,
+    /// Replace the registers of the syscall
+    SetRegs(Regs),
+    /// Reply with the FPU and SSE registers
+    GetFpRegs,
+    /// Replace the FPU and SSE registers
+    SetFpRegs(FpRegs),
+    /// Replace the arguments of the syscall
+    SetArgs(usize, usize, usize),
+    /// Reply with the memory at an address, of some length
+    Peek(usize, usize),
+    /// Write memory at an address
+    Poke(usize, Vec<u8>),
+    /// Resume the tracee for a single instruction
+    Step,
+    /// Resume the tracee until the next event
+    Continue,
+    /// Resume the tracee, which is no longer supervised
+    Detach,
+    /// Resume the tracee until the next event, with a pending signal
+    Signal(usize),
+}
+
+/// The tracee end of a supervisor
+///
+/// This is stored in the supervised context, and is served by the tracee itself while it is
+/// stopped.
+#[derive(Clone)]
+pub struct SupervisedResource {
+    /// The PID of the tracee
+    pid: usize,
+    /// Events to the supervisor, tagged with the PID of the tracee
+    send: Weak<WaitQueue<(usize, SupervisorEvent)>>,
+    /// Commands from the supervisor
+    recv: Arc<WaitQueue<SupervisorCommand>>,
+    /// Whether children of the tracee are supervised as well
+    descendants: bool,
+    /// The classes of events the tracee stops for, see `SUPERVISE_MASK_*`
+    mask: usize,
+    /// The syscall filter, see `SUPERVISE_FILTER`
+    filter: Arc<Vec<SuperviseRule>>,
+    /// The rule for syscalls which are not in `filter`
+    default_rule: SuperviseRule,
+    /// Whether the tracee is torn down as soon as it exits, see `SUPERVISE_EXIT_POLICY`
+    reap: bool,
+}
+
+impl SupervisedResource {
+    /// The tracee end for a child of the tracee, if it should be supervised as well
+    pub fn inherit(&self, pid: usize) -> Option<SupervisedResource> {
+        if self.descendants {
+            Some(SupervisedResource {
+                pid: pid,
+                send: self.send.clone(),
+                recv: Arc::new(WaitQueue::new()),
+                descendants: true,
+                mask: self.mask,
+                filter: self.filter.clone(),
+                default_rule: self.default_rule,
+                reap: self.reap,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Check if the supervisor still exists
+    pub fn is_attached(&self) -> bool {
+        self.send.upgrade().is_some()
+    }
+
+    /// Does the tracee stop for this class of events?
+    pub fn stops_for(&self, class: usize) -> bool {
+        self.mask & class == class
+    }
+
+    /// The rule of the syscall filter for a syscall
+    pub fn rule(&self, syscall: usize) -> SuperviseRule {
+        self.filter.iter().find(|rule| rule.syscall == syscall).map_or(self.default_rule, |rule| *rule)
+    }
+
+    /// Send an event to the supervisor, waiting while its queue is full
+    fn send(&self, mut event: SupervisorEvent, reason: &str) {
+        while let Some(send) = self.send.upgrade() {
+            match send.try_send((self.pid, event), reason) {
+                Ok(()) => return,
+                Err((_, rejected)) => event = rejected,
+            }
+
+            // Wait without holding the queue open, so that dropping the supervisor ends the wait.
+            // The supervisor keeps the queue alive until then, as nothing else runs before this
+            // context blocks.
+            let space = &send.space as *const WaitCondition;
+            drop(send);
+            unsafe { (*space).wait(reason) };
+        }
+    }
+
+    /// Is the tracee torn down as soon as it exits, instead of lingering as a zombie?
+    pub fn reaps(&self) -> bool {
+        self.reap
+    }
+
+    /// Tell the supervisor that the tracee exited with `status`
+    ///
+    /// The tracee does not stop for this event, see `exit` for one that does.
+    pub fn exit(&self, status: usize) {
+        let event = Packet {
+            id: self.pid,
+            a: SUPERVISE_EVENT_EXIT,
+            b: status,
+            c: 0,
+            d: 0,
+            magic: PACKET_MAGIC,
+        };
+        self.send(SupervisorEvent::Stop(event), "SupervisedResource::exit");
+    }
+
+    /// Wait for a command from the supervisor
+    ///
+    /// Returns `None` once the supervisor is gone, so that a tracee it did not resume on its way
+    /// out does not wait forever.
+    fn receive(&self, reason: &str) -> Option<SupervisorCommand> {
+        loop {
+            if let Some(command) = self.recv.receive_nonblocking() {
+                return Some(command);
+            }
+
+            if !self.is_attached() {
+                return None;
+            }
+
+            self.recv.condition.wait(reason);
+        }
+    }
+
+    /// Stop with an event, and serve the supervisor until it resumes the tracee
+    ///
+    /// If the supervisor goes away first, the tracee continues as if it was never supervised.
+    /// Returns true if the supervisor resumed the tracee with `SUPERVISE_RETURN`, in which case a
+    /// syscall must not be run, and a signal must not be delivered. While it is stopped, the
+    /// registers of the tracee are kept in `Context::stopped_regs`.
+    pub fn trap(&self, event: Packet, regs: &mut Regs) -> bool {
+        let resumed = self.serve(event, regs);
+        set_stopped_regs(None);
+        resumed
+    }
+
+    /// Serve the supervisor of a stopped tracee, see `trap`
+    fn serve(&self, mut event: Packet, regs: &mut Regs) -> bool {
+        if !self.is_attached() {
+            regs.flags &= !TRAP_FLAG;
+            return false;
+        }
+
+        event.id = self.pid;
+        self.send(SupervisorEvent::Stop(event), "SupervisedResource::trap");
+
+        let syscall = event.a < SUPERVISE_EVENT_STEP;
+
+        loop {
+            set_stopped_regs(Some(*regs));
+
+            let command = match self.receive("SupervisedResource::trap") {
+                Some(command) => command,
+                None => {
+                    regs.flags &= !TRAP_FLAG;
+                    return false;
+                },
+            };
+
+            match command {
+                SupervisorCommand::Return(value) => {
+                    regs.flags &= !TRAP_FLAG;
+                    if syscall {
+                        regs.ax = value;
+                    }
+                    return true;
+                },
+                SupervisorCommand::Step => {
+                    regs.flags |= TRAP_FLAG;
+                    return false;
+                },
+                SupervisorCommand::Continue | SupervisorCommand::Detach => {
+                    regs.flags &= !TRAP_FLAG;
+                    return false;
+                },
+                SupervisorCommand::Signal(signal) => {
+                    if let Ok(mut cur) = unsafe { &mut *::env().contexts.get() }.current_mut() {
+                        cur.signal(signal);
+                    }
+                    regs.flags &= !TRAP_FLAG;
+                    return false;
+                },
+                SupervisorCommand::GetRegs => {
+                    self.send(SupervisorEvent::Regs(*regs), "SupervisedResource::trap GetRegs");
+                },
+                SupervisorCommand::GetFpRegs => {
+                    self.send(SupervisorEvent::FpRegs(save_fp_regs()),
+                              "SupervisedResource::trap GetFpRegs");
+                },
+                SupervisorCommand::SetFpRegs(fp_regs) => load_fp_regs(fp_regs),
+                SupervisorCommand::SetArgs(b, c, d) => {
+                    if syscall {
+                        regs.bx = b;
+                        regs.cx = c;
+                        regs.dx = d;
+                    }
+                },
+                SupervisorCommand::SetRegs(mut new_regs) => {
+                    // Checked by the supervisor already, but the registers it may not change
+                    // are kept regardless, and the rest replaced at once
+                    new_regs.cs = regs.cs;
+                    new_regs.ss = regs.ss;
+                    new_regs.flags = (new_regs.flags & SUPERVISE_FLAGS) |
+                                     (regs.flags & !SUPERVISE_FLAGS);
+                    *regs = new_regs;
+                },
+                SupervisorCommand::Peek(address, len) => {
+                    let contexts = unsafe { &*::env().contexts.get() };
+                    let data = contexts.current().and_then(|cur| {
+                        cur.get_slice(address as *const u8, len)
+                    });
+
+                    if let Ok(data) = data {
+                        let chunks = (data.len() + SUPERVISE_CHUNK - 1) / SUPERVISE_CHUNK;
+                        for (i, chunk) in data.chunks(SUPERVISE_CHUNK).enumerate() {
+                            self.send(SupervisorEvent::Memory(chunk.to_vec(), i + 1 == chunks),
+                                      "SupervisedResource::trap Peek");
+                        }
+                    } else {
+                        self.send(SupervisorEvent::Fault, "SupervisedResource::trap Peek");
+                    }
+                },
+                SupervisorCommand::Poke(address, data) => {
+                    let contexts = unsafe { &*::env().contexts.get() };
+                    let memory = contexts.current().and_then(|cur| {
+                        cur.get_slice_mut(address as *mut u8, data.len())
+                    });
+
+                    if let Ok(memory) = memory {
+                        memory.copy_from_slice(&data);
+                    }
+                },
+            }
+        }
+    }
+}
+
+impl Drop for SupervisedResource {
+    /// Wake the supervisor if this was its last tracee, so that a read does not wait forever
+    fn drop(&mut self) {
+        if let Some(send) = self.send.upgrade() {
+            if Arc::weak_count(&send) == 1 {
+                send.condition.notify("SupervisedResource::drop");
+            }
+        }
+    }
+}
+
+/// Set the registers the current context is stopped with
+fn set_stopped_regs(regs: Option<Regs>) {
+    if let Ok(cur) = unsafe { &mut *::env().contexts.get() }.current_mut() {
+        cur.stopped_regs = regs;
+    }
+}
+
+/// Save the FPU and SSE registers of the current context, and return them
+///
+/// The registers are only saved to the context when it is switched away from, so a stopped tracee
+/// saves them itself before they are sent to the supervisor.
+fn save_fp_regs() -> FpRegs {
+    match unsafe { &mut *::env().contexts.get() }.current_mut() {
+        Ok(cur) => {
+            unsafe { cur.save_fx() };
+            cur.fp_regs()
+        },
+        Err(_) => FpRegs::default(),
+    }
+}
+
+/// Replace the FPU and SSE registers of the current context, loading them at once
+///
+/// MXCSR bits the processor does not support are cleared, as loading them would fault.
+fn load_fp_regs(mut fp_regs: FpRegs) {
+    if let Ok(cur) = unsafe { &mut *::env().contexts.get() }.current_mut() {
+        unsafe { cur.save_fx() };
+        let mask = match cur.fp_regs().mxcsr_mask {
+            0 => SUPERVISE_MXCSR,
+            mask => mask,
+        };
+        fp_regs.mxcsr &= mask;
+        fp_regs.mxcsr_mask = mask;
+        cur.set_fp_regs(&fp_regs);
+        unsafe { cur.load_fx() };
+    }
+}
+
+/// Send a copy of an event of a context to its observers
+///
+/// Observers never make the context wait: the event is dropped for an observer which has too many
+/// unread events. Observers which no longer exist are removed.
+pub fn notify_observers(context: &mut Context, mut event: Packet) {
+    if context.observers.is_empty() {
+        return;
+    }
+
+    event.id = context.pid;
+    context.observers.retain(|observer| {
+        if let Some(queue) = observer.upgrade() {
+            let _ = queue.try_send((event.id, SupervisorEvent::Stop(event)), "notify_observers");
+            true
+        } else {
+            false
+        }
+    });
+}
+
+/// Handle a debug exception
+///
+/// If the current context is stepping under a supervisor, this clears the trap flag and stops the
+/// context until the supervisor resumes it. Returns false if the exception was not caused by a
+/// supervisor.
+pub fn debug(regs: &mut Regs) -> bool {
+    let contexts = unsafe { &mut *::env().contexts.get() };
+    if let Ok(cur) = contexts.current_mut() {
+        if regs.flags & TRAP_FLAG == TRAP_FLAG {
+            if let Some(resource) = cur.supervised_resource.clone() {
+                regs.flags &= !TRAP_FLAG;
+
+                let event = Packet {
+                    id: 0,
+                    a: SUPERVISE_EVENT_STEP,
+                    b: regs.ip,
+                    c: 0,
+                    d: 0,
+                    magic: PACKET_MAGIC,
+                };
+
+                notify_observers(cur, event);
+
+                cur.blocked_syscall = true;
+                resource.trap(event, regs);
+                cur.blocked_syscall = false;
+
+                ::syscall::process::deliver_signals(regs);
+
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+/// Tell the supervisor of the exiting current context, if any, and let go of it
+///
+/// Unless the supervisor chose `SUPERVISE_EXIT_REAP`, the context lingers as a zombie, stopped at
+/// its exit event with `regs`, until the supervisor resumes it or goes away, so that nothing of it
+/// has been torn down yet while the supervisor inspects it.
+pub fn exit(context: &mut Context, status: usize, regs: &Regs) {
+    let event = Packet {
+        id: 0,
+        a: SUPERVISE_EVENT_EXIT,
+        b: status,
+        c: 0,
+        d: 0,
+        magic: PACKET_MAGIC,
+    };
+
+    if let Some(resource) = context.supervised_resource.clone() {
+        if !resource.reaps() {
+            let mut regs = *regs;
+            context.blocked_syscall = true;
+            resource.trap(event, &mut regs);
+            context.blocked_syscall = false;
+        }
+    }
+
+    context.supervised = false;
+    if let Some(resource) = context.supervised_resource.take() {
+        if resource.reaps() {
+            resource.exit(status);
+        }
+    }
+}
+
+/// Stop the current context for an event, if it is supervised, and stops for that class of events
+///
+/// The event is sent with `a`, `b`, and `c`, and observers are sent it whether or not the context
+/// stops. Returns true if the supervisor resumed the context with `SUPERVISE_RETURN`.
+pub fn stop(class: usize, a: usize, b: usize, c: usize, regs: &mut Regs) -> bool {
+    let contexts = unsafe { &mut *::env().contexts.get() };
+    if let Ok(cur) = contexts.current_mut() {
+        let event = Packet {
+            id: 0,
+            a: a,
+            b: b,
+            c: c,
+            d: 0,
+            magic: PACKET_MAGIC,
+        };
+        notify_observers(cur, event);
+
+        if let Some(resource) = cur.supervised_resource.clone() {
+            if resource.stops_for(class) {
+                cur.blocked_syscall = true;
+                let handled = resource.trap(event, regs);
+                cur.blocked_syscall = false;
+
+                return handled;
+            }
+        }
+    }
+
+    false
+}
 
 /// A supervisor resource.
 ///
-/// Reading from it will simply read the relevant registers to the buffer (see `Packet`).
+/// Reading from it will block until a tracee stops at a syscall, or after a step, and then read
+/// the event as a `Packet`, with the PID of the tracee as its `id`. That tracee receives the
+/// commands written afterwards. The events of several tracees are read in turn, one tracee after
+/// another by PID.
+///
+/// After a `SUPERVISE_GETREGS` command, the next read must be exactly the size of `Regs`, and
+/// reads the registers of the tracee, and likewise with `SUPERVISE_GETFPREGS` and `FpRegs`. After
+/// a `SUPERVISE_PEEK` command, the following reads return the memory, in chunks of at most
+/// `SUPERVISE_CHUNK` bytes, or `EFAULT`. Replies are read before any other events.
+///
+/// Reads block by default. After `SUPERVISE_NONBLOCK`, reads return `EAGAIN` when there are no
+/// events. Once every tracee is gone and its events are read, reads return `ESRCH`. A blocked
+/// read can be canceled with `SUPERVISE_INTERRUPT`, from another thread, and returns `EINTR`.
+///
+/// Writing a `Packet` sends a command to the stopped tracee (see `SUPERVISE_*`). Writing fewer
+/// bytes than a `Packet` returns `EINVAL`, unless the old protocol is enabled with
+/// `SUPERVISE_LEGACY_WRITE`. Then, the bytes are taken as a big endian return value, and the
+/// stopped tracee resumes with it, skipping the syscall, or the write returns `EBUSY` if the
+/// tracee is not stopped.
+///
+/// Dropping the last duplicate of the resource detaches it from all of its tracees.
 ///
-/// Writing will simply left shift EAX by one byte, and then OR it with the byte from the buffer,
-/// effectively writing the buffer to the EAX register (truncating the additional bytes).
+/// An observe only supervisor, created with `SupervisorResource::observe`, reads the same events,
+/// but never stops the tracee, and returns `EACCES` for every write.
 pub struct SupervisorResource {
-    /// The jailed context.
-    ctx: *mut Context,
+    /// The tracee that commands are sent to, which is the tracee of the last stop event read, and
+    /// where the next read starts looking for an event, see `next_event`
+    target: usize,
+    /// Whether every child of the supervisor, and their descendants, are supervised
+    all: bool,
+    /// Events from the tracees, tagged with the PID of the tracee
+    recv: Arc<WaitQueue<(usize, SupervisorEvent)>>,
+    /// The number of replies requested, which have not been read yet
+    replies: usize,
+    /// Whether reads return `EAGAIN` instead of blocking
+    nonblock: bool,
+    /// Whether this only observes the tracee, without commanding it
+    observe: bool,
+    /// Whether writes shorter than a packet are taken as `SUPERVISE_RETURN`, see
+    /// `SUPERVISE_LEGACY_WRITE`
+    legacy_write: bool,
 }
 
 impl SupervisorResource {
     /// Create a new supervisor resource, supervising some PID.
-    pub unsafe fn new(ctx: *mut Context) -> Result<SupervisorResource> {
-        Ok(SupervisorResource {
-            ctx: ctx,
-        })
+    ///
+    /// The tracee must be a child of the current context, and can only have one supervisor. A PID
+    /// of 0 supervises every child of the current context which has no supervisor, along with any
+    /// children they create.
+    ///
+    /// Fails with `EINVAL` if the PID is the current context, `ESRCH` if there is no such PID,
+    /// `EACCES` if it is not a child of the current context, such as an ancestor, and `EPERM` if it
+    /// already has a supervisor.
+    pub fn new(pid: usize) -> Result<SupervisorResource> {
+        let contexts = unsafe { &mut *::env().contexts.get() };
+        let cur_pid = try!(contexts.current()).pid;
+
+        // The context would wait on itself
+        if pid == cur_pid {
+            return Err(Error::with_cause(EINVAL, "supervising itself"));
+        }
+
+        let supervisor = SupervisorResource {
+            target: pid,
+            all: pid == 0,
+            recv: Arc::new(WaitQueue::new_bounded(SUPERVISE_QUEUE)),
+            replies: 0,
+            nonblock: false,
+            legacy_write: false,
+            observe: false,
+        };
+
+        if supervisor.all {
+            for mut context in contexts.iter_mut() {
+                if context.ppid == cur_pid && context.pid != cur_pid &&
+                   !SupervisorResource::is_supervised(context) {
+                    supervisor.attach(context);
+                }
+            }
+        } else {
+            let jailed = try!(contexts.find_mut(pid)
+                                      .or(Err(Error::with_cause(ESRCH, "no such tracee"))));
+
+            // Make sure that this is actually a child process of the invoker.
+            if jailed.ppid != cur_pid {
+                return Err(Error::with_cause(EACCES, "tracee is not a child"));
+            }
+
+            if SupervisorResource::is_supervised(jailed) {
+                return Err(Error::with_cause(EPERM, "tracee already has a supervisor"));
+            }
+
+            supervisor.attach(jailed);
+        }
+
+        Ok(supervisor)
+    }
+
+    /// Create an observe only supervisor resource, observing some PID.
+    ///
+    /// The observer reads copies of the events of the tracee, without stopping it, and cannot send
+    /// it commands. Any number of observers may coexist with each other, and with a supervisor.
+    /// Events which happen while the observer has `SUPERVISE_QUEUE` unread events are dropped.
+    ///
+    /// Fails with `EINVAL` if the PID is the current context, `ESRCH` if there is no such PID, and
+    /// `EACCES` if it is not a child of the current context.
+    pub fn observe(pid: usize) -> Result<SupervisorResource> {
+        let contexts = unsafe { &mut *::env().contexts.get() };
+        let cur_pid = try!(contexts.current()).pid;
+
+        if pid == cur_pid {
+            return Err(Error::with_cause(EINVAL, "observing itself"));
+        }
+
+        let observed = try!(contexts.find_mut(pid)
+                                    .or(Err(Error::with_cause(ESRCH, "no such tracee"))));
+        if observed.ppid != cur_pid {
+            return Err(Error::with_cause(EACCES, "tracee is not a child"));
+        }
+
+        let observer = SupervisorResource {
+            target: pid,
+            all: false,
+            recv: Arc::new(WaitQueue::new_bounded(SUPERVISE_QUEUE)),
+            replies: 0,
+            nonblock: false,
+            legacy_write: false,
+            observe: true,
+        };
+        observed.observers.push(Arc::downgrade(&observer.recv));
+
+        Ok(observer)
+    }
+
+    /// Does the context have a supervisor?
+    ///
+    /// A tracee end left behind by a supervisor which no longer exists does not count, so the
+    /// context can be supervised again.
+    fn is_supervised(context: &Context) -> bool {
+        context.supervised_resource.as_ref().map_or(false, |resource| resource.is_attached())
+    }
+
+    /// Supervise a context
+    fn attach(&self, context: &mut Context) {
+        context.supervised = true;
+        context.supervised_resource = Some(SupervisedResource {
+            pid: context.pid,
+            send: Arc::downgrade(&self.recv),
+            recv: Arc::new(WaitQueue::new()),
+            descendants: self.all,
+            mask: SUPERVISE_MASK_ALL,
+            filter: Arc::new(Vec::new()),
+            default_rule: SuperviseRule::default(),
+            reap: false,
+        });
+    }
+
+    /// Is the context supervised by this resource?
+    fn owns(&self, context: &Context) -> bool {
+        if let Some(ref resource) = context.supervised_resource {
+            if let Some(send) = resource.send.upgrade() {
+                return &*send as *const _ == &*self.recv as *const _;
+            }
+        }
+        false
+    }
+
+    /// Stop supervising a context, resuming it if it is stopped
+    fn release(&self, context: &mut Context) {
+        if self.owns(context) {
+            context.supervised = false;
+            if let Some(resource) = context.supervised_resource.take() {
+                // A stopped tracee still holds the commands, so it can be woken
+                resource.recv.send(SupervisorCommand::Detach, "SupervisorResource::release");
+            }
+        }
+    }
+
+    /// Stop supervising every tracee, if this is the last duplicate of the resource
+    fn release_all(&self) {
+        if Arc::strong_count(&self.recv) == 1 {
+            let contexts = unsafe { &mut *::env().contexts.get() };
+            for mut context in contexts.iter_mut() {
+                self.release(context);
+            }
+        }
+    }
+
+    /// Check that a range belongs to the memory of the tracee
+    fn permission(&self, address: usize, len: usize, writeable: bool) -> Result<()> {
+        let contexts = unsafe { &*::env().contexts.get() };
+        try!(contexts.find(self.target)).permission(address, len, writeable)
+    }
+
+    /// Stop supervising the tracee, resuming it if it is stopped
+    ///
+    /// This does nothing if the tracee has exited, or has already been detached.
+    fn detach(&self) {
+        let contexts = unsafe { &mut *::env().contexts.get() };
+        if self.target > 0 {
+            if let Ok(mut jailed) = contexts.find_mut(self.target) {
+                self.release(jailed);
+            }
+        }
+    }
+
+    /// Check registers written with `SUPERVISE_SETREGS` against those of the stopped tracee
+    ///
+    /// Returns `EPERM` if they change the segment selectors or any flag outside of
+    /// `SUPERVISE_FLAGS`, such as IF or IOPL, and `EBUSY` if the tracee is not stopped.
+    fn check_regs(&self, regs: &Regs) -> Result<()> {
+        let jailed = try!(self.tracee());
+        let stopped = try!(jailed.stopped_regs
+                                 .ok_or(Error::with_cause(EBUSY, "tracee not stopped")));
+
+        if regs.cs != stopped.cs || regs.ss != stopped.ss ||
+           (regs.flags ^ stopped.flags) & !SUPERVISE_FLAGS != 0 {
+            return Err(Error::with_cause(EPERM, "registers change privileged state"));
+        }
+
+        Ok(())
+    }
+
+    /// Find the tracee that commands are sent to
+    fn tracee<'a>(&self) -> Result<&'a mut Context> {
+        if self.target == 0 {
+            return Err(Error::with_cause(ESRCH, "no tracee has stopped yet"));
+        }
+
+        let contexts = unsafe { &mut *::env().contexts.get() };
+        let cur_pid = try!(contexts.current()).pid;
+        let jailed = try!(contexts.find_mut(self.target));
+
+        if !self.all && jailed.ppid != cur_pid {
+            return Err(Error::with_cause(EACCES, "tracee is not a child"));
+        }
+
+        if !jailed.supervised || !self.owns(jailed) {
+            return Err(Error::with_cause(EPERM, "tracee has another supervisor"));
+        }
+
+        Ok(jailed)
+    }
+
+    /// Set what happens to the tracee when it exits, see `SUPERVISE_EXIT_POLICY`
+    fn set_exit_policy(&self, policy: usize) -> Result<()> {
+        let reap = match policy {
+            SUPERVISE_EXIT_ZOMBIE => false,
+            SUPERVISE_EXIT_REAP => true,
+            _ => return Err(Error::with_cause(EINVAL, "unknown exit policy")),
+        };
+
+        if let Some(ref mut resource) = try!(self.tracee()).supervised_resource {
+            resource.reap = reap;
+        }
+
+        Ok(())
+    }
+
+    /// Set the classes of events the tracee stops for
+    fn set_mask(&self, mask: usize) -> Result<()> {
+        if mask & !SUPERVISE_MASK_ALL != 0 {
+            return Err(Error::with_cause(EINVAL, "unknown mask bits"));
+        }
+
+        if let Some(ref mut resource) = try!(self.tracee()).supervised_resource {
+            resource.mask = mask;
+        }
+
+        Ok(())
+    }
+
+    /// Replace the syscall filter of the tracee
+    fn set_filter(&self, default_rule: SuperviseRule, filter: Vec<SuperviseRule>) -> Result<()> {
+        for rule in filter.iter().chain(Some(&default_rule)) {
+            if rule.action > SUPERVISE_FILTER_DENY ||
+               (rule.action == SUPERVISE_FILTER_DENY && rule.errno == 0) {
+                return Err(Error::with_cause(EINVAL, "invalid filter rule"));
+            }
+        }
+
+        if let Some(ref mut resource) = try!(self.tracee()).supervised_resource {
+            resource.filter = Arc::new(filter);
+            resource.default_rule = default_rule;
+        }
+
+        Ok(())
+    }
+
+    /// Send a command to the tracee, which must be stopped
+    fn command(&self, command: SupervisorCommand) -> Result<()> {
+        let jailed = try!(self.tracee());
+
+        if !jailed.blocked_syscall {
+            return Err(Error::with_cause(EBUSY, "tracee not stopped"));
+        }
+
+        if let Some(ref resource) = jailed.supervised_resource {
+            resource.recv.send(command, "SupervisorResource::command");
+        }
+
+        Ok(())
+    }
+
+    /// The index of the next event to read, taking the tracees in turn
+    ///
+    /// This is the first event of the tracee with the lowest PID after `target`, or of the tracee
+    /// with the lowest PID if there is none, so a tracee which stops over and over can not keep the
+    /// others from being served. The events of each tracee are still read in order.
+    fn next_event(&self) -> Option<usize> {
+        let target = self.target;
+        unsafe { self.recv.inner() }.iter()
+                                    .enumerate()
+                                    .min_by_key(|&(_, &(pid, _))| (pid <= target, pid))
+                                    .map(|(i, _)| i)
+    }
+
+    /// Take the next event, see `next_event`, if it is a stop event
+    fn take_stop(&self) -> Option<(usize, Packet)> {
+        let inner = unsafe { self.recv.inner() };
+        let i = match self.next_event() {
+            Some(i) => i,
+            None => return None,
+        };
+
+        let stop = match inner.get(i) {
+            Some(&(pid, SupervisorEvent::Stop(packet))) => (pid, packet),
+            _ => return None,
+        };
+        inner.remove(i);
+        self.recv.space.notify("SupervisorResource::take_stop");
+        Some(stop)
+    }
+
+    /// Take the next event, see `next_event`
+    fn take_next(&self) -> Option<(usize, SupervisorEvent)> {
+        let inner = unsafe { self.recv.inner() };
+        match self.next_event() {
+            Some(i) => {
+                self.recv.space.notify("SupervisorResource::take_next");
+                inner.remove(i)
+            },
+            None => None,
+        }
+    }
+
+    /// Take the first reply from a tracee, leaving any other events queued
+    fn take_reply(&self) -> Option<(usize, SupervisorEvent)> {
+        let inner = unsafe { self.recv.inner() };
+        match inner.iter().position(|&(_, ref event)| event.is_reply()) {
+            Some(i) => {
+                self.recv.space.notify("SupervisorResource::take_reply");
+                inner.remove(i)
+            },
+            None => None,
+        }
+    }
+
+    /// Tear down the tracee `pid`, or `target` if `pid` is 0, at once, see `SUPERVISE_FORCEKILL`
+    ///
+    /// Returns `ESRCH` unless it is a tracee of this resource, `EPERM` unless the current context
+    /// is its parent or has an IOPL of 3, and `EBUSY` if its exit event could not be queued.
+    fn force_kill(&self, pid: usize) -> Result<()> {
+        let pid = if pid == 0 { self.target } else { pid };
+        {
+            let contexts = unsafe { & *::env().contexts.get() };
+            let current = try!(contexts.current());
+            let tracee = try!(contexts.find(pid));
+            if !self.owns(tracee) {
+                return Err(Error::with_cause(ESRCH, "tracee has another supervisor"));
+            }
+            if tracee.ppid != current.pid && current.iopl != 3 {
+                return Err(Error::with_cause(EPERM, "tracee is not a child"));
+            }
+        }
+
+        if self.recv.is_full() {
+            return Err(Error::with_cause(EBUSY, "event queue full"));
+        }
+
+        ::syscall::process::force_exit(pid, 128 + SIGKILL)
+    }
+
+    /// Check if a tracee is left which could send an event
+    ///
+    /// A supervisor of every child gets a tracee whenever the current context creates a child.
+    fn has_tracees(&self) -> bool {
+        self.all || Arc::weak_count(&self.recv) > 0
+    }
+
+    /// Copy `first` to `buf`, followed by as many already queued stop events as fit whole
+    ///
+    /// Returns the number of bytes copied.
+    fn read_stops(&mut self, first: Packet, buf: &mut [u8]) -> usize {
+        let size = mem::size_of::<Packet>();
+
+        let mut packet = first;
+        let mut i = 0;
+        loop {
+            buf[i..i + size].copy_from_slice(&packet);
+            i += size;
+
+            if i + size > buf.len() {
+                break;
+            }
+
+            match self.take_stop() {
+                Some((pid, next)) => {
+                    self.target = pid;
+                    packet = next;
+                },
+                None => break,
+            }
+        }
+
+        i
+    }
+
+    /// Wait for the next event, or the next reply if one was requested
+    ///
+    /// The queue is also woken without an event, such as when the last tracee goes away, so only
+    /// an event taken from the queue is ever returned. Returns `EAGAIN` if nothing is queued and
+    /// the resource does not block, `ESRCH` if nothing is queued and no tracee is left, and `EINTR`
+    /// if the wait is canceled with `SUPERVISE_INTERRUPT`.
+    fn receive(&self) -> Result<(usize, SupervisorEvent)> {
+        loop {
+            let event = if self.replies > 0 {
+                self.take_reply()
+            } else {
+                self.take_next()
+            };
+            if let Some(event) = event {
+                return Ok(event);
+            }
+
+            if self.nonblock {
+                return Err(Error::with_cause(EAGAIN, "no events"));
+            }
+            if !self.has_tracees() {
+                return Err(Error::with_cause(ESRCH, "no tracees left"));
+            }
+
+            if !self.recv.condition.wait_interruptible("SupervisorResource::read") {
+                return Err(Error::with_cause(EINTR, "read interrupted"));
+            }
+        }
     }
 }
 
 impl Resource for SupervisorResource {
-    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
-        let ctx = unsafe { &mut *self.ctx };
-        while !ctx.blocked_syscall {
-            unsafe { context_switch() };
+    /// Detach the tracees, if this is the last duplicate of the resource
+    ///
+    /// The tracees are resumed before the file descriptor is gone, instead of whenever the resource
+    /// is dropped.
+    fn close(&mut self) -> Result<()> {
+        self.release_all();
+        Ok(())
+    }
+
+    /// Duplicate the resource
+    ///
+    /// The duplicate shares the events of the original, so each event is read by only one of
+    /// them. The tracees are detached when the last of them is dropped.
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box SupervisorResource {
+            target: self.target,
+            all: self.all,
+            recv: self.recv.clone(),
+            replies: self.replies,
+            nonblock: self.nonblock,
+            legacy_write: self.legacy_write,
+            observe: self.observe,
+        })
+    }
+
+    /// Run a supervisor command which takes at most one argument, like writing its packet
+    ///
+    /// `SUPERVISE_NONBLOCK`, `SUPERVISE_MASK`, `SUPERVISE_EXIT_POLICY`, `SUPERVISE_RETURN`,
+    /// `SUPERVISE_CONTINUE`, `SUPERVISE_DETACH`, `SUPERVISE_SIGNAL`, `SUPERVISE_STEP` and
+    /// `SUPERVISE_LEGACY_WRITE` are supported, with `arg` in place of `a`. `SUPERVISE_INTERRUPT`
+    /// cancels blocked reads, returning how many. Returns `EACCES` for other commands when
+    /// observing, and `EINVAL` for other commands.
+    fn control(&mut self, cmd: usize, arg: usize) -> Result<usize> {
+        if cmd == SUPERVISE_INTERRUPT {
+            return Ok(self.recv.interrupt(arg, "SupervisorResource::control"));
+        }
+
+        if self.observe {
+            return Err(Error::with_cause(EACCES, "observers can not command"));
+        }
+
+        match cmd {
+            SUPERVISE_NONBLOCK => self.nonblock = arg != 0,
+            SUPERVISE_LEGACY_WRITE => self.legacy_write = arg != 0,
+            SUPERVISE_MASK => try!(self.set_mask(arg)),
+            SUPERVISE_EXIT_POLICY => try!(self.set_exit_policy(arg)),
+            SUPERVISE_FORCEKILL => try!(self.force_kill(arg)),
+            SUPERVISE_RETURN => try!(self.command(SupervisorCommand::Return(arg))),
+            SUPERVISE_CONTINUE => try!(self.command(SupervisorCommand::Continue)),
+            SUPERVISE_DETACH => self.detach(),
+            SUPERVISE_SIGNAL => {
+                if arg == 0 || arg >= NSIG {
+                    return Err(Error::with_cause(EINVAL, "invalid signal"));
+                }
+                try!(self.command(SupervisorCommand::Signal(arg)));
+            }
+            SUPERVISE_STEP => try!(self.command(SupervisorCommand::Step)),
+            _ => return Err(Error::with_cause(EINVAL, "unknown command")),
         }
 
-        let call: Packet = ctx.regs.into();
+        Ok(0)
+    }
+
+    /// Get or set `O_NONBLOCK`, which is the same as `SUPERVISE_NONBLOCK`
+    fn fcntl(&mut self, cmd: usize, arg: usize) -> Result<usize> {
+        match cmd {
+            F_GETFL => Ok(self.flags()),
+            F_SETFL => {
+                self.nonblock = arg & O_NONBLOCK == O_NONBLOCK;
+                Ok(0)
+            }
+            _ => Err(Error::with_cause(EINVAL, "unknown fcntl command")),
+        }
+    }
 
-        for (&a, b) in call.iter().zip(buf.iter_mut()) {
-            *b = a;
+    fn flags(&self) -> usize {
+        if self.nonblock {
+            O_NONBLOCK
+        } else {
+            0
         }
+    }
+
+    /// An event or a reply is queued
+    fn is_readable(&self) -> bool {
+        self.recv.peek()
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let (pid, event) = try!(self.receive());
+
+        let data = match event {
+            SupervisorEvent::Stop(ref packet) => {
+                if buf.len() < mem::size_of::<Packet>() {
+                    // Leave the event for a read which fits it
+                    unsafe { self.recv.inner() }.push_front((pid, SupervisorEvent::Stop(*packet)));
+                    return Err(Error::with_cause(EINVAL, "buffer smaller than a packet"));
+                }
+
+                self.target = pid;
+                return Ok(self.read_stops(*packet, buf));
+            },
+            SupervisorEvent::Regs(ref regs) => {
+                if buf.len() != mem::size_of::<Regs>() {
+                    // Leave the registers for a read of the right size
+                    unsafe { self.recv.inner() }.push_front((pid, SupervisorEvent::Regs(*regs)));
+                    return Err(Error::with_cause(EINVAL, "buffer not the size of the registers"));
+                }
+
+                self.replies -= 1;
+
+                unsafe {
+                    slice::from_raw_parts(regs as *const Regs as *const u8, mem::size_of::<Regs>())
+                }
+            },
+            SupervisorEvent::FpRegs(ref fp_regs) => {
+                if buf.len() != mem::size_of::<FpRegs>() {
+                    // Leave the registers for a read of the right size
+                    let event = SupervisorEvent::FpRegs(*fp_regs);
+                    unsafe { self.recv.inner() }.push_front((pid, event));
+                    return Err(Error::with_cause(EINVAL,
+                                                 "buffer not the size of the FPU registers"));
+                }
 
-        ctx.blocked_syscall = false;
+                self.replies -= 1;
 
-        Ok(cmp::min(buf.len(), call.len()))
+                unsafe {
+                    slice::from_raw_parts(fp_regs as *const FpRegs as *const u8,
+                                          mem::size_of::<FpRegs>())
+                }
+            },
+            SupervisorEvent::Memory(ref data, last) => {
+                if buf.len() < data.len() {
+                    // Leave the rest of the memory for the next read
+                    let rest = SupervisorEvent::Memory(data[buf.len()..].to_vec(), last);
+                    unsafe { self.recv.inner() }.push_front((pid, rest));
+                } else if last {
+                    self.replies -= 1;
+                }
+
+                data
+            },
+            SupervisorEvent::Fault => {
+                self.replies -= 1;
+                return Err(Error::with_cause(EFAULT, "tracee memory not mapped"));
+            },
+        };
+
+        let count = cmp::min(buf.len(), data.len());
+        buf[..count].copy_from_slice(&data[..count]);
+        Ok(count)
     }
 
     fn write(&mut self, buf: &[u8]) -> Result<usize> {
-        let ctx = unsafe { &mut *self.ctx };
+        if self.observe {
+            return Err(Error::with_cause(EACCES, "observers can not command"));
+        }
+
+        if buf.len() < mem::size_of::<Packet>() {
+            if !self.legacy_write {
+                return Err(Error::with_cause(EINVAL, "write shorter than a packet"));
+            }
+
+            let mut value = 0;
+            for &i in buf.iter().take(mem::size_of::<usize>()) {
+                value <<= 8;
+                value |= i as usize;
+            }
+
+            try!(self.command(SupervisorCommand::Return(value)));
+
+            return Ok(cmp::min(mem::size_of::<usize>(), buf.len()));
+        }
 
-        for &i in buf.iter().take(mem::size_of::<usize>()) {
-            ctx.regs.ax <<= 8;
-            ctx.regs.ax |= i as usize;
+        // The buffer may not be aligned for a packet, so it is copied into one which is
+        let mut packet = Packet::default();
+        packet.copy_from_slice(&buf[..mem::size_of::<Packet>()]);
+        let payload = &buf[mem::size_of::<Packet>()..];
+
+        if packet.magic != PACKET_MAGIC {
+            return Err(Error::with_cause(EINVAL, "bad packet magic"));
         }
 
-        ctx.block("SupervisorResource::write");
+        match packet.id {
+            SUPERVISE_NONBLOCK | SUPERVISE_MASK | SUPERVISE_EXIT_POLICY | SUPERVISE_RETURN |
+            SUPERVISE_CONTINUE | SUPERVISE_DETACH | SUPERVISE_SIGNAL | SUPERVISE_STEP |
+            SUPERVISE_LEGACY_WRITE if payload.is_empty() => {
+                try!(self.control(packet.id, packet.a));
+            },
+            SUPERVISE_FILTER if payload.len() % mem::size_of::<SuperviseRule>() == 0 => {
+                let default_rule = SuperviseRule {
+                    syscall: 0,
+                    action: packet.a,
+                    errno: packet.b,
+                };
+                let filter = payload.chunks(mem::size_of::<SuperviseRule>()).map(|chunk| {
+                    let mut rule = SuperviseRule::default();
+                    unsafe {
+                        slice::from_raw_parts_mut(&mut rule as *mut SuperviseRule as *mut u8,
+                                                  mem::size_of::<SuperviseRule>())
+                    }.copy_from_slice(chunk);
+                    rule
+                }).collect();
+                try!(self.set_filter(default_rule, filter));
+            },
+            SUPERVISE_GETREGS if payload.is_empty() => {
+                try!(self.command(SupervisorCommand::GetRegs));
+                self.replies += 1;
+            },
+            SUPERVISE_SETREGS if payload.len() == mem::size_of::<Regs>() => {
+                let mut regs = Regs::default();
+                unsafe {
+                    slice::from_raw_parts_mut(&mut regs as *mut Regs as *mut u8,
+                                              mem::size_of::<Regs>())
+                }.copy_from_slice(payload);
+
+                try!(self.check_regs(&regs));
+                try!(self.command(SupervisorCommand::SetRegs(regs)));
+            },
+            SUPERVISE_GETFPREGS if payload.is_empty() => {
+                try!(self.command(SupervisorCommand::GetFpRegs));
+                self.replies += 1;
+            },
+            SUPERVISE_SETFPREGS => {
+                if payload.len() != mem::size_of::<FpRegs>() {
+                    return Err(Error::with_cause(EINVAL,
+                                                 "payload not the size of the FPU registers"));
+                }
+
+                let mut fp_regs = FpRegs::default();
+                unsafe {
+                    slice::from_raw_parts_mut(&mut fp_regs as *mut FpRegs as *mut u8,
+                                              mem::size_of::<FpRegs>())
+                }.copy_from_slice(payload);
+
+                if fp_regs.mxcsr & !SUPERVISE_MXCSR != 0 {
+                    return Err(Error::with_cause(EINVAL, "unsupported MXCSR bits"));
+                }
+                try!(self.command(SupervisorCommand::SetFpRegs(fp_regs)));
+            },
+            SUPERVISE_PEEK if payload.is_empty() && packet.b > 0 => {
+                try!(self.permission(packet.a, packet.b, false));
+                try!(self.command(SupervisorCommand::Peek(packet.a, packet.b)));
+                self.replies += 1;
+            },
+            SUPERVISE_SETARGS if payload.is_empty() => {
+                try!(self.command(SupervisorCommand::SetArgs(packet.a, packet.b, packet.c)));
+            },
+            SUPERVISE_POKE => {
+                try!(self.permission(packet.a, payload.len(), true));
+                try!(self.command(SupervisorCommand::Poke(packet.a, payload.to_vec())));
+            },
+            _ => return Err(Error::with_cause(EINVAL, "unknown command")),
+        }
 
-        Ok(cmp::min(mem::size_of::<usize>(), buf.len()))
+        Ok(buf.len())
     }
 
     // TODO implement seek?
 }
+
+impl Drop for SupervisorResource {
+    fn drop(&mut self) {
+        self.release_all();
+    }
+}
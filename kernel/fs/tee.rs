@@ -0,0 +1,127 @@
+use alloc::boxed::Box;
+
+use core::cmp;
+
+use fs::{Resource, ResourceSeek};
+
+use system::error::Result;
+use system::syscall::Stat;
+
+/// A resource which writes everything to two resources, and reads from the first
+///
+/// A write returns the bytes both sinks accepted. If the second accepts fewer than the first, the
+/// write is short, and the first remembers how far it is ahead, so when the caller retries with
+/// the rest of the buffer, as it should after a short write, only the second is given the bytes
+/// the first already has. Mapping is not supported, as a mapping could only reach one of them.
+pub struct Tee<A: Resource + ?Sized, B: Resource + ?Sized> {
+    first: Box<A>,
+    second: Box<B>,
+    /// The bytes at the start of the next write which the first has and the second does not
+    ahead: usize,
+}
+
+impl<A: Resource + ?Sized, B: Resource + ?Sized> Tee<A, B> {
+    /// Write to both `first` and `second`, and read from `first`
+    pub fn new(first: Box<A>, second: Box<B>) -> Tee<A, B> {
+        Tee {
+            first: first,
+            second: second,
+            ahead: 0,
+        }
+    }
+
+    /// The resource which is read from
+    pub fn first(&self) -> &A {
+        &self.first
+    }
+
+    /// The other resource which is written to
+    pub fn second(&self) -> &B {
+        &self.second
+    }
+}
+
+impl<A: Resource + ?Sized, B: Resource + ?Sized> Resource for Tee<A, B> {
+    /// Close both, even if the first fails
+    fn close(&mut self) -> Result<()> {
+        let first = self.first.close();
+        try!(self.second.close());
+        first
+    }
+
+    fn control(&mut self, cmd: usize, arg: usize) -> Result<usize> {
+        self.first.control(cmd, arg)
+    }
+
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box Tee::new(try!(self.first.dup()), try!(self.second.dup())))
+    }
+
+    fn fcntl(&mut self, cmd: usize, arg: usize) -> Result<usize> {
+        self.first.fcntl(cmd, arg)
+    }
+
+    fn flags(&self) -> usize {
+        self.first.flags()
+    }
+
+    fn is_readable(&self) -> bool {
+        self.first.is_readable()
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.first.is_terminal()
+    }
+
+    fn is_writable(&self) -> bool {
+        self.first.is_writable() && self.second.is_writable()
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        self.first.path(buf)
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        self.first.read(buf)
+    }
+
+    /// Write to the first, then what it accepted to the second
+    ///
+    /// Returns the bytes the second accepted, which are never more than the first accepted, or the
+    /// first error of either. If the second fails, the first is still ahead by what it accepted.
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let skip = cmp::min(self.ahead, buf.len());
+        let count = if skip < buf.len() || buf.is_empty() {
+            skip + try!(self.first.write(&buf[skip..]))
+        } else {
+            skip
+        };
+
+        self.ahead = count;
+        let written = try!(self.second.write(&buf[..count]));
+        self.ahead = count - cmp::min(written, count);
+        Ok(written)
+    }
+
+    /// Seek both to the same offset, returning the offset of the first
+    fn seek(&mut self, pos: ResourceSeek) -> Result<usize> {
+        let offset = try!(self.first.seek(pos));
+        try!(self.second.seek(ResourceSeek::Start(offset)));
+        self.ahead = 0;
+        Ok(offset)
+    }
+
+    fn stat(&self, stat: &mut Stat) -> Result<usize> {
+        self.first.stat(stat)
+    }
+
+    fn sync(&mut self) -> Result<()> {
+        try!(self.first.sync());
+        self.second.sync()
+    }
+
+    fn truncate(&mut self, len: usize) -> Result<()> {
+        try!(self.first.truncate(len));
+        self.second.truncate(len)
+    }
+}
@@ -9,8 +9,25 @@ use core::result::Result::{Ok, Err};
 
 use common::slice::GetSlice;
 
-use system::error::{Result, Error};
-use system::syscall::{O_CREAT, O_RDWR, O_TRUNC};
+use system::error::{Result, Error, EINVAL};
+use system::syscall::{O_APPEND, O_CLOEXEC, O_CREAT, O_EXCL, O_NONBLOCK, O_RDWR, O_TRUNC};
+
+/// The open flags which can be given in the query of a URL, see `Url::flags`
+pub const URL_FLAGS: [(&'static str, usize); 6] = [("append", O_APPEND),
+                                                   ("cloexec", O_CLOEXEC),
+                                                   ("create", O_CREAT),
+                                                   ("excl", O_EXCL),
+                                                   ("nonblock", O_NONBLOCK),
+                                                   ("trunc", O_TRUNC)];
+
+/// The encoding of an introspection resource, such as `memory:`, see `Url::format`
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Format {
+    /// Human readable text, the default
+    Text,
+    /// A struct of `system::syscall`, whose layout is stable
+    Binary,
+}
 
 /// A URL, see wiki
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
@@ -72,6 +89,55 @@ impl<'a> Url<'a> {
         self.reference
     }
 
+    /// Get the reference without its query
+    pub fn path(self) -> &'a str {
+        match self.reference.find('?') {
+            Some(i) => &self.reference[..i],
+            None => self.reference,
+        }
+    }
+
+    /// Get the query (after the first '?') of the reference, which is empty if there is none
+    pub fn query(self) -> &'a str {
+        match self.reference.find('?') {
+            Some(i) => &self.reference[i + 1..],
+            None => "",
+        }
+    }
+
+    /// Add the open flags named in the query, such as `?nonblock&append`, to `flags`
+    ///
+    /// Schemes which take flags from the URL call this in `open`, and use `path` instead of
+    /// `reference` for the rest of the URL. The names are those of `URL_FLAGS`, and empty names
+    /// are skipped. Returns `EINVAL` if a name is unknown, so a misspelt flag is never ignored.
+    pub fn flags(self, flags: usize) -> Result<usize> {
+        let mut flags = flags;
+        for name in self.query().split('&').filter(|name| !name.is_empty()) {
+            match URL_FLAGS.iter().find(|&&(flag_name, _)| flag_name == name) {
+                Some(&(_, flag)) => flags |= flag,
+                None => return Err(Error::new(EINVAL)),
+            }
+        }
+        Ok(flags)
+    }
+
+    /// Get the format chosen with `format=text` or `format=binary` in the query, such as
+    /// `memory:?format=binary`, or `Format::Text` if there is none
+    ///
+    /// Schemes which take other options in the query skip the `format=` option. Returns `EINVAL`
+    /// for any other format, so tools never get text when they asked for a struct.
+    pub fn format(self) -> Result<Format> {
+        let mut format = Format::Text;
+        for option in self.query().split('&').filter(|option| option.starts_with("format=")) {
+            format = match &option[7..] {
+                "text" => Format::Text,
+                "binary" => Format::Binary,
+                _ => return Err(Error::new(EINVAL)),
+            };
+        }
+        Ok(format)
+    }
+
     /// To owned equivalent
     pub fn to_owned(&self) -> OwnedUrl {
         OwnedUrl {
@@ -1,26 +1,68 @@
 use super::{Resource, ResourceSeek};
 
+use alloc::arc::Arc;
 use alloc::boxed::Box;
 
 use collections::{String, Vec};
 
 use core::cmp::{max, min};
 
-use system::error::Result;
+use system::error::{Error, Result, EINVAL, ENOSPC, ENXIO};
+use system::syscall::{F_GETFL, F_SETFL, MAP_WRITE, MODE_FILE, O_APPEND, Stat};
 
 /// A vector resource
+///
+/// The data can be shared between resources, such as the ones handed out for the same content by
+/// a scheme, or the duplicates a forked context gets, in which case it is only copied when one of
+/// them writes to it.
+///
+/// The data can be capped to a maximum size, see `bounded`. A write or truncate which would grow
+/// it past the cap fails with `ENOSPC`, and changes nothing.
 pub struct VecResource {
     path: String,
-    data: Vec<u8>,
+    data: Arc<Vec<u8>>,
     seek: usize,
+    /// Whether every write goes to the end, set with `O_APPEND`
+    append: bool,
+    /// The size the data can grow to, or `None` if it is unbounded
+    max_size: Option<usize>,
 }
 
 impl VecResource {
     pub fn new(path: String, data: Vec<u8>) -> Self {
+        VecResource::from_shared(path, Arc::new(data))
+    }
+
+    /// Create a resource reading from shared data, without copying it
+    pub fn from_shared(path: String, data: Arc<Vec<u8>>) -> Self {
         VecResource {
             path: path,
             data: data,
             seek: 0,
+            append: false,
+            max_size: None,
+        }
+    }
+
+    /// Create a resource whose data never grows past `max_size` bytes
+    ///
+    /// Data longer than the cap can still be read, and shrunk.
+    pub fn bounded(path: String, data: Vec<u8>, max_size: usize) -> Self {
+        VecResource {
+            max_size: Some(max_size),
+            ..VecResource::new(path, data)
+        }
+    }
+
+    /// Check that the data can be `len` bytes long
+    ///
+    /// Returns `ENOSPC` if `len` is past the cap, unless the data is already as long.
+    fn check_size(&self, len: usize) -> Result<()> {
+        match self.max_size {
+            Some(max_size) if len > max_size && len > self.data.len() => {
+                Err(Error::new(ENOSPC))
+            },
+            _ => Ok(()),
         }
     }
 
@@ -30,14 +72,50 @@ impl VecResource {
 }
 
 impl Resource for VecResource {
+    /// Duplicate the resource
+    ///
+    /// The duplicate has its own cursor, and its own copy of the data once either of them writes.
     fn dup(&self) -> Result<Box<Resource>> {
         Ok(box VecResource {
             path: self.path.clone(),
             data: self.data.clone(),
             seek: self.seek,
+            append: self.append,
+            max_size: self.max_size,
         })
     }
 
+    /// Get or set `O_APPEND`
+    ///
+    /// A scheme can pass the flags it was opened with to `F_SETFL`, to append from the start.
+    fn fcntl(&mut self, cmd: usize, arg: usize) -> Result<usize> {
+        match cmd {
+            F_GETFL => Ok(self.flags()),
+            F_SETFL => {
+                self.append = arg & O_APPEND == O_APPEND;
+                Ok(0)
+            }
+            _ => Err(Error::new(EINVAL)),
+        }
+    }
+
+    fn flags(&self) -> usize {
+        if self.append {
+            O_APPEND
+        } else {
+            0
+        }
+    }
+
+    /// Map a private copy of the data into the current context, zeroed past its end
+    ///
+    /// Writes to the mapping do not change the data. Returns `EINVAL` if `len` is 0.
+    fn mmap(&mut self, len: usize, flags: usize) -> Result<usize> {
+        let contexts = unsafe { & *::env().contexts.get() };
+        let current = try!(contexts.current());
+        current.map_copy(&self.data, len, flags & MAP_WRITE == MAP_WRITE)
+    }
+
     fn path(&self, buf: &mut [u8]) -> Result <usize> {
         let path = self.path.as_bytes();
 
@@ -63,15 +141,43 @@ impl Resource for VecResource {
         return Ok(i);
     }
 
-    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+    /// Read up to and including the next `\n`, or as much of the line as fits in `buf`
+    ///
+    /// The rest of a line which does not fit is left for the next read. Returns 0 at the end.
+    fn read_line(&mut self, buf: &mut [u8]) -> Result<usize> {
         let mut i = 0;
         while i < buf.len() && self.seek < self.data.len() {
-            self.data[self.seek] = buf[i];
+            let b = self.data[self.seek];
+            buf[i] = b;
+            self.seek += 1;
+            i += 1;
+            if b == b'\n' {
+                break;
+            }
+        }
+        Ok(i)
+    }
+
+    /// Write at the cursor, or at the end if `O_APPEND` is set
+    ///
+    /// Returns `ENOSPC` if the data would grow past its cap.
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if self.append {
+            self.seek = self.data.len();
+        }
+
+        try!(self.check_size(self.seek.saturating_add(buf.len())));
+
+        let data = Arc::make_mut(&mut self.data);
+
+        let mut i = 0;
+        while i < buf.len() && self.seek < data.len() {
+            data[self.seek] = buf[i];
             self.seek += 1;
             i += 1;
         }
         while i < buf.len() {
-            self.data.push(buf[i]);
+            data.push(buf[i]);
             self.seek += 1;
             i += 1;
         }
@@ -82,26 +188,44 @@ impl Resource for VecResource {
         match pos {
             ResourceSeek::Start(offset) => self.seek = min(self.data.len(), offset),
             ResourceSeek::Current(offset) =>
-                self.seek = max(0, min(self.seek as isize, self.seek as isize + offset)) as usize,
+                self.seek = max(0,
+                                min(self.data.len() as isize,
+                                    self.seek as isize + offset)) as usize,
             ResourceSeek::End(offset) =>
                 self.seek = max(0,
-                                min(self.seek as isize,
-                                    self.data.len() as isize +
-                                    offset)) as usize,
+                                min(self.data.len() as isize,
+                                    self.data.len() as isize + offset)) as usize,
+            // The data is dense, so every offset before the end is data, and the end is the hole
+            ResourceSeek::Data(offset) | ResourceSeek::Hole(offset) if offset >= self.data.len() =>
+                return Err(Error::new(ENXIO)),
+            ResourceSeek::Data(offset) => self.seek = offset,
+            ResourceSeek::Hole(_) => self.seek = self.data.len(),
         }
         return Ok(self.seek);
     }
 
+    fn stat(&self, stat: &mut Stat) -> Result<usize> {
+        stat.st_mode = MODE_FILE;
+        stat.st_size = self.data.len() as u32;
+        Ok(0)
+    }
+
     fn sync(&mut self) -> Result<()> {
         Ok(())
     }
 
+    /// Resize the data to `len` bytes, zero filling
+    ///
+    /// Returns `ENOSPC` if the data would grow past its cap.
     fn truncate(&mut self, len: usize) -> Result<()> {
-        while len > self.data.len() {
-            self.data.push(0);
+        try!(self.check_size(len));
+
+        let data = Arc::make_mut(&mut self.data);
+        while len > data.len() {
+            data.push(0);
         }
-        self.data.truncate(len);
-        self.seek = min(self.seek, self.data.len());
+        data.truncate(len);
+        self.seek = min(self.seek, data.len());
         Ok(())
     }
 }
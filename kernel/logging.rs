@@ -72,6 +72,7 @@ pub fn syslog_inner(level: LogLevel, message: fmt::Arguments) {
     };
 
     let _ = write!(unsafe { &mut *::env().log.get() }, "[{}.{:>03}] {}{}\n", time.secs, time.nanos/1000000, prefix, message);
+    ::env().log_written.notify("syslog");
     if display {
         let _ = write!(::common::debug::SerialConsole::new(), "[{}.{:>03}] {}{}\n", time.secs, time.nanos/1000000, prefix, message);
     }
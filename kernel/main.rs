@@ -48,17 +48,46 @@ use graphics::display;
 
 use network::schemes::{ArpScheme, EthernetScheme, IcmpScheme, IpScheme, NetConfigScheme, TcpScheme, UdpScheme};
 
+use schemes::console::ConsoleScheme;
 use schemes::context::ContextScheme;
+use schemes::cpu::CpuScheme;
+use schemes::cwd::CwdScheme;
 use schemes::debug::DebugScheme;
 use schemes::disk::DiskScheme;
 use schemes::display::DisplayScheme;
 use schemes::env::EnvScheme;
+use schemes::event::EventScheme;
+use schemes::faults::FaultsScheme;
+use schemes::full::FullScheme;
+use schemes::futex::FutexScheme;
+use schemes::hostname::HostnameScheme;
 use schemes::initfs::InitFsScheme;
 use schemes::interrupt::InterruptScheme;
+use schemes::irq::IrqScheme;
+use schemes::kill::KillScheme;
+use schemes::kparam::KparamScheme;
+use schemes::log::LogScheme;
+use schemes::loopback::LoopbackScheme;
 use schemes::memory::MemoryScheme;
+use schemes::null::NullScheme;
+use schemes::pipe::PipeScheme;
+use schemes::pressure::PressureScheme;
 use schemes::pty::PtyScheme;
+use schemes::random::RandomScheme;
+use schemes::repeat::RepeatScheme;
+use schemes::schemes::SchemesScheme;
+use schemes::shm::ShmScheme;
+use schemes::sleep::SleepScheme;
+use schemes::sysinfo::SysInfoScheme;
 use schemes::syslog::SyslogScheme;
 use schemes::test::TestScheme;
+use schemes::test_random::TestRandomScheme;
+use schemes::time::TimeScheme;
+use schemes::timer::TimerScheme;
+use schemes::trace::TraceScheme;
+use schemes::uptime::UptimeScheme;
+use schemes::watchdog::WatchdogScheme;
+use schemes::zero::ZeroScheme;
 
 use syscall::process::exit;
 use syscall::execute::execute;
@@ -192,15 +221,6 @@ pub fn env() -> &'static Environment {
     }
 }
 
-/// The PIT (programmable interval timer) duration.
-///
-/// This duration defines the PIT interval, which is added to the monotonic clock and the real time
-/// clock, when interrupt 0x20 is received.
-static PIT_DURATION: Duration = Duration {
-    secs: 0,
-    nanos: 4500572,
-};
-
 /// The idle loop.
 ///
 /// This loop runs while the system is idle.
@@ -403,20 +423,52 @@ unsafe fn init(tss_data: usize) {
 
             *env.clock_realtime.get() = Rtc::new().time();
 
+            common::random::init();
+
             (&mut *env.schemes.get()).push(Ps2::new());
 
             pci::pci_init(env);
 
             (&mut *env.schemes.get()).push(DebugScheme::new());
             (&mut *env.schemes.get()).push(InitFsScheme::new());
+            (&mut *env.schemes.get()).push(box ConsoleScheme);
             (&mut *env.schemes.get()).push(box ContextScheme);
+            (&mut *env.schemes.get()).push(box CpuScheme);
+            (&mut *env.schemes.get()).push(box CwdScheme);
             (&mut *env.schemes.get()).push(box DisplayScheme);
             (&mut *env.schemes.get()).push(box EnvScheme);
+            (&mut *env.schemes.get()).push(box EventScheme);
+            (&mut *env.schemes.get()).push(box FaultsScheme);
+            (&mut *env.schemes.get()).push(box FullScheme);
+            (&mut *env.schemes.get()).push(box FutexScheme);
+            (&mut *env.schemes.get()).push(box HostnameScheme);
             (&mut *env.schemes.get()).push(box InterruptScheme);
+            (&mut *env.schemes.get()).push(box IrqScheme);
+            (&mut *env.schemes.get()).push(box KillScheme);
+            (&mut *env.schemes.get()).push(box KparamScheme);
+            (&mut *env.schemes.get()).push(box LogScheme);
+            (&mut *env.schemes.get()).push(box LoopbackScheme);
             (&mut *env.schemes.get()).push(box MemoryScheme);
+            (&mut *env.schemes.get()).push(box NullScheme);
+            (&mut *env.schemes.get()).push(PipeScheme::new());
+            (&mut *env.schemes.get()).push(box PressureScheme);
             (&mut *env.schemes.get()).push(PtyScheme::new());
+            (&mut *env.schemes.get()).push(RandomScheme::random());
+            (&mut *env.schemes.get()).push(RandomScheme::urandom());
+            (&mut *env.schemes.get()).push(box RepeatScheme);
+            (&mut *env.schemes.get()).push(box SchemesScheme);
+            (&mut *env.schemes.get()).push(ShmScheme::new());
+            (&mut *env.schemes.get()).push(box SleepScheme);
+            (&mut *env.schemes.get()).push(box SysInfoScheme);
             (&mut *env.schemes.get()).push(box SyslogScheme);
             (&mut *env.schemes.get()).push(box TestScheme);
+            (&mut *env.schemes.get()).push(box TestRandomScheme);
+            (&mut *env.schemes.get()).push(box TimeScheme);
+            (&mut *env.schemes.get()).push(box TimerScheme);
+            (&mut *env.schemes.get()).push(box TraceScheme);
+            (&mut *env.schemes.get()).push(box UptimeScheme);
+            (&mut *env.schemes.get()).push(box WatchdogScheme);
+            (&mut *env.schemes.get()).push(box ZeroScheme);
 
             //TODO: Do not do this! Find a better way
             let mut disks = Vec::new();
@@ -487,7 +539,7 @@ unsafe fn init(tss_data: usize) {
 /// Interrupt and exception handling.
 pub extern "cdecl" fn kernel(interrupt: usize, mut regs: &mut Regs) {
     macro_rules! exception_inner {
-        ($name:expr) => ({
+        ($name:expr, $error:expr) => ({
             {
                 let contexts = unsafe { &mut *::env().contexts.get() };
                 if let Ok(context) = contexts.current() {
@@ -583,15 +635,18 @@ pub extern "cdecl" fn kernel(interrupt: usize, mut regs: &mut Regs) {
                 }
             }
             */
+
+            let address = if interrupt == 0xE { cr2 } else { 0 };
+            schemes::faults::record(interrupt, address, $error, regs.ip);
         })
     };
 
     macro_rules! exception {
         ($name:expr) => ({
-            exception_inner!($name);
+            exception_inner!($name, 0);
 
             loop {
-                exit(127);
+                exit(127, regs);
             }
         })
     };
@@ -606,11 +661,11 @@ pub extern "cdecl" fn kernel(interrupt: usize, mut regs: &mut Regs) {
             regs.ss = 0;
             //regs.ss = regs.error;
 
-            exception_inner!($name);
+            exception_inner!($name, error);
             syslog_info!("    ERR: {:08X}", error);
 
             loop {
-                exit(127);
+                exit(127, regs);
             }
         })
     };
@@ -620,22 +675,41 @@ pub extern "cdecl" fn kernel(interrupt: usize, mut regs: &mut Regs) {
         unsafe { (&mut *env().interrupts.get())[interrupt as usize] += 1 };
     }
 
+    if interrupt >= 0x20 && interrupt < 0x30 {
+        schemes::trace::record(syscall::TRACE_IRQ, interrupt - 0x20, 0);
+        common::random::add_interrupt_entropy(interrupt);
+        env().irq.notify("interrupt");
+    }
+
     match interrupt {
         0x20 => {
+            // The interval of the PIT, which may be changed through `timer:`
+            let tick = unsafe { & *env().pit.get() }.duration();
             {
                 let mut clock_monotonic = unsafe { &mut *env().clock_monotonic.get() };
-                *clock_monotonic = *clock_monotonic + PIT_DURATION;
+                *clock_monotonic = *clock_monotonic + tick;
             }
             {
                 let mut clock_realtime = unsafe { &mut *env().clock_realtime.get() };
-                *clock_realtime = *clock_realtime + PIT_DURATION;
+                *clock_realtime = *clock_realtime + tick;
             }
 
+            unsafe { *env().ticks.get() += 1 };
+            env().tick.notify("tick");
+
+            schemes::sysinfo::sample();
+            schemes::watchdog::check(Duration::monotonic());
+
+            // A context with a high priority may keep running for another slice
+            let mut preempt = true;
             if let Ok(mut current) = unsafe { &mut *env().contexts.get() }.current_mut() {
-                current.time += 1;
+                current.tick(regs);
+                preempt = !current.take_credit(0);
             }
 
-            unsafe { context_switch(); }
+            if preempt {
+                unsafe { context_switch(); }
+            }
         }
         i @ 0x21 ... 0x2F => {
             env().on_irq(i as u8 - 0x20);
@@ -648,7 +722,9 @@ pub extern "cdecl" fn kernel(interrupt: usize, mut regs: &mut Regs) {
             }
         },
         0x0 => exception!("Divide by zero exception"),
-        0x1 => exception!("Debug exception"),
+        0x1 => if !fs::supervisor_resource::debug(regs) {
+            exception!("Debug exception");
+        },
         0x2 => exception!("Non-maskable interrupt"),
         0x3 => exception!("Breakpoint exception"),
         0x4 => exception!("Overflow exception"),
@@ -0,0 +1,251 @@
+use alloc::boxed::Box;
+
+use core::cmp;
+
+use drivers::kb_layouts::layouts::{Keymap, Layout};
+
+use env::console::{Tty, WinSize};
+
+use fs::{KScheme, Resource, Url};
+
+use system::error::{Error, Result, EINVAL, ENOENT};
+use system::syscall::{CONSOLE_ECHO, CONSOLE_GETPIXELS, CONSOLE_GETWINSZ, CONSOLE_RAW,
+                      CONSOLE_SETPIXELS, CONSOLE_SETWINSZ, SIGWINCH};
+
+fn tty() -> &'static mut Tty {
+    unsafe { &mut (*::env().console.get()).tty }
+}
+
+fn size() -> &'static mut WinSize {
+    unsafe { &mut (*::env().console.get()).size }
+}
+
+/// Pack two dimensions into one value, the first in the high 16 bits
+fn pack(high: usize, low: usize) -> usize {
+    high << 16 | low
+}
+
+/// Unpack two dimensions packed with `pack`
+///
+/// Returns `EINVAL` if either is 0, or the value has more than 32 bits.
+fn unpack(value: usize) -> Result<(usize, usize)> {
+    let (high, low) = (value >> 16, value & 0xFFFF);
+    if high == 0 || high > 0xFFFF || low == 0 {
+        return Err(Error::new(EINVAL));
+    }
+    Ok((high, low))
+}
+
+/// Resize the window, telling the foreground context with SIGWINCH
+///
+/// Returns the old size.
+fn resize(new_size: WinSize) -> WinSize {
+    let old_size = *size();
+    *size() = new_size;
+
+    let contexts = unsafe { &mut *::env().contexts.get() };
+    if let Ok(context) = contexts.find_mut(tty().foreground) {
+        context.signal(SIGWINCH);
+    }
+
+    old_size
+}
+
+/// A console resource
+///
+/// While any console resource is open, it takes the keyboard input of the kernel console, instead
+/// of `debug:`.
+pub struct ConsoleResource;
+
+impl ConsoleResource {
+    pub fn new() -> ConsoleResource {
+        let tty = tty();
+        tty.open += 1;
+        if let Ok(current) = unsafe { & *::env().contexts.get() }.current() {
+            tty.foreground = current.pid;
+        }
+        ConsoleResource
+    }
+}
+
+impl Resource for ConsoleResource {
+    /// Switch the line discipline with `CONSOLE_RAW` or `CONSOLE_ECHO`, or get or set the window
+    /// size with `CONSOLE_GETWINSZ`, `CONSOLE_SETWINSZ`, `CONSOLE_GETPIXELS` and
+    /// `CONSOLE_SETPIXELS`
+    /// Switching returns 1 if the mode was on before, or 0 if it was off, and setting a size
+    /// returns the old one. Returns `EINVAL` if a dimension of a new size is 0 or too large.
+    fn control(&mut self, cmd: usize, arg: usize) -> Result<usize> {
+        let tty = tty();
+        match cmd {
+            CONSOLE_RAW => {
+                let raw = tty.raw;
+                tty.set_raw(arg != 0);
+                Ok(raw as usize)
+            },
+            CONSOLE_ECHO => {
+                let echo = tty.echo;
+                tty.echo = arg != 0;
+                Ok(echo as usize)
+            },
+            CONSOLE_GETWINSZ => Ok(pack(size().rows, size().columns)),
+            CONSOLE_SETWINSZ => {
+                let (rows, columns) = try!(unpack(arg));
+                let old_size = resize(WinSize {
+                    rows: rows,
+                    columns: columns,
+                    ..*size()
+                });
+                Ok(pack(old_size.rows, old_size.columns))
+            },
+            CONSOLE_GETPIXELS => Ok(pack(size().height, size().width)),
+            CONSOLE_SETPIXELS => {
+                let (height, width) = try!(unpack(arg));
+                let old_size = resize(WinSize {
+                    width: width,
+                    height: height,
+                    ..*size()
+                });
+                Ok(pack(old_size.height, old_size.width))
+            },
+            _ => Err(Error::new(EINVAL)),
+        }
+    }
+
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box ConsoleResource::new())
+    }
+
+    /// There is input left to read
+    fn is_readable(&self) -> bool {
+        !tty().input.is_empty()
+    }
+
+    fn is_terminal(&self) -> bool {
+        true
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = b"console:";
+
+        for (b, p) in buf.iter_mut().zip(path.iter()) {
+            *b = *p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    /// Read input, blocking until a whole line was typed, or any byte in raw mode
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let tty = tty();
+        loop {
+            let count = tty.try_read(buf);
+            if count > 0 {
+                return Ok(count);
+            }
+            tty.input.readable.wait("ConsoleResource::read");
+        }
+    }
+
+    /// Write to the display
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        unsafe { &mut *::env().console.get() }.write(buf);
+        Ok(buf.len())
+    }
+
+    fn sync(&mut self) -> Result<()> {
+        let console = unsafe { &mut *::env().console.get() };
+        if let Some(ref mut inner) = console.inner {
+            inner.redraw = true;
+        }
+        console.write(&[]);
+        Ok(())
+    }
+}
+
+impl Drop for ConsoleResource {
+    fn drop(&mut self) {
+        tty().open -= 1;
+    }
+}
+
+/// The keyboard layout of the console
+///
+/// Reading returns the name of the layout, followed by a newline.
+pub struct KeymapResource {
+    pos: usize,
+}
+
+impl Resource for KeymapResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box KeymapResource { pos: self.pos })
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = b"console:keymap";
+
+        for (b, p) in buf.iter_mut().zip(path.iter()) {
+            *b = *p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let name = format!("{}\n", unsafe { & *::env().console.get() }.layout.name());
+
+        let mut i = 0;
+        for (b, n) in buf.iter_mut().zip(name.bytes().skip(self.pos)) {
+            *b = n;
+            i += 1;
+        }
+        self.pos += i;
+
+        Ok(i)
+    }
+
+    /// Load the keymap blob in `buf`, which has to be written whole, see `KEYMAP_SIZE`
+    ///
+    /// Returns `EINVAL` if it is not a valid keymap, in which case the built-in English layout is
+    /// used.
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let console = unsafe { &mut *::env().console.get() };
+        match Keymap::parse(buf) {
+            Ok(keymap) => {
+                console.layout = Layout::Custom(keymap);
+                Ok(buf.len())
+            },
+            Err(err) => {
+                console.layout = Layout::English;
+                Err(err)
+            },
+        }
+    }
+
+    fn sync(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The console scheme
+///
+/// Reads are line buffered and echoed, until `CONSOLE_RAW` or `CONSOLE_ECHO` are switched off with
+/// `sys_control`. `console:keymap` holds the keyboard layout, see `KeymapResource`.
+pub struct ConsoleScheme;
+
+impl KScheme for ConsoleScheme {
+    fn scheme(&self) -> &str {
+        "console"
+    }
+
+    fn open(&mut self, url: Url, _: usize) -> Result<Box<Resource>> {
+        match url.reference().trim_matches('/') {
+            "" => Ok(box ConsoleResource::new()),
+            "keymap" => Ok(box KeymapResource { pos: 0 }),
+            _ => Err(Error::new(ENOENT)),
+        }
+    }
+}
@@ -1,13 +1,508 @@
 use alloc::boxed::Box;
 
 use collections::string::{String, ToString};
+use collections::vec::Vec;
 
-use arch::context;
+use core::{cmp, mem, ptr, slice, str};
 
-use fs::{KScheme, Resource, Url, VecResource};
+use arch::context::{self, Context, ContextFile, ContextMemory, CONTEXT_AFFINITY_ALL,
+                    CONTEXT_PRIORITY_DEFAULT, CONTEXT_PRIORITY_MAX};
 
-use system::error::Result;
+use fs::{is_write, Format, KScheme, Resource, ResourceSeek, Url, VecResource};
 
+use syscall;
+
+use system::error::{Error, Result, EACCES, EBUSY, EINVAL, ENOENT, EPERM, ESRCH};
+use system::syscall::{ContextInfo, CONTEXT_INFO_BLOCKED, CONTEXT_INFO_EXITED, CONTEXT_INFO_SLEEPING,
+                      CONTEXT_INFO_SUPERVISED, CONTEXT_INFO_USER, CONTEXT_INFO_VFORK};
+
+/// The memory used by a context
+fn memory(context: &Context) -> usize {
+    let mut memory = 0;
+    if context.kernel_stack > 0 {
+        memory += context::CONTEXT_STACK_SIZE;
+    }
+    if let Some(ref stack) = context.stack {
+        memory += stack.virtual_size;
+    }
+    memory += unsafe { (*context.image.get()).size() };
+    memory += unsafe { (*context.heap.get()).size() };
+    memory += unsafe { (*context.mmap.get()).size() };
+    memory
+}
+
+/// Format a size in the largest fitting unit
+fn size_string(size: usize) -> String {
+    if size >= 1024 * 1024 * 1024 {
+        format!("{} GB", size / 1024 / 1024 / 1024)
+    } else if size >= 1024 * 1024 {
+        format!("{} MB", size / 1024 / 1024)
+    } else if size >= 1024 {
+        format!("{} KB", size / 1024)
+    } else {
+        format!("{} B", size)
+    }
+}
+
+/// The state of a context
+fn state(context: &Context) -> &'static str {
+    if context.exited {
+        "Exited"
+    } else if context.wake.is_some() {
+        "Sleeping"
+    } else if context.blocked > 0 {
+        "Blocked"
+    } else {
+        "Runnable"
+    }
+}
+
+/// The arguments of a context, each followed by a NUL
+fn cmdline(context: &Context) -> Vec<u8> {
+    let mut cmdline = Vec::new();
+    for arg in context.args.iter() {
+        cmdline.extend_from_slice(arg.as_bytes());
+        cmdline.push(0);
+    }
+    cmdline
+}
+
+/// The most frames `backtrace` walks
+pub const BACKTRACE_MAX: usize = 64;
+
+/// Walk at most `max` frame pointers of a context stopped by its supervisor, from its saved
+/// registers
+///
+/// Returns the instruction pointer, followed by the return address of every frame, which are left
+/// for userspace to symbolize. The memory walked may be corrupt, so the walk stops at a frame
+/// outside of the stack of the context, and at one which does not point further up the stack,
+/// which also ends any cycle. Returns `EBUSY` unless the context is stopped.
+pub fn backtrace_frames(context: &Context, max: usize) -> Result<Vec<usize>> {
+    let regs = try!(context.stopped_regs.ok_or(Error::new(EBUSY)));
+
+    let (start, end) = match context.stack {
+        Some(ref stack) => (stack.virtual_address, stack.virtual_address + stack.virtual_size),
+        None => (0, 0),
+    };
+    let frame_size = 2 * mem::size_of::<usize>();
+
+    let mut frames = vec![regs.ip];
+    let mut bp = regs.bp;
+    while frames.len() <= max {
+        if bp < start || bp >= end || end - bp < frame_size {
+            break;
+        }
+        let frame = match context.translate(bp, frame_size) {
+            Ok(frame) => frame,
+            Err(_) => break,
+        };
+        let (next, ip) = unsafe {
+            (ptr::read(frame as *const usize),
+             ptr::read((frame + mem::size_of::<usize>()) as *const usize))
+        };
+        if ip == 0 {
+            break;
+        }
+        frames.push(ip);
+
+        if next <= bp {
+            break;
+        }
+        bp = next;
+    }
+
+    Ok(frames)
+}
+
+/// Walk the frame pointers of a stopped context, like `backtrace_frames` with `BACKTRACE_MAX`
+///
+/// The result has the instruction pointers in hexadecimal, one per line.
+pub fn backtrace(context: &Context) -> Result<String> {
+    let mut string = String::new();
+    for ip in try!(backtrace_frames(context, BACKTRACE_MAX)).iter() {
+        string.push_str(&format!("{:X}\n", ip));
+    }
+    Ok(string)
+}
+
+/// The regions of memory a context maps into the page tables when it is switched to, one per line
+///
+/// Each line has the start and end address of the pages in hexadecimal, the permissions, which are
+/// `r` followed by `w` if the region is writeable and `s` if it is shared, or `p` if it is private,
+/// and the zone of the region. Regions are sorted by address.
+fn maps(context: &Context) -> String {
+    let mut regions: Vec<(&ContextMemory, &str)> = Vec::new();
+    if let Some(ref stack) = context.stack {
+        regions.push((stack, "stack"));
+    }
+    let zones = [(&context.image, "image"), (&context.heap, "heap"), (&context.mmap, "mmap")];
+    for &(zone, name) in zones.iter() {
+        for memory in unsafe { &*zone.get() }.memory.iter() {
+            regions.push((memory, name));
+        }
+    }
+    regions.sort_by_key(|&(memory, _)| memory.virtual_address);
+
+    let mut string = String::new();
+    for &(memory, name) in regions.iter() {
+        let pages = (memory.virtual_size + 4095) / 4096;
+        string.push_str(&format!("{:08X}-{:08X} r{}{} {}\n",
+                                 memory.virtual_address,
+                                 memory.virtual_address + pages * 4096,
+                                 if memory.writeable { 'w' } else { '-' },
+                                 if memory.shared.is_some() { 's' } else { 'p' },
+                                 name));
+    }
+    string
+}
+
+/// The open files of a context, one per line, as the descriptor followed by the path of its
+/// resource
+///
+/// Files whose resource has no path are left out. Files are sorted by descriptor.
+fn fds(context: &Context) -> String {
+    let mut files: Vec<&ContextFile> = unsafe { &*context.files.get() }.iter().collect();
+    files.sort_by_key(|file| file.fd);
+
+    let mut string = String::new();
+    let mut buf = [0; 4096];
+    for file in files.iter() {
+        if let Ok(count) = file.resource.path(&mut buf) {
+            string.push_str(&format!("{} {}\n", file.fd, String::from_utf8_lossy(&buf[..count])));
+        }
+    }
+    string
+}
+
+/// Check that the current context may inspect the memory and files of `context`
+///
+/// Returns `EPERM` unless `context` is supervised or the current context has I/O privilege.
+fn check_inspect(context: &Context) -> Result<()> {
+    let current = try!(unsafe { & *::env().contexts.get() }.current());
+    if !context.supervised && current.iopl != 3 {
+        return Err(Error::new(EPERM));
+    }
+    Ok(())
+}
+
+/// The signal masks of a context
+///
+/// The pending mask, then the blocked mask, each a hexadecimal word on its own line, where bit `n`
+/// stands for signal `n`.
+fn signals(context: &Context) -> String {
+    format!("{:x}\n{:x}\n", context.pending_signals, context.blocked_signals)
+}
+
+/// The `ContextInfo` of a context, with the part of its name which fits
+fn info(context: &Context) -> ContextInfo {
+    let mut flags = 0;
+    if context.stack.is_some() {
+        flags |= CONTEXT_INFO_USER;
+    }
+    if context.blocked > 0 {
+        flags |= CONTEXT_INFO_BLOCKED;
+    }
+    if context.exited {
+        flags |= CONTEXT_INFO_EXITED;
+    }
+    if context.vfork.is_some() {
+        flags |= CONTEXT_INFO_VFORK;
+    }
+    if context.wake.is_some() {
+        flags |= CONTEXT_INFO_SLEEPING;
+    }
+    if context.supervised {
+        flags |= CONTEXT_INFO_SUPERVISED;
+    }
+
+    let mut info = ContextInfo {
+        pid: context.pid,
+        ppid: context.ppid,
+        flags: flags,
+        switch: context.switch,
+        time: context.time,
+        memory: memory(context),
+        files: unsafe { (*context.files.get()).len() },
+        iopl: context.iopl,
+        priority: context.priority,
+        name: [0; 32],
+    };
+    for (b, n) in info.name.iter_mut().zip(context.name.bytes()) {
+        *b = n;
+    }
+    info
+}
+
+/// Detailed information about one context
+fn details(context: &Context) -> String {
+    let mut string = String::new();
+    string.push_str(&format!("Name: {}\n", context.name));
+    string.push_str(&format!("PID: {}\n", context.pid));
+    string.push_str(&format!("PPID: {}\n", context.ppid));
+    string.push_str(&format!("State: {}\n", state(context)));
+    string.push_str(&format!("Supervised: {}\n", context.supervised));
+    if let Some((_, number, _, _, _)) = context.current_syscall {
+        string.push_str(&format!("Syscall: {}\n", syscall::name(number)));
+    }
+    string.push_str(&format!("Switches: {}\n", context.switch));
+    string.push_str(&format!("Time: {}\n", context.time));
+    string.push_str(&format!("Memory: {}\n", size_string(memory(context))));
+    string.push_str(&format!("Files: {}\n", unsafe { (*context.files.get()).len() }));
+    string.push_str(&format!("File Limit: {}\n", context.file_limit));
+    string.push_str(&format!("Priority: {}\n", context.priority));
+    string.push_str(&format!("IOPL: {}\n", context.iopl));
+    string.push_str(&format!("CWD: {}\n", unsafe { &*context.cwd.get() }));
+    string
+}
+
+/// Set the scheduling priority of the context `pid`, on behalf of the current context
+///
+/// Any context may lower a priority, but only contexts with an IOPL of 3 may raise one above
+/// `CONTEXT_PRIORITY_DEFAULT`. Returns `EINVAL` if `priority` is 0 or above `CONTEXT_PRIORITY_MAX`,
+/// `EPERM` if it may not be set, and `ESRCH` if there is no context `pid`.
+pub fn set_priority(pid: usize, priority: usize) -> Result<()> {
+    if priority == 0 || priority > CONTEXT_PRIORITY_MAX {
+        return Err(Error::new(EINVAL));
+    }
+
+    let contexts = unsafe { &mut *::env().contexts.get() };
+    let iopl = try!(contexts.current()).iopl;
+    let target = try!(contexts.find_mut(pid).or(Err(Error::new(ESRCH))));
+    if priority > CONTEXT_PRIORITY_DEFAULT && priority > target.priority && iopl != 3 {
+        return Err(Error::new(EPERM));
+    }
+
+    target.priority = priority;
+    Ok(())
+}
+
+/// Set the CPUs the context `pid` may run on, on behalf of the current context
+///
+/// Bit `n` of `affinity` allows CPU `n`. Any context may set its own affinity, but only contexts
+/// with an IOPL of 3 may set that of another. Returns `EINVAL` if `affinity` is empty or allows a
+/// CPU which does not exist, `EPERM` if it may not be set, and `ESRCH` if there is no context
+/// `pid`.
+pub fn set_affinity(pid: usize, affinity: usize) -> Result<()> {
+    if affinity == 0 || affinity & !CONTEXT_AFFINITY_ALL != 0 {
+        return Err(Error::new(EINVAL));
+    }
+
+    let contexts = unsafe { &mut *::env().contexts.get() };
+    let (cur_pid, iopl) = {
+        let current = try!(contexts.current());
+        (current.pid, current.iopl)
+    };
+    let target = try!(contexts.find_mut(pid));
+    if target.pid != cur_pid && iopl != 3 {
+        return Err(Error::new(EPERM));
+    }
+
+    target.affinity = affinity;
+    Ok(())
+}
+
+/// The CPU affinity of a context, read and written as a hexadecimal mask, see `set_affinity`
+pub struct AffinityResource {
+    pid: usize,
+    pos: usize,
+}
+
+impl Resource for AffinityResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box AffinityResource {
+            pid: self.pid,
+            pos: self.pos,
+        })
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = format!("context:{}/affinity", self.pid);
+
+        for (b, p) in buf.iter_mut().zip(path.bytes()) {
+            *b = p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    /// Read the affinity, followed by a newline
+    ///
+    /// Returns `ESRCH` if the context exited.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let contexts = unsafe { & *::env().contexts.get() };
+        let affinity = try!(contexts.find(self.pid)).affinity;
+        let data = format!("{:x}\n", affinity);
+
+        let mut i = 0;
+        for (b, d) in buf.iter_mut().zip(data.bytes().skip(self.pos)) {
+            *b = d;
+            i += 1;
+        }
+        self.pos += i;
+
+        Ok(i)
+    }
+
+    /// Set the affinity written as a hexadecimal mask, with an optional `0x`
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let affinity = try!(str::from_utf8(buf)
+                                .ok()
+                                .and_then(|affinity| {
+                                    let affinity = affinity.trim();
+                                    let digits = if affinity.starts_with("0x") {
+                                        &affinity[2..]
+                                    } else {
+                                        affinity
+                                    };
+                                    usize::from_str_radix(digits, 16).ok()
+                                })
+                                .ok_or(Error::new(EINVAL)));
+
+        try!(set_affinity(self.pid, affinity));
+        Ok(buf.len())
+    }
+
+    fn sync(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The scheduling priority of a context, read and written as a decimal number
+pub struct PriorityResource {
+    pid: usize,
+    pos: usize,
+}
+
+impl Resource for PriorityResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box PriorityResource {
+            pid: self.pid,
+            pos: self.pos,
+        })
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = format!("context:{}/priority", self.pid);
+
+        for (b, p) in buf.iter_mut().zip(path.bytes()) {
+            *b = p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    /// Read the priority, followed by a newline
+    ///
+    /// Returns `ESRCH` if the context exited.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let contexts = unsafe { & *::env().contexts.get() };
+        let priority = try!(contexts.find(self.pid).or(Err(Error::new(ESRCH)))).priority;
+        let data = format!("{}\n", priority);
+
+        let mut i = 0;
+        for (b, d) in buf.iter_mut().zip(data.bytes().skip(self.pos)) {
+            *b = d;
+            i += 1;
+        }
+        self.pos += i;
+
+        Ok(i)
+    }
+
+    /// Set the priority written as a decimal number, see `set_priority`
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let priority = try!(str::from_utf8(buf)
+                                .ok()
+                                .and_then(|priority| priority.trim().parse::<usize>().ok())
+                                .ok_or(Error::new(EINVAL)));
+
+        try!(set_priority(self.pid, priority));
+        Ok(buf.len())
+    }
+
+    fn sync(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The CPU time of a context, as `User: N` and `System: N` lines counting ticks of the PIT
+///
+/// Reading from the start takes a new snapshot of the counters, so seeking back to the start and
+/// reading again shows the time used since.
+pub struct TimesResource {
+    pid: usize,
+    data: Vec<u8>,
+    pos: usize,
+}
+
+impl Resource for TimesResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box TimesResource {
+            pid: self.pid,
+            data: self.data.clone(),
+            pos: self.pos,
+        })
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = format!("context:{}/times", self.pid);
+
+        for (b, p) in buf.iter_mut().zip(path.bytes()) {
+            *b = p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    /// Returns `ESRCH` if the context exited before a snapshot was taken.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if self.pos == 0 {
+            let contexts = unsafe { & *::env().contexts.get() };
+            let context = try!(contexts.find(self.pid).or(Err(Error::new(ESRCH))));
+            self.data = format!("User: {}\nSystem: {}\n",
+                                context.user_time,
+                                context.time - context.user_time)
+                            .into_bytes();
+        }
+
+        let mut i = 0;
+        for (b, d) in buf.iter_mut().zip(self.data.iter().skip(self.pos)) {
+            *b = *d;
+            i += 1;
+        }
+        self.pos += i;
+
+        Ok(i)
+    }
+
+    /// Only seeking from the start, or getting the offset with `ResourceSeek::Current(0)`, is
+    /// supported
+    fn seek(&mut self, pos: ResourceSeek) -> Result<usize> {
+        match pos {
+            ResourceSeek::Start(offset) => self.pos = offset,
+            ResourceSeek::Current(0) => (),
+            _ => return Err(Error::new(EINVAL)),
+        }
+        Ok(self.pos)
+    }
+
+    fn sync(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The context scheme
+///
+/// `context:` lists every context, one per line, and `context:?format=binary` reads a
+/// `ContextInfo` of each, both from a single snapshot, so a context can not be half listed or
+/// listed twice. `context:PID` describes a single context.
+/// `context:PID/affinity` holds the CPUs it may run on, see `AffinityResource`,
+/// `context:PID/cmdline` holds its arguments, each followed by a NUL, `context:PID/fd` its open
+/// files, see `fds`, and `context:PID/maps` its memory regions, see `maps`, both of which only
+/// privileged contexts may read unless it is supervised,
+/// `context:PID/priority` its scheduling priority, `context:PID/signals` its pending and blocked
+/// signals, see `signals`, which is protected like `fd`, `context:PID/stack` its backtrace while it
+/// is stopped, see `backtrace`, and `context:PID/times` the CPU time it used, see `TimesResource`.
 pub struct ContextScheme;
 
 impl KScheme for ContextScheme {
@@ -15,7 +510,85 @@ impl KScheme for ContextScheme {
         "context"
     }
 
-    fn open(&mut self, _: Url, _: usize) -> Result<Box<Resource>> {
+    /// The signals of a context are read only
+    fn check_access(&self, url: &Url, flags: usize) -> Result<()> {
+        if is_write(flags) && url.path().trim_matches('/').ends_with("/signals") {
+            Err(Error::new(EACCES))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn open(&mut self, url: Url, _: usize) -> Result<Box<Resource>> {
+        let reference = url.path().trim_matches('/');
+        if !reference.is_empty() {
+            let mut parts = reference.splitn(2, '/');
+            let contexts = unsafe { & *::env().contexts.get() };
+            let context = try!(parts.next()
+                                    .and_then(|pid| pid.parse::<usize>().ok())
+                                    .and_then(|pid| contexts.find(pid).ok())
+                                    .ok_or(Error::new(ENOENT)));
+            return match parts.next() {
+                None => Ok(box VecResource::new(format!("context:{}", context.pid),
+                                                details(context).into_bytes())),
+                Some("affinity") => {
+                    Ok(box AffinityResource {
+                        pid: context.pid,
+                        pos: 0,
+                    })
+                }
+                Some("cmdline") => {
+                    Ok(box VecResource::new(format!("context:{}/cmdline", context.pid),
+                                            cmdline(context)))
+                }
+                Some("fd") => {
+                    try!(check_inspect(context));
+                    Ok(box VecResource::new(format!("context:{}/fd", context.pid),
+                                            fds(context).into_bytes()))
+                }
+                Some("maps") => {
+                    try!(check_inspect(context));
+                    Ok(box VecResource::new(format!("context:{}/maps", context.pid),
+                                            maps(context).into_bytes()))
+                }
+                Some("priority") => {
+                    Ok(box PriorityResource {
+                        pid: context.pid,
+                        pos: 0,
+                    })
+                }
+                Some("signals") => {
+                    try!(check_inspect(context));
+                    Ok(box VecResource::new(format!("context:{}/signals", context.pid),
+                                            signals(context).into_bytes()))
+                }
+                Some("stack") => {
+                    Ok(box VecResource::new(format!("context:{}/stack", context.pid),
+                                            try!(backtrace(context)).into_bytes()))
+                }
+                Some("times") => {
+                    Ok(box TimesResource {
+                        pid: context.pid,
+                        data: Vec::new(),
+                        pos: 0,
+                    })
+                }
+                Some(_) => Err(Error::new(ENOENT)),
+            };
+        }
+
+        if try!(url.format()) == Format::Binary {
+            let mut data = Vec::new();
+            for context in unsafe { & *::env().contexts.get() }.iter() {
+                let info = info(context);
+                data.extend_from_slice(unsafe {
+                    slice::from_raw_parts(&info as *const ContextInfo as *const u8,
+                                          mem::size_of::<ContextInfo>())
+                });
+            }
+            return Ok(box VecResource::new("context:?format=binary".to_string(), data));
+        }
+
         let mut string = format!("{:<6}{:<6}{:<8}{:<8}{:<8}{:<6}{:<6}{:<6}{}\n",
                                  "PID",
                                  "PPID",
@@ -29,27 +602,6 @@ impl KScheme for ContextScheme {
         {
             let contexts = unsafe { & *::env().contexts.get() };
             for context in contexts.iter() {
-                let mut memory = 0;
-                if context.kernel_stack > 0 {
-                    memory += context::CONTEXT_STACK_SIZE;
-                }
-                if let Some(ref stack) = context.stack {
-                    memory += stack.virtual_size;
-                }
-                memory += unsafe { (*context.image.get()).size() };
-                memory += unsafe { (*context.heap.get()).size() };
-                memory += unsafe { (*context.mmap.get()).size() };
-
-                let memory_string = if memory >= 1024 * 1024 * 1024 {
-                    format!("{} GB", memory / 1024 / 1024 / 1024)
-                } else if memory >= 1024 * 1024 {
-                    format!("{} MB", memory / 1024 / 1024)
-                } else if memory >= 1024 {
-                    format!("{} KB", memory / 1024)
-                } else {
-                    format!("{} B", memory)
-                };
-
                 let mut flags_string = String::new();
                 if context.stack.is_some() {
                     flags_string.push('U');
@@ -77,7 +629,7 @@ impl KScheme for ContextScheme {
                                    context.ppid,
                                    context.switch,
                                    context.time,
-                                   memory_string,
+                                   size_string(memory(context)),
                                    unsafe { (*context.files.get()).len() },
                                    flags_string,
                                    context.iopl,
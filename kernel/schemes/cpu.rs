@@ -0,0 +1,94 @@
+use alloc::boxed::Box;
+
+use collections::String;
+
+use core::cmp;
+
+use common::cpuid;
+
+use fs::{KScheme, Resource, ResourceSeek, Url};
+
+use system::error::{Error, Result, EACCES, EINVAL, ENOENT};
+
+/// The feature flags of the processor, one per line
+///
+/// The flags are detected at every read.
+pub struct CpuFeaturesResource {
+    pos: usize,
+}
+
+impl CpuFeaturesResource {
+    fn list() -> String {
+        let mut list = String::new();
+        for feature in cpuid::features() {
+            list.push_str(feature);
+            list.push('\n');
+        }
+        list
+    }
+}
+
+impl Resource for CpuFeaturesResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box CpuFeaturesResource { pos: self.pos })
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = b"cpu:features";
+
+        for (b, p) in buf.iter_mut().zip(path.iter()) {
+            *b = *p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let list = CpuFeaturesResource::list();
+
+        let mut i = 0;
+        for (b, s) in buf.iter_mut().zip(list.bytes().skip(self.pos)) {
+            *b = s;
+            i += 1;
+        }
+        self.pos += i;
+
+        Ok(i)
+    }
+
+    /// The feature flags are read only
+    fn write(&mut self, _: &[u8]) -> Result<usize> {
+        Err(Error::new(EACCES))
+    }
+
+    fn seek(&mut self, pos: ResourceSeek) -> Result<usize> {
+        let len = CpuFeaturesResource::list().len();
+        self.pos = match pos {
+            ResourceSeek::Start(offset) => cmp::min(len, offset),
+            ResourceSeek::Current(offset) =>
+                cmp::max(0, cmp::min(len as isize, self.pos as isize + offset)) as usize,
+            ResourceSeek::End(offset) =>
+                cmp::max(0, cmp::min(len as isize, len as isize + offset)) as usize,
+            ResourceSeek::Data(_) | ResourceSeek::Hole(_) => return Err(Error::new(EINVAL)),
+        };
+        Ok(self.pos)
+    }
+}
+
+/// The processor information scheme
+///
+/// `cpu:features` lists the feature flags detected with `cpuid`.
+pub struct CpuScheme;
+
+impl KScheme for CpuScheme {
+    fn scheme(&self) -> &str {
+        "cpu"
+    }
+
+    fn open(&mut self, url: Url, _: usize) -> Result<Box<Resource>> {
+        match url.reference().trim_matches('/') {
+            "features" => Ok(box CpuFeaturesResource { pos: 0 }),
+            _ => Err(Error::new(ENOENT)),
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use alloc::boxed::Box;
+
+use collections::string::String;
+
+use core::{cmp, str};
+
+use fs::{KScheme, Resource, ResourceSeek, Url};
+
+use system::error::{Error, Result, EINVAL, ENOENT};
+
+/// The working directory of the current context
+fn cwd() -> Result<&'static mut String> {
+    let contexts = unsafe { & *::env().contexts.get() };
+    let current = try!(contexts.current());
+    Ok(unsafe { &mut *current.cwd.get() })
+}
+
+/// A working directory resource
+///
+/// Every read sees the working directory of the context reading, and every write changes it.
+pub struct CwdResource {
+    pos: usize,
+}
+
+impl Resource for CwdResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box CwdResource { pos: self.pos })
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = b"cwd:";
+
+        for (b, p) in buf.iter_mut().zip(path.iter()) {
+            *b = *p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let cwd = try!(cwd());
+
+        let mut i = 0;
+        for (b, c) in buf.iter_mut().zip(cwd.bytes().skip(self.pos)) {
+            *b = c;
+            i += 1;
+        }
+        self.pos += i;
+
+        Ok(i)
+    }
+
+    /// Change the working directory to `buf`, without a trailing newline
+    ///
+    /// The path is resolved against the current working directory, like `chdir`, and a `/` is
+    /// added if it does not end with one, so that relative paths resolve inside it. Returns
+    /// `EINVAL` if the path is empty or not UTF-8, and `ENOENT` if it does not resolve to a
+    /// registered scheme, in which case the working directory is left alone.
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let path = if buf.ends_with(b"\n") {
+            &buf[..buf.len() - 1]
+        } else {
+            buf
+        };
+
+        let path = try!(str::from_utf8(path).or(Err(Error::new(EINVAL))));
+        if path.is_empty() {
+            return Err(Error::new(EINVAL));
+        }
+
+        let mut path = {
+            let contexts = unsafe { & *::env().contexts.get() };
+            try!(contexts.current()).canonicalize(path)
+        };
+
+        {
+            let env = ::env();
+            let scheme = match path.find(':') {
+                Some(i) => try!(env.resolve_scheme(&path[..i])),
+                None => return Err(Error::new(ENOENT)),
+            };
+            if scheme.is_empty() || !env.schemes().any(|other| other.scheme() == scheme) {
+                return Err(Error::new(ENOENT));
+            }
+        }
+
+        if !path.ends_with('/') && !path.ends_with(':') {
+            path.push('/');
+        }
+        *try!(cwd()) = path;
+        self.pos = 0;
+
+        Ok(buf.len())
+    }
+
+    fn seek(&mut self, pos: ResourceSeek) -> Result<usize> {
+        let len = try!(cwd()).len();
+        self.pos = match pos {
+            ResourceSeek::Start(offset) => cmp::min(len, offset),
+            ResourceSeek::Current(offset) =>
+                cmp::max(0, cmp::min(len as isize, self.pos as isize + offset)) as usize,
+            ResourceSeek::End(offset) =>
+                cmp::max(0, cmp::min(len as isize, len as isize + offset)) as usize,
+            ResourceSeek::Data(_) | ResourceSeek::Hole(_) => return Err(Error::new(EINVAL)),
+        };
+        Ok(self.pos)
+    }
+}
+
+/// The working directory scheme
+///
+/// `cwd:` reads and writes the working directory of the current context, which relative paths
+/// are resolved against. Children get a copy of it, or share it if they are created with
+/// `CLONE_FS`, and it is kept across exec.
+pub struct CwdScheme;
+
+impl KScheme for CwdScheme {
+    fn scheme(&self) -> &str {
+        "cwd"
+    }
+
+    fn open(&mut self, _: Url, _: usize) -> Result<Box<Resource>> {
+        Ok(box CwdResource { pos: 0 })
+    }
+}
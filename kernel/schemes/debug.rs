@@ -23,6 +23,16 @@ impl Resource for DebugResource {
         })
     }
 
+    /// There is a command left over, or a new one was entered
+    fn is_readable(&self) -> bool {
+        !self.command.is_empty() || unsafe { & *::env().console.get() }.commands.peek()
+    }
+
+    /// The kernel console
+    fn is_terminal(&self) -> bool {
+        true
+    }
+
     fn path(&self, buf: &mut [u8]) -> Result <usize> {
         let path = self.path.as_bytes();
 
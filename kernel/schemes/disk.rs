@@ -11,7 +11,7 @@ use fs::{KScheme, Resource, ResourceSeek, Url, VecResource};
 
 use syscall::{MODE_DIR, MODE_FILE, Stat};
 
-use system::error::{Error, Result, ENOENT};
+use system::error::{Error, Result, EINVAL, ENOENT};
 
 /// A disk resource
 pub struct DiskResource {
@@ -56,6 +56,7 @@ impl Resource for DiskResource {
             ResourceSeek::Start(offset) => self.seek = cmp::min(size, offset as u64),
             ResourceSeek::Current(offset) => self.seek = cmp::min(size, cmp::max(0, self.seek as i64 + offset as i64) as u64),
             ResourceSeek::End(offset) => self.seek = cmp::min(size, cmp::max(0, size as i64 + offset as i64) as u64),
+            ResourceSeek::Data(_) | ResourceSeek::Hole(_) => return Err(Error::new(EINVAL)),
         }
         Ok(self.seek as usize)
     }
@@ -84,6 +84,7 @@ impl Resource for DisplayResource {
                 ResourceSeek::Start(offset) => cmp::min(display.size, cmp::max(0, offset)),
                 ResourceSeek::Current(offset) => cmp::min(display.size, cmp::max(0, self.seek as isize + offset) as usize),
                 ResourceSeek::End(offset) => cmp::min(display.size, cmp::max(0, display.size as isize + offset) as usize),
+                ResourceSeek::Data(_) | ResourceSeek::Hole(_) => return Err(Error::new(EINVAL)),
             };
 
             Ok(self.seek)
@@ -3,9 +3,15 @@ use arch::context::EnvVar;
 use collections::string::String;
 use core::cmp::min;
 use fs::resource::ResourceSeek;
-use fs::{KScheme, Resource, Url};
+use fs::{open_named, KScheme, Resource, Url};
 use system::error::{EINVAL, Error, Result};
+use system::syscall::O_TRUNC;
 
+/// The environment variables of the current context
+///
+/// `env:` lists every variable as `NAME=value` lines, and `env:NAME` reads or writes the value of a
+/// single variable. Opening a variable which is not set returns `ENOENT`, so that it can be told
+/// apart from an empty one, unless it is created with `O_CREAT`, as an empty variable.
 pub struct EnvScheme;
 
 impl KScheme for EnvScheme {
@@ -13,14 +19,22 @@ impl KScheme for EnvScheme {
         "env"
     }
 
-    fn open(&mut self, url: Url, _: usize) -> Result<Box<Resource>> {
-        let name = url.reference();
+    fn open(&mut self, url: Url, flags: usize) -> Result<Box<Resource>> {
+        let flags = try!(url.flags(flags));
+        let name = url.path().trim_left_matches('/');
         if name.contains('=') { return Err(Error::new(EINVAL)) }
-        if name == "" || name == "/" {
+        if name == "" {
             Ok(box EnvListResource {
                 pos: 0
             })
         } else {
+            let contexts = unsafe { &mut *::env().contexts.get() };
+            let current = try!(contexts.current_mut());
+            let exists = current.get_env_var(name).is_ok();
+            if try!(open_named(exists, flags)) || flags & O_TRUNC == O_TRUNC {
+                try!(current.set_env_var(name, ""));
+            }
+
             Ok(box EnvVariableResource {
                 name: String::from(name),
                 pos: 0
@@ -29,7 +43,7 @@ impl KScheme for EnvScheme {
     }
 
     fn unlink(&mut self, url: Url) -> Result<()> {
-        let name = url.reference();
+        let name = url.reference().trim_left_matches('/');
         let contexts = unsafe { & *::env().contexts.get() };
         let current = try!(contexts.current());
         current.remove_env_var(name)
@@ -59,6 +73,16 @@ impl Resource for EnvListResource {
         Ok(box EnvListResource { pos: 0 })
     }
 
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = b"env:";
+
+        for (b, p) in buf.iter_mut().zip(path.iter()) {
+            *b = *p;
+        }
+
+        Ok(min(buf.len(), path.len()))
+    }
+
     fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
         let mut i = 0;
         let string = try!(self.get_list_str());
@@ -81,6 +105,7 @@ impl Resource for EnvListResource {
                 let string = try!(self.get_list_str());
                 self.pos = (string.bytes().count() as isize + offset) as usize;
             }
+            ResourceSeek::Data(_) | ResourceSeek::Hole(_) => return Err(Error::new(EINVAL)),
         }
         Ok(self.pos)
     }
@@ -96,6 +121,16 @@ impl Resource for EnvVariableResource {
         Ok(box EnvVariableResource { name: self.name.clone(), pos: 0 })
     }
 
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = format!("env:{}", self.name);
+
+        for (b, p) in buf.iter_mut().zip(path.bytes()) {
+            *b = p;
+        }
+
+        Ok(min(buf.len(), path.len()))
+    }
+
     fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
         let contexts = unsafe { & *::env().contexts.get() };
         let current = try!(contexts.current());
@@ -133,6 +168,7 @@ impl Resource for EnvVariableResource {
                 let value = try!(current.get_env_var(&self.name));
                 self.pos = (value.bytes().count() as isize + offset) as usize;
             }
+            ResourceSeek::Data(_) | ResourceSeek::Hole(_) => return Err(Error::new(EINVAL)),
         }
         Ok(self.pos)
     }
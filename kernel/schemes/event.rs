@@ -0,0 +1,177 @@
+use alloc::boxed::Box;
+
+use collections::Vec;
+
+use core::{cmp, mem, ptr};
+
+use fs::{KScheme, Resource, Url};
+
+use system::error::{Error, Result, EAGAIN, EINVAL};
+use system::syscall::{Event, EVENT_READ, EVENT_WRITE, F_GETFL, F_SETFL, O_NONBLOCK};
+
+/// An event resource
+///
+/// Holds a set of file descriptors of the context which opened it, and the readiness each of them
+/// is waited for.
+pub struct EventResource {
+    events: Vec<Event>,
+    /// Whether reads return `EAGAIN` instead of blocking
+    nonblock: bool,
+}
+
+impl EventResource {
+    /// Write the registered events which are ready to `buf`, returning how many were written
+    fn ready(&self, buf: &mut [u8]) -> Result<usize> {
+        let contexts = unsafe { & *::env().contexts.get() };
+        let current = try!(contexts.current());
+
+        let mut count = 0;
+        for event in self.events.iter() {
+            if (count + 1) * mem::size_of::<Event>() > buf.len() {
+                break;
+            }
+
+            let resource = try!(current.get_file(event.id));
+
+            let mut flags = 0;
+            if event.flags & EVENT_READ == EVENT_READ && resource.is_readable() {
+                flags |= EVENT_READ;
+            }
+            if event.flags & EVENT_WRITE == EVENT_WRITE && resource.is_writable() {
+                flags |= EVENT_WRITE;
+            }
+
+            if flags != 0 {
+                let offset = count * mem::size_of::<Event>();
+                let ready = Event {
+                    id: event.id,
+                    flags: flags,
+                };
+                unsafe { ptr::write(buf[offset..].as_mut_ptr() as *mut Event, ready) };
+                count += 1;
+            }
+        }
+
+        Ok(count)
+    }
+}
+
+impl Resource for EventResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box EventResource {
+            events: self.events.clone(),
+            nonblock: self.nonblock,
+        })
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = b"event:";
+
+        for (b, p) in buf.iter_mut().zip(path.iter()) {
+            *b = *p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    /// Wait until a registered file descriptor is ready, and read the events of those which are
+    ///
+    /// Resources do not report when they become ready, so while none is, the context blocks until
+    /// the next tick of the PIT, and checks again. Returns `EINVAL` if `buf` is smaller than an
+    /// `Event`, `EBADF` if a registered file descriptor was closed, and 0 if nothing is registered.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if buf.len() < mem::size_of::<Event>() {
+            return Err(Error::new(EINVAL));
+        }
+
+        if self.events.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            let count = try!(self.ready(buf));
+            if count > 0 {
+                return Ok(count * mem::size_of::<Event>());
+            }
+
+            if self.nonblock {
+                return Err(Error::new(EAGAIN));
+            }
+
+            ::env().tick.wait("EventResource::read");
+        }
+    }
+
+    /// Register or unregister the file descriptors of the `Event`s in `buf`
+    ///
+    /// Returns `EINVAL` if `buf` is not made of whole events, or has unknown flags, and `EBADF` if
+    /// a file descriptor is not open. No events are registered if any of them fails.
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if buf.len() % mem::size_of::<Event>() != 0 {
+            return Err(Error::new(EINVAL));
+        }
+
+        let contexts = unsafe { & *::env().contexts.get() };
+        let current = try!(contexts.current());
+
+        let mut events = Vec::new();
+        for chunk in buf.chunks(mem::size_of::<Event>()) {
+            let event = unsafe { ptr::read(chunk.as_ptr() as *const Event) };
+            if event.flags & !(EVENT_READ | EVENT_WRITE) != 0 {
+                return Err(Error::new(EINVAL));
+            }
+            if event.flags != 0 {
+                try!(current.get_file(event.id));
+            }
+            events.push(event);
+        }
+
+        for event in events {
+            self.events.retain(|registered| registered.id != event.id);
+            if event.flags != 0 {
+                self.events.push(event);
+            }
+        }
+
+        Ok(buf.len())
+    }
+
+    /// Get or set `O_NONBLOCK`
+    fn fcntl(&mut self, cmd: usize, arg: usize) -> Result<usize> {
+        match cmd {
+            F_GETFL => Ok(self.flags()),
+            F_SETFL => {
+                self.nonblock = arg & O_NONBLOCK == O_NONBLOCK;
+                Ok(0)
+            }
+            _ => Err(Error::new(EINVAL)),
+        }
+    }
+
+    fn flags(&self) -> usize {
+        if self.nonblock {
+            O_NONBLOCK
+        } else {
+            0
+        }
+    }
+}
+
+/// The event scheme
+///
+/// Every `event:` handle waits on its own set of file descriptors, like `poll`.
+pub struct EventScheme;
+
+impl KScheme for EventScheme {
+    fn scheme(&self) -> &str {
+        "event"
+    }
+
+    fn open(&mut self, url: Url, flags: usize) -> Result<Box<Resource>> {
+        let flags = try!(url.flags(flags));
+        Ok(box EventResource {
+            events: Vec::new(),
+            nonblock: flags & O_NONBLOCK == O_NONBLOCK,
+        })
+    }
+}
@@ -0,0 +1,169 @@
+use alloc::boxed::Box;
+
+use collections::string::ToString;
+use collections::vec::Vec;
+use collections::vec_deque::VecDeque;
+
+use core::{cmp, fmt};
+
+use fs::{KScheme, Resource, ResourceSeek, Url};
+
+use system::error::{Error, Result, EINVAL, EPERM};
+
+/// The number of faults kept, after which the oldest are dropped
+pub const FAULTS_CAPACITY: usize = 32;
+
+/// A fault which killed a context, instead of the kernel
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Fault {
+    /// The faulting context
+    pub pid: usize,
+    /// The exception
+    pub interrupt: usize,
+    /// The address which could not be accessed, for a page fault, or 0
+    pub address: usize,
+    /// The error code pushed by the exception, or 0
+    pub error: usize,
+    /// The instruction pointer of the faulting instruction
+    pub ip: usize,
+}
+
+/// The most recent faults
+pub struct Faults {
+    records: VecDeque<Fault>,
+}
+
+impl Faults {
+    pub fn new() -> Faults {
+        Faults { records: VecDeque::with_capacity(FAULTS_CAPACITY) }
+    }
+
+    /// Remember `fault`, dropping the oldest fault if `FAULTS_CAPACITY` are kept
+    pub fn push(&mut self, fault: Fault) {
+        if self.records.len() >= FAULTS_CAPACITY {
+            self.records.pop_front();
+        }
+        self.records.push_back(fault);
+    }
+
+    /// Forget every fault
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+}
+
+/// The faults, oldest first, one per line
+impl fmt::Display for Faults {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for fault in self.records.iter() {
+            try!(write!(f,
+                        "PID {} INT {:X} IP {:08X} ADDR {:08X} ERR {:X}\n",
+                        fault.pid,
+                        fault.interrupt,
+                        fault.ip,
+                        fault.address,
+                        fault.error));
+        }
+        Ok(())
+    }
+}
+
+/// Record a fault of the current context, from the exception handler
+pub fn record(interrupt: usize, address: usize, error: usize, ip: usize) {
+    let contexts = unsafe { & *::env().contexts.get() };
+    let pid = contexts.current().map(|context| context.pid).unwrap_or(0);
+    unsafe { &mut *::env().faults.get() }.push(Fault {
+        pid: pid,
+        interrupt: interrupt,
+        address: address,
+        error: error,
+        ip: ip,
+    });
+}
+
+/// A handle to the recent faults
+///
+/// Reading from the start takes a new snapshot of the faults, and writing anything clears them.
+pub struct FaultsResource {
+    data: Vec<u8>,
+    pos: usize,
+}
+
+impl Resource for FaultsResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box FaultsResource {
+            data: self.data.clone(),
+            pos: self.pos,
+        })
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = b"faults:";
+
+        for (b, p) in buf.iter_mut().zip(path.iter()) {
+            *b = *p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if self.pos == 0 {
+            self.data = unsafe { & *::env().faults.get() }.to_string().into_bytes();
+        }
+
+        let mut i = 0;
+        for (b, d) in buf.iter_mut().zip(self.data.iter().skip(self.pos)) {
+            *b = *d;
+            i += 1;
+        }
+        self.pos += i;
+
+        Ok(i)
+    }
+
+    /// Clear the faults
+    ///
+    /// Returns `EPERM` if the current context has no I/O privilege.
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if try!(unsafe { & *::env().contexts.get() }.current()).iopl != 3 {
+            return Err(Error::new(EPERM));
+        }
+
+        unsafe { &mut *::env().faults.get() }.clear();
+        Ok(buf.len())
+    }
+
+    /// Only seeking from the start is supported
+    fn seek(&mut self, pos: ResourceSeek) -> Result<usize> {
+        match pos {
+            ResourceSeek::Start(offset) => self.pos = offset,
+            _ => return Err(Error::new(EINVAL)),
+        }
+        Ok(self.pos)
+    }
+
+    fn sync(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The fault scheme
+///
+/// `faults:` lists the last `FAULTS_CAPACITY` exceptions the kernel recovered from by killing the
+/// faulting context, oldest first, one per line, with the PID, the exception, the instruction
+/// pointer, the address of a page fault and the error code, in hexadecimal but for the PID.
+pub struct FaultsScheme;
+
+impl KScheme for FaultsScheme {
+    fn scheme(&self) -> &str {
+        "faults"
+    }
+
+    fn open(&mut self, _: Url, _: usize) -> Result<Box<Resource>> {
+        Ok(box FaultsResource {
+            data: Vec::new(),
+            pos: 0,
+        })
+    }
+}
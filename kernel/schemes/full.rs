@@ -0,0 +1,57 @@
+use alloc::boxed::Box;
+
+use core::cmp;
+
+use fs::{KScheme, Resource, Url};
+
+use system::error::{Error, Result, ENOSPC};
+
+/// A resource which reads as zeros, and is always out of space
+pub struct FullResource;
+
+impl Resource for FullResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box FullResource)
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = b"full:";
+
+        for (b, p) in buf.iter_mut().zip(path.iter()) {
+            *b = *p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    /// Fill `buf` with zeros
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        for b in buf.iter_mut() {
+            *b = 0;
+        }
+        Ok(buf.len())
+    }
+
+    /// Fail with `ENOSPC`
+    fn write(&mut self, _: &[u8]) -> Result<usize> {
+        Err(Error::new(ENOSPC))
+    }
+
+    /// Nothing is buffered, so there is nothing to sync
+    fn sync(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The full scheme
+pub struct FullScheme;
+
+impl KScheme for FullScheme {
+    fn scheme(&self) -> &str {
+        "full"
+    }
+
+    fn open(&mut self, _: Url, _: usize) -> Result<Box<Resource>> {
+        Ok(box FullResource)
+    }
+}
@@ -0,0 +1,238 @@
+use alloc::boxed::Box;
+
+use collections::{BTreeMap, Vec};
+use collections::string::String;
+use collections::vec_deque::VecDeque;
+
+use core::cell::UnsafeCell;
+use core::{cmp, mem, str};
+
+use fs::{KScheme, Resource, Url};
+
+use sync::WaitCondition;
+
+use system::error::{Error, Result, EAGAIN, EINTR, EINVAL, ENOENT};
+
+/// The contexts waiting on futexes, see `::env().futexes`
+///
+/// A futex is a word of userspace memory, which is keyed by its physical address, so contexts
+/// sharing the memory at different virtual addresses wait on the same futex.
+pub struct Futexes {
+    /// The PIDs waiting on each futex, in the order they started waiting
+    waiters: UnsafeCell<BTreeMap<usize, VecDeque<usize>>>,
+    /// Waited on by every waiter, which is only woken by `interrupt`
+    condition: WaitCondition,
+}
+
+impl Futexes {
+    pub fn new() -> Futexes {
+        Futexes {
+            waiters: UnsafeCell::new(BTreeMap::new()),
+            condition: WaitCondition::new(),
+        }
+    }
+
+    /// The physical address of the word at `address` of the current context, and its value
+    ///
+    /// Returns `EINVAL` if `address` is not aligned to a word, and `EFAULT` if it is not readable
+    /// memory of the current context.
+    fn word(address: usize) -> Result<(usize, usize)> {
+        if address % mem::size_of::<usize>() != 0 {
+            return Err(Error::new(EINVAL));
+        }
+
+        let current = try!(unsafe { & *::env().contexts.get() }.current());
+        let value = *try!(current.get_ref(address as *const usize));
+        let key = try!(current.translate(address, mem::size_of::<usize>()));
+        Ok((key, value))
+    }
+
+    /// Block the current context until it is woken by `wake`, if the word at `address` of the
+    /// current context holds `expected`
+    ///
+    /// Returns `EAGAIN` at once if it holds anything else, as userspace changed it since it
+    /// decided to wait. Kernel code runs with interrupts disabled, so the word can not change, and
+    /// no waiters can be woken, between the comparison and queuing the current context. Returns
+    /// `EINTR` if a signal which is not blocked is pending, before or while waiting.
+    pub fn wait(&self, address: usize, expected: usize) -> Result<()> {
+        let (key, value) = try!(Futexes::word(address));
+        if value != expected {
+            return Err(Error::new(EAGAIN));
+        }
+
+        let contexts = unsafe { & *::env().contexts.get() };
+        let pid = try!(contexts.current()).pid;
+        let waiters = unsafe { &mut *self.waiters.get() };
+        waiters.entry(key).or_insert_with(VecDeque::new).push_back(pid);
+
+        // Only `wake` removes the context from the queue, so any other wakeup, such as that of a
+        // signal, checks for signals and waits again
+        while waiters.get(&key).map_or(false, |queue| queue.contains(&pid)) {
+            if try!(contexts.current()).deliverable_signals() != 0 {
+                self.remove(key, pid);
+                return Err(Error::new(EINTR));
+            }
+            self.condition.wait_interruptible("Futexes::wait");
+        }
+
+        Ok(())
+    }
+
+    /// Remove `pid` from the queue of the futex at the physical address `key`
+    fn remove(&self, key: usize, pid: usize) {
+        let waiters = unsafe { &mut *self.waiters.get() };
+        let empty = match waiters.get_mut(&key) {
+            Some(queue) => {
+                queue.retain(|&waiter| waiter != pid);
+                queue.is_empty()
+            }
+            None => false,
+        };
+        if empty {
+            waiters.remove(&key);
+        }
+    }
+
+    /// Remove `pid` from the queue of every futex, as the context exits
+    pub fn forget(&self, pid: usize) {
+        let waiters = unsafe { &mut *self.waiters.get() };
+        for queue in waiters.values_mut() {
+            queue.retain(|&waiter| waiter != pid);
+        }
+        let empty: Vec<usize> = waiters.iter()
+                                       .filter(|&(_, queue)| queue.is_empty())
+                                       .map(|(&key, _)| key)
+                                       .collect();
+        for key in empty {
+            waiters.remove(&key);
+        }
+    }
+
+    /// Wake up to `count` contexts waiting on the word at `address` of the current context, in
+    /// the order they started waiting
+    ///
+    /// A queued context which is not blocked on the futex, as a signal woke it and it has not
+    /// run yet, is left in the queue, and not counted, so the wakeup goes to one which is.
+    /// Returns the number of contexts woken.
+    pub fn wake(&self, address: usize, count: usize) -> Result<usize> {
+        let (key, _) = try!(Futexes::word(address));
+
+        let waiters = unsafe { &mut *self.waiters.get() };
+        let mut woken = 0;
+        let empty = match waiters.get_mut(&key) {
+            Some(queue) => {
+                let condition = &self.condition;
+                queue.retain(|&pid| {
+                    if woken < count && condition.interrupt(pid, "Futexes::wake") > 0 {
+                        woken += 1;
+                        false
+                    } else {
+                        true
+                    }
+                });
+                queue.is_empty()
+            }
+            None => false,
+        };
+        if empty {
+            waiters.remove(&key);
+        }
+
+        Ok(woken)
+    }
+}
+
+/// A futex resource
+///
+/// A write is one command, `wait ADDRESS VALUE`, which blocks until woken if the word at
+/// `ADDRESS` still holds `VALUE`, or `wake ADDRESS COUNT`, which wakes up to `COUNT` waiters, all
+/// in decimal. A read returns the number of contexts woken by the last `wake`, followed by a
+/// newline.
+pub struct FutexResource {
+    woken: String,
+    pos: usize,
+}
+
+impl Resource for FutexResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box FutexResource {
+            woken: self.woken.clone(),
+            pos: self.pos,
+        })
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = b"futex:";
+
+        for (b, p) in buf.iter_mut().zip(path.iter()) {
+            *b = *p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let mut i = 0;
+        for (b, w) in buf.iter_mut().zip(self.woken.bytes().skip(self.pos)) {
+            *b = w;
+            i += 1;
+        }
+        self.pos += i;
+
+        Ok(i)
+    }
+
+    /// Run the command in `buf`
+    ///
+    /// Returns `EINVAL` if it is not a command, and the errors of `Futexes::wait` and
+    /// `Futexes::wake`.
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let command = try!(str::from_utf8(buf).or(Err(Error::new(EINVAL))));
+        let mut args = command.split_whitespace();
+        let name = args.next();
+        let mut numbers = args.map(|arg| arg.parse::<usize>().ok());
+        let (address, arg) = match (numbers.next(), numbers.next(), numbers.next()) {
+            (Some(Some(address)), Some(Some(arg)), None) => (address, arg),
+            _ => return Err(Error::new(EINVAL)),
+        };
+
+        match name {
+            Some("wait") => try!(::env().futexes.wait(address, arg)),
+            Some("wake") => {
+                let woken = try!(::env().futexes.wake(address, arg));
+                self.woken = format!("{}\n", woken);
+                self.pos = 0;
+            }
+            _ => return Err(Error::new(EINVAL)),
+        }
+
+        Ok(buf.len())
+    }
+
+    fn sync(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The futex scheme
+///
+/// `futex:` opens a resource to wait on and wake futexes with, see `FutexResource`, for
+/// synchronization in userspace which only enters the kernel when it has to wait.
+pub struct FutexScheme;
+
+impl KScheme for FutexScheme {
+    fn scheme(&self) -> &str {
+        "futex"
+    }
+
+    fn open(&mut self, url: Url, _: usize) -> Result<Box<Resource>> {
+        if !url.reference().trim_matches('/').is_empty() {
+            return Err(Error::new(ENOENT));
+        }
+
+        Ok(box FutexResource {
+            woken: String::new(),
+            pos: 0,
+        })
+    }
+}
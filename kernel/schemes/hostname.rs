@@ -0,0 +1,112 @@
+use alloc::boxed::Box;
+
+use collections::string::String;
+
+use core::{cmp, str};
+
+use fs::{check_privileged, is_write, KScheme, Resource, ResourceSeek, Url};
+
+use system::error::{Error, Result, EINVAL, EPERM};
+
+/// The longest hostname which can be set
+pub const HOSTNAME_MAX: usize = 255;
+
+/// A hostname resource
+///
+/// Every read sees the current hostname, and every write replaces it.
+pub struct HostnameResource {
+    pos: usize,
+}
+
+impl Resource for HostnameResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box HostnameResource { pos: self.pos })
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = b"hostname:";
+
+        for (b, p) in buf.iter_mut().zip(path.iter()) {
+            *b = *p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let hostname = unsafe { & *::env().hostname.get() };
+
+        let mut i = 0;
+        for (b, h) in buf.iter_mut().zip(hostname.bytes().skip(self.pos)) {
+            *b = h;
+            i += 1;
+        }
+        self.pos += i;
+
+        Ok(i)
+    }
+
+    /// Replace the hostname with `buf`, without a trailing newline
+    ///
+    /// Returns `EPERM` unless the current context has an IOPL of 3, and `EINVAL` if the hostname
+    /// is empty, longer than `HOSTNAME_MAX`, not UTF-8, or contains a NUL or a newline.
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let contexts = unsafe { & *::env().contexts.get() };
+        if try!(contexts.current()).iopl != 3 {
+            return Err(Error::new(EPERM));
+        }
+
+        let name = if buf.ends_with(b"\n") {
+            &buf[..buf.len() - 1]
+        } else {
+            buf
+        };
+
+        if name.is_empty() || name.len() > HOSTNAME_MAX ||
+           name.iter().any(|&b| b == 0 || b == b'\n') {
+            return Err(Error::new(EINVAL));
+        }
+
+        let hostname = try!(str::from_utf8(name).or(Err(Error::new(EINVAL))));
+        unsafe { *::env().hostname.get() = String::from(hostname) };
+
+        Ok(buf.len())
+    }
+
+    fn seek(&mut self, pos: ResourceSeek) -> Result<usize> {
+        let len = unsafe { & *::env().hostname.get() }.len();
+        self.pos = match pos {
+            ResourceSeek::Start(offset) => cmp::min(len, offset),
+            ResourceSeek::Current(offset) =>
+                cmp::max(0, cmp::min(len as isize, self.pos as isize + offset)) as usize,
+            ResourceSeek::End(offset) =>
+                cmp::max(0, cmp::min(len as isize, len as isize + offset)) as usize,
+            ResourceSeek::Data(_) | ResourceSeek::Hole(_) => return Err(Error::new(EINVAL)),
+        };
+        Ok(self.pos)
+    }
+}
+
+/// The hostname scheme
+///
+/// `hostname:` reads and writes the hostname, which is shared by every context.
+pub struct HostnameScheme;
+
+impl KScheme for HostnameScheme {
+    fn scheme(&self) -> &str {
+        "hostname"
+    }
+
+    /// Only privileged contexts may open the hostname for writing
+    fn check_access(&self, _: &Url, flags: usize) -> Result<()> {
+        if is_write(flags) {
+            check_privileged()
+        } else {
+            Ok(())
+        }
+    }
+
+    fn open(&mut self, _: Url, _: usize) -> Result<Box<Resource>> {
+        Ok(box HostnameResource { pos: 0 })
+    }
+}
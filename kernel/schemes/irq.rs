@@ -0,0 +1,124 @@
+use alloc::boxed::Box;
+
+use collections::string::ToString;
+
+use core::cmp;
+
+use fs::{KScheme, Resource, Url, VecResource};
+
+use schemes::interrupt;
+
+use system::error::{Error, Result, EAGAIN, EINVAL};
+use system::syscall::O_NONBLOCK;
+
+/// The number of IRQ lines, which are the interrupts from 0x20
+pub const IRQ_COUNT: usize = 16;
+
+/// The number of times the IRQ `irq` fired
+fn count(irq: usize) -> u64 {
+    unsafe { &*::env().interrupts.get() }[0x20 + irq]
+}
+
+/// Parse the IRQ of an IRQ URL path, which may start with `//`
+///
+/// Returns `None` for an empty path, or `EINVAL` if the IRQ is not a number below `IRQ_COUNT`.
+pub fn parse_irq(path: &str) -> Result<Option<usize>> {
+    let path = path.trim_left_matches('/');
+    if path.is_empty() {
+        return Ok(None);
+    }
+
+    match path.parse::<usize>() {
+        Ok(irq) if irq < IRQ_COUNT => Ok(Some(irq)),
+        _ => Err(Error::new(EINVAL)),
+    }
+}
+
+/// A resource waiting for an IRQ
+pub struct IrqResource {
+    irq: usize,
+    /// The count of the IRQ when it was last read
+    seen: u64,
+    nonblock: bool,
+}
+
+impl IrqResource {
+    /// Wait for the IRQ `irq` to fire from now on
+    pub fn new(irq: usize, nonblock: bool) -> IrqResource {
+        IrqResource {
+            irq: irq,
+            seen: count(irq),
+            nonblock: nonblock,
+        }
+    }
+}
+
+impl Resource for IrqResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box IrqResource {
+            irq: self.irq,
+            seen: self.seen,
+            nonblock: self.nonblock,
+        })
+    }
+
+    /// The IRQ fired since the last read
+    fn is_readable(&self) -> bool {
+        count(self.irq) != self.seen
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = format!("irq:{}", self.irq);
+
+        for (b, p) in buf.iter_mut().zip(path.bytes()) {
+            *b = p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    /// Wait for the IRQ to fire, and read how many times it has, as "N\n"
+    ///
+    /// Interrupts which fired since the last read are not waited for again. Without blocking,
+    /// returns `EAGAIN` if there are none.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        loop {
+            let count = count(self.irq);
+            if count != self.seen {
+                self.seen = count;
+
+                let text = format!("{}\n", count);
+                for (b, t) in buf.iter_mut().zip(text.bytes()) {
+                    *b = t;
+                }
+                return Ok(cmp::min(buf.len(), text.len()));
+            }
+
+            if self.nonblock {
+                return Err(Error::new(EAGAIN));
+            }
+
+            ::env().irq.wait("IrqResource::read");
+        }
+    }
+}
+
+/// The IRQ scheme
+///
+/// Reading `irq:` returns the table of interrupt counts, like `interrupt:`. Reading `irq:N` waits
+/// for the IRQ `N` to fire, so that drivers can handle interrupts in userspace.
+pub struct IrqScheme;
+
+impl KScheme for IrqScheme {
+    fn scheme(&self) -> &str {
+        "irq"
+    }
+
+    fn open(&mut self, url: Url, flags: usize) -> Result<Box<Resource>> {
+        let flags = try!(url.flags(flags));
+        match try!(parse_irq(url.path())) {
+            Some(irq) => Ok(box IrqResource::new(irq, flags & O_NONBLOCK == O_NONBLOCK)),
+            None => Ok(box VecResource::new("irq:".to_string(), interrupt::table().into_bytes())),
+        }
+    }
+}
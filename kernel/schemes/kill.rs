@@ -0,0 +1,96 @@
+use alloc::boxed::Box;
+
+use core::{cmp, str};
+
+use fs::{KScheme, Resource, Url};
+
+use system::error::{Error, Result, EINVAL, EPERM, ESRCH};
+use system::syscall::NSIG;
+
+/// Check whether the current context may signal the context `pid`
+///
+/// Only the context itself, its parent, and contexts with an IOPL of 3 may signal it. Returns
+/// `ESRCH` if there is no context `pid`, and `EPERM` if it may not be signaled.
+pub fn check_kill(pid: usize) -> Result<()> {
+    let contexts = unsafe { & *::env().contexts.get() };
+    let current = try!(contexts.current());
+    let target = try!(contexts.find(pid));
+    if target.pid != current.pid && target.ppid != current.pid && current.iopl != 3 {
+        return Err(Error::new(EPERM));
+    }
+    Ok(())
+}
+
+/// Send `signal` to the context `pid`, on behalf of the current context
+///
+/// The current context must be allowed to by `check_kill`. The signal is taken when the context
+/// next returns from a syscall.
+pub fn kill(pid: usize, signal: usize) -> Result<()> {
+    if signal == 0 || signal >= NSIG {
+        return Err(Error::new(EINVAL));
+    }
+
+    try!(check_kill(pid));
+    try!(unsafe { &mut *::env().contexts.get() }.find_mut(pid)).signal(signal);
+    Ok(())
+}
+
+fn parse_pid(url: &Url) -> Result<usize> {
+    url.reference().trim_matches('/').parse::<usize>().or(Err(Error::new(ESRCH)))
+}
+
+/// A resource which signals a context
+pub struct KillResource {
+    pid: usize,
+}
+
+impl Resource for KillResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box KillResource { pid: self.pid })
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = format!("kill:{}", self.pid);
+
+        for (b, p) in buf.iter_mut().zip(path.bytes()) {
+            *b = p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    /// Send the signal written as a decimal number, such as `15`
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let signal = try!(str::from_utf8(buf)
+                              .ok()
+                              .and_then(|signal| signal.trim().parse::<usize>().ok())
+                              .ok_or(Error::new(EINVAL)));
+
+        try!(kill(self.pid, signal));
+        Ok(buf.len())
+    }
+}
+
+/// The signal scheme
+///
+/// Writing a signal number to `kill:PID` sends that signal to the context. Unknown PIDs return
+/// `ESRCH`, invalid signals `EINVAL`, and contexts which may not be signaled `EPERM`.
+pub struct KillScheme;
+
+impl KScheme for KillScheme {
+    fn scheme(&self) -> &str {
+        "kill"
+    }
+
+    /// Only contexts which may signal the context can open it, see `check_kill`
+    fn check_access(&self, url: &Url, _: usize) -> Result<()> {
+        check_kill(try!(parse_pid(url)))
+    }
+
+    fn open(&mut self, url: Url, _: usize) -> Result<Box<Resource>> {
+        let pid = try!(parse_pid(&url));
+        try!(unsafe { & *::env().contexts.get() }.find(pid));
+
+        Ok(box KillResource { pid: pid })
+    }
+}
@@ -0,0 +1,182 @@
+use alloc::boxed::Box;
+
+use collections::string::String;
+
+use core::{cmp, str};
+
+use common::random::{self, REKEY_REQUESTS_MAX};
+
+use drivers::pit::{PIT_HZ_MAX, PIT_HZ_MIN};
+
+use fs::{KScheme, Resource, ResourceSeek, Url, VecResource};
+
+use system::error::{Error, Result, EINVAL, ENOENT, EPERM};
+
+/// The highest limit on open files which can be given to new contexts
+pub const FILE_LIMIT_MAX: usize = 65536;
+
+/// A tunable kernel parameter
+pub struct Param {
+    /// The name, as in `kparam:NAME`
+    pub name: &'static str,
+    /// The lowest value which can be set
+    pub min: usize,
+    /// The highest value which can be set
+    pub max: usize,
+    /// Read the current value
+    pub get: fn() -> usize,
+    /// Set a value between `min` and `max`
+    pub set: fn(usize) -> Result<()>,
+}
+
+fn file_limit() -> usize {
+    unsafe { *::env().file_limit.get() }
+}
+
+fn set_file_limit(limit: usize) -> Result<()> {
+    unsafe { *::env().file_limit.get() = limit };
+    Ok(())
+}
+
+fn timer_frequency() -> usize {
+    unsafe { & *::env().pit.get() }.frequency()
+}
+
+fn set_timer_frequency(hz: usize) -> Result<()> {
+    unsafe { &mut *::env().pit.get() }.set_frequency(hz)
+}
+
+/// The kernel parameters, in the order they are listed
+///
+/// `file_limit` is the limit on open files of new root contexts, which children inherit,
+/// `rekey_requests` the number of requests the CSPRNG serves between rekeys, and
+/// `timer_frequency` the tick rate of the PIT in Hz, which is also the rate of time slices.
+pub static PARAMS: [Param; 3] = [
+    Param {
+        name: "file_limit",
+        min: 1,
+        max: FILE_LIMIT_MAX,
+        get: file_limit,
+        set: set_file_limit,
+    },
+    Param {
+        name: "rekey_requests",
+        min: 1,
+        max: REKEY_REQUESTS_MAX,
+        get: random::rekey_requests,
+        set: random::set_rekey_requests,
+    },
+    Param {
+        name: "timer_frequency",
+        min: PIT_HZ_MIN,
+        max: PIT_HZ_MAX,
+        get: timer_frequency,
+        set: set_timer_frequency,
+    },
+];
+
+/// A kernel parameter, read and written as a decimal number
+///
+/// Every read sees the current value.
+pub struct ParamResource {
+    param: &'static Param,
+    pos: usize,
+}
+
+impl Resource for ParamResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box ParamResource {
+            param: self.param,
+            pos: self.pos,
+        })
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = format!("kparam:{}", self.param.name);
+
+        for (b, p) in buf.iter_mut().zip(path.bytes()) {
+            *b = p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    /// Read the value, followed by a newline
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let data = format!("{}\n", (self.param.get)());
+
+        let mut i = 0;
+        for (b, d) in buf.iter_mut().zip(data.bytes().skip(self.pos)) {
+            *b = d;
+            i += 1;
+        }
+        self.pos += i;
+
+        Ok(i)
+    }
+
+    /// Set the value written as a decimal number
+    ///
+    /// Returns `EPERM` unless the current context has an IOPL of 3, and `EINVAL` if it is not a
+    /// number between the `min` and `max` of the parameter.
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let contexts = unsafe { & *::env().contexts.get() };
+        if try!(contexts.current()).iopl != 3 {
+            return Err(Error::new(EPERM));
+        }
+
+        let value = try!(str::from_utf8(buf)
+                             .ok()
+                             .and_then(|value| value.trim().parse::<usize>().ok())
+                             .ok_or(Error::new(EINVAL)));
+
+        if value < self.param.min || value > self.param.max {
+            return Err(Error::new(EINVAL));
+        }
+
+        try!((self.param.set)(value));
+        Ok(buf.len())
+    }
+
+    /// Only seeking from the start is supported
+    fn seek(&mut self, pos: ResourceSeek) -> Result<usize> {
+        match pos {
+            ResourceSeek::Start(offset) => self.pos = offset,
+            _ => return Err(Error::new(EINVAL)),
+        }
+        Ok(self.pos)
+    }
+
+    fn sync(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The kernel parameter scheme
+///
+/// `kparam:` lists the names of the parameters, one per line, and `kparam:NAME` reads the value of
+/// one, which privileged contexts can also set, see `PARAMS`.
+pub struct KparamScheme;
+
+impl KScheme for KparamScheme {
+    fn scheme(&self) -> &str {
+        "kparam"
+    }
+
+    fn open(&mut self, url: Url, _: usize) -> Result<Box<Resource>> {
+        let name = url.reference().trim_matches('/');
+        if name.is_empty() {
+            let mut list = String::new();
+            for param in PARAMS.iter() {
+                list.push_str(param.name);
+                list.push('\n');
+            }
+            return Ok(box VecResource::new(String::from("kparam:"), list.into_bytes()));
+        }
+
+        match PARAMS.iter().find(|param| param.name == name) {
+            Some(param) => Ok(box ParamResource { param: param, pos: 0 }),
+            None => Err(Error::new(ENOENT)),
+        }
+    }
+}
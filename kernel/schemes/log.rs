@@ -0,0 +1,129 @@
+use alloc::boxed::Box;
+
+use core::{cmp, str};
+
+use fs::{KScheme, Resource, Url};
+
+use logging::{self, LogLevel};
+
+use system::error::{Error, Result, EAGAIN, EINVAL};
+use system::syscall::{F_GETFL, F_SETFL, O_NONBLOCK};
+
+/// The longest message which can be written to `log:`
+pub const LOG_LINE_MAX: usize = 1024;
+
+/// A kernel log resource
+///
+/// Follows the kernel log from its oldest message, like `dmesg -w`. Messages dropped from the log
+/// before they were read are skipped.
+pub struct LogResource {
+    /// The offset of the next byte to read, counted from the first message ever logged
+    pos: usize,
+    /// Whether reads return `EAGAIN` instead of blocking when all messages were read
+    nonblock: bool,
+}
+
+impl Resource for LogResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box LogResource {
+            pos: self.pos,
+            nonblock: self.nonblock,
+        })
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = b"log:";
+
+        for (b, p) in buf.iter_mut().zip(path.iter()) {
+            *b = *p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    /// Read the next kernel log messages, waiting for new ones once all of them were read
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            {
+                let log = unsafe { & *::env().log.get() };
+                self.pos = cmp::max(self.pos, log.dropped);
+
+                let count = log.read_at(self.pos - log.dropped, buf);
+                if count > 0 {
+                    self.pos += count;
+                    return Ok(count);
+                }
+            }
+
+            if self.nonblock {
+                return Err(Error::new(EAGAIN));
+            }
+
+            ::env().log_written.wait("LogResource::read");
+        }
+    }
+
+    /// Log `buf` as an informational message, without a trailing newline
+    ///
+    /// Returns `EINVAL` if the message is longer than `LOG_LINE_MAX`, not UTF-8, or contains a
+    /// newline.
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let line = if buf.ends_with(b"\n") {
+            &buf[..buf.len() - 1]
+        } else {
+            buf
+        };
+
+        if line.len() > LOG_LINE_MAX || line.contains(&b'\n') {
+            return Err(Error::new(EINVAL));
+        }
+
+        let message = try!(str::from_utf8(line).or(Err(Error::new(EINVAL))));
+        logging::syslog(LogLevel::Info, message);
+
+        Ok(buf.len())
+    }
+
+    /// Get or set `O_NONBLOCK`
+    fn fcntl(&mut self, cmd: usize, arg: usize) -> Result<usize> {
+        match cmd {
+            F_GETFL => Ok(self.flags()),
+            F_SETFL => {
+                self.nonblock = arg & O_NONBLOCK == O_NONBLOCK;
+                Ok(0)
+            }
+            _ => Err(Error::new(EINVAL)),
+        }
+    }
+
+    fn flags(&self) -> usize {
+        if self.nonblock {
+            O_NONBLOCK
+        } else {
+            0
+        }
+    }
+}
+
+/// The kernel log scheme
+///
+/// Unlike `syslog:`, reading `log:` blocks for new messages, and userspace can write to it.
+pub struct LogScheme;
+
+impl KScheme for LogScheme {
+    fn scheme(&self) -> &str {
+        "log"
+    }
+
+    fn open(&mut self, url: Url, flags: usize) -> Result<Box<Resource>> {
+        let flags = try!(url.flags(flags));
+        Ok(box LogResource {
+            pos: 0,
+            nonblock: flags & O_NONBLOCK == O_NONBLOCK,
+        })
+    }
+}
@@ -0,0 +1,204 @@
+use alloc::arc::Arc;
+use alloc::boxed::Box;
+
+use collections::{String, Vec};
+
+use core::cmp;
+
+use common::time::{Duration, NANOS_PER_MILLI};
+
+use fs::{KScheme, Resource, Url};
+use fs::url::URL_FLAGS;
+
+use sync::WaitQueue;
+
+use system::error::{Error, Result, EAGAIN, EINVAL};
+use system::syscall::{F_GETFL, F_SETFL, O_NONBLOCK};
+
+/// A message written to a loopback resource, which can be read once it is due
+#[derive(Clone)]
+struct Message {
+    due: Duration,
+    data: Vec<u8>,
+}
+
+/// How a loopback resource delays and drops messages
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LoopbackOptions {
+    /// The delay before a message can be read, in milliseconds
+    pub delay: usize,
+    /// Drop every Nth message, or none if 0
+    pub drop: usize,
+}
+
+/// Parse the query of a `loopback:` URL, such as `delay=10&drop=3&nonblock`
+///
+/// Open flags may be named too, see `Url::flags`, and are added to `flags`. Returns `EINVAL` if a
+/// delay or drop is not a number, if the drop is 0, or if any other name is unknown.
+pub fn parse_options(query: &str, flags: usize) -> Result<(LoopbackOptions, usize)> {
+    let mut options = LoopbackOptions {
+        delay: 0,
+        drop: 0,
+    };
+    let mut flags = flags;
+    for option in query.split('&').filter(|option| !option.is_empty()) {
+        if option.starts_with("delay=") {
+            options.delay = try!(option[6..].parse::<usize>().or(Err(Error::new(EINVAL))));
+        } else if option.starts_with("drop=") {
+            options.drop = try!(option[5..].parse::<usize>().or(Err(Error::new(EINVAL))));
+            if options.drop == 0 {
+                return Err(Error::new(EINVAL));
+            }
+        } else {
+            match URL_FLAGS.iter().find(|&&(name, _)| name == option) {
+                Some(&(_, flag)) => flags |= flag,
+                None => return Err(Error::new(EINVAL)),
+            }
+        }
+    }
+    Ok((options, flags))
+}
+
+/// A loopback resource
+///
+/// Every write is a message, which is queued to be read back from the same resource, or one
+/// duplicated from it, after the delay. Each read returns one message, and the rest of a message
+/// longer than the buffer is discarded. Reads block until a message is due, or return `EAGAIN`
+/// with `O_NONBLOCK`. With a drop, every Nth message written is discarded, though the write still
+/// succeeds, as a lost packet would.
+pub struct LoopbackResource {
+    queue: Arc<WaitQueue<Message>>,
+    options: LoopbackOptions,
+    /// The number of messages written
+    written: usize,
+    /// Whether reads return `EAGAIN` instead of blocking when no message is due
+    nonblock: bool,
+}
+
+impl LoopbackResource {
+    pub fn new(options: LoopbackOptions, flags: usize) -> LoopbackResource {
+        LoopbackResource {
+            queue: Arc::new(WaitQueue::new()),
+            options: options,
+            written: 0,
+            nonblock: flags & O_NONBLOCK == O_NONBLOCK,
+        }
+    }
+
+    /// The time until the next message is due, or `None` if there is no message
+    fn until_due(&self) -> Option<Duration> {
+        unsafe { self.queue.inner() }.front().map(|message| {
+            let now = Duration::monotonic();
+            if message.due > now {
+                message.due - now
+            } else {
+                Duration::new(0, 0)
+            }
+        })
+    }
+}
+
+impl Resource for LoopbackResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box LoopbackResource {
+            queue: self.queue.clone(),
+            options: self.options,
+            written: self.written,
+            nonblock: self.nonblock,
+        })
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let mut path = String::from("loopback:");
+        if self.options.delay > 0 || self.options.drop > 0 {
+            path.push_str(&format!("?delay={}&drop={}", self.options.delay, self.options.drop));
+        }
+
+        for (b, p) in buf.iter_mut().zip(path.bytes()) {
+            *b = p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    /// Read the next message, waiting until one is due
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        loop {
+            match self.until_due() {
+                Some(time) if time.secs == 0 && time.nanos == 0 => {
+                    if let Some(message) = self.queue.receive_nonblocking() {
+                        let count = cmp::min(buf.len(), message.data.len());
+                        buf[..count].copy_from_slice(&message.data[..count]);
+                        return Ok(count);
+                    }
+                },
+                _ if self.nonblock => return Err(Error::new(EAGAIN)),
+                Some(time) => {
+                    self.queue.condition.wait_for("LoopbackResource::read", time);
+                },
+                None => self.queue.condition.wait("LoopbackResource::read"),
+            }
+        }
+    }
+
+    /// Queue `buf` as a message, unless it is dropped
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.written += 1;
+        if self.options.drop == 0 || self.written % self.options.drop != 0 {
+            let delay = Duration::new((self.options.delay / 1000) as i64,
+                                      (self.options.delay % 1000) as i32 * NANOS_PER_MILLI);
+            self.queue.send(Message {
+                                due: Duration::monotonic() + delay,
+                                data: buf.to_vec(),
+                            },
+                            "LoopbackResource::write");
+        }
+        Ok(buf.len())
+    }
+
+    /// Get or set `O_NONBLOCK`
+    fn fcntl(&mut self, cmd: usize, arg: usize) -> Result<usize> {
+        match cmd {
+            F_GETFL => Ok(self.flags()),
+            F_SETFL => {
+                self.nonblock = arg & O_NONBLOCK == O_NONBLOCK;
+                Ok(0)
+            }
+            _ => Err(Error::new(EINVAL)),
+        }
+    }
+
+    fn flags(&self) -> usize {
+        if self.nonblock {
+            O_NONBLOCK
+        } else {
+            0
+        }
+    }
+
+    /// A message is due
+    fn is_readable(&self) -> bool {
+        match self.until_due() {
+            Some(time) => time.secs == 0 && time.nanos == 0,
+            None => false,
+        }
+    }
+}
+
+/// The loopback scheme
+///
+/// Each open of `loopback:` creates a new, empty loopback resource, a trivial peer for testing
+/// packet transports, timeouts and non-blocking reads. See `parse_options` for the query, and
+/// `LoopbackResource`.
+pub struct LoopbackScheme;
+
+impl KScheme for LoopbackScheme {
+    fn scheme(&self) -> &str {
+        "loopback"
+    }
+
+    fn open(&mut self, url: Url, flags: usize) -> Result<Box<Resource>> {
+        let (options, flags) = try!(parse_options(url.query(), flags));
+        Ok(box LoopbackResource::new(options, flags))
+    }
+}
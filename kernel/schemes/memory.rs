@@ -2,13 +2,104 @@ use alloc::boxed::Box;
 
 use arch::memory;
 
-use collections::string::ToString;
+use collections::Vec;
 
-use fs::{KScheme, Resource, Url, VecResource};
+use core::{cmp, mem, slice};
 
-use system::error::Result;
+use fs::{Format, KScheme, Resource, ResourceSeek, Url};
+
+use system::error::{Error, Result, EACCES, EINVAL};
+use system::syscall::MemoryInfo;
+
+/// A memory usage resource
+///
+/// The usage is measured at every read, so rewinding and reading again reflects any changes.
+pub struct MemoryResource {
+    pos: usize,
+    format: Format,
+}
+
+impl MemoryResource {
+    /// The usage as text, in KB, or as a `MemoryInfo`, in bytes
+    fn summary(&self) -> Vec<u8> {
+        let used = memory::memory_used();
+        let free = memory::memory_free();
+        match self.format {
+            Format::Text => {
+                format!("Memory Total: {} KB\nMemory Used: {} KB\nMemory Free: {} KB\n",
+                        (used + free) / 1024,
+                        used / 1024,
+                        free / 1024)
+                    .into_bytes()
+            }
+            Format::Binary => {
+                let info = MemoryInfo {
+                    total: used + free,
+                    used: used,
+                    free: free,
+                };
+                unsafe {
+                    slice::from_raw_parts(&info as *const MemoryInfo as *const u8,
+                                          mem::size_of::<MemoryInfo>())
+                }.to_vec()
+            }
+        }
+    }
+}
+
+impl Resource for MemoryResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box MemoryResource {
+            pos: self.pos,
+            format: self.format,
+        })
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = b"memory:";
+
+        for (b, p) in buf.iter_mut().zip(path.iter()) {
+            *b = *p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let summary = self.summary();
+
+        let mut i = 0;
+        for (b, s) in buf.iter_mut().zip(summary.iter().skip(self.pos)) {
+            *b = *s;
+            i += 1;
+        }
+        self.pos += i;
+
+        Ok(i)
+    }
+
+    /// The memory usage is read only
+    fn write(&mut self, _: &[u8]) -> Result<usize> {
+        Err(Error::new(EACCES))
+    }
+
+    fn seek(&mut self, pos: ResourceSeek) -> Result<usize> {
+        let len = self.summary().len();
+        self.pos = match pos {
+            ResourceSeek::Start(offset) => cmp::min(len, offset),
+            ResourceSeek::Current(offset) =>
+                cmp::max(0, cmp::min(len as isize, self.pos as isize + offset)) as usize,
+            ResourceSeek::End(offset) =>
+                cmp::max(0, cmp::min(len as isize, len as isize + offset)) as usize,
+            ResourceSeek::Data(_) | ResourceSeek::Hole(_) => return Err(Error::new(EINVAL)),
+        };
+        Ok(self.pos)
+    }
+}
 
 /// A memory scheme
+///
+/// `memory:` reads the memory usage as text, and `memory:?format=binary` as a `MemoryInfo`.
 pub struct MemoryScheme;
 
 impl KScheme for MemoryScheme {
@@ -16,10 +107,10 @@ impl KScheme for MemoryScheme {
         "memory"
     }
 
-    fn open(&mut self, _: Url, _: usize) -> Result<Box<Resource>> {
-        let string = format!("Memory Used: {} KB\nMemory Free: {} KB\n",
-                             memory::memory_used() / 1024,
-                             memory::memory_free() / 1024);
-        Ok(box VecResource::new("memory:".to_string(), string.into_bytes()))
+    fn open(&mut self, url: Url, _: usize) -> Result<Box<Resource>> {
+        Ok(box MemoryResource {
+            pos: 0,
+            format: try!(url.format()),
+        })
     }
 }
@@ -1,5 +1,11 @@
+/// Console scheme
+pub mod console;
 /// Context scheme
 pub mod context;
+/// Processor information scheme
+pub mod cpu;
+/// Working directory scheme
+pub mod cwd;
 /// Debug scheme
 pub mod debug;
 /// Disk scheme
@@ -8,17 +14,67 @@ pub mod disk;
 pub mod display;
 /// Environment variables scheme
 pub mod env;
+/// Event scheme
+pub mod event;
+/// Recent faults scheme
+pub mod faults;
+/// Full scheme
+pub mod full;
+/// Futex scheme
+pub mod futex;
+/// Hostname scheme
+pub mod hostname;
 /// Init Filesystem
 pub mod initfs;
 /// Interrupt scheme
 pub mod interrupt;
+/// IRQ scheme
+pub mod irq;
+/// Signal scheme
+pub mod kill;
+/// Kernel parameter scheme
+pub mod kparam;
+/// Following kernel log scheme
+pub mod log;
+/// Loopback scheme, echoing writes back to reads
+pub mod loopback;
 /// Memory scheme
 pub mod memory;
+/// Null scheme
+pub mod null;
 /// Pipes
 pub mod pipe;
+/// Memory pressure scheme
+pub mod pressure;
 /// Psuedoterminals
 pub mod pty;
+/// Random number scheme
+pub mod random;
+/// Repeated pattern scheme
+pub mod repeat;
+/// Scheme listing scheme
+pub mod schemes;
+/// Shared memory scheme
+pub mod shm;
+/// Sleep scheme
+pub mod sleep;
+/// System information scheme
+pub mod sysinfo;
 /// Logging scheme
 pub mod syslog;
 /// Tests
 pub mod test;
+/// Deterministic random number scheme, for tests
+pub mod test_random;
+/// Clock scheme
+pub mod time;
+/// Timer frequency scheme
+pub mod timer;
+/// Kernel event tracing scheme
+pub mod trace;
+/// Uptime scheme
+pub mod uptime;
+/// Watchdog scheme
+pub mod watchdog;
+/// Zero scheme
+pub mod zero;
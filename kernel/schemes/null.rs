@@ -0,0 +1,54 @@
+use alloc::boxed::Box;
+
+use core::cmp;
+
+use fs::{KScheme, Resource, Url};
+
+use system::error::Result;
+
+/// A resource which is always empty, and discards everything written to it
+pub struct NullResource;
+
+impl Resource for NullResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box NullResource)
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = b"null:";
+
+        for (b, p) in buf.iter_mut().zip(path.iter()) {
+            *b = *p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    /// Read nothing, as the resource is always at its end
+    fn read(&mut self, _: &mut [u8]) -> Result<usize> {
+        Ok(0)
+    }
+
+    /// Discard `buf`
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        Ok(buf.len())
+    }
+
+    /// Nothing is buffered, so there is nothing to sync
+    fn sync(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The null scheme
+pub struct NullScheme;
+
+impl KScheme for NullScheme {
+    fn scheme(&self) -> &str {
+        "null"
+    }
+
+    fn open(&mut self, _: Url, _: usize) -> Result<Box<Resource>> {
+        Ok(box NullResource)
+    }
+}
@@ -1,31 +1,46 @@
 use alloc::arc::{Arc, Weak};
 use alloc::boxed::Box;
 
+use collections::{String, Vec};
+
 use core::cmp;
 
-use fs::Resource;
+use fs::{KScheme, Resource, Url};
+
+use sync::{ByteQueue, WaitCondition};
 
-use sync::WaitQueue;
+use system::error::{Error, Result, EINVAL, ENXIO, EPIPE};
+use system::syscall::{O_RDONLY, O_RDWR, O_WRONLY};
 
-use system::error::{Error, Result, EPIPE};
+/// The number of bytes a pipe holds before writes block
+pub const PIPE_CAPACITY: usize = 65536;
 
 /// Read side of a pipe
 pub struct PipeRead {
-    vec: Arc<WaitQueue<u8>>
+    vec: Arc<ByteQueue>,
+    /// Whether `PipeScheme` holds a reference to the pipe, which is not a write side
+    named: bool,
 }
 
 impl PipeRead {
     pub fn new() -> Self {
         PipeRead {
-            vec: Arc::new(WaitQueue::new())
+            vec: Arc::new(ByteQueue::new(PIPE_CAPACITY)),
+            named: false,
         }
     }
+
+    /// The number of open write sides
+    fn writers(&self) -> usize {
+        Arc::weak_count(&self.vec) - if self.named { 1 } else { 0 }
+    }
 }
 
 impl Resource for PipeRead {
     fn dup(&self) -> Result<Box<Resource>> {
         Ok(box PipeRead {
             vec: self.vec.clone(),
+            named: self.named,
         })
     }
 
@@ -39,40 +54,50 @@ impl Resource for PipeRead {
         Ok(cmp::min(buf.len(), path.len()))
     }
 
+    /// There is something to read, or every write side is closed
+    fn is_readable(&self) -> bool {
+        !self.vec.is_empty() || self.writers() == 0
+    }
+
+    fn is_writable(&self) -> bool {
+        false
+    }
+
+    /// Read from the pipe, blocking until there is something to read
+    ///
+    /// Returns 0 once the pipe is empty and every write side is closed.
     fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
-        if Arc::weak_count(&self.vec) == 0 && unsafe { self.vec.inner() }.is_empty() {
-            Ok(0)
-        } else {
-            if !buf.is_empty() {
-                buf[0] = self.vec.receive("PipeRead::read");
-            }
+        if buf.is_empty() {
+            return Ok(0);
+        }
 
-            let mut i = 1;
+        loop {
+            let i = self.vec.try_read_bytes(buf);
+            if i > 0 {
+                return Ok(i);
+            }
 
-            while i < buf.len() {
-                match unsafe { self.vec.inner() }.pop_front() {
-                    Some(b) => {
-                        buf[i] = b;
-                        i += 1;
-                    },
-                    None => break
-                }
+            if self.writers() == 0 {
+                return Ok(0);
             }
 
-            Ok(i)
+            self.vec.readable.wait("PipeRead::read");
         }
     }
 }
 
-/// Read side of a pipe
+/// Write side of a pipe
 pub struct PipeWrite {
-    vec: Weak<WaitQueue<u8>>,
+    vec: Weak<ByteQueue>,
+    /// Whether `PipeScheme` holds a reference to the pipe, which is not a write side
+    named: bool,
 }
 
 impl PipeWrite {
     pub fn new(read: &PipeRead) -> Self {
         PipeWrite {
             vec: Arc::downgrade(&read.vec),
+            named: read.named,
         }
     }
 }
@@ -81,9 +106,19 @@ impl Resource for PipeWrite {
     fn dup(&self) -> Result<Box<Resource>> {
         Ok(box PipeWrite {
             vec: self.vec.clone(),
+            named: self.named,
         })
     }
 
+    fn is_readable(&self) -> bool {
+        false
+    }
+
+    /// There is space to write, or every read side is closed
+    fn is_writable(&self) -> bool {
+        self.vec.upgrade().map_or(true, |vec| !vec.is_full())
+    }
+
     fn path(&self, buf: &mut [u8]) -> Result<usize> {
         let path = b"pipe:w";
 
@@ -94,17 +129,34 @@ impl Resource for PipeWrite {
         Ok(cmp::min(buf.len(), path.len()))
     }
 
+    /// Write to the pipe, blocking while it is full
+    ///
+    /// Returns `EPIPE` if every read side is closed before anything was written.
     fn write(&mut self, buf: &[u8]) -> Result<usize> {
-        match self.vec.upgrade() {
-            Some(vec) => {
-                for &b in buf.iter() {
-                    vec.send(b, "PipeWrite::write");
-                }
-
-                Ok(buf.len())
-            },
-            None => Err(Error::new(EPIPE))
+        let mut i = 0;
+        while i < buf.len() {
+            let vec = match self.vec.upgrade() {
+                Some(vec) => vec,
+                None => return if i > 0 {
+                    Ok(i)
+                } else {
+                    Err(Error::new(EPIPE))
+                },
+            };
+
+            i += vec.try_write_bytes(&buf[i..]);
+
+            if i < buf.len() {
+                // Wait without holding the pipe open, so that closing the last read side ends the
+                // wait. The read side keeps the pipe alive until then, as nothing else runs before
+                // this context blocks.
+                let writable = &vec.writable as *const WaitCondition;
+                drop(vec);
+                unsafe { (*writable).wait("PipeWrite::write") };
+            }
         }
+
+        Ok(i)
     }
 
     fn sync(&mut self) -> Result<()> {
@@ -112,3 +164,75 @@ impl Resource for PipeWrite {
         Ok(())
     }
 }
+
+impl Drop for PipeWrite {
+    /// Wake the readers when the last write side closes, so that they can return 0
+    fn drop(&mut self) {
+        if let Some(vec) = self.vec.upgrade() {
+            let others = if self.named { 1 } else { 0 };
+            if Arc::weak_count(&vec) == 1 + others {
+                vec.readable.notify("PipeWrite::drop");
+            }
+        }
+    }
+}
+
+/// The named pipe scheme
+///
+/// Opening `pipe:name` with `O_RDONLY` creates the pipe, or opens another read side if it exists.
+/// Opening it with `O_WRONLY` opens a write side, or returns `ENXIO` if the pipe has no read side.
+/// The pipe is removed once every read side is closed.
+pub struct PipeScheme {
+    pipes: Vec<(String, Weak<ByteQueue>)>,
+}
+
+impl PipeScheme {
+    pub fn new() -> Box<PipeScheme> {
+        box PipeScheme { pipes: Vec::new() }
+    }
+}
+
+impl KScheme for PipeScheme {
+    fn scheme(&self) -> &str {
+        "pipe"
+    }
+
+    fn open(&mut self, url: Url, flags: usize) -> Result<Box<Resource>> {
+        self.pipes.retain(|&(_, ref vec)| vec.upgrade().is_some());
+
+        let name = url.reference().trim_matches('/');
+        let existing = self.pipes
+                           .iter()
+                           .find(|&&(ref pipe_name, _)| pipe_name == name)
+                           .and_then(|&(_, ref vec)| vec.upgrade());
+
+        match flags & (O_WRONLY | O_RDWR) {
+            O_RDONLY => {
+                if let Some(vec) = existing {
+                    Ok(box PipeRead {
+                        vec: vec,
+                        named: true,
+                    })
+                } else {
+                    let read = PipeRead {
+                        vec: Arc::new(ByteQueue::new(PIPE_CAPACITY)),
+                        named: true,
+                    };
+                    self.pipes.push((String::from(name), Arc::downgrade(&read.vec)));
+                    Ok(box read)
+                }
+            },
+            O_WRONLY => {
+                if let Some(vec) = existing {
+                    Ok(box PipeWrite {
+                        vec: Arc::downgrade(&vec),
+                        named: true,
+                    })
+                } else {
+                    Err(Error::new(ENXIO))
+                }
+            },
+            _ => Err(Error::new(EINVAL)),
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use alloc::boxed::Box;
+
+use arch::memory;
+
+use core::cmp;
+
+use fs::{KScheme, Resource, Url};
+
+use system::error::{Error, Result, EAGAIN, EINVAL, ENOENT};
+use system::syscall::O_NONBLOCK;
+
+/// Parse the threshold of a pressure URL path, such as `//memory/256`
+///
+/// Returns `ENOENT` if the path does not start with `memory`, or `EINVAL` if the threshold is
+/// missing, zero or not a number.
+pub fn parse_threshold(path: &str) -> Result<usize> {
+    let mut parts = path.trim_left_matches('/').splitn(2, '/');
+    if parts.next() != Some("memory") {
+        return Err(Error::new(ENOENT));
+    }
+
+    match parts.next().unwrap_or("").parse::<usize>() {
+        Ok(threshold) if threshold > 0 => Ok(threshold),
+        _ => Err(Error::new(EINVAL)),
+    }
+}
+
+/// A memory pressure resource
+pub struct PressureResource {
+    /// The number of free pages below which memory is under pressure
+    threshold: usize,
+    nonblock: bool,
+}
+
+impl PressureResource {
+    pub fn new(threshold: usize, nonblock: bool) -> PressureResource {
+        PressureResource {
+            threshold: threshold,
+            nonblock: nonblock,
+        }
+    }
+}
+
+impl Resource for PressureResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box PressureResource::new(self.threshold, self.nonblock))
+    }
+
+    /// Memory is under pressure
+    fn is_readable(&self) -> bool {
+        memory::clusters_free() < self.threshold
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = format!("pressure:memory/{}", self.threshold);
+
+        for (b, p) in buf.iter_mut().zip(path.bytes()) {
+            *b = p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    /// Wait until fewer pages than the threshold are free, and read how many are, as "N\n"
+    ///
+    /// This returns immediately for as long as memory stays under pressure. Without blocking,
+    /// returns `EAGAIN` if it is not.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        loop {
+            let free = memory::clusters_free();
+            if free < self.threshold {
+                let text = format!("{}\n", free);
+                for (b, t) in buf.iter_mut().zip(text.bytes()) {
+                    *b = t;
+                }
+                return Ok(cmp::min(buf.len(), text.len()));
+            }
+
+            if self.nonblock {
+                return Err(Error::new(EAGAIN));
+            }
+
+            memory::watch_pressure(self.threshold);
+            ::env().memory_pressure.wait("PressureResource::read");
+        }
+    }
+}
+
+/// The pressure scheme
+///
+/// Reading `pressure://memory/N` waits for fewer than `N` pages of memory to be free, so that a
+/// service can free its caches before allocations fail. Every waiting reader is woken.
+pub struct PressureScheme;
+
+impl KScheme for PressureScheme {
+    fn scheme(&self) -> &str {
+        "pressure"
+    }
+
+    fn open(&mut self, url: Url, flags: usize) -> Result<Box<Resource>> {
+        let flags = try!(url.flags(flags));
+        let threshold = try!(parse_threshold(url.path()));
+        Ok(box PressureResource::new(threshold, flags & O_NONBLOCK == O_NONBLOCK))
+    }
+}
@@ -97,6 +97,10 @@ impl Resource for PtyMaster {
         })
     }
 
+    fn is_readable(&self) -> bool {
+        self.inner.output.peek()
+    }
+
     fn path(&self, buf: &mut [u8]) -> Result<usize> {
         let path = format!("pty:{}", self.inner.id);
 
@@ -149,6 +153,16 @@ impl Resource for PtySlave {
         })
     }
 
+    /// There is input, or the master is closed
+    fn is_readable(&self) -> bool {
+        self.inner.upgrade().map_or(true, |inner| inner.input.peek())
+    }
+
+    /// The slave end is the terminal of the program using the pty
+    fn is_terminal(&self) -> bool {
+        true
+    }
+
     fn path(&self, buf: &mut [u8]) -> Result<usize> {
         match self.inner.upgrade() {
             Some(inner) => {
@@ -0,0 +1,480 @@
+use alloc::boxed::Box;
+
+use collections::string::String;
+use collections::vec::Vec;
+
+use core::{cmp, mem};
+
+use common::random;
+
+use fs::{KScheme, Resource, ResourceSeek, Url, VecResource};
+use fs::url::URL_FLAGS;
+
+use system::error::{Error, Result, EAGAIN, EINVAL};
+use system::syscall::O_NONBLOCK;
+
+/// Parse the range requested by a random URL reference
+///
+/// An empty reference requests raw bytes. `low-high` requests numbers in `[low, high]`, and `dieN`
+/// requests numbers in `[1, N]`.
+pub fn parse_range(reference: &str) -> Result<Option<(usize, usize)>> {
+    if reference.is_empty() {
+        return Ok(None);
+    }
+
+    let (low, high) = if reference.starts_with("die") {
+        (1, try!(reference[3..].parse::<usize>().or(Err(Error::new(EINVAL)))))
+    } else {
+        let mut parts = reference.splitn(2, '-');
+        let low = try!(parts.next().unwrap_or("").parse::<usize>().or(Err(Error::new(EINVAL))));
+        let high = try!(parts.next().unwrap_or("").parse::<usize>().or(Err(Error::new(EINVAL))));
+        (low, high)
+    };
+
+    if low > high {
+        Err(Error::new(EINVAL))
+    } else {
+        Ok(Some((low, high)))
+    }
+}
+
+/// The largest block size which can be requested with `bs=`
+pub const BLOCK_SIZE_MAX: usize = 1024 * 1024;
+
+/// The options of a random URL reference
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RandomOptions {
+    /// The inclusive range of numbers to produce, or `None` for raw bytes
+    pub range: Option<(usize, usize)>,
+    /// The size of the blocks raw bytes are generated in, or `None` to generate them per read
+    pub block_size: Option<usize>,
+    /// The number of blocks to produce before reads return EOF, or `None` for no limit
+    pub count: Option<usize>,
+    /// Whether the blocks are keys, requested with `keys?count=N&size=K`, see `parse_keys`
+    pub keys: bool,
+}
+
+/// Parse the options of a random URL reference
+///
+/// The reference is a list of `&` separated options, which may start with `//`. `bs=N` generates
+/// raw bytes in blocks of `N` bytes, `count=N` ends the output after `N` blocks, and anything else
+/// is a range as accepted by `parse_range`. Returns `EINVAL` if a block size is zero, larger than
+/// `BLOCK_SIZE_MAX` or not a number, if `count` is given without `bs`, or if `bs` is combined with a
+/// range.
+pub fn parse_options(reference: &str) -> Result<RandomOptions> {
+    let mut options = RandomOptions {
+        range: None,
+        block_size: None,
+        count: None,
+        keys: false,
+    };
+
+    let reference = reference.trim_left_matches('/');
+    if reference.is_empty() {
+        return Ok(options);
+    }
+
+    for option in reference.split('&') {
+        if option.starts_with("bs=") {
+            let block_size = try!(option[3..].parse::<usize>().or(Err(Error::new(EINVAL))));
+            if block_size == 0 || block_size > BLOCK_SIZE_MAX {
+                return Err(Error::new(EINVAL));
+            }
+            options.block_size = Some(block_size);
+        } else if option.starts_with("count=") {
+            options.count = Some(try!(option[6..].parse::<usize>().or(Err(Error::new(EINVAL)))));
+        } else if options.range.is_none() {
+            options.range = try!(parse_range(option));
+        } else {
+            return Err(Error::new(EINVAL));
+        }
+    }
+
+    if options.block_size.is_none() && options.count.is_some() {
+        return Err(Error::new(EINVAL));
+    }
+    if options.block_size.is_some() && options.range.is_some() {
+        return Err(Error::new(EINVAL));
+    }
+
+    Ok(options)
+}
+
+/// Parse the query of a `keys` URL, such as `count=N&size=K`
+///
+/// Reading produces `N` keys of `K` bytes back to back, each a block of fresh bytes, and then EOF.
+/// Open flags may be named too, see `Url::flags`, and are added to `flags`. Returns `EINVAL` if the
+/// count or size is missing, zero or not a number, if the size is larger than `BLOCK_SIZE_MAX`, or
+/// if any other name is unknown.
+pub fn parse_keys(query: &str, flags: usize) -> Result<(RandomOptions, usize)> {
+    let mut count = None;
+    let mut size = None;
+    let mut flags = flags;
+    for option in query.split('&').filter(|option| !option.is_empty()) {
+        if option.starts_with("count=") {
+            count = Some(try!(option[6..].parse::<usize>().or(Err(Error::new(EINVAL)))));
+        } else if option.starts_with("size=") {
+            size = Some(try!(option[5..].parse::<usize>().or(Err(Error::new(EINVAL)))));
+        } else {
+            match URL_FLAGS.iter().find(|&&(name, _)| name == option) {
+                Some(&(_, flag)) => flags |= flag,
+                None => return Err(Error::new(EINVAL)),
+            }
+        }
+    }
+
+    match (count, size) {
+        (Some(count), Some(size)) if count > 0 && size > 0 && size <= BLOCK_SIZE_MAX => {
+            Ok((RandomOptions {
+                range: None,
+                block_size: Some(size),
+                count: Some(count),
+                keys: true,
+            },
+                flags))
+        }
+        _ => Err(Error::new(EINVAL)),
+    }
+}
+
+/// A random number resource
+///
+/// Nothing is cached: every read draws fresh bytes from the kernel entropy pool. With a block
+/// size, bytes are generated a block at a time, and each of them is only read once.
+pub struct RandomResource {
+    /// Wait for the CSPRNG to gather entropy before reading
+    blocking: bool,
+    options: RandomOptions,
+    /// The current block, empty without a block size
+    block: Vec<u8>,
+    /// The offset of the next unread byte in `block`
+    offset: usize,
+    /// The number of bytes left before EOF, or `None` for no limit
+    remaining: Option<usize>,
+}
+
+impl RandomResource {
+    /// Create a random resource with the given options
+    ///
+    /// Returns `EINVAL` if the block size times the count does not fit in a `usize`.
+    pub fn new(blocking: bool, options: RandomOptions) -> Result<RandomResource> {
+        let block_size = options.block_size.unwrap_or(0);
+        let remaining = match options.count {
+            Some(count) => Some(try!(count.checked_mul(block_size).ok_or(Error::new(EINVAL)))),
+            None => None,
+        };
+
+        Ok(RandomResource {
+            blocking: blocking,
+            options: options,
+            block: vec![0; block_size],
+            offset: block_size,
+            remaining: remaining,
+        })
+    }
+
+    /// Copy bytes of the current block to `buf`, generating a new block when it is used up
+    fn read_blocks(&mut self, buf: &mut [u8]) -> usize {
+        let mut i = 0;
+        while i < buf.len() && self.remaining != Some(0) {
+            if self.offset >= self.block.len() {
+                random::fill(&mut self.block);
+                self.offset = 0;
+            }
+
+            let mut count = cmp::min(buf.len() - i, self.block.len() - self.offset);
+            if let Some(remaining) = self.remaining {
+                count = cmp::min(count, remaining);
+                self.remaining = Some(remaining - count);
+            }
+
+            buf[i..i + count].copy_from_slice(&self.block[self.offset..self.offset + count]);
+            self.offset += count;
+            i += count;
+        }
+        i
+    }
+}
+
+impl Resource for RandomResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        let mut resource = try!(RandomResource::new(self.blocking, self.options));
+        resource.remaining = self.remaining;
+        Ok(box resource)
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let mut path = String::from(if self.blocking {
+            "random:"
+        } else {
+            "urandom:"
+        });
+        if let Some((low, high)) = self.options.range {
+            path.push_str(&format!("{}-{}", low, high));
+        }
+        if self.options.keys {
+            path.push_str(&format!("keys?count={}&size={}",
+                                   self.options.count.unwrap_or(0),
+                                   self.options.block_size.unwrap_or(0)));
+        } else if let Some(block_size) = self.options.block_size {
+            path.push_str(&format!("bs={}", block_size));
+            if let Some(count) = self.options.count {
+                path.push_str(&format!("&count={}", count));
+            }
+        }
+
+        for (b, p) in buf.iter_mut().zip(path.bytes()) {
+            *b = p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    /// Fill `buf` with bytes from the kernel entropy pool
+    ///
+    /// A blocking resource sleeps until `random::ENTROPY_THRESHOLD` bits of entropy have been
+    /// gathered from interrupts, checking again on every tick of the PIT. A resource opened with a
+    /// range produces a single number from that range, in little endian, padded or truncated to
+    /// the size of `buf`. A resource opened with a count returns 0 once all of its blocks have
+    /// been read.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if self.blocking {
+            while !random::ready() {
+                ::env().tick.wait("RandomResource::read");
+            }
+        }
+
+        if self.options.block_size.is_some() {
+            return Ok(self.read_blocks(buf));
+        }
+
+        if let Some((low, high)) = self.options.range {
+            let value = random::rand_range(low, high);
+            for (i, b) in buf.iter_mut().enumerate() {
+                *b = if i < mem::size_of::<usize>() {
+                    (value >> (i * 8)) as u8
+                } else {
+                    0
+                };
+            }
+        } else {
+            random::fill(buf);
+        }
+
+        Ok(buf.len())
+    }
+
+    /// Add `buf` to the kernel entropy pool
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        random::add_entropy(buf);
+        Ok(buf.len())
+    }
+
+    /// Nothing is buffered, so there is nothing to sync
+    fn sync(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The counters of the kernel entropy pool, one per line
+fn stats() -> String {
+    let stats = random::stats();
+    format!("Entropy: {} bits\nServed: {} bytes\nRekeys: {}\nForks: {}\nErasures: {}\n",
+            stats.entropy,
+            stats.served,
+            stats.rekeys,
+            stats.forks,
+            stats.erasures)
+}
+
+/// A resource to erase the key of the kernel CSPRNG
+///
+/// Reading returns the number of erasures so far, in decimal, followed by a newline, so that a
+/// caller can confirm that its own erasure happened. Writing anything erases the key at once, see
+/// `random::erase`, which needs no privilege, as it only destroys state. Bytes which resources
+/// opened with a block size have already buffered are not erased.
+pub struct RekeyResource {
+    path: String,
+    /// The offset of the next byte to read
+    pos: usize,
+    /// The count read, taken when reading starts
+    data: Vec<u8>,
+}
+
+impl Resource for RekeyResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box RekeyResource {
+            path: self.path.clone(),
+            pos: self.pos,
+            data: self.data.clone(),
+        })
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        for (b, p) in buf.iter_mut().zip(self.path.bytes()) {
+            *b = p;
+        }
+
+        Ok(cmp::min(buf.len(), self.path.len()))
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if self.pos == 0 {
+            self.data = format!("{}\n", random::stats().erasures).into_bytes();
+        }
+
+        let mut i = 0;
+        for (b, d) in buf.iter_mut().zip(self.data.iter().skip(self.pos)) {
+            *b = *d;
+            i += 1;
+        }
+        self.pos += i;
+
+        Ok(i)
+    }
+
+    /// Erase the key, whatever `buf` holds, and start reading the count over
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        random::erase();
+        self.pos = 0;
+        Ok(buf.len())
+    }
+
+    fn seek(&mut self, pos: ResourceSeek) -> Result<usize> {
+        match pos {
+            ResourceSeek::Start(0) => {
+                self.pos = 0;
+                Ok(0)
+            },
+            _ => Err(Error::new(EINVAL)),
+        }
+    }
+
+    fn sync(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A barrier which waits for the kernel entropy pool to be seeded
+///
+/// Every read returns a single byte of 1 once `random::ENTROPY_THRESHOLD` bits of entropy have
+/// been gathered, without taking any bytes from the CSPRNG. Until then, a read blocks, checking
+/// again on every tick of the PIT, or returns `EAGAIN` if the barrier was opened with
+/// `O_NONBLOCK`.
+pub struct ReadyResource {
+    path: String,
+    nonblock: bool,
+}
+
+impl Resource for ReadyResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box ReadyResource {
+            path: self.path.clone(),
+            nonblock: self.nonblock,
+        })
+    }
+
+    fn is_readable(&self) -> bool {
+        random::ready()
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        for (b, p) in buf.iter_mut().zip(self.path.bytes()) {
+            *b = p;
+        }
+
+        Ok(cmp::min(buf.len(), self.path.len()))
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        while !random::ready() {
+            if self.nonblock {
+                return Err(Error::new(EAGAIN));
+            }
+            ::env().tick.wait("ReadyResource::read");
+        }
+
+        match buf.first_mut() {
+            Some(b) => {
+                *b = 1;
+                Ok(1)
+            },
+            None => Ok(0),
+        }
+    }
+
+    fn sync(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A random number scheme
+///
+/// `random://stats` and `urandom://stats` describe the state of the kernel entropy pool, instead of
+/// producing random numbers, `random://keys?count=N&size=K` produces a batch of keys, see
+/// `parse_keys`, `random://rekey` erases the key of the CSPRNG, see `RekeyResource`, and
+/// `random://ready` waits until the CSPRNG is seeded, see `ReadyResource`.
+pub struct RandomScheme {
+    /// Whether opened resources block until entropy is available
+    blocking: bool,
+}
+
+impl RandomScheme {
+    /// The `random:` scheme, which blocks until the CSPRNG has gathered entropy
+    pub fn random() -> Box<RandomScheme> {
+        box RandomScheme { blocking: true }
+    }
+
+    /// The `urandom:` scheme, which never blocks
+    pub fn urandom() -> Box<RandomScheme> {
+        box RandomScheme { blocking: false }
+    }
+}
+
+impl KScheme for RandomScheme {
+    fn scheme(&self) -> &str {
+        if self.blocking {
+            "random"
+        } else {
+            "urandom"
+        }
+    }
+
+    /// `random:` does not block with `O_NONBLOCK`, such as `random://?nonblock`, like `urandom:`
+    fn open(&mut self, url: Url, flags: usize) -> Result<Box<Resource>> {
+        if url.path().trim_left_matches('/') == "keys" {
+            let (options, flags) = try!(parse_keys(url.query(), flags));
+            let blocking = self.blocking && flags & O_NONBLOCK != O_NONBLOCK;
+            return Ok(box try!(RandomResource::new(blocking, options)));
+        }
+
+        let blocking = self.blocking && try!(url.flags(flags)) & O_NONBLOCK != O_NONBLOCK;
+
+        if url.path().trim_left_matches('/') == "stats" {
+            let path = format!("{}:stats", self.scheme());
+            return Ok(box VecResource::new(path, stats().into_bytes()));
+        }
+
+        // The barrier blocks on either scheme, unless it is opened with `O_NONBLOCK`
+        if url.path().trim_left_matches('/') == "ready" {
+            let nonblock = try!(url.flags(flags)) & O_NONBLOCK == O_NONBLOCK;
+            if nonblock && !random::ready() {
+                return Err(Error::new(EAGAIN));
+            }
+            return Ok(box ReadyResource {
+                path: format!("{}:ready", self.scheme()),
+                nonblock: nonblock,
+            });
+        }
+
+        if url.path().trim_left_matches('/') == "rekey" {
+            return Ok(box RekeyResource {
+                path: format!("{}:rekey", self.scheme()),
+                pos: 0,
+                data: Vec::new(),
+            });
+        }
+
+        Ok(box try!(RandomResource::new(blocking, try!(parse_options(url.path())))))
+    }
+}
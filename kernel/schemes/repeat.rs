@@ -0,0 +1,71 @@
+use alloc::boxed::Box;
+
+use collections::Vec;
+
+use core::cmp;
+
+use fs::{KScheme, Resource, Url};
+
+use system::error::Result;
+
+/// A resource which reads as an endless repetition of a pattern
+///
+/// The pattern carries on from one read to the next, so a pattern longer than one byte is never
+/// cut short.
+pub struct RepeatResource {
+    pattern: Vec<u8>,
+    /// The offset in `pattern` of the next byte to read
+    pos: usize,
+}
+
+impl Resource for RepeatResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box RepeatResource {
+            pattern: self.pattern.clone(),
+            pos: self.pos,
+        })
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let mut path = b"repeat:".to_vec();
+        path.extend_from_slice(&self.pattern);
+
+        for (b, p) in buf.iter_mut().zip(path.iter()) {
+            *b = *p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    /// Fill `buf` with the pattern
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        for b in buf.iter_mut() {
+            *b = self.pattern[self.pos];
+            self.pos = (self.pos + 1) % self.pattern.len();
+        }
+        Ok(buf.len())
+    }
+}
+
+/// A scheme of repeated patterns, like `yes`
+///
+/// `repeat:PATTERN` repeats `PATTERN`, and `repeat:` repeats `y\n`.
+pub struct RepeatScheme;
+
+impl KScheme for RepeatScheme {
+    fn scheme(&self) -> &str {
+        "repeat"
+    }
+
+    fn open(&mut self, url: Url, _: usize) -> Result<Box<Resource>> {
+        let pattern = url.reference().trim_left_matches('/');
+        Ok(box RepeatResource {
+            pattern: if pattern.is_empty() {
+                b"y\n".to_vec()
+            } else {
+                pattern.as_bytes().to_vec()
+            },
+            pos: 0,
+        })
+    }
+}
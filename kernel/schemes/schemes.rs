@@ -0,0 +1,74 @@
+use alloc::boxed::Box;
+
+use core::cmp;
+
+use fs::{KScheme, Resource, ResourceSeek, Url};
+
+use system::error::{Error, Result, EACCES, EINVAL};
+
+/// A list of the registered schemes
+///
+/// The list is made at every read, so rewinding and reading again shows newly registered schemes.
+pub struct SchemesResource {
+    pos: usize,
+}
+
+impl Resource for SchemesResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box SchemesResource { pos: self.pos })
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = b"schemes:";
+
+        for (b, p) in buf.iter_mut().zip(path.iter()) {
+            *b = *p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let list = ::env().scheme_list();
+
+        let mut i = 0;
+        for (b, s) in buf.iter_mut().zip(list.bytes().skip(self.pos)) {
+            *b = s;
+            i += 1;
+        }
+        self.pos += i;
+
+        Ok(i)
+    }
+
+    /// The list is read only
+    fn write(&mut self, _: &[u8]) -> Result<usize> {
+        Err(Error::new(EACCES))
+    }
+
+    fn seek(&mut self, pos: ResourceSeek) -> Result<usize> {
+        let len = ::env().scheme_list().len();
+        self.pos = match pos {
+            ResourceSeek::Start(offset) => cmp::min(len, offset),
+            ResourceSeek::Current(offset) =>
+                cmp::max(0, cmp::min(len as isize, self.pos as isize + offset)) as usize,
+            ResourceSeek::End(offset) =>
+                cmp::max(0, cmp::min(len as isize, len as isize + offset)) as usize,
+            ResourceSeek::Data(_) | ResourceSeek::Hole(_) => return Err(Error::new(EINVAL)),
+        };
+        Ok(self.pos)
+    }
+}
+
+/// The scheme listing scheme
+pub struct SchemesScheme;
+
+impl KScheme for SchemesScheme {
+    fn scheme(&self) -> &str {
+        "schemes"
+    }
+
+    fn open(&mut self, _: Url, _: usize) -> Result<Box<Resource>> {
+        Ok(box SchemesResource { pos: 0 })
+    }
+}
@@ -0,0 +1,227 @@
+use alloc::arc::Arc;
+use alloc::boxed::Box;
+
+use arch::memory::Memory;
+
+use collections::{String, Vec};
+
+use core::cell::UnsafeCell;
+use core::cmp;
+
+use fs::{open_named, KScheme, Resource, Url};
+
+use system::error::{Error, Result, EBUSY, EINVAL, ENOENT};
+use system::syscall::{MAP_WRITE, O_TRUNC, SHM_PERSIST};
+
+/// A named segment of shared memory
+pub struct ShmSegment {
+    /// The pages of the segment, or `None` while it is empty
+    memory: Option<Arc<Memory<u8>>>,
+    /// The size set with `truncate`, which the pages are rounded up from
+    size: usize,
+    /// The number of open handles to the segment
+    handles: usize,
+    /// Whether the segment is kept when its last handle is closed
+    persistent: bool,
+}
+
+impl ShmSegment {
+    /// Whether the segment is still in use, and must be kept by `ShmScheme`
+    fn alive(&self) -> bool {
+        self.handles > 0 || self.persistent
+    }
+
+    /// Resize the segment to `size` bytes, keeping its contents and zeroing the rest
+    ///
+    /// Returns `EBUSY` if the segment is mapped, as its pages can not move, and `ENOMEM` if there
+    /// is no memory.
+    fn resize(&mut self, size: usize) -> Result<()> {
+        if let Some(ref memory) = self.memory {
+            if Arc::strong_count(memory) > 1 {
+                return Err(Error::new(EBUSY));
+            }
+        }
+
+        let pages = (size + 4095) / 4096;
+        let memory = if pages > 0 {
+            let mut memory = try!(Memory::new_aligned(pages * 4096, 4096));
+            unsafe { ::memset(memory.as_mut_ptr(), 0, memory.len()) };
+            if let Some(ref old) = self.memory {
+                let count = cmp::min(self.size, size);
+                memory.as_mut_slice()[..count].copy_from_slice(&old.as_slice()[..count]);
+            }
+            Some(Arc::new(memory))
+        } else {
+            None
+        };
+
+        self.memory = memory;
+        self.size = size;
+        Ok(())
+    }
+}
+
+/// A handle to a shared memory segment
+///
+/// Mapping the handle maps the pages of the segment itself, so every process which maps it sees
+/// the writes of the others. The mappings keep the pages alive after the handle is closed.
+pub struct ShmResource {
+    name: String,
+    segment: Arc<UnsafeCell<ShmSegment>>,
+}
+
+impl ShmResource {
+    /// Open a handle to `segment`
+    fn new(name: String, segment: Arc<UnsafeCell<ShmSegment>>) -> ShmResource {
+        unsafe { (*segment.get()).handles += 1 };
+        ShmResource {
+            name: name,
+            segment: segment,
+        }
+    }
+
+    fn segment(&self) -> &mut ShmSegment {
+        unsafe { &mut *self.segment.get() }
+    }
+}
+
+impl Resource for ShmResource {
+    /// Mark the segment persistent with `SHM_PERSIST`
+    /// Returns 1 if the segment was persistent before, or 0 if it was not.
+    fn control(&mut self, cmd: usize, arg: usize) -> Result<usize> {
+        match cmd {
+            SHM_PERSIST => {
+                let segment = self.segment();
+                let persistent = segment.persistent;
+                segment.persistent = arg != 0;
+                Ok(persistent as usize)
+            },
+            _ => Err(Error::new(EINVAL)),
+        }
+    }
+
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box ShmResource::new(self.name.clone(), self.segment.clone()))
+    }
+
+    /// Map the first `len` bytes of the segment into the current context
+    ///
+    /// Returns `EINVAL` if `len` is 0 or larger than the segment.
+    fn mmap(&mut self, len: usize, flags: usize) -> Result<usize> {
+        let segment = self.segment();
+        if len == 0 || len > segment.size {
+            return Err(Error::new(EINVAL));
+        }
+        let memory = try!(segment.memory.as_ref().ok_or(Error::new(EINVAL)));
+
+        let contexts = unsafe { & *::env().contexts.get() };
+        let current = try!(contexts.current());
+        current.map_shared(memory, len, flags & MAP_WRITE == MAP_WRITE)
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = format!("shm:{}", self.name);
+
+        for (b, p) in buf.iter_mut().zip(path.bytes()) {
+            *b = p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    fn sync(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Set the size of the segment
+    ///
+    /// Returns `EBUSY` while the segment is mapped.
+    fn truncate(&mut self, len: usize) -> Result<()> {
+        self.segment().resize(len)
+    }
+}
+
+impl Drop for ShmResource {
+    /// Free the pages of the segment when its last handle is closed, unless it is persistent
+    fn drop(&mut self) {
+        let segment = self.segment();
+        segment.handles -= 1;
+        if !segment.alive() {
+            segment.memory = None;
+            segment.size = 0;
+        }
+    }
+}
+
+/// The shared memory scheme
+///
+/// Opening `shm:name` with `O_CREAT`, such as `shm:name?create`, creates an empty segment, which is
+/// sized with `ftruncate` before it is mapped. A segment is freed when its last handle is closed,
+/// unless it was marked persistent with `SHM_PERSIST`, in which case it is kept until it is
+/// unlinked.
+pub struct ShmScheme {
+    segments: Vec<(String, Arc<UnsafeCell<ShmSegment>>)>,
+}
+
+impl ShmScheme {
+    pub fn new() -> Box<ShmScheme> {
+        box ShmScheme { segments: Vec::new() }
+    }
+}
+
+impl KScheme for ShmScheme {
+    fn scheme(&self) -> &str {
+        "shm"
+    }
+
+    fn open(&mut self, url: Url, flags: usize) -> Result<Box<Resource>> {
+        self.segments.retain(|&(_, ref segment)| unsafe { &*segment.get() }.alive());
+
+        let flags = try!(url.flags(flags));
+        let name = url.path().trim_matches('/');
+        if name.is_empty() {
+            return Err(Error::new(EINVAL));
+        }
+
+        let found = self.segments
+                        .iter()
+                        .find(|&&(ref segment_name, _)| segment_name == name)
+                        .map(|&(_, ref segment)| segment.clone());
+        let segment = match found {
+            Some(segment) => {
+                try!(open_named(true, flags));
+                segment
+            },
+            None => {
+                try!(open_named(false, flags));
+                let segment = Arc::new(UnsafeCell::new(ShmSegment {
+                    memory: None,
+                    size: 0,
+                    handles: 0,
+                    persistent: false,
+                }));
+                self.segments.push((String::from(name), segment.clone()));
+                segment
+            },
+        };
+
+        let resource = ShmResource::new(String::from(name), segment);
+        if flags & O_TRUNC == O_TRUNC {
+            try!(resource.segment().resize(0));
+        }
+        Ok(box resource)
+    }
+
+    /// Remove the name of a segment, which is freed once its last handle is closed
+    fn unlink(&mut self, url: Url) -> Result<()> {
+        let name = url.path().trim_matches('/');
+        match self.segments.iter().position(|&(ref segment_name, _)| segment_name == name) {
+            Some(i) => {
+                let (_, segment) = self.segments.remove(i);
+                unsafe { (*segment.get()).persistent = false };
+                Ok(())
+            },
+            None => Err(Error::new(ENOENT)),
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use alloc::boxed::Box;
+
+use collections::string::String;
+
+use core::cmp;
+
+use common::time::{Duration, NANOS_PER_MILLI, NANOS_PER_SEC};
+
+use fs::{KScheme, Resource, Url};
+
+use sync::WaitQueue;
+
+use system::error::{Error, Result, EAGAIN, EINVAL};
+use system::syscall::O_NONBLOCK;
+
+/// Parse the duration of a sleep URL reference
+///
+/// The reference may start with `//`, and is a number of seconds, with an optional `s` suffix, or
+/// milliseconds with an `ms` suffix. Returns `EINVAL` if it is missing or not a number.
+pub fn parse_duration(reference: &str) -> Result<Duration> {
+    let duration = reference.trim_left_matches('/');
+    let nanos = if duration.ends_with("ms") {
+        try!(duration[..duration.len() - 2].parse::<u32>().or(Err(Error::new(EINVAL)))) as i64 *
+        NANOS_PER_MILLI as i64
+    } else {
+        let secs = if duration.ends_with('s') {
+            &duration[..duration.len() - 1]
+        } else {
+            duration
+        };
+        try!(secs.parse::<u32>().or(Err(Error::new(EINVAL)))) as i64 * NANOS_PER_SEC as i64
+    };
+
+    Ok(Duration::new(nanos / NANOS_PER_SEC as i64, (nanos % NANOS_PER_SEC as i64) as i32))
+}
+
+/// A sleep resource
+///
+/// Every read waits for the duration, and then returns 0, or returns `EAGAIN` at once if the
+/// resource does not block.
+pub struct SleepResource {
+    /// The duration, as given in the URL
+    path: String,
+    duration: Duration,
+    nonblock: bool,
+    /// A queue nothing is ever sent to, which is waited on until its timeout
+    queue: WaitQueue<()>,
+}
+
+impl Resource for SleepResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box SleepResource {
+            path: self.path.clone(),
+            duration: self.duration,
+            nonblock: self.nonblock,
+            queue: WaitQueue::new(),
+        })
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        for (b, p) in buf.iter_mut().zip(self.path.bytes()) {
+            *b = p;
+        }
+
+        Ok(cmp::min(buf.len(), self.path.len()))
+    }
+
+    fn read(&mut self, _: &mut [u8]) -> Result<usize> {
+        if self.nonblock {
+            return Err(Error::new(EAGAIN));
+        }
+
+        let deadline = Duration::monotonic() + self.duration;
+        loop {
+            let now = Duration::monotonic();
+            if now >= deadline {
+                return Ok(0);
+            }
+            self.queue.receive_for("SleepResource::read", deadline - now);
+        }
+    }
+
+    fn sync(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The sleep scheme
+///
+/// `sleep://500ms` or `sleep:2s` opens a resource whose reads wait for that long, see
+/// `parse_duration`. With `O_NONBLOCK`, as with `sleep:1?nonblock`, reads return `EAGAIN` instead.
+pub struct SleepScheme;
+
+impl KScheme for SleepScheme {
+    fn scheme(&self) -> &str {
+        "sleep"
+    }
+
+    fn open(&mut self, url: Url, flags: usize) -> Result<Box<Resource>> {
+        let duration = try!(parse_duration(url.path()));
+        let nonblock = try!(url.flags(flags)) & O_NONBLOCK == O_NONBLOCK;
+
+        Ok(box SleepResource {
+            path: format!("sleep:{}", url.path().trim_left_matches('/')),
+            duration: duration,
+            nonblock: nonblock,
+            queue: WaitQueue::new(),
+        })
+    }
+}
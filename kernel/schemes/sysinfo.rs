@@ -0,0 +1,163 @@
+use alloc::boxed::Box;
+
+use arch::memory;
+
+use collections::string::String;
+use collections::vec::Vec;
+
+use core::cmp;
+
+use common::time::{Duration, NANOS_PER_MILLI};
+
+use fs::{KScheme, Resource, ResourceSeek, Url};
+
+use system::error::{Error, Result, EACCES, EINVAL};
+
+/// The number of recent ticks of the PIT the load is averaged over
+pub const LOAD_TICKS: usize = 256;
+
+/// The number of runnable contexts, counted on recent ticks of the PIT
+pub struct Load {
+    /// The counts of the last `LOAD_TICKS` ticks, in a ring
+    samples: Vec<usize>,
+    /// The index of the oldest count, once `samples` is full
+    next: usize,
+}
+
+impl Load {
+    pub fn new() -> Load {
+        Load {
+            samples: Vec::new(),
+            next: 0,
+        }
+    }
+
+    /// Count `runnable` contexts for a tick, replacing the oldest count
+    pub fn push(&mut self, runnable: usize) {
+        if self.samples.len() < LOAD_TICKS {
+            self.samples.push(runnable);
+        } else {
+            self.samples[self.next] = runnable;
+            self.next = (self.next + 1) % LOAD_TICKS;
+        }
+    }
+
+    /// The average number of runnable contexts, in hundredths, or 0 before the first tick
+    pub fn average(&self) -> usize {
+        if self.samples.is_empty() {
+            0
+        } else {
+            self.samples.iter().fold(0, |sum, count| sum + count) * 100 / self.samples.len()
+        }
+    }
+}
+
+/// Count the runnable contexts for the load, on a tick of the PIT
+///
+/// A context is runnable unless it is blocked, sleeping, or has exited, like in `context:`.
+pub fn sample() {
+    let contexts = unsafe { & *::env().contexts.get() };
+    let runnable = contexts.iter()
+                           .filter(|context| {
+                               context.blocked == 0 && context.wake.is_none() && !context.exited
+                           })
+                           .count();
+    unsafe { (*::env().load.get()).push(runnable) };
+}
+
+/// A system information resource
+///
+/// The information is gathered at every read, so rewinding and reading again reflects any changes.
+pub struct SysInfoResource {
+    pos: usize,
+}
+
+impl SysInfoResource {
+    /// The lines described by `SysInfoScheme`
+    fn summary() -> String {
+        let uptime = Duration::monotonic();
+        let used = memory::memory_used();
+        let free = memory::memory_free();
+        let contexts = unsafe { & *::env().contexts.get() }.len();
+        let load = unsafe { & *::env().load.get() }.average();
+        format!("Uptime: {}.{:03} s\nMemory Total: {} KB\nMemory Free: {} KB\nContexts: {}\n\
+                 Load: {}.{:02}\n",
+                uptime.secs,
+                uptime.nanos / NANOS_PER_MILLI,
+                (used + free) / 1024,
+                free / 1024,
+                contexts,
+                load / 100,
+                load % 100)
+    }
+}
+
+impl Resource for SysInfoResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box SysInfoResource { pos: self.pos })
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = b"sysinfo:";
+
+        for (b, p) in buf.iter_mut().zip(path.iter()) {
+            *b = *p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let summary = SysInfoResource::summary();
+
+        let mut i = 0;
+        for (b, s) in buf.iter_mut().zip(summary.bytes().skip(self.pos)) {
+            *b = s;
+            i += 1;
+        }
+        self.pos += i;
+
+        Ok(i)
+    }
+
+    /// The information is read only
+    fn write(&mut self, _: &[u8]) -> Result<usize> {
+        Err(Error::new(EACCES))
+    }
+
+    fn seek(&mut self, pos: ResourceSeek) -> Result<usize> {
+        let len = SysInfoResource::summary().len();
+        self.pos = match pos {
+            ResourceSeek::Start(offset) => cmp::min(len, offset),
+            ResourceSeek::Current(offset) =>
+                cmp::max(0, cmp::min(len as isize, self.pos as isize + offset)) as usize,
+            ResourceSeek::End(offset) =>
+                cmp::max(0, cmp::min(len as isize, len as isize + offset)) as usize,
+            ResourceSeek::Data(_) | ResourceSeek::Hole(_) => return Err(Error::new(EINVAL)),
+        };
+        Ok(self.pos)
+    }
+}
+
+/// The system information scheme
+///
+/// Reads the state of the system in one read, as lines of `Name: value`, always in this order:
+///
+/// - `Uptime`: the time since boot, in seconds with millisecond precision, like `uptime:`
+/// - `Memory Total` and `Memory Free`: in KB, like `memory:`
+/// - `Contexts`: the number of contexts, including those which have exited but were not reaped
+/// - `Load`: the average number of runnable contexts over the last `LOAD_TICKS` ticks of the PIT,
+///   with two decimals
+///
+/// New lines are only ever added at the end, so existing ones can be parsed by position.
+pub struct SysInfoScheme;
+
+impl KScheme for SysInfoScheme {
+    fn scheme(&self) -> &str {
+        "sysinfo"
+    }
+
+    fn open(&mut self, _: Url, _: usize) -> Result<Box<Resource>> {
+        Ok(box SysInfoResource { pos: 0 })
+    }
+}
@@ -1,5 +1,6 @@
 use fs::{KScheme, Resource, Url};
 use alloc::boxed::Box;
+use core::cmp;
 use system::error::Result;
 
 /// The kernel log scheme.
@@ -31,6 +32,17 @@ impl Resource for SyslogResource {
         }))
     }
 
+    /// Returns the path `syslog:`
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = b"syslog:";
+
+        for (b, p) in buf.iter_mut().zip(path.iter()) {
+            *b = *p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
     /// Fills `buf` with the kernel log. Each message is prefixed by its log level:
     /// - `CRIT`
     /// - `ERROR`
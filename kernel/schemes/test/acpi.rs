@@ -0,0 +1,56 @@
+pub fn tables() -> bool {
+    use acpi::{Acpi, SDTHeader};
+    use alloc::boxed::Box;
+    use collections::Vec;
+    use core::mem;
+    use fs::{KScheme, ResourceSeek, Url};
+    use system::error::{ENOENT, EPERM};
+
+    fn table(signature: &[u8; 4], revision: u8) -> &'static SDTHeader {
+        let mut header = SDTHeader::default();
+        header.signature = *signature;
+        header.length = mem::size_of::<SDTHeader>() as u32;
+        header.revision = revision;
+        unsafe { &*Box::into_raw(box header) }
+    }
+
+    fn read(acpi: &mut Acpi, url: &str) -> Option<Vec<u8>> {
+        let mut resource = match acpi.open(Url::from_str(url).unwrap(), 0) {
+            Ok(resource) => resource,
+            Err(_) => return None,
+        };
+        let mut data = Vec::new();
+        resource.read_to_end(&mut data).ok().map(|_| data)
+    }
+
+    let mut acpi = Acpi::with_tables(&[table(b"APIC", 1), table(b"SSDT", 2), table(b"SSDT", 3)]);
+
+    // Tables sharing a signature are numbered
+    test!(read(&mut acpi, "acpi:") == Some(b"APIC\nSSDT1\nSSDT2\n".to_vec()));
+
+    // A table reads as its raw bytes
+    let data = match read(&mut acpi, "acpi://SSDT2") {
+        Some(data) => data,
+        None => fail!(),
+    };
+    test!(data.len() == mem::size_of::<SDTHeader>());
+    test!(&data[..4] == b"SSDT");
+    test!(data[8] == 3);
+
+    test!(acpi.open(Url::from_str("acpi:SSDT").unwrap(), 0).err().map(|err| err.errno) ==
+          Some(ENOENT));
+    test!(acpi.open(Url::from_str("acpi:FACP").unwrap(), 0).err().map(|err| err.errno) ==
+          Some(ENOENT));
+
+    // Tables are read only
+    let mut resource = match acpi.open(Url::from_str("acpi:APIC").unwrap(), 0) {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+    test!(resource.write(b"XXXX").err().map(|err| err.errno) == Some(EPERM));
+    test!(resource.seek(ResourceSeek::End(0)).ok() == Some(mem::size_of::<SDTHeader>()));
+    let mut path = [0; 16];
+    test!(resource.path(&mut path).ok() == Some(9));
+    test!(&path[..9] == b"acpi:APIC");
+    succ!();
+}
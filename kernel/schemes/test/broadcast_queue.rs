@@ -0,0 +1,37 @@
+pub fn lagged() -> bool {
+    use sync::{Broadcast, BroadcastQueue};
+
+    let queue = BroadcastQueue::new(2);
+    let mut reader = queue.reader();
+
+    // Sending never blocks, the oldest values are dropped instead
+    for i in 0..5 {
+        queue.send(i, "test");
+    }
+    test!(queue.peek(&reader));
+    test!(queue.receive_nonblocking(&mut reader) == Some(Broadcast::Lagged(3)));
+    test!(queue.receive_nonblocking(&mut reader) == Some(Broadcast::Value(3)));
+    test!(queue.receive(&mut reader, "test") == Broadcast::Value(4));
+    succ!();
+}
+
+pub fn readers() -> bool {
+    use sync::{Broadcast, BroadcastQueue};
+
+    let queue = BroadcastQueue::new(4);
+    let mut first = queue.reader();
+    queue.send(1, "test");
+
+    // A reader only sees values sent after it was created
+    let mut second = queue.reader();
+    test!(!queue.peek(&second));
+    queue.send(2, "test");
+
+    // Every reader sees every value, at its own pace
+    test!(queue.receive_nonblocking(&mut first) == Some(Broadcast::Value(1)));
+    test!(queue.receive_nonblocking(&mut second) == Some(Broadcast::Value(2)));
+    test!(queue.receive_nonblocking(&mut second) == None);
+    test!(queue.receive_nonblocking(&mut first) == Some(Broadcast::Value(2)));
+    test!(queue.receive_nonblocking(&mut first) == None);
+    succ!();
+}
@@ -0,0 +1,21 @@
+pub fn bounded() -> bool {
+    use sync::ByteQueue;
+
+    let queue = ByteQueue::new(4);
+
+    test!(queue.try_write_bytes(b"abcdef") == 4);
+    test!(queue.is_full());
+    test!(queue.try_write_bytes(b"g") == 0);
+
+    let mut buf = [0; 3];
+    test!(queue.try_read_bytes(&mut buf) == 3);
+    test!(&buf == b"abc");
+
+    // Reading frees space
+    test!(queue.try_write_bytes(b"ef") == 2);
+    test!(queue.read_bytes(&mut buf, "test") == 3);
+    test!(&buf == b"def");
+    test!(queue.is_empty());
+    test!(queue.try_read_bytes(&mut buf) == 0);
+    succ!();
+}
@@ -0,0 +1,217 @@
+pub fn canonical() -> bool {
+    use env::console::Tty;
+
+    let mut tty = Tty::new();
+    let mut buf = [0; 16];
+
+    // Nothing is read until the line ends, and backspace edits the line
+    test!(&tty.input(b"lx") == b"lx");
+    test!(&tty.input(b"\x7F") == b"\x08 \x08");
+    test!(tty.try_read(&mut buf) == 0);
+    test!(&tty.input(b"s\n") == b"s\n");
+
+    // Erasing an empty line echoes nothing
+    test!(tty.input(b"\x08\x08\x08\x08").is_empty());
+    test!(&tty.input(b"pwd\n") == b"pwd\n");
+
+    // One line is read at a time
+    test!(tty.try_read(&mut buf) == 3);
+    test!(&buf[..3] == b"ls\n");
+    test!(tty.try_read(&mut buf[..2]) == 2);
+    test!(&buf[..2] == b"pw");
+    test!(tty.try_read(&mut buf) == 2);
+    test!(&buf[..2] == b"d\n");
+    test!(tty.try_read(&mut buf) == 0);
+    succ!();
+}
+
+pub fn raw() -> bool {
+    use env::console::Tty;
+
+    let mut tty = Tty::new();
+    let mut buf = [0; 16];
+
+    // The line being edited is read when switching to raw mode
+    tty.input(b"ab");
+    tty.set_raw(true);
+    test!(&tty.input(b"\x7F\x1B[A") == b"\x7F\x1B[A");
+    test!(tty.try_read(&mut buf) == 6);
+    test!(&buf[..6] == b"ab\x7F\x1B[A");
+
+    // Without echo, input is still read
+    tty.echo = false;
+    test!(tty.input(b"q").is_empty());
+    test!(tty.try_read(&mut buf) == 1);
+    test!(buf[0] == b'q');
+
+    tty.set_raw(false);
+    test!(tty.input(b"x").is_empty());
+    test!(tty.try_read(&mut buf) == 0);
+    succ!();
+}
+
+pub fn keymap() -> bool {
+    use collections::Vec;
+    use core::mem;
+    use drivers::kb_layouts::layouts::{self, Layout, KEYMAP_NAME_SIZE, KEYMAP_SIZE};
+    use fs::{KScheme, Url};
+    use schemes::console::ConsoleScheme;
+    use system::error::EINVAL;
+
+    fn name() -> Option<Vec<u8>> {
+        let mut resource = match ConsoleScheme.open(Url::from_str("console:keymap").unwrap(), 0) {
+            Ok(resource) => resource,
+            Err(_) => return None,
+        };
+        let mut data = Vec::new();
+        resource.read_to_end(&mut data).ok().map(|_| data)
+    }
+
+    fn load(blob: &[u8]) -> Result<usize, isize> {
+        ConsoleScheme.open(Url::from_str("console:keymap").unwrap(), 0)
+                     .and_then(|mut resource| resource.write(blob))
+                     .map_err(|err| err.errno)
+    }
+
+    fn layout() -> &'static Layout {
+        unsafe { &(*::env().console.get()).layout }
+    }
+
+    let saved = mem::replace(unsafe { &mut (*::env().console.get()).layout }, Layout::English);
+
+    // The English layout, with 'q' and 'Q' swapped for 'x' and 'X'
+    let mut blob = vec![0; KEYMAP_NAME_SIZE];
+    blob[..7].copy_from_slice(b"Swapped");
+    for scancode in 0..58 {
+        for &(shift, altgr) in [(false, false), (true, false), (false, true)].iter() {
+            let character = match (scancode, shift, altgr) {
+                (0x10, false, _) => 'x',
+                (0x10, true, _) => 'X',
+                _ => layouts::char_for_scancode(scancode, shift, altgr, &Layout::English),
+            };
+            let code = character as u32;
+            for i in 0..4 {
+                blob.push((code >> (i * 8)) as u8);
+            }
+        }
+    }
+
+    let english = name();
+    let loaded = load(&blob);
+    let loaded_name = name();
+    let q = layouts::char_for_scancode(0x10, false, false, layout());
+    let shift_q = layouts::char_for_scancode(0x10, true, false, layout());
+    let w = layouts::char_for_scancode(0x11, false, false, layout());
+
+    // Invalid keymaps fall back to the English layout
+    let short = load(&blob[..KEYMAP_SIZE - 1]);
+    let short_name = name();
+    let reloaded = load(&blob);
+    blob[KEYMAP_NAME_SIZE + 3] = 0xFF;
+    let bad_char = load(&blob);
+    let bad_char_name = name();
+
+    unsafe { (*::env().console.get()).layout = saved };
+
+    test!(english == Some(b"English\n".to_vec()));
+    test!(loaded == Ok(KEYMAP_SIZE));
+    test!(loaded_name == Some(b"Swapped\n".to_vec()));
+    test!(q == 'x' && shift_q == 'X' && w == 'w');
+    test!(short == Err(EINVAL));
+    test!(short_name == Some(b"English\n".to_vec()));
+    test!(reloaded == Ok(KEYMAP_SIZE));
+    test!(bad_char == Err(EINVAL));
+    test!(bad_char_name == Some(b"English\n".to_vec()));
+    succ!();
+}
+
+pub fn terminal() -> bool {
+    use syscall::fs::{close, control, open};
+    use system::syscall::CONTROL_ISATTY;
+
+    fn isatty(path: &[u8]) -> Option<usize> {
+        let fd = match open(path.as_ptr(), 0) {
+            Ok(fd) => fd,
+            Err(_) => return None,
+        };
+        let result = control(fd, CONTROL_ISATTY, 0).ok();
+        let _ = close(fd);
+        result
+    }
+
+    // Every resource answers, whether or not it has commands of its own
+    test!(isatty(b"console:\0") == Some(1));
+    test!(isatty(b"debug:\0") == Some(1));
+    test!(isatty(b"null:\0") == Some(0));
+    test!(isatty(b"zero:\0") == Some(0));
+    succ!();
+}
+
+pub fn winsize() -> bool {
+    use fs::Resource;
+    use schemes::console::ConsoleResource;
+    use system::error::EINVAL;
+    use system::syscall::{CONSOLE_GETPIXELS, CONSOLE_GETWINSZ, CONSOLE_SETPIXELS,
+                          CONSOLE_SETWINSZ, SIGWINCH};
+
+    fn winched() -> bool {
+        let contexts = unsafe { &mut *::env().contexts.get() };
+        match contexts.current_mut() {
+            Ok(current) => {
+                let pending = current.pending_signals & 1 << SIGWINCH != 0;
+                current.pending_signals &= !(1 << SIGWINCH);
+                pending
+            },
+            Err(_) => false,
+        }
+    }
+
+    let console = unsafe { &mut *::env().console.get() };
+    let saved_size = console.size;
+    let saved_foreground = console.tty.foreground;
+    let saved_pending = match unsafe { & *::env().contexts.get() }.current() {
+        Ok(current) => current.pending_signals,
+        Err(_) => return false,
+    };
+
+    // Opening the console makes the current context the foreground
+    let mut resource = ConsoleResource::new();
+    let winsz = resource.control(CONSOLE_GETWINSZ, 0);
+    let pixels = resource.control(CONSOLE_GETPIXELS, 0);
+
+    winched();
+    let set_winsz = resource.control(CONSOLE_SETWINSZ, 30 << 16 | 100);
+    let new_winsz = resource.control(CONSOLE_GETWINSZ, 0);
+    let winsz_winched = winched();
+    let zero_rows = resource.control(CONSOLE_SETWINSZ, 100).err().map(|err| err.errno);
+    let zero_columns = resource.control(CONSOLE_SETWINSZ, 30 << 16).err().map(|err| err.errno);
+    let invalid_winched = winched();
+
+    let set_pixels = resource.control(CONSOLE_SETPIXELS, 480 << 16 | 800);
+    let new_pixels = resource.control(CONSOLE_GETPIXELS, 0);
+    let pixels_winched = winched();
+    let kept_winsz = resource.control(CONSOLE_GETWINSZ, 0);
+    let zero_pixels = resource.control(CONSOLE_SETPIXELS, 0).err().map(|err| err.errno);
+    drop(resource);
+
+    console.size = saved_size;
+    console.tty.foreground = saved_foreground;
+    if let Ok(current) = unsafe { &mut *::env().contexts.get() }.current_mut() {
+        current.pending_signals = saved_pending;
+    }
+
+    test!(winsz == Ok(saved_size.rows << 16 | saved_size.columns));
+    test!(pixels == Ok(saved_size.height << 16 | saved_size.width));
+    test!(set_winsz == winsz);
+    test!(new_winsz == Ok(30 << 16 | 100));
+    test!(winsz_winched);
+    test!(zero_rows == Some(EINVAL));
+    test!(zero_columns == Some(EINVAL));
+    test!(!invalid_winched);
+    test!(set_pixels == pixels);
+    test!(new_pixels == Ok(480 << 16 | 800));
+    test!(pixels_winched);
+    test!(kept_winsz == Ok(30 << 16 | 100));
+    test!(zero_pixels == Some(EINVAL));
+    succ!();
+}
@@ -0,0 +1,647 @@
+pub fn details() -> bool {
+    use collections::Vec;
+    use fs::{KScheme, Url};
+    use schemes::context::ContextScheme;
+
+    let pid = match unsafe { & *::env().contexts.get() }.current() {
+        Ok(context) => context.pid,
+        Err(_) => fail!(),
+    };
+
+    let mut scheme = ContextScheme;
+    let path = format!("context:{}", pid);
+    let mut resource = match scheme.open(Url::from_str(&path).unwrap(), 0) {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+
+    let mut data = Vec::new();
+    let mut buf = [0; 64];
+    while let Ok(count) = resource.read(&mut buf) {
+        if count == 0 {
+            break;
+        }
+        data.extend_from_slice(&buf[..count]);
+    }
+
+    let pid_line = format!("PID: {}", pid);
+    test!(data.split(|&b| b == b'\n').any(|line| line == pid_line.as_bytes()));
+
+    test!(scheme.open(Url::from_str("context:bogus").unwrap(), 0).is_err());
+    test!(scheme.open(Url::from_str("context:4294967295").unwrap(), 0).is_err());
+    succ!();
+}
+
+pub fn file_limit() -> bool {
+    use syscall::fs::{close, dup, open};
+    use system::error::EMFILE;
+
+    fn set_file_limit(limit: usize) {
+        if let Ok(context) = unsafe { &mut *::env().contexts.get() }.current_mut() {
+            context.file_limit = limit;
+        }
+    }
+
+    let (limit, files) = match unsafe { & *::env().contexts.get() }.current() {
+        Ok(context) => (context.file_limit, unsafe { (*context.files.get()).len() }),
+        Err(_) => fail!(),
+    };
+
+    let fd = match open(b"null:\0".as_ptr(), 0) {
+        Ok(fd) => fd,
+        Err(_) => fail!(),
+    };
+
+    // Nothing more can be opened at the limit
+    set_file_limit(files + 1);
+    let opened = open(b"null:\0".as_ptr(), 0).err().map(|err| err.errno);
+    let duped = dup(fd).err().map(|err| err.errno);
+    set_file_limit(files + 2);
+    let under = dup(fd);
+    set_file_limit(limit);
+
+    test!(close(fd).is_ok());
+    if let Ok(fd) = under {
+        test!(close(fd).is_ok());
+    }
+
+    test!(opened == Some(EMFILE));
+    test!(duped == Some(EMFILE));
+    test!(under.is_ok());
+    succ!();
+}
+
+pub fn cloexec() -> bool {
+    use syscall::fs::{close, fcntl, open};
+    use system::error::EBADF;
+    use system::syscall::{FD_CLOEXEC, F_GETFD, F_SETFD, O_CLOEXEC};
+
+    let kept = match open(b"null:\0".as_ptr(), 0) {
+        Ok(fd) => fd,
+        Err(_) => fail!(),
+    };
+    let marked = match open(b"null:\0".as_ptr(), 0) {
+        Ok(fd) => fd,
+        Err(_) => fail!(),
+    };
+    let opened = match open(b"null:\0".as_ptr(), O_CLOEXEC) {
+        Ok(fd) => fd,
+        Err(_) => fail!(),
+    };
+
+    test!(fcntl(kept, F_GETFD, 0).ok() == Some(0));
+    test!(fcntl(marked, F_SETFD, FD_CLOEXEC).is_ok());
+    test!(fcntl(marked, F_GETFD, 0).ok() == Some(FD_CLOEXEC));
+    test!(fcntl(opened, F_GETFD, 0).ok() == Some(FD_CLOEXEC));
+
+    // Executing closes the marked files, and keeps the others
+    match unsafe { & *::env().contexts.get() }.current() {
+        Ok(context) => context.close_on_exec(),
+        Err(_) => fail!(),
+    }
+
+    let kept_flags = fcntl(kept, F_GETFD, 0).ok();
+    test!(close(kept).is_ok());
+    test!(kept_flags == Some(0));
+    test!(fcntl(marked, F_GETFD, 0).err().map(|err| err.errno) == Some(EBADF));
+    test!(fcntl(opened, F_GETFD, 0).err().map(|err| err.errno) == Some(EBADF));
+    succ!();
+}
+
+pub fn cmdline() -> bool {
+    use collections::Vec;
+    use fs::{KScheme, Url};
+    use schemes::context::ContextScheme;
+    use schemes::test::helpers::read_path;
+    use system::error::ENOENT;
+
+    let (pid, args) = match unsafe { & *::env().contexts.get() }.current() {
+        Ok(context) => (context.pid, context.args.clone()),
+        Err(_) => fail!(),
+    };
+
+    let mut expected = Vec::new();
+    for arg in args.iter() {
+        expected.extend_from_slice(arg.as_bytes());
+        expected.push(0);
+    }
+    test!(read_path(&format!("context://{}/cmdline", pid)) == Ok(expected));
+
+    // Kernel threads have no arguments
+    let kernel = unsafe { & *::env().contexts.get() }.iter()
+                                                     .find(|context| context.stack.is_none())
+                                                     .map(|context| context.pid);
+    if let Some(kernel) = kernel {
+        test!(read_path(&format!("context:{}/cmdline", kernel)) == Ok(Vec::new()));
+    }
+
+    let mut scheme = ContextScheme;
+    let bogus = format!("context:{}/bogus", pid);
+    for path in ["context:4294967295/cmdline", "context:bogus/cmdline", &bogus[..]].iter() {
+        let err = scheme.open(Url::from_str(path).unwrap(), 0).err();
+        test!(err.map(|err| err.errno) == Some(ENOENT));
+    }
+    succ!();
+}
+
+pub fn credit() -> bool {
+    use arch::context::{CONTEXT_PRIORITY_DEFAULT, CONTEXT_PRIORITY_MAX};
+
+    let context = match unsafe { &mut *::env().contexts.get() }.current_mut() {
+        Ok(context) => context,
+        Err(_) => fail!(),
+    };
+    let credit = context.credit;
+
+    // Earning more than is spent saves up no more than CONTEXT_PRIORITY_MAX
+    context.credit = 0;
+    let taken = (0..16).all(|_| context.take_credit(CONTEXT_PRIORITY_MAX));
+    let capped = context.credit;
+    let mut slices = 0;
+    while context.take_credit(0) {
+        slices += 1;
+    }
+
+    context.credit = credit;
+
+    test!(taken);
+    test!(capped == CONTEXT_PRIORITY_MAX - CONTEXT_PRIORITY_DEFAULT);
+    test!(slices == CONTEXT_PRIORITY_MAX / CONTEXT_PRIORITY_DEFAULT - 1);
+    succ!();
+}
+
+pub fn priority() -> bool {
+    use arch::context::{CONTEXT_PRIORITY_DEFAULT, CONTEXT_PRIORITY_MAX};
+    use fs::{KScheme, Url};
+    use schemes::context::ContextScheme;
+    use schemes::test::helpers::with_iopl;
+    use system::error::{EINVAL, EPERM};
+
+    fn set_priority(priority: usize) {
+        if let Ok(context) = unsafe { &mut *::env().contexts.get() }.current_mut() {
+            context.priority = priority;
+        }
+    }
+
+    let (pid, priority) = match unsafe { & *::env().contexts.get() }.current() {
+        Ok(context) => (context.pid, context.priority),
+        Err(_) => fail!(),
+    };
+
+    let path = format!("context:{}/priority", pid);
+    let mut resource = match ContextScheme.open(Url::from_str(&path).unwrap(), 0) {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+
+    set_priority(CONTEXT_PRIORITY_DEFAULT);
+    let (lowered, lowered_to, restored, raised) = with_iopl(0, || {
+        let lowered = resource.write(b"2\n").ok();
+        let lowered_to = unsafe { & *::env().contexts.get() }.current().ok().map(|context| {
+            context.priority
+        });
+        let restored = resource.write(b"8").ok();
+        let raised = resource.write(b"9").err().map(|err| err.errno);
+        (lowered, lowered_to, restored, raised)
+    });
+    let (privileged, zero, high, bogus) = with_iopl(3, || {
+        let privileged = resource.write(format!("{}", CONTEXT_PRIORITY_MAX).as_bytes()).ok();
+        let zero = resource.write(b"0").err().map(|err| err.errno);
+        let too_high = format!("{}", CONTEXT_PRIORITY_MAX + 1);
+        let high = resource.write(too_high.as_bytes()).err().map(|err| err.errno);
+        let bogus = resource.write(b"high").err().map(|err| err.errno);
+        (privileged, zero, high, bogus)
+    });
+
+    let mut buf = [0; 8];
+    let read = resource.read(&mut buf).ok();
+    let end = resource.read(&mut buf[..]).ok();
+
+    set_priority(priority);
+
+    test!(lowered == Some(2));
+    test!(lowered_to == Some(2));
+    test!(restored == Some(1));
+    test!(raised == Some(EPERM));
+    test!(privileged.is_some());
+    test!(zero == Some(EINVAL));
+    test!(high == Some(EINVAL));
+    test!(bogus == Some(EINVAL));
+    test!(read == Some(3));
+    test!(&buf[..3] == format!("{}\n", CONTEXT_PRIORITY_MAX).as_bytes());
+    test!(end == Some(0));
+    succ!();
+}
+
+pub fn stack() -> bool {
+    use arch::context::ContextMemory;
+    use arch::regs::Regs;
+    use collections::Vec;
+    use core::mem;
+    use fs::{KScheme, Url};
+    use schemes::context::{backtrace, backtrace_frames, ContextScheme, BACKTRACE_MAX};
+    use schemes::test::helpers::with_child;
+    use system::error::EBUSY;
+
+    fn walk(pid: usize) -> Option<usize> {
+        unsafe { & *::env().contexts.get() }.find(pid).ok().and_then(|child| {
+            backtrace_frames(child, BACKTRACE_MAX).ok().map(|walked| walked.len())
+        })
+    }
+
+    with_child("test_stack", |pid| {
+        let contexts = unsafe { &mut *::env().contexts.get() };
+
+        // Two frames, at the start and in the middle of the stack of the child
+        let address = 0x1000_0000;
+        let word = mem::size_of::<usize>();
+        let mut frames: Vec<usize> = vec![0; 4096 / word];
+        frames[0] = address + 8 * word;
+        frames[1] = 0xAAAA;
+        frames[8] = 0;
+        frames[9] = 0xBBBB;
+        if let Ok(child) = contexts.find_mut(pid) {
+            child.stack = Some(ContextMemory {
+                physical_address: frames.as_ptr() as usize,
+                virtual_address: address,
+                virtual_size: 4096,
+                writeable: false,
+                allocated: false,
+                shared: None,
+            });
+        }
+
+        let path = format!("context:{}/stack", pid);
+        let running = ContextScheme.open(Url::from_str(&path).unwrap(), 0)
+                                   .err()
+                                   .map(|err| err.errno);
+        test!(running == Some(EBUSY));
+
+        let mut regs = Regs::default();
+        regs.ip = 0x1234;
+        regs.bp = address;
+        let walked = contexts.find_mut(pid).ok().and_then(|child| {
+            child.stopped_regs = Some(regs);
+            backtrace(child).ok()
+        });
+        test!(walked.as_ref().map(|walked| &walked[..]) == Some("1234\nAAAA\nBBBB\n"));
+
+        // A frame pointing back down the stack ends a cycle
+        frames[8] = address;
+        test!(walk(pid) == Some(3));
+
+        // A frame pointing out of the stack ends the walk, even if it is mapped elsewhere
+        frames[8] = address + 4096;
+        test!(walk(pid) == Some(3));
+
+        // A long chain is cut short
+        for i in 0..frames.len() / 2 - 1 {
+            frames[2 * i] = address + (i + 1) * 2 * word;
+            frames[2 * i + 1] = 0x1000 + i;
+        }
+        test!(walk(pid) == Some(BACKTRACE_MAX + 1));
+        succ!();
+    })
+}
+
+pub fn times() -> bool {
+    use arch::regs::Regs;
+    use fs::{KScheme, ResourceSeek, Url};
+    use schemes::context::ContextScheme;
+    use schemes::test::helpers::with_child;
+
+    fn tick(pid: usize, cs: usize, count: usize) {
+        if let Ok(child) = unsafe { &mut *::env().contexts.get() }.find_mut(pid) {
+            let mut regs = Regs::default();
+            regs.cs = cs;
+            for _ in 0..count {
+                child.tick(&regs);
+            }
+        }
+    }
+
+    with_child("test_times", |pid| {
+        let path = format!("context:{}/times", pid);
+        let mut resource = match ContextScheme.open(Url::from_str(&path).unwrap(), 0) {
+            Ok(resource) => resource,
+            Err(_) => fail!(),
+        };
+
+        // The child spins in userspace, with a syscall in between
+        tick(pid, 0x1B, 3);
+        tick(pid, 0x8, 1);
+        let mut buf = [0; 64];
+        let first = resource.read(&mut buf).ok();
+        test!(first.map(|count| &buf[..count]) == Some(&b"User: 3\nSystem: 1\n"[..]));
+
+        // Reading again from the start takes a new snapshot
+        tick(pid, 0x1B, 2);
+        test!(resource.seek(ResourceSeek::Start(0)).ok() == Some(0));
+        let second = resource.read(&mut buf).ok();
+        test!(second.map(|count| &buf[..count]) == Some(&b"User: 5\nSystem: 1\n"[..]));
+        succ!();
+    })
+}
+
+pub fn fds() -> bool {
+    use arch::context::ContextFile;
+    use collections::Vec;
+    use fs::{Resource, VecResource};
+    use schemes::test::helpers::{read_path, with_child, with_iopl};
+    use system::error::EPERM;
+
+    /// A resource without a path
+    struct Pathless;
+
+    impl Resource for Pathless {}
+
+    with_child("test_fds", |pid| {
+        let contexts = unsafe { &mut *::env().contexts.get() };
+        if let Ok(child) = contexts.find_mut(pid) {
+            let files = unsafe { &mut *child.files.get() };
+            files.push(ContextFile {
+                fd: 4,
+                resource: box VecResource::new("test:b".into(), Vec::new()),
+                cloexec: false,
+            });
+            files.push(ContextFile {
+                fd: 1,
+                resource: box Pathless,
+                cloexec: false,
+            });
+            files.push(ContextFile {
+                fd: 2,
+                resource: box VecResource::new("test:a".into(), Vec::new()),
+                cloexec: true,
+            });
+        }
+
+        let path = format!("context:{}/fd", pid);
+        let privileged = with_iopl(3, || read_path(&path));
+        let unprivileged = with_iopl(0, || read_path(&path));
+        if let Ok(child) = contexts.find_mut(pid) {
+            child.supervised = true;
+        }
+        let supervised = with_iopl(0, || read_path(&path));
+
+        let expected = b"2 test:a\n4 test:b\n";
+        test!(privileged.as_ref().map(|data| &data[..]) == Ok(&expected[..]));
+        test!(unprivileged == Err(EPERM));
+        test!(supervised.as_ref().map(|data| &data[..]) == Ok(&expected[..]));
+        succ!();
+    })
+}
+
+pub fn shared_files() -> bool {
+    use alloc::arc::Arc;
+    use arch::context::ContextFile;
+    use core::cell::UnsafeCell;
+    use fs::Resource;
+    use schemes::test::helpers::{exit, with_child};
+    use system::error::Result;
+
+    /// A resource which counts how many times it is closed
+    struct Counted(Arc<UnsafeCell<usize>>);
+
+    impl Resource for Counted {
+        fn close(&mut self) -> Result<()> {
+            unsafe { *self.0.get() += 1 };
+            Ok(())
+        }
+    }
+
+    let closed = Arc::new(UnsafeCell::new(0));
+    with_child("test_shared_files_first", |first| {
+        with_child("test_shared_files_second", |second| {
+            let contexts = unsafe { &mut *::env().contexts.get() };
+            let files = match contexts.find(first) {
+                Ok(context) => context.files.clone(),
+                Err(_) => fail!(),
+            };
+            unsafe { &mut *files.get() }.push(ContextFile {
+                fd: 0,
+                resource: box Counted(closed.clone()),
+                cloexec: false,
+            });
+            match contexts.find_mut(second) {
+                Ok(context) => context.files = files,
+                Err(_) => fail!(),
+            }
+
+            // The files stay open while another context shares them
+            match contexts.find_mut(first) {
+                Ok(context) => context.exit(),
+                Err(_) => fail!(),
+            }
+            test!(unsafe { *closed.get() } == 0);
+
+            // Dropping the last context which shares them closes them
+            exit(second);
+            test!(unsafe { *closed.get() } == 1);
+            succ!();
+        })
+    })
+}
+
+pub fn maps() -> bool {
+    use arch::context::ContextMemory;
+    use schemes::test::helpers::{read_path, with_child, with_iopl};
+    use system::error::EPERM;
+
+    fn region(address: usize, size: usize, writeable: bool) -> ContextMemory {
+        ContextMemory {
+            physical_address: address,
+            virtual_address: address,
+            virtual_size: size,
+            writeable: writeable,
+            allocated: false,
+            shared: None,
+        }
+    }
+
+    with_child("test_maps", |pid| {
+        let contexts = unsafe { &mut *::env().contexts.get() };
+        if let Ok(child) = contexts.find_mut(pid) {
+            child.stack = Some(region(0x8000_0000, 4096, true));
+            unsafe {
+                (*child.mmap.get()).memory.push(region(0x6000_0000, 4096, true));
+                (*child.image.get()).memory.push(region(0x4000_0000, 0x1800, false));
+            }
+        }
+
+        let path = format!("context:{}/maps", pid);
+        let privileged = with_iopl(3, || read_path(&path));
+        let unprivileged = with_iopl(0, || read_path(&path));
+        if let Ok(child) = contexts.find_mut(pid) {
+            child.supervised = true;
+        }
+        let supervised = with_iopl(0, || read_path(&path));
+
+        let expected = b"40000000-40002000 r-p image\n\
+                         60000000-60001000 rwp mmap\n\
+                         80000000-80001000 rwp stack\n";
+        test!(privileged.as_ref().map(|data| &data[..]) == Ok(&expected[..]));
+        test!(unprivileged == Err(EPERM));
+        test!(supervised.as_ref().map(|data| &data[..]) == Ok(&expected[..]));
+        succ!();
+    })
+}
+
+pub fn signals() -> bool {
+    use fs::Url;
+    use schemes::test::helpers::{read_path, with_child, with_iopl};
+    use system::error::{EACCES, EPERM};
+    use system::syscall::{O_WRONLY, SIGHUP, SIGTERM, SIGUSR1};
+
+    with_child("test_signals", |pid| {
+        let contexts = unsafe { &mut *::env().contexts.get() };
+        if let Ok(child) = contexts.find_mut(pid) {
+            child.pending_signals = 1 << SIGHUP | 1 << SIGTERM;
+            child.blocked_signals = 1 << SIGTERM | 1 << SIGUSR1;
+        }
+
+        let path = format!("context:{}/signals", pid);
+        let (privileged, write) = with_iopl(3, || {
+            (read_path(&path),
+             ::env().open(Url::from_str(&path).unwrap(), O_WRONLY).err().map(|err| err.errno))
+        });
+        let unprivileged = with_iopl(0, || read_path(&path));
+        if let Ok(child) = contexts.find_mut(pid) {
+            child.supervised = true;
+        }
+        let supervised = with_iopl(0, || read_path(&path));
+
+        let expected = format!("{:x}\n{:x}\n",
+                               1 << SIGHUP | 1 << SIGTERM,
+                               1 << SIGTERM | 1 << SIGUSR1);
+        test!(privileged.as_ref().map(|data| &data[..]) == Ok(expected.as_bytes()));
+        test!(write == Some(EACCES));
+        test!(unprivileged == Err(EPERM));
+        test!(supervised.as_ref().map(|data| &data[..]) == Ok(expected.as_bytes()));
+        succ!();
+    })
+}
+
+pub fn affinity() -> bool {
+    use arch::context::CONTEXT_AFFINITY_ALL;
+    use fs::{KScheme, Url};
+    use schemes::context::ContextScheme;
+    use schemes::test::helpers::{with_child, with_iopl};
+    use system::error::{EINVAL, EPERM};
+
+    fn write(pid: usize, affinity: &str) -> Option<isize> {
+        let path = format!("context:{}/affinity", pid);
+        match ContextScheme.open(Url::from_str(&path).unwrap(), 0) {
+            Ok(mut resource) => resource.write(affinity.as_bytes()).err().map(|err| err.errno),
+            Err(err) => Some(err.errno),
+        }
+    }
+
+    let pid = match unsafe { & *::env().contexts.get() }.current() {
+        Ok(context) => context.pid,
+        Err(_) => fail!(),
+    };
+
+    with_child("test_affinity", |child_pid| {
+        // Contexts may only set their own affinity, unless they are privileged
+        let (own, other) = with_iopl(0, || (write(pid, "1\n"), write(child_pid, "1")));
+        let (privileged, empty, missing, bogus) = with_iopl(3, || {
+            (write(child_pid, "0x1"),
+             write(child_pid, "0"),
+             write(child_pid, &format!("{:x}", CONTEXT_AFFINITY_ALL + 1)),
+             write(child_pid, "all"))
+        });
+
+        let path = format!("context:{}/affinity", child_pid);
+        let mut buf = [0; 8];
+        let read = ContextScheme.open(Url::from_str(&path).unwrap(), 0)
+                                .and_then(|mut resource| resource.read(&mut buf))
+                                .ok();
+        let affinity = unsafe { & *::env().contexts.get() }.find(child_pid)
+                                                            .ok()
+                                                            .map(|child| child.affinity);
+
+        test!(own == None);
+        test!(other == Some(EPERM));
+        test!(privileged == None);
+        test!(empty == Some(EINVAL));
+        test!(missing == Some(EINVAL));
+        test!(bogus == Some(EINVAL));
+        test!(affinity == Some(1));
+        test!(read == Some(2));
+        test!(&buf[..2] == b"1\n");
+        succ!();
+    })
+}
+
+pub fn snapshot() -> bool {
+    use collections::Vec;
+    use core::{mem, ptr};
+    use fs::{KScheme, Url};
+    use schemes::context::ContextScheme;
+    use schemes::test::helpers::{exit, read_path, spawn};
+    use system::error::EINVAL;
+    use system::syscall::{ContextInfo, CONTEXT_INFO_BLOCKED};
+
+    fn snapshot() -> Option<Vec<ContextInfo>> {
+        let data = match read_path("context:?format=binary") {
+            Ok(data) => data,
+            Err(_) => return None,
+        };
+        // Only whole records are returned
+        if data.len() % mem::size_of::<ContextInfo>() != 0 {
+            return None;
+        }
+        Some(data.chunks(mem::size_of::<ContextInfo>())
+                 .map(|record| unsafe { ptr::read(record.as_ptr() as *const ContextInfo) })
+                 .collect())
+    }
+
+    // Every context, with its own fields, and no other
+    fn consistent(infos: &[ContextInfo]) -> bool {
+        let contexts = unsafe { & *::env().contexts.get() };
+        infos.len() == contexts.inner.len() &&
+        infos.iter().zip(contexts.inner.iter()).all(|(info, context)| {
+            let name = &info.name[..];
+            let len = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+            info.pid == context.pid && info.ppid == context.ppid &&
+            context.name.as_bytes().starts_with(&name[..len])
+        })
+    }
+
+    let first = spawn("test_snapshot_first", 0);
+    let second = spawn("test_snapshot_second", 0);
+    let before = snapshot();
+    let before_consistent = before.as_ref().map_or(false, |infos| consistent(infos));
+
+    exit(first);
+    let third = spawn("test_snapshot_third", 0);
+    let after = snapshot();
+    let after_consistent = after.as_ref().map_or(false, |infos| consistent(infos));
+
+    exit(second);
+    exit(third);
+
+    let bogus = ContextScheme.open(Url::from_str("context:?format=xml").unwrap(), 0)
+                             .err()
+                             .map(|err| err.errno);
+
+    let pids = |infos: &Option<Vec<ContextInfo>>| -> Vec<usize> {
+        infos.as_ref().map_or(Vec::new(), |infos| infos.iter().map(|info| info.pid).collect())
+    };
+    let before_pids = pids(&before);
+    let after_pids = pids(&after);
+
+    test!(before_consistent);
+    test!(after_consistent);
+    test!(before_pids.contains(&first) && before_pids.contains(&second));
+    test!(!before_pids.contains(&third));
+    test!(!after_pids.contains(&first));
+    test!(after_pids.contains(&second) && after_pids.contains(&third));
+    test!(after.as_ref().map_or(false, |infos| {
+        infos.iter().any(|info| info.pid == third && info.flags & CONTEXT_INFO_BLOCKED != 0 &&
+                                &info.name[..19] == b"test_snapshot_third" && info.name[19] == 0)
+    }));
+    test!(bogus == Some(EINVAL));
+    succ!();
+}
@@ -0,0 +1,63 @@
+pub fn change() -> bool {
+    use collections::String;
+    use fs::{KScheme, Resource, ResourceSeek, Url};
+    use schemes::cwd::CwdScheme;
+    use system::error::{EINVAL, ENOENT};
+
+    fn cwd() -> Option<&'static mut String> {
+        unsafe { & *::env().contexts.get() }.current().ok().map(|current| {
+            unsafe { &mut *current.cwd.get() }
+        })
+    }
+
+    fn read(resource: &mut Resource) -> Option<String> {
+        let mut buf = [0; 64];
+        if resource.seek(ResourceSeek::Start(0)).is_err() {
+            return None;
+        }
+        resource.read(&mut buf)
+                .ok()
+                .map(|count| String::from_utf8_lossy(&buf[..count]).into_owned())
+    }
+
+    let saved = match cwd() {
+        Some(cwd) => cwd.clone(),
+        None => fail!(),
+    };
+
+    let mut resource = match CwdScheme.open(Url::from_str("cwd:").unwrap(), 0) {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+
+    // Paths are resolved against the working directory, which always ends with a slash
+    let absolute = resource.write(b"null:/a\n").ok();
+    let absolute_cwd = read(&mut *resource);
+    let relative = resource.write(b"b").ok();
+    let relative_cwd = read(&mut *resource);
+
+    // Nothing changes for a path without a registered scheme
+    let unknown = resource.write(b"nonexistent:/x").err().map(|err| err.errno);
+    let empty = resource.write(b"").err().map(|err| err.errno);
+    let unknown_cwd = read(&mut *resource);
+    if let Some(cwd) = cwd() {
+        cwd.clear();
+    }
+    let schemeless = resource.write(b"a").err().map(|err| err.errno);
+    let schemeless_cwd = read(&mut *resource);
+
+    if let Some(cwd) = cwd() {
+        *cwd = saved;
+    }
+
+    test!(absolute == Some(8));
+    test!(absolute_cwd == Some(String::from("null:/a/")));
+    test!(relative == Some(1));
+    test!(relative_cwd == Some(String::from("null:/a/b/")));
+    test!(unknown == Some(ENOENT));
+    test!(empty == Some(EINVAL));
+    test!(unknown_cwd == Some(String::from("null:/a/b/")));
+    test!(schemeless == Some(ENOENT));
+    test!(schemeless_cwd == Some(String::new()));
+    succ!();
+}
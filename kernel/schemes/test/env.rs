@@ -0,0 +1,30 @@
+pub fn variable() -> bool {
+    use fs::{KScheme, Url};
+    use schemes::env::EnvScheme;
+    use system::error::{EEXIST, ENOENT};
+    use system::syscall::{O_CREAT, O_EXCL};
+
+    let mut scheme = EnvScheme;
+    let url = Url::from_str("env://TEST_ENV_VARIABLE").unwrap();
+
+    // A missing variable can not be opened, unless it is created
+    test!(scheme.open(url, 0).err().map(|err| err.errno) == Some(ENOENT));
+    let mut resource = match scheme.open(url, O_CREAT | O_EXCL) {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+    let mut buf = [0; 8];
+    test!(resource.read(&mut buf).ok() == Some(0));
+    test!(scheme.open(url, O_CREAT | O_EXCL).err().map(|err| err.errno) == Some(EEXIST));
+
+    test!(resource.write(b"value").ok() == Some(5));
+    test!(resource.read(&mut buf).ok() == Some(5));
+    test!(&buf[..5] == b"value");
+
+    // Creating an existing variable opens it
+    test!(scheme.open(url, O_CREAT).is_ok());
+
+    test!(scheme.unlink(Url::from_str("env:TEST_ENV_VARIABLE").unwrap()).is_ok());
+    test!(scheme.unlink(Url::from_str("env:TEST_ENV_VARIABLE").unwrap()).is_err());
+    succ!();
+}
@@ -0,0 +1,64 @@
+pub fn ring() -> bool {
+    use collections::string::ToString;
+    use schemes::faults::{Fault, Faults, FAULTS_CAPACITY};
+
+    let mut faults = Faults::new();
+    test!(faults.to_string().is_empty());
+
+    for pid in 0..FAULTS_CAPACITY + 2 {
+        faults.push(Fault {
+            pid: pid,
+            interrupt: 0xD,
+            address: 0,
+            error: 0x10,
+            ip: 0x12AB,
+        });
+    }
+
+    // The oldest faults are dropped
+    let string = faults.to_string();
+    test!(string.lines().count() == FAULTS_CAPACITY);
+    test!(string.starts_with("PID 2 INT D IP 000012AB ADDR 00000000 ERR 10\n"));
+    test!(string.ends_with(&format!("PID {} INT D IP 000012AB ADDR 00000000 ERR 10\n",
+                                    FAULTS_CAPACITY + 1)));
+
+    faults.clear();
+    test!(faults.to_string().is_empty());
+    succ!();
+}
+
+pub fn read() -> bool {
+    use collections::Vec;
+    use fs::{KScheme, ResourceSeek, Url};
+    use schemes::faults::{self, FaultsScheme};
+    use schemes::test::helpers::with_iopl;
+    use system::error::EPERM;
+
+    let pid = match unsafe { & *::env().contexts.get() }.current() {
+        Ok(context) => context.pid,
+        Err(_) => fail!(),
+    };
+
+    faults::record(0xE, 0x1000, 6, 0x4000_0000);
+
+    let mut resource = match FaultsScheme.open(Url::from_str("faults:").unwrap(), 0) {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+    let mut data = Vec::new();
+    test!(resource.read_to_end(&mut data).is_ok());
+    let line = format!("PID {} INT E IP 40000000 ADDR 00001000 ERR 6\n", pid);
+    test!(data.ends_with(line.as_bytes()));
+
+    // Only privileged contexts may clear the faults
+    let unprivileged = with_iopl(0, || resource.write(b"\n").err().map(|err| err.errno));
+    let privileged = with_iopl(3, || resource.write(b"\n").ok());
+    test!(unprivileged == Some(EPERM));
+    test!(privileged == Some(1));
+
+    data.clear();
+    test!(resource.seek(ResourceSeek::Start(0)).ok() == Some(0));
+    test!(resource.read_to_end(&mut data).is_ok());
+    test!(data.is_empty());
+    succ!();
+}
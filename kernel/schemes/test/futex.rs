@@ -0,0 +1,72 @@
+pub fn errors() -> bool {
+    use arch::context::ContextMemory;
+    use core::mem;
+    use alloc::boxed::Box;
+    use fs::{KScheme, Resource, Url};
+    use schemes::futex::FutexScheme;
+    use system::error::{EAGAIN, EFAULT, EINTR, EINVAL, ENOENT};
+    use system::syscall::SIGCONT;
+
+    fn run(resource: &mut Box<Resource>, command: &str) -> Option<isize> {
+        resource.write(command.as_bytes()).err().map(|err| err.errno)
+    }
+
+    test!(FutexScheme.open(Url::from_str("futex:bogus").unwrap(), 0)
+                     .err()
+                     .map(|err| err.errno) == Some(ENOENT));
+
+    let mut resource = match FutexScheme.open(Url::from_str("futex:").unwrap(), 0) {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+
+    // Make the word readable memory of the current context
+    let word: usize = 7;
+    let address = &word as *const usize as usize;
+    let region = ContextMemory {
+        physical_address: address,
+        virtual_address: address,
+        virtual_size: mem::size_of::<usize>(),
+        writeable: true,
+        allocated: false,
+        shared: None,
+    };
+    let mmap = match unsafe { & *::env().contexts.get() }.current() {
+        Ok(context) => context.mmap.clone(),
+        Err(_) => fail!(),
+    };
+    unsafe { (*mmap.get()).memory.push(region) };
+
+    let changed = run(&mut resource, &format!("wait {} 8", address));
+    let unaligned = run(&mut resource, &format!("wait {} 7", address + 1));
+    let missing = run(&mut resource, &format!("wake {} 1", mem::size_of::<usize>()));
+    let wake = run(&mut resource, &format!("wake {} 1", address));
+    let bogus = run(&mut resource, &format!("signal {} 1", address));
+    let short = run(&mut resource, &format!("wait {}", address));
+
+    // A pending signal ends the wait before it blocks, leaving nothing queued
+    if let Ok(context) = unsafe { &mut *::env().contexts.get() }.current_mut() {
+        context.pending_signals |= 1 << SIGCONT;
+    }
+    let signaled = run(&mut resource, &format!("wait {} 7", address));
+    let after_signal = run(&mut resource, &format!("wake {} 1", address));
+    if let Ok(context) = unsafe { &mut *::env().contexts.get() }.current_mut() {
+        context.pending_signals &= !(1 << SIGCONT);
+    }
+
+    unsafe { (*mmap.get()).memory.retain(|memory| memory.virtual_address != address) };
+
+    test!(changed == Some(EAGAIN));
+    test!(unaligned == Some(EINVAL));
+    test!(missing == Some(EFAULT));
+    test!(wake == None);
+    test!(bogus == Some(EINVAL));
+    test!(short == Some(EINVAL));
+    test!(signaled == Some(EINTR));
+    test!(after_signal == None);
+
+    let mut buf = [0; 8];
+    test!(resource.read(&mut buf).ok() == Some(2));
+    test!(&buf[..2] == b"0\n");
+    succ!();
+}
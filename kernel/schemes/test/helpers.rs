@@ -0,0 +1,113 @@
+use alloc::boxed::Box;
+
+use arch::context::{Context, ContextFile};
+
+use collections::Vec;
+
+use core::mem;
+
+use fs::{Resource, Url};
+
+use system::syscall::O_RDONLY;
+
+/// Create a child of `ppid` named `name`, which never runs, and return its PID
+///
+/// The child is left in the context list until it is removed with `exit`.
+pub fn spawn(name: &str, ppid: usize) -> usize {
+    let mut child = unsafe { Context::root() };
+    child.name = name.into();
+    child.ppid = ppid;
+    child.iopl = 0;
+    // Keep the child from being scheduled
+    child.blocked = 1;
+    let pid = child.pid;
+    unsafe { (&mut *::env().contexts.get()).push(child) };
+    pid
+}
+
+/// Remove the child `pid` created by `spawn`
+pub fn exit(pid: usize) {
+    unsafe { &mut *::env().contexts.get() }.inner.retain(|context| context.pid != pid);
+}
+
+/// Run `test` with the PID of a new child named `name` of the current context, which never runs
+pub fn with_child<F: FnOnce(usize) -> bool>(name: &str, test: F) -> bool {
+    let cur_pid = match unsafe { & *::env().contexts.get() }.current() {
+        Ok(context) => context.pid,
+        Err(_) => return false,
+    };
+
+    with_context(name, cur_pid, test)
+}
+
+/// Run `test` with the PID of a new child named `name` of `ppid`, which never runs
+pub fn with_context<F: FnOnce(usize) -> bool>(name: &str, ppid: usize, test: F) -> bool {
+    let pid = spawn(name, ppid);
+
+    let result = test(pid);
+
+    exit(pid);
+
+    result
+}
+
+/// Set the I/O privilege level of the current context to `iopl`, returning the previous one
+fn set_iopl(iopl: usize) -> usize {
+    match unsafe { &mut *::env().contexts.get() }.current_mut() {
+        Ok(context) => mem::replace(&mut context.iopl, iopl),
+        Err(_) => iopl,
+    }
+}
+
+/// Run `f` with the I/O privilege level of the current context set to `iopl`
+///
+/// The previous level is restored afterwards.
+pub fn with_iopl<T, F: FnOnce() -> T>(iopl: usize, f: F) -> T {
+    let previous = set_iopl(iopl);
+
+    let result = f();
+
+    set_iopl(previous);
+
+    result
+}
+
+/// Run `f`, restoring the tick count of the PIT afterwards
+///
+/// Tests may advance `::env().ticks` to simulate ticks, without the rest of the kernel seeing time
+/// jump.
+pub fn with_ticks<T, F: FnOnce() -> T>(f: F) -> T {
+    let ticks = unsafe { *::env().ticks.get() };
+
+    let result = f();
+
+    unsafe { *::env().ticks.get() = ticks };
+
+    result
+}
+
+/// Open `path` for reading and read all of it, returning the errno of the first error
+pub fn read_path(path: &str) -> Result<Vec<u8>, isize> {
+    let mut data = Vec::new();
+    try!(Url::from_str(path).and_then(|url| ::env().open(url, O_RDONLY))
+                            .and_then(|mut resource| resource.read_to_end(&mut data))
+                            .map_err(|err| err.errno));
+    Ok(data)
+}
+
+/// Open `resource` as a file of the current context, returning its file descriptor
+///
+/// The file is closed with `syscall::fs::close`, like any other.
+pub fn install(resource: Box<Resource>) -> Option<usize> {
+    let current = match unsafe { & *::env().contexts.get() }.current() {
+        Ok(current) => current,
+        Err(_) => return None,
+    };
+    let fd = current.next_fd();
+    unsafe { &mut *current.files.get() }.push(ContextFile {
+        fd: fd,
+        resource: resource,
+        cloexec: false,
+    });
+    Some(fd)
+}
@@ -0,0 +1,61 @@
+pub fn write() -> bool {
+    use fs::{KScheme, Url};
+    use schemes::hostname::{HostnameScheme, HOSTNAME_MAX};
+    use schemes::test::helpers::with_iopl;
+    use system::error::{EINVAL, EPERM};
+
+    let hostname = unsafe { & *::env().hostname.get() }.clone();
+
+    let mut resource = match HostnameScheme.open(Url::from_str("hostname:").unwrap(), 0) {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+
+    // Only privileged contexts may change the hostname
+    let denied = with_iopl(0, || resource.write(b"test").err().map(|err| err.errno));
+    let (written, empty, newline, nul, long) = with_iopl(3, || {
+        (resource.write(b"test\n").ok(),
+         resource.write(b"").err().map(|err| err.errno),
+         resource.write(b"a\nb").err().map(|err| err.errno),
+         resource.write(b"a\0b").err().map(|err| err.errno),
+         resource.write(&[b'a'; HOSTNAME_MAX + 1]).err().map(|err| err.errno))
+    });
+
+    let mut buf = [0; 16];
+    let read = resource.read(&mut buf).ok();
+
+    unsafe { *::env().hostname.get() = hostname };
+
+    test!(denied == Some(EPERM));
+    test!(written == Some(5));
+    test!(empty == Some(EINVAL));
+    test!(newline == Some(EINVAL));
+    test!(nul == Some(EINVAL));
+    test!(long == Some(EINVAL));
+    test!(read == Some(4));
+    test!(&buf[..4] == b"test");
+    succ!();
+}
+
+pub fn access() -> bool {
+    use fs::Url;
+    use schemes::test::helpers::with_iopl;
+    use system::error::EPERM;
+    use system::syscall::{O_RDONLY, O_RDWR, O_WRONLY};
+
+    fn open(flags: usize) -> Option<isize> {
+        ::env().open(Url::from_str("hostname:").unwrap(), flags).err().map(|err| err.errno)
+    }
+
+    // Opening for writing is denied before the scheme opens anything
+    let (read, write, read_write) = with_iopl(0, || {
+        (open(O_RDONLY), open(O_WRONLY), open(O_RDWR))
+    });
+    let privileged = with_iopl(3, || open(O_RDWR));
+
+    test!(read == None);
+    test!(write == Some(EPERM));
+    test!(read_write == Some(EPERM));
+    test!(privileged == None);
+    succ!();
+}
@@ -0,0 +1,47 @@
+pub fn read() -> bool {
+    use collections::String;
+    use fs::Url;
+    use schemes::irq::parse_irq;
+    use system::error::EAGAIN;
+
+    test!(parse_irq("").ok() == Some(None));
+    test!(parse_irq("//1").ok() == Some(Some(1)));
+    test!(parse_irq("15").ok() == Some(Some(15)));
+    test!(parse_irq("16").is_err());
+    test!(parse_irq("pit").is_err());
+
+    // The table lists the counters
+    let mut table = match Url::from_str("irq:").unwrap().open() {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+    let mut buf = [0; 5];
+    test!(table.read(&mut buf).ok() == Some(5));
+    test!(&buf == b"INT  ");
+
+    let mut resource = match Url::from_str("irq:15?nonblock").unwrap().open() {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+    test!(!resource.is_readable());
+    let mut buf = [0; 32];
+    test!(resource.read(&mut buf).err().map(|err| err.errno) == Some(EAGAIN));
+
+    // Firing the IRQ makes it readable, once
+    let interrupts = unsafe { &mut *::env().interrupts.get() };
+    interrupts[0x2F] += 1;
+    let count = interrupts[0x2F];
+    let readable = resource.is_readable();
+    let first = resource.read(&mut buf).ok();
+    let text = match first {
+        Some(len) => String::from_utf8(buf[..len].to_vec()).ok(),
+        None => None,
+    };
+    let second = resource.read(&mut buf[..]).err().map(|err| err.errno);
+    interrupts[0x2F] -= 1;
+
+    test!(readable);
+    test!(text == Some(format!("{}\n", count)));
+    test!(second == Some(EAGAIN));
+    succ!();
+}
@@ -0,0 +1,35 @@
+pub fn errors() -> bool {
+    use schemes::kill::kill;
+    use system::error::{EINVAL, ESRCH};
+    use system::syscall::{NSIG, SIGCONT};
+
+    let contexts = unsafe { &mut *::env().contexts.get() };
+    let pid = match contexts.current() {
+        Ok(context) => context.pid,
+        Err(_) => fail!(),
+    };
+
+    match kill(pid, 0) {
+        Err(err) => test!(err.errno == EINVAL),
+        Ok(_) => fail!(),
+    }
+    match kill(pid, NSIG) {
+        Err(err) => test!(err.errno == EINVAL),
+        Ok(_) => fail!(),
+    }
+    match kill(usize::max_value(), SIGCONT) {
+        Err(err) => test!(err.errno == ESRCH),
+        Ok(_) => fail!(),
+    }
+
+    // SIGCONT is ignored, so signaling it is harmless
+    test!(kill(pid, SIGCONT).is_ok());
+    match contexts.current_mut() {
+        Ok(context) => {
+            test!(context.pending_signals == 1 << SIGCONT);
+            context.pending_signals = 0;
+        },
+        Err(_) => fail!(),
+    }
+    succ!();
+}
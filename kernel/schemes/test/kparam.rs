@@ -0,0 +1,66 @@
+pub fn tune() -> bool {
+    use collections::string::String;
+    use common::random;
+    use fs::{KScheme, Url};
+    use schemes::kparam::KparamScheme;
+    use schemes::test::helpers::with_iopl;
+    use system::error::{EINVAL, ENOENT, EPERM};
+
+    fn read(url: &str) -> Option<String> {
+        let mut resource = match KparamScheme.open(Url::from_str(url).unwrap(), 0) {
+            Ok(resource) => resource,
+            Err(_) => return None,
+        };
+        let mut buf = [0; 256];
+        resource.read(&mut buf)
+                .ok()
+                .and_then(|count| String::from_utf8(buf[..count].to_vec()).ok())
+    }
+
+    fn write(name: &str, value: &str) -> Option<isize> {
+        match KparamScheme.open(Url::from_str(&format!("kparam:{}", name)).unwrap(), 0) {
+            Ok(mut resource) => resource.write(value.as_bytes()).err().map(|err| err.errno),
+            Err(err) => Some(err.errno),
+        }
+    }
+
+    test!(read("kparam:") == Some(String::from("file_limit\nrekey_requests\ntimer_frequency\n")));
+    test!(KparamScheme.open(Url::from_str("kparam:bogus").unwrap(), 0)
+                      .err()
+                      .map(|err| err.errno) == Some(ENOENT));
+
+    let file_limit = unsafe { *::env().file_limit.get() };
+    let rekey_requests = random::rekey_requests();
+
+    test!(read("kparam:file_limit") == Some(format!("{}\n", file_limit)));
+    test!(read("kparam://rekey_requests") == Some(format!("{}\n", rekey_requests)));
+
+    test!(with_iopl(0, || write("file_limit", "64")) == Some(EPERM));
+
+    with_iopl(3, || {
+        let set = write("file_limit", "64\n");
+        let limited = unsafe { *::env().file_limit.get() };
+        let zero = write("file_limit", "0");
+        let huge = write("file_limit", "1000000");
+        let bogus = write("file_limit", "many");
+        let rekey = write("rekey_requests", "16");
+        let rekey_zero = write("rekey_requests", "0");
+        let slow = write("timer_frequency", "1");
+        let rekeyed = random::rekey_requests();
+        write("file_limit", &format!("{}", file_limit));
+        write("rekey_requests", &format!("{}", rekey_requests));
+
+        test!(set == None);
+        test!(limited == 64);
+        test!(zero == Some(EINVAL));
+        test!(huge == Some(EINVAL));
+        test!(bogus == Some(EINVAL));
+        test!(rekey == None);
+        test!(rekeyed == 16);
+        test!(rekey_zero == Some(EINVAL));
+        test!(slow == Some(EINVAL));
+        test!(unsafe { *::env().file_limit.get() } == file_limit);
+        test!(random::rekey_requests() == rekey_requests);
+        succ!();
+    })
+}
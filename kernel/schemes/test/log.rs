@@ -0,0 +1,34 @@
+pub fn follow() -> bool {
+    use fs::{KScheme, Url};
+    use schemes::log::{LogScheme, LOG_LINE_MAX};
+    use system::error::{EAGAIN, EINVAL};
+    use system::syscall::O_NONBLOCK;
+
+    let mut resource = match LogScheme.open(Url::from_str("log:").unwrap(), O_NONBLOCK) {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+
+    // Catch up with the log
+    let mut buf = [0; 512];
+    loop {
+        match resource.read(&mut buf) {
+            Ok(count) => test!(count > 0),
+            Err(err) => {
+                test!(err.errno == EAGAIN);
+                break;
+            }
+        }
+    }
+
+    test!(resource.write(b"log scheme test\n").ok() == Some(16));
+    let count = match resource.read(&mut buf) {
+        Ok(count) => count,
+        Err(_) => fail!(),
+    };
+    test!(buf[..count].ends_with(b"log scheme test\n"));
+
+    test!(resource.write(b"two\nlines").err().map(|err| err.errno) == Some(EINVAL));
+    test!(resource.write(&[b'a'; LOG_LINE_MAX + 1]).err().map(|err| err.errno) == Some(EINVAL));
+    succ!();
+}
@@ -0,0 +1,108 @@
+use alloc::boxed::Box;
+
+use fs::{KScheme, Resource, Url};
+
+use schemes::loopback::LoopbackScheme;
+
+use system::error::Result;
+
+fn open(url: &str) -> Result<Box<Resource>> {
+    LoopbackScheme.open(Url::from_str(url).unwrap(), 0)
+}
+
+pub fn echo() -> bool {
+    use system::error::{EAGAIN, EINVAL};
+    use system::syscall::{F_GETFL, O_NONBLOCK};
+
+    let mut resource = match open("loopback://?nonblock") {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+    let mut buf = [0; 8];
+
+    test!(resource.fcntl(F_GETFL, 0).ok() == Some(O_NONBLOCK));
+    test!(!resource.is_readable());
+    test!(resource.read(&mut buf).err().map(|err| err.errno) == Some(EAGAIN));
+
+    // Each read returns one message, cut short to the buffer
+    test!(resource.write(b"ping").ok() == Some(4));
+    test!(resource.write(b"loopback").ok() == Some(8));
+    test!(resource.is_readable());
+    test!(resource.read(&mut buf).ok() == Some(4));
+    test!(&buf[..4] == b"ping");
+    test!(resource.read(&mut buf[..4]).ok() == Some(4));
+    test!(&buf[..4] == b"loop");
+    test!(resource.read(&mut buf).err().map(|err| err.errno) == Some(EAGAIN));
+
+    // Duplicates share the queue, but each open is a new one
+    let mut dup = match resource.dup() {
+        Ok(dup) => dup,
+        Err(_) => fail!(),
+    };
+    test!(dup.write(b"pong").ok() == Some(4));
+    test!(resource.read(&mut buf).ok() == Some(4));
+    test!(&buf[..4] == b"pong");
+    test!(resource.write(b"other").ok() == Some(5));
+    let mut other = match open("loopback:?nonblock") {
+        Ok(other) => other,
+        Err(_) => fail!(),
+    };
+    test!(!other.is_readable());
+    test!(other.read(&mut buf).err().map(|err| err.errno) == Some(EAGAIN));
+
+    for url in ["loopback:?delay=x", "loopback:?drop=0", "loopback:?echo"].iter() {
+        test!(open(url).err().map(|err| err.errno) == Some(EINVAL));
+    }
+    succ!();
+}
+
+pub fn delay() -> bool {
+    use common::time::{Duration, NANOS_PER_MILLI};
+    use system::error::EAGAIN;
+    use system::syscall::F_SETFL;
+
+    let mut resource = match open("loopback:?delay=10&nonblock") {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+    let mut buf = [0; 8];
+
+    let mut path = [0; 32];
+    let path_len = resource.path(&mut path);
+    test!(path_len.ok() == Some(25));
+    test!(&path[..25] == b"loopback:?delay=10&drop=0");
+
+    // A message is not due before the delay
+    let written = Duration::monotonic();
+    test!(resource.write(b"late").ok() == Some(4));
+    test!(!resource.is_readable());
+    test!(resource.read(&mut buf).err().map(|err| err.errno) == Some(EAGAIN));
+
+    // Blocking reads wait for it
+    test!(resource.fcntl(F_SETFL, 0).is_ok());
+    test!(resource.read(&mut buf).ok() == Some(4));
+    test!(&buf[..4] == b"late");
+    test!(Duration::monotonic() >= written + Duration::new(0, 10 * NANOS_PER_MILLI));
+    succ!();
+}
+
+pub fn lossy() -> bool {
+    use system::error::EAGAIN;
+
+    let mut resource = match open("loopback:?drop=3&nonblock") {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+    let mut buf = [0; 1];
+
+    // Dropped messages are still written
+    for i in 1..7 {
+        test!(resource.write(&[i]).ok() == Some(1));
+    }
+    for &i in [1, 2, 4, 5].iter() {
+        test!(resource.read(&mut buf).ok() == Some(1));
+        test!(buf[0] == i);
+    }
+    test!(resource.read(&mut buf).err().map(|err| err.errno) == Some(EAGAIN));
+    succ!();
+}
@@ -30,8 +30,45 @@ macro_rules! fail {
 }
 
 // Add your test here!
+pub mod acpi;
+pub mod broadcast_queue;
+pub mod byte_queue;
+pub mod console;
+pub mod context;
+pub mod cwd;
+pub mod env;
+pub mod faults;
+pub mod futex;
 pub mod get_slice;
+pub mod helpers;
+pub mod hostname;
+pub mod irq;
+pub mod kill;
+pub mod kparam;
+pub mod log;
+pub mod loopback;
 pub mod meta;
+pub mod null;
+pub mod pipe;
+pub mod pressure;
+pub mod random;
+pub mod rate_limited;
+pub mod repeat;
+pub mod resource;
+pub mod schemes;
+pub mod shm;
+pub mod sleep;
+pub mod supervisor;
+pub mod sysinfo;
+pub mod tee;
+pub mod time;
+pub mod timer;
+pub mod trace;
+pub mod uptime;
+pub mod url;
+pub mod vec_resource;
+pub mod wait_queue;
+pub mod watchdog;
 
 pub struct TestScheme;
 
@@ -89,7 +126,134 @@ impl KScheme for TestScheme {
         // Add your test here!
         reg_test!(meta::meta_test_woah, "Testing the testing (wut)");
         reg_test!(!meta::meta_test_woah_fail, "Testing the fail testing (wut)");
+        reg_test!(acpi::tables, "Listing and reading ACPI tables");
+        reg_test!(broadcast_queue::lagged, "Broadcast queues dropping old values");
+        reg_test!(broadcast_queue::readers, "Broadcast queues with many readers");
+        reg_test!(byte_queue::bounded, "Bounded byte queues");
+        reg_test!(console::canonical, "Console line editing");
+        reg_test!(console::keymap, "Loading a console keymap");
+        reg_test!(console::raw, "Console raw mode");
+        reg_test!(console::terminal, "Querying whether a descriptor is a terminal");
+        reg_test!(console::winsize, "Console window size");
+        reg_test!(context::affinity, "Setting the CPU affinity of a context");
+        reg_test!(context::cloexec, "Closing FD_CLOEXEC files on exec");
+        reg_test!(context::cmdline, "Reading the arguments of a context");
+        reg_test!(context::credit, "Capping the scheduling credit of a context");
+        reg_test!(context::details, "Describing a context with context:PID");
+        reg_test!(context::fds, "Open files of a context");
+        reg_test!(context::file_limit, "Limiting the open files of a context");
+        reg_test!(context::maps, "Memory regions of a context");
+        reg_test!(context::priority, "Setting the priority of a context");
+        reg_test!(context::shared_files, "Closing files shared with CLONE_FILES");
+        reg_test!(context::signals, "Pending and blocked signals of a context");
+        reg_test!(context::snapshot, "Reading every context in one snapshot");
+        reg_test!(context::stack, "Backtraces of stopped contexts");
+        reg_test!(context::times, "User and system time of a context");
+        reg_test!(cwd::change, "Changing the working directory with cwd:");
+        reg_test!(env::variable, "Setting and reading env: variables");
+        reg_test!(faults::read, "Reading and clearing faults:");
+        reg_test!(faults::ring, "Recent faults");
+        reg_test!(futex::errors, "Futex commands which do not block");
         reg_test!(get_slice::test, "GetSlice");
+        reg_test!(hostname::access, "Opening hostname: for writing needs privilege");
+        reg_test!(hostname::write, "Setting the hostname with hostname:");
+        reg_test!(irq::read, "Waiting for IRQs");
+        reg_test!(kill::errors, "Signal errors of kill:");
+        reg_test!(kparam::tune, "Tuning kernel parameters with kparam:");
+        reg_test!(log::follow, "Following the kernel log with log:");
+        reg_test!(loopback::delay, "Delayed loopback: messages");
+        reg_test!(loopback::echo, "Echoing writes with loopback:");
+        reg_test!(loopback::lossy, "Dropping loopback: messages");
+        reg_test!(null::empty_io, "Empty reads and writes on null: and zero:");
+        reg_test!(pipe::broken, "Closing a pipe under a blocked writer");
+        reg_test!(pipe::named, "Named pipes");
+        reg_test!(pipe::readiness, "Pipe readiness");
+        reg_test!(pressure::clusters, "Counting free clusters");
+        reg_test!(pressure::read, "Memory pressure");
+        reg_test!(random::blocks, "Random output in blocks");
+        reg_test!(random::chacha20_block, "ChaCha20 block function");
+        reg_test!(random::entropy_pool, "Entropy pool rekeying");
+        reg_test!(random::fork, "Random streams diverge on fork");
+        reg_test!(random::fresh, "Fresh bytes on every random read");
+        reg_test!(random::getrandom, "getrandom flags");
+        reg_test!(random::keys, "Batches of random keys");
+        reg_test!(random::range, "Random ranges");
+        reg_test!(random::ready, "Waiting for the entropy pool to be seeded");
+        reg_test!(random::rekey, "Erasing the key of the CSPRNG");
+        reg_test!(random::seeded, "Deterministic random streams");
+        reg_test!(random::stats, "Entropy pool statistics");
+        reg_test!(rate_limited::throughput, "Rate limited throughput per tick");
+        reg_test!(repeat::pattern, "Repeated patterns with repeat:");
+        reg_test!(resource::read_exact, "Reading exactly a buffer");
+        reg_test!(resource::write_all, "Writing all of a buffer");
+        reg_test!(schemes::aliases, "Scheme aliases");
+        reg_test!(schemes::handles, "Handles chosen by the provider of a scheme");
+        reg_test!(schemes::list, "Listing schemes with schemes:");
+        reg_test!(schemes::pass_fd, "Passing descriptors from the provider of a scheme");
+        reg_test!(schemes::registry, "Registering and deregistering schemes");
+        reg_test!(shm::lifetime, "Freeing and keeping shm: segments");
+        reg_test!(shm::share, "Sharing shm: segments between handles");
+        reg_test!(sleep::duration, "Sleeping for the duration of a sleep: URL");
+        reg_test!(supervisor::causes, "Causes of supervisor errors");
+        reg_test!(supervisor::close, "Closing a supervisor");
+        reg_test!(supervisor::control, "Supervisor commands through control");
+        reg_test!(supervisor::cycles, "Supervising oneself or an ancestor");
+        reg_test!(supervisor::errors, "Errors when supervising");
+        reg_test!(supervisor::exit, "Supervisor exit events");
+        reg_test!(supervisor::exit_policy, "Zombie and reaped tracees");
+        reg_test!(supervisor::filter, "Supervisor syscall filters");
+        reg_test!(supervisor::forcekill, "Force killing a tracee stuck in the kernel");
+        reg_test!(supervisor::fpregs, "Supervisors reading and replacing FPU registers");
+        reg_test!(supervisor::interrupt, "Canceling a blocked supervisor read");
+        reg_test!(supervisor::legacy_write, "Supervisor writes shorter than a packet");
+        reg_test!(supervisor::mask, "Supervisor event mask");
+        reg_test!(supervisor::observe, "Observe only supervisors");
+        reg_test!(supervisor::resupervise, "Supervising a child again after detaching");
+        reg_test!(supervisor::round_robin, "Supervisors of every child serving them in turn");
+        reg_test!(supervisor::setargs, "Supervisors rewriting syscall arguments");
+        reg_test!(supervisor::setregs, "Supervisors replacing registers");
+        reg_test!(supervisor::supervisor_gone, "Tracees resume when the supervisor is gone");
+        reg_test!(supervisor::tracee_gone, "Supervisor reads fail when the tracees are gone");
+        reg_test!(supervisor::unaligned, "Supervisor packets in misaligned buffers");
+        reg_test!(sysinfo::load, "Load averaged over recent ticks");
+        reg_test!(sysinfo::read, "System information");
+        reg_test!(tee::both, "Tee writes reaching both resources");
+        reg_test!(tee::errors, "Tee errors from either resource");
+        reg_test!(tee::short, "Tee short writes when one resource accepts less");
+        reg_test!(time::access, "Opening time: clocks for writing needs privilege");
+        reg_test!(time::monotonic, "The monotonic clock of time:");
+        reg_test!(time::set_realtime, "Setting the realtime clock of time:");
+        reg_test!(timer::frequency, "Setting the tick frequency with timer:");
+        reg_test!(trace::record, "Recording kernel events");
+        reg_test!(uptime::binary, "Uptime as an UptimeInfo");
+        reg_test!(uptime::read, "Uptime");
+        reg_test!(url::flags, "Open flags in URLs");
+        reg_test!(url::format, "Choosing text or binary output in URLs");
+        reg_test!(vec_resource::append, "VecResource append");
+        reg_test!(vec_resource::bounded, "VecResource writes capped to a maximum size");
+        reg_test!(vec_resource::copy, "Copying between VecResources");
+        reg_test!(vec_resource::cursors, "VecResource duplicates with their own cursors");
+        reg_test!(vec_resource::flags, "VecResource flags");
+        reg_test!(vec_resource::fmap, "Mapping files with fmap");
+        reg_test!(vec_resource::fork, "VecResource data shared across fork until written");
+        reg_test!(vec_resource::mmap, "VecResource memory maps");
+        reg_test!(vec_resource::path, "VecResource path");
+        reg_test!(vec_resource::read, "VecResource sequential reads");
+        reg_test!(vec_resource::read_line, "VecResource line reads");
+        reg_test!(vec_resource::read_to_end, "VecResource reads to the end");
+        reg_test!(vec_resource::readv, "VecResource vectored reads");
+        reg_test!(vec_resource::seek, "VecResource seeking");
+        reg_test!(vec_resource::seek_data_hole, "VecResource data and hole seeking");
+        reg_test!(vec_resource::shared, "VecResource shared data");
+        reg_test!(vec_resource::stat, "VecResource stat");
+        reg_test!(vec_resource::tell, "VecResource offset and size through seeking");
+        reg_test!(vec_resource::truncate, "VecResource truncate");
+        reg_test!(vec_resource::write, "VecResource writes at the cursor");
+        reg_test!(vec_resource::writev, "VecResource vectored writes");
+        reg_test!(wait_queue::bounded, "Bounded wait queues");
+        reg_test!(wait_queue::receive_all, "Draining wait queues");
+        reg_test!(watchdog::expire, "Watchdogs expiring unless pet");
+        reg_test!(watchdog::options, "Watchdog URL options");
 
         Ok(box VecResource::new("test:".to_string(), string.into_bytes()))
     }
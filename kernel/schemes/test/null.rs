@@ -0,0 +1,22 @@
+pub fn empty_io() -> bool {
+    use fs::Resource;
+    use schemes::null::NullResource;
+    use schemes::zero::ZeroResource;
+
+    let mut null = NullResource;
+    let mut buf = [0xFF; 4];
+    test!(null.read(&mut buf).ok() == Some(0));
+    test!(null.read(&mut []).ok() == Some(0));
+    test!(null.write(&buf).ok() == Some(4));
+    test!(null.write(&[]).ok() == Some(0));
+    test!(buf == [0xFF; 4]);
+    test!(null.sync().is_ok());
+
+    let mut zero = ZeroResource;
+    test!(zero.read(&mut buf).ok() == Some(4));
+    test!(buf == [0; 4]);
+    test!(zero.read(&mut []).ok() == Some(0));
+    test!(zero.write(&[]).ok() == Some(0));
+    test!(zero.sync().is_ok());
+    succ!();
+}
@@ -0,0 +1,118 @@
+pub fn broken() -> bool {
+    use alloc::arc::Arc;
+    use arch::context::{context_switch, Context};
+    use core::cell::UnsafeCell;
+    use fs::Resource;
+    use schemes::pipe::{PipeRead, PipeWrite, PIPE_CAPACITY};
+
+    fn blocked(pid: usize) -> bool {
+        match unsafe { & *::env().contexts.get() }.find(pid) {
+            Ok(context) => context.blocked > 0,
+            Err(_) => false,
+        }
+    }
+
+    let read = PipeRead::new();
+    let mut write = PipeWrite::new(&read);
+
+    // The writer fills the pipe, and blocks waiting for the rest to fit
+    let result: Arc<UnsafeCell<Option<Result<usize, isize>>>> = Arc::new(UnsafeCell::new(None));
+    let written = result.clone();
+    let pid = Context::spawn("test_pipe_broken".into(),
+                             box move || {
+                                 let data = vec![0; PIPE_CAPACITY + 1];
+                                 let count = write.write(&data).map_err(|err| err.errno);
+                                 unsafe { *written.get() = Some(count) };
+                             });
+    for _ in 0..16 {
+        if blocked(pid) {
+            break;
+        }
+        unsafe { context_switch() };
+    }
+    test!(blocked(pid));
+
+    // Closing the read side frees the pipe, and the writer returns what it wrote
+    drop(read);
+    for _ in 0..16 {
+        if unsafe { (*result.get()).is_some() } {
+            break;
+        }
+        unsafe { context_switch() };
+    }
+    test!(unsafe { *result.get() } == Some(Ok(PIPE_CAPACITY)));
+    succ!();
+}
+
+pub fn named() -> bool {
+    use fs::{KScheme, Url};
+    use schemes::pipe::PipeScheme;
+    use system::syscall::{O_RDONLY, O_WRONLY};
+
+    let mut scheme = PipeScheme::new();
+
+    // A pipe without a read side can not be written
+    test!(scheme.open(Url::from_str("pipe:test").unwrap(), O_WRONLY).is_err());
+
+    let mut read = match scheme.open(Url::from_str("pipe:test").unwrap(), O_RDONLY) {
+        Ok(read) => read,
+        Err(_) => fail!(),
+    };
+    let mut write = match scheme.open(Url::from_str("pipe:test").unwrap(), O_WRONLY) {
+        Ok(write) => write,
+        Err(_) => fail!(),
+    };
+
+    test!(write.write(b"abc").ok() == Some(3));
+    let mut buf = [0; 4];
+    test!(read.read(&mut buf).ok() == Some(3));
+    test!(&buf[..3] == b"abc");
+
+    // Closing the write side ends the pipe
+    drop(write);
+    test!(read.read(&mut buf).ok() == Some(0));
+
+    // Closing the read side breaks the pipe
+    let mut write = match scheme.open(Url::from_str("pipe:test").unwrap(), O_WRONLY) {
+        Ok(write) => write,
+        Err(_) => fail!(),
+    };
+    drop(read);
+    test!(write.write(b"abc").is_err());
+    test!(scheme.open(Url::from_str("pipe:test").unwrap(), O_WRONLY).is_err());
+    succ!();
+}
+
+pub fn readiness() -> bool {
+    use fs::{KScheme, Url};
+    use schemes::pipe::PipeScheme;
+    use system::syscall::{O_RDONLY, O_WRONLY};
+
+    let mut scheme = PipeScheme::new();
+
+    let mut read = match scheme.open(Url::from_str("pipe:ready").unwrap(), O_RDONLY) {
+        Ok(read) => read,
+        Err(_) => fail!(),
+    };
+    let mut write = match scheme.open(Url::from_str("pipe:ready").unwrap(), O_WRONLY) {
+        Ok(write) => write,
+        Err(_) => fail!(),
+    };
+
+    test!(!read.is_readable());
+    test!(!read.is_writable());
+    test!(write.is_writable());
+    test!(!write.is_readable());
+
+    test!(write.write(b"abc").ok() == Some(3));
+    test!(read.is_readable());
+
+    let mut buf = [0; 4];
+    test!(read.read(&mut buf).ok() == Some(3));
+    test!(!read.is_readable());
+
+    // A closed write side makes the read side readable, to read the end of the pipe
+    drop(write);
+    test!(read.is_readable());
+    succ!();
+}
@@ -0,0 +1,52 @@
+pub fn clusters() -> bool {
+    use arch::memory::{self, CLUSTER_SIZE};
+
+    test!(memory::clusters_free() * CLUSTER_SIZE == memory::memory_free());
+
+    // Allocating and freeing keeps the count up to date
+    let free = memory::clusters_free();
+    let ptr = unsafe { memory::alloc(3 * CLUSTER_SIZE) };
+    test!(ptr > 0);
+    test!(memory::clusters_free() == free - 3);
+    unsafe { memory::unalloc(ptr) };
+    test!(memory::clusters_free() == free);
+    succ!();
+}
+
+pub fn read() -> bool {
+    use arch::memory;
+    use collections::String;
+    use fs::Url;
+    use schemes::pressure::parse_threshold;
+    use system::error::EAGAIN;
+
+    test!(parse_threshold("//memory/256").ok() == Some(256));
+    test!(parse_threshold("memory/0").is_err());
+    test!(parse_threshold("memory").is_err());
+    test!(parse_threshold("disk/256").is_err());
+
+    // Memory is under pressure below a threshold above the free pages
+    let url = format!("pressure://memory/{}", memory::clusters_free() + 1024);
+    let mut resource = match Url::from_str(&url).unwrap().open() {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+    test!(resource.is_readable());
+    let mut buf = [0; 32];
+    let count = match resource.read(&mut buf) {
+        Ok(count) => count,
+        Err(_) => fail!(),
+    };
+    let text = String::from_utf8(buf[..count].to_vec()).unwrap();
+    test!(text.ends_with('\n'));
+    test!(text.trim().parse::<usize>().is_ok());
+
+    // But not below a single page, which would block
+    let mut resource = match Url::from_str("pressure://memory/1?nonblock").unwrap().open() {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+    test!(!resource.is_readable());
+    test!(resource.read(&mut buf).err().map(|err| err.errno) == Some(EAGAIN));
+    succ!();
+}
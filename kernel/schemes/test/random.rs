@@ -0,0 +1,362 @@
+pub fn chacha20_block() -> bool {
+    use common::random::Csprng;
+
+    // RFC 7539, section 2.3.2
+    let key = [0x03020100, 0x07060504, 0x0b0a0908, 0x0f0e0d0c,
+               0x13121110, 0x17161514, 0x1b1a1918, 0x1f1e1d1c];
+    let mut csprng = Csprng::from_parts(key, 0x09000000 << 32 | 1, [0x4a000000, 0]);
+
+    let mut block = [0; 16];
+    csprng.next_block(&mut block);
+
+    test!(block == [0x10, 0xf1, 0xe7, 0xe4, 0xd1, 0x3b, 0x59, 0x15,
+                    0x50, 0x0f, 0xdd, 0x1f, 0xa3, 0x20, 0x71, 0xc4]);
+    succ!();
+}
+
+pub fn range() -> bool {
+    use common::random::rand_range;
+    use schemes::random::parse_range;
+
+    test!(parse_range("").ok() == Some(None));
+    test!(parse_range("0-99").ok() == Some(Some((0, 99))));
+    test!(parse_range("die6").ok() == Some(Some((1, 6))));
+    test!(parse_range("100-0").is_err());
+    test!(parse_range("die0").is_err());
+    test!(parse_range("0-").is_err());
+    test!(parse_range("abc").is_err());
+
+    for _ in 0..256 {
+        let value = rand_range(10, 20);
+        test!(value >= 10 && value <= 20);
+    }
+    test!(rand_range(7, 7) == 7);
+    succ!();
+}
+
+pub fn entropy_pool() -> bool {
+    use common::random::{EntropyPool, REKEY_BYTES, REKEY_REQUESTS};
+
+    let mut pool = EntropyPool::new();
+
+    // Credit is only given to the CSPRNG when the pool is rekeyed
+    pool.add_entropy(&[1; REKEY_BYTES / 2], 8);
+    test!(pool.entropy() == 0);
+    pool.add_entropy(&[2; REKEY_BYTES / 2], 0);
+    test!(pool.entropy() == 8);
+
+    // Serving enough requests rekeys as well
+    pool.add_entropy(&[3], 1);
+    let mut buf = [0; 4];
+    for _ in 0..REKEY_REQUESTS - 1 {
+        pool.fill(&mut buf);
+    }
+    test!(pool.entropy() == 8);
+    pool.fill(&mut buf);
+    test!(pool.entropy() == 9);
+    succ!();
+}
+
+pub fn fork() -> bool {
+    use common::random::{self, EntropyPool};
+
+    let mut parent = EntropyPool::new();
+    let mut child = EntropyPool::new();
+
+    // Pools in the same state produce the same stream
+    let mut a = [0; 32];
+    let mut b = [0; 32];
+    parent.fill(&mut a);
+    child.fill(&mut b);
+    test!(a == b);
+
+    // Rekeying for the child makes the streams diverge immediately
+    child.fork(2);
+    parent.fill(&mut a);
+    child.fill(&mut b);
+    test!(a != b);
+
+    // The kernel pool diverges between forks as well
+    let mut first = [0; 32];
+    let mut second = [0; 32];
+    random::fork(3);
+    random::fill(&mut first);
+    random::fork(4);
+    random::fill(&mut second);
+    test!(first != second);
+    succ!();
+}
+
+pub fn getrandom() -> bool {
+    use syscall::random::getrandom;
+    use system::error::EINVAL;
+    use system::syscall::{GRND_NONBLOCK, GRND_RANDOM};
+
+    match getrandom(0 as *mut u8, 0, 0x80) {
+        Err(err) => test!(err.errno == EINVAL),
+        Ok(_) => fail!(),
+    }
+    test!(getrandom(0 as *mut u8, 0, GRND_NONBLOCK | GRND_RANDOM).ok() == Some(0));
+    succ!();
+}
+
+pub fn fresh() -> bool {
+    use fs::{KScheme, Url};
+    use schemes::random::RandomScheme;
+
+    // urandom: shares its resource with random:, without waiting for entropy
+    let mut resource = match RandomScheme::urandom().open(Url::from_str("urandom:").unwrap(), 0) {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+
+    let mut first = [0; 32];
+    let mut second = [0; 32];
+    test!(resource.read(&mut first).ok() == Some(32));
+    test!(resource.read(&mut second).ok() == Some(32));
+    test!(first != second);
+    succ!();
+}
+
+pub fn blocks() -> bool {
+    use fs::{KScheme, Url};
+    use schemes::random::{parse_options, RandomScheme, BLOCK_SIZE_MAX};
+
+    let options = match parse_options("//bs=4096&count=2") {
+        Ok(options) => options,
+        Err(_) => fail!(),
+    };
+    test!(options.block_size == Some(4096));
+    test!(options.count == Some(2));
+    test!(options.range == None);
+    test!(parse_options("").ok().map(|options| options.block_size) == Some(None));
+    test!(parse_options("bs=0").is_err());
+    test!(parse_options("bs=abc").is_err());
+    test!(parse_options(&format!("bs={}", BLOCK_SIZE_MAX + 1)).is_err());
+    test!(parse_options("count=2").is_err());
+    test!(parse_options("bs=16&0-99").is_err());
+
+    let url = Url::from_str("urandom:bs=16&count=2").unwrap();
+    let mut resource = match RandomScheme::urandom().open(url, 0) {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+
+    // Reads are not limited to a block, but stop after the last one
+    let mut buf = [0; 24];
+    test!(resource.read(&mut buf).ok() == Some(24));
+    test!(resource.read(&mut buf).ok() == Some(8));
+    test!(resource.read(&mut buf).ok() == Some(0));
+    succ!();
+}
+
+pub fn keys() -> bool {
+    use core::usize;
+    use fs::{KScheme, Url};
+    use schemes::random::{parse_keys, RandomScheme};
+    use system::syscall::O_NONBLOCK;
+
+    let (options, flags) = match parse_keys("count=3&size=8&nonblock", 0) {
+        Ok(keys) => keys,
+        Err(_) => fail!(),
+    };
+    test!(options.count == Some(3) && options.block_size == Some(8) && options.keys);
+    test!(flags == O_NONBLOCK);
+    test!(parse_keys("count=3", 0).is_err());
+    test!(parse_keys("size=8", 0).is_err());
+    test!(parse_keys("count=0&size=8", 0).is_err());
+    test!(parse_keys("count=3&size=0", 0).is_err());
+    test!(parse_keys("count=3&size=8&bogus", 0).is_err());
+
+    // The total size must fit in a usize
+    let overflow = format!("random://keys?count={}&size=2&nonblock", usize::MAX);
+    test!(RandomScheme::random().open(Url::from_str(&overflow).unwrap(), 0).is_err());
+
+    let url = Url::from_str("random://keys?count=3&size=8").unwrap();
+    let mut resource = match RandomScheme::random().open(url, O_NONBLOCK) {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+
+    let mut path = [0; 64];
+    let count = resource.path(&mut path).unwrap_or(0);
+    test!(&path[..count] == b"random:keys?count=3&size=8");
+
+    // Every key is fresh, and there are no more after the last one
+    let mut keys = [0; 32];
+    test!(resource.read(&mut keys[..20]).ok() == Some(20));
+    test!(resource.read(&mut keys[20..]).ok() == Some(4));
+    test!(resource.read(&mut keys).ok() == Some(0));
+    test!(&keys[0..8] != &keys[8..16] && &keys[8..16] != &keys[16..24]);
+    succ!();
+}
+
+pub fn seeded() -> bool {
+    use fs::{KScheme, Url};
+    use schemes::test_random::{parse_seed, TestRandomScheme};
+
+    test!(parse_seed("").ok() == Some(0));
+    test!(parse_seed("//42").ok() == Some(42));
+    test!(parse_seed("abc").is_err());
+
+    let mut first = match TestRandomScheme.open(Url::from_str("test-random:42").unwrap(), 0) {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+    let mut second = match TestRandomScheme.open(Url::from_str("test-random:42").unwrap(), 0) {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+    let mut other = match TestRandomScheme.open(Url::from_str("test-random:43").unwrap(), 0) {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+
+    // The same seed gives the same stream, however it is split into reads
+    let mut a = [0; 100];
+    let mut b = [0; 100];
+    let mut c = [0; 100];
+    test!(first.read(&mut a).ok() == Some(100));
+    test!(second.read(&mut b[..30]).ok() == Some(30));
+    test!(second.read(&mut b[30..]).ok() == Some(70));
+    test!(other.read(&mut c).ok() == Some(100));
+    test!(&a[..] == &b[..]);
+    test!(&a[..] != &c[..]);
+
+    // A duplicate continues where the original is
+    let mut dup = match first.dup() {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+    test!(first.read(&mut a[..10]).ok() == Some(10));
+    test!(dup.read(&mut b[..10]).ok() == Some(10));
+    test!(&a[..10] == &b[..10]);
+    succ!();
+}
+
+pub fn stats() -> bool {
+    use collections::{String, Vec};
+    use common::random::{self, EntropyPool, REKEY_BYTES};
+    use fs::Url;
+
+    let mut pool = EntropyPool::new();
+    test!(pool.stats().served == 0 && pool.stats().rekeys == 0 && pool.stats().forks == 0);
+
+    let mut buf = [0; 48];
+    pool.fill(&mut buf);
+    pool.add_entropy(&[1; REKEY_BYTES], 8);
+    pool.fork(2);
+    let stats = pool.stats();
+    test!(stats.served == 48);
+    test!(stats.rekeys == 1);
+    test!(stats.forks == 1);
+    test!(stats.entropy == 8);
+
+    // The scheme reports the counters of the kernel pool
+    let served = random::stats().served;
+    let mut resource = match Url::from_str("urandom://stats").unwrap().open() {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+    let mut text = Vec::new();
+    test!(resource.read_to_end(&mut text).is_ok());
+    let text = String::from_utf8(text).unwrap();
+    test!(text.starts_with("Entropy: "));
+    test!(text.contains(&format!("Served: {} bytes\n", served)));
+    test!(text.contains("Rekeys: ") && text.contains("Forks: "));
+    succ!();
+}
+
+pub fn rekey() -> bool {
+    use collections::{String, Vec};
+    use common::random::{self, Csprng};
+    use fs::{Resource, ResourceSeek, Url};
+
+    fn generator() -> Csprng {
+        Csprng::from_parts([1, 2, 3, 4, 5, 6, 7, 8], 0, [9, 10])
+    }
+
+    fn count(resource: &mut Resource) -> Option<usize> {
+        let mut text = Vec::new();
+        if resource.seek(ResourceSeek::Start(0)).is_err() ||
+           resource.read_to_end(&mut text).is_err() {
+            return None;
+        }
+        String::from_utf8(text).ok().and_then(|text| text.trim_right().parse::<usize>().ok())
+    }
+
+    // Erasing discards the rest of the buffered block, and is deterministic
+    let mut erased = generator();
+    let mut kept = generator();
+    let mut again = generator();
+    let mut a = [0; 48];
+    let mut b = [0; 48];
+    let mut c = [0; 48];
+    erased.next_block(&mut a[..16]);
+    kept.next_block(&mut b[..16]);
+    again.next_block(&mut c[..16]);
+    erased.erase();
+    again.erase();
+    erased.next_block(&mut a);
+    kept.next_block(&mut b);
+    again.next_block(&mut c);
+    test!(a != b);
+    test!(a == c);
+
+    // Anyone can erase the key of the kernel CSPRNG, and see the count go up
+    let mut resource = match Url::from_str("urandom://rekey").unwrap().open() {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+    let before = match count(&mut *resource) {
+        Some(before) => before,
+        None => fail!(),
+    };
+    test!(before == random::stats().erasures);
+    test!(resource.write(b"rekey").ok() == Some(5));
+    test!(count(&mut *resource) == Some(before + 1));
+    test!(random::stats().erasures == before + 1);
+
+    let mut path = [0; 16];
+    test!(resource.path(&mut path).ok() == Some(13));
+    test!(&path[..13] == b"urandom:rekey");
+    succ!();
+}
+
+pub fn ready() -> bool {
+    use common::random;
+    use fs::Url;
+    use system::error::EAGAIN;
+
+    // Without entropy yet, a nonblocking barrier cannot be opened
+    let opened = Url::from_str("urandom://ready?nonblock").unwrap().open();
+    if !random::ready() {
+        test!(opened.err().map(|err| err.errno) == Some(EAGAIN));
+        succ!();
+    }
+
+    let mut resource = match opened {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+    let served = random::stats().served;
+    let mut buf = [0; 4];
+    test!(resource.is_readable());
+    test!(resource.read(&mut buf).ok() == Some(1));
+    test!(buf == [1, 0, 0, 0]);
+    test!(resource.read(&mut buf[..0]).ok() == Some(0));
+
+    let mut path = [0; 16];
+    test!(resource.path(&mut path).ok() == Some(13));
+    test!(&path[..13] == b"urandom:ready");
+
+    // Once seeded, the blocking barrier returns at once, and neither takes from the CSPRNG
+    let mut resource = match Url::from_str("random://ready").unwrap().open() {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+    test!(resource.read(&mut buf).ok() == Some(1));
+    test!(buf[0] == 1);
+    test!(random::stats().served == served);
+    succ!();
+}
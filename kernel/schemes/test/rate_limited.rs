@@ -0,0 +1,40 @@
+pub fn throughput() -> bool {
+    use collections::Vec;
+    use collections::string::ToString;
+    use fs::{RateLimited, Resource, VecResource};
+    use schemes::pipe::{PipeRead, PipeWrite};
+    use schemes::test::helpers::with_ticks;
+    use system::error::{EAGAIN, EINVAL};
+
+    let resource = box VecResource::new("test:".to_string(), Vec::new());
+    test!(RateLimited::new(resource, 0, true).err().map(|err| err.errno) == Some(EINVAL));
+
+    with_ticks(|| {
+        let mut read = PipeRead::new();
+        let mut write = match RateLimited::new(box PipeWrite::new(&read), 4, true) {
+            Ok(write) => write,
+            Err(_) => fail!(),
+        };
+
+        // No more than 4 bytes are written per tick, with ticks simulated
+        let start = unsafe { *::env().ticks.get() };
+        for _ in 0..3 {
+            test!(write.is_writable());
+            test!(write.write(b"abc").ok() == Some(3));
+            test!(write.write(b"abcdef").ok() == Some(1));
+            test!(!write.is_writable());
+            test!(write.write(b"abcdef").err().map(|err| err.errno) == Some(EAGAIN));
+            unsafe { *::env().ticks.get() += 1 };
+        }
+        test!(unsafe { *::env().ticks.get() } - start == 3);
+
+        let mut buf = [0; 16];
+        test!(read.read(&mut buf).ok() == Some(12));
+        test!(&buf[..12] == b"abcaabcaabca");
+
+        // The budget does not build up over idle ticks
+        unsafe { *::env().ticks.get() += 5 };
+        test!(write.write(b"abcdef").ok() == Some(4));
+        succ!();
+    })
+}
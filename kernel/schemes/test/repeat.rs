@@ -0,0 +1,24 @@
+pub fn pattern() -> bool {
+    use fs::{KScheme, Url};
+    use schemes::repeat::RepeatScheme;
+
+    let mut yes = match RepeatScheme.open(Url::from_str("repeat:").unwrap(), 0) {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+    let mut buf = [0; 5];
+    test!(yes.read(&mut buf).ok() == Some(5));
+    test!(&buf == b"y\ny\ny");
+
+    // The pattern carries on across reads
+    let mut abc = match RepeatScheme.open(Url::from_str("repeat://abc").unwrap(), 0) {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+    test!(abc.read(&mut buf).ok() == Some(5));
+    test!(&buf == b"abcab");
+    test!(abc.read(&mut buf[..2]).ok() == Some(2));
+    test!(&buf[..2] == b"ca");
+    test!(abc.read(&mut []).ok() == Some(0));
+    succ!();
+}
@@ -0,0 +1,75 @@
+use collections::Vec;
+
+use fs::Resource;
+
+use system::error::Result;
+
+/// A resource which reads and writes at most `chunk` bytes at a time
+struct ShortResource {
+    data: Vec<u8>,
+    pos: usize,
+    chunk: usize,
+}
+
+impl Resource for ShortResource {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let mut i = 0;
+        while i < buf.len() && i < self.chunk && self.pos < self.data.len() {
+            buf[i] = self.data[self.pos];
+            self.pos += 1;
+            i += 1;
+        }
+        Ok(i)
+    }
+
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let count = if buf.len() < self.chunk {
+            buf.len()
+        } else {
+            self.chunk
+        };
+        self.data.extend_from_slice(&buf[..count]);
+        Ok(count)
+    }
+}
+
+pub fn read_exact() -> bool {
+    use system::error::EIO;
+
+    let mut resource = ShortResource {
+        data: b"abcdefg".to_vec(),
+        pos: 0,
+        chunk: 2,
+    };
+
+    // Short reads are retried until the buffer is full
+    let mut buf = [0; 5];
+    test!(resource.read_exact(&mut buf).is_ok());
+    test!(&buf == b"abcde");
+
+    // The end of the resource is an error
+    let mut buf = [0; 3];
+    test!(resource.read_exact(&mut buf).err().map(|err| err.errno) == Some(EIO));
+    test!(resource.read_exact(&mut []).is_ok());
+    succ!();
+}
+
+pub fn write_all() -> bool {
+    use system::error::EIO;
+
+    let mut resource = ShortResource {
+        data: Vec::new(),
+        pos: 0,
+        chunk: 3,
+    };
+
+    // Short writes are retried until everything is written
+    test!(resource.write_all(b"abcdefgh").is_ok());
+    test!(&resource.data[..] == b"abcdefgh");
+
+    // A write which accepts nothing is an error
+    resource.chunk = 0;
+    test!(resource.write_all(b"i").err().map(|err| err.errno) == Some(EIO));
+    test!(resource.write_all(b"").is_ok());
+    succ!();
+}
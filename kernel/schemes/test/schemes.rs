@@ -0,0 +1,220 @@
+pub fn list() -> bool {
+    use collections::Vec;
+    use fs::{KScheme, Url};
+    use schemes::schemes::SchemesScheme;
+
+    let mut resource = match SchemesScheme.open(Url::from_str("schemes:").unwrap(), 0) {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+
+    let mut data = Vec::new();
+    let mut buf = [0; 16];
+    while let Ok(count) = resource.read(&mut buf) {
+        if count == 0 {
+            break;
+        }
+        data.extend_from_slice(&buf[..count]);
+    }
+
+    test!(data.split(|&b| b == b'\n').any(|name| name == b"schemes"));
+    test!(data.split(|&b| b == b'\n').any(|name| name == b"test"));
+    succ!();
+}
+
+pub fn registry() -> bool {
+    use fs::{KScheme, Url};
+    use schemes::null::NullScheme;
+    use system::error::{ENODEV, ENOENT};
+
+    struct RegistryScheme;
+
+    impl KScheme for RegistryScheme {
+        fn scheme(&self) -> &str {
+            "test_registry"
+        }
+    }
+
+    let env = ::env();
+
+    test!(env.register_scheme(box RegistryScheme).is_ok());
+    test!(env.register_scheme(box RegistryScheme).is_err());
+    test!(env.register_scheme(box NullScheme).is_err());
+    test!(env.scheme_list().lines().any(|name| name == "test_registry"));
+
+    test!(env.deregister_scheme("test_registry").is_ok());
+    test!(env.deregister_scheme("test_registry").is_err());
+    test!(!env.scheme_list().lines().any(|name| name == "test_registry"));
+
+    match env.open(Url::from_str("test_registry:").unwrap(), 0) {
+        Err(err) => test!(err.errno == ENODEV),
+        Ok(_) => fail!(),
+    }
+    match env.open(Url::from_str("test_unregistered:").unwrap(), 0) {
+        Err(err) => test!(err.errno == ENOENT),
+        Ok(_) => fail!(),
+    }
+    succ!();
+}
+
+pub fn aliases() -> bool {
+    use fs::Url;
+    use system::error::{EEXIST, ELOOP};
+
+    let env = ::env();
+
+    test!(env.alias_scheme("test_alias", "null").is_ok());
+    test!(env.alias_scheme("test_alias_2", "test_alias").is_ok());
+
+    // Aliases open the scheme they stand for, through other aliases
+    let mut resource = match env.open(Url::from_str("test_alias_2:").unwrap(), 0) {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+    let mut buf = [0; 16];
+    let count = match resource.path(&mut buf) {
+        Ok(count) => count,
+        Err(_) => fail!(),
+    };
+    test!(&buf[..count] == b"null:");
+
+    test!(env.alias_scheme("test_alias", "zero").err().map(|err| err.errno) == Some(EEXIST));
+    test!(env.alias_scheme("null", "zero").err().map(|err| err.errno) == Some(EEXIST));
+    test!(env.alias_scheme("test_alias_3", "test_alias_3").err().map(|err| err.errno) ==
+          Some(ELOOP));
+    test!(env.alias_scheme("test_alias_3", "test_alias_2").is_ok());
+    test!(env.alias_scheme("test_alias_4", "test_alias_3").is_ok());
+    test!(env.unalias_scheme("test_alias_3").is_ok());
+    test!(env.alias_scheme("test_alias_3", "test_alias_4").err().map(|err| err.errno) ==
+          Some(ELOOP));
+    test!(env.unalias_scheme("test_alias_4").is_ok());
+
+    test!(env.scheme_list().lines().any(|line| line == "test_alias_2 -> test_alias"));
+
+    test!(env.unalias_scheme("test_alias_2").is_ok());
+    test!(env.unalias_scheme("test_alias").is_ok());
+    test!(env.unalias_scheme("test_alias").is_err());
+    test!(env.open(Url::from_str("test_alias:").unwrap(), 0).is_err());
+    succ!();
+}
+
+pub fn pass_fd() -> bool {
+    use collections::Vec;
+    use core::{mem, slice};
+    use fs::{KScheme, Resource, Scheme, Url};
+    use syscall::fs::{close, open};
+    use system::error::{Error, Result, EBADF, EBUSY, EINVAL, ENOENT};
+    use system::scheme::Packet;
+    use system::syscall::{SCHEME_PASS_DUP, SCHEME_PASS_MOVE, SYS_OPEN};
+
+    // The provider runs in this context, so it replies before the request is sent
+    fn reply(server: &mut Box<Resource>, id: usize, result: Result<usize>) -> bool {
+        let mut packet = Packet::default();
+        packet.id = id;
+        packet.a = Error::mux(result);
+        server.write(unsafe {
+            slice::from_raw_parts(&packet as *const Packet as *const u8, mem::size_of::<Packet>())
+        }).is_ok()
+    }
+
+    fn path(resource: &Box<Resource>) -> Option<Vec<u8>> {
+        let mut buf = [0; 16];
+        resource.path(&mut buf).ok().map(|count| buf[..count].to_vec())
+    }
+
+    fn is_open(fd: usize) -> bool {
+        let contexts = unsafe { & *::env().contexts.get() };
+        contexts.current().and_then(|current| current.get_file(fd)).is_ok()
+    }
+
+    let (mut scheme, mut server) = match Scheme::new("test_pass") {
+        Ok(pair) => pair,
+        Err(_) => fail!(),
+    };
+    let url = Url::from_str("test_pass:file").unwrap();
+    let fd = match open(b"null:\0".as_ptr(), 0) {
+        Ok(fd) => fd,
+        Err(_) => fail!(),
+    };
+
+    // A duplicate is passed, and the provider keeps its descriptor
+    test!(server.control(SCHEME_PASS_DUP, fd).is_ok());
+    test!(server.control(SCHEME_PASS_DUP, fd).err().map(|err| err.errno) == Some(EBUSY));
+    test!(reply(&mut server, 1, Ok(0)));
+    match scheme.open(url, 0) {
+        Ok(resource) => test!(path(&resource) == Some(b"null:".to_vec())),
+        Err(_) => fail!(),
+    }
+    test!(is_open(fd));
+
+    // A failed request drops the descriptor
+    test!(server.control(SCHEME_PASS_DUP, fd).is_ok());
+    test!(reply(&mut server, 2, Err(Error::new(ENOENT))));
+    test!(scheme.open(url, 0).err().map(|err| err.errno) == Some(ENOENT));
+
+    // The descriptor itself is moved
+    test!(server.control(SCHEME_PASS_MOVE, fd).is_ok());
+    test!(reply(&mut server, 3, Ok(0)));
+    match scheme.open(url, 0) {
+        Ok(resource) => test!(path(&resource) == Some(b"null:".to_vec())),
+        Err(_) => fail!(),
+    }
+    test!(!is_open(fd));
+    test!(close(fd).err().map(|err| err.errno) == Some(EBADF));
+
+    test!(server.control(SCHEME_PASS_MOVE, fd).err().map(|err| err.errno) == Some(EBADF));
+    test!(server.control(0, 0).err().map(|err| err.errno) == Some(EINVAL));
+
+    // The provider saw every request
+    let mut packet = Packet::default();
+    test!(server.read(unsafe {
+        slice::from_raw_parts_mut(&mut packet as *mut Packet as *mut u8, mem::size_of::<Packet>())
+    }).ok() == Some(mem::size_of::<Packet>()));
+    test!(packet.id == 1 && packet.a == SYS_OPEN);
+    succ!();
+}
+
+pub fn handles() -> bool {
+    use core::{mem, slice};
+    use fs::{KScheme, Resource, ResourceSeek, Scheme, Url};
+    use system::error::Error;
+    use system::scheme::Packet;
+    use system::syscall::{SEEK_SET, SYS_CLOSE, SYS_LSEEK, SYS_OPEN};
+
+    // The provider runs in this context, so it replies before the request is sent
+    fn reply(server: &mut Box<Resource>, id: usize, value: usize) -> bool {
+        let mut packet = Packet::default();
+        packet.id = id;
+        packet.a = Error::mux(Ok(value));
+        server.write(unsafe {
+            slice::from_raw_parts(&packet as *const Packet as *const u8, mem::size_of::<Packet>())
+        }).is_ok()
+    }
+
+    let (mut scheme, mut server) = match Scheme::new("test_handles") {
+        Ok(pair) => pair,
+        Err(_) => fail!(),
+    };
+
+    // The provider picks the handle of the opened resource
+    test!(reply(&mut server, 1, 7));
+    let mut resource = match scheme.open(Url::from_str("test_handles:file").unwrap(), 0x4) {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+    test!(reply(&mut server, 2, 42));
+    test!(resource.seek(ResourceSeek::Start(42)).ok() == Some(42));
+    test!(reply(&mut server, 3, 0));
+    drop(resource);
+
+    // Every request on the resource carries the handle
+    let mut packets = [Packet::default(); 3];
+    test!(server.read(unsafe {
+        slice::from_raw_parts_mut(packets.as_mut_ptr() as *mut u8, mem::size_of::<[Packet; 3]>())
+    }).ok() == Some(mem::size_of::<[Packet; 3]>()));
+    test!(packets[0].id == 1 && packets[0].a == SYS_OPEN && packets[0].c == 0x4);
+    test!(packets[1].id == 2 && packets[1].a == SYS_LSEEK && packets[1].b == 7 &&
+          packets[1].c == 42 && packets[1].d == SEEK_SET);
+    test!(packets[2].id == 3 && packets[2].a == SYS_CLOSE && packets[2].b == 7);
+    succ!();
+}
@@ -0,0 +1,114 @@
+pub fn lifetime() -> bool {
+    use core::slice;
+    use fs::{KScheme, Url};
+    use schemes::shm::ShmScheme;
+    use schemes::test::helpers::install;
+    use syscall::fs::{close, fmap};
+    use system::error::ENOENT;
+    use system::syscall::{MAP_WRITE, O_CREAT, SHM_PERSIST};
+
+    let mut scheme = ShmScheme::new();
+    let url = Url::from_str("shm:lifetime").unwrap();
+
+    // A segment is freed with its last handle
+    match scheme.open(url, O_CREAT) {
+        Ok(mut resource) => test!(resource.truncate(4096).is_ok()),
+        Err(_) => fail!(),
+    }
+    test!(scheme.open(url, 0).err().map(|err| err.errno) == Some(ENOENT));
+
+    // Unless it is persistent
+    {
+        let mut resource = match scheme.open(url, O_CREAT) {
+            Ok(resource) => resource,
+            Err(_) => fail!(),
+        };
+        test!(resource.truncate(4096).is_ok());
+        test!(resource.control(SHM_PERSIST, 1).ok() == Some(0));
+        let fd = match install(resource) {
+            Some(fd) => fd,
+            None => fail!(),
+        };
+        let address = fmap(fd, 4096, MAP_WRITE);
+        test!(close(fd).is_ok());
+        match address {
+            Ok(address) => unsafe { slice::from_raw_parts_mut(address as *mut u8, 4096) }[0] = 42,
+            Err(_) => fail!(),
+        }
+    }
+    let fd = match scheme.open(url, 0).ok().and_then(install) {
+        Some(fd) => fd,
+        None => fail!(),
+    };
+    let address = fmap(fd, 4096, 0);
+    test!(close(fd).is_ok());
+    match address {
+        Ok(address) => test!(unsafe { slice::from_raw_parts(address as *const u8, 4096) }[0] == 42),
+        Err(_) => fail!(),
+    }
+
+    // Until it is unlinked
+    test!(scheme.unlink(url).is_ok());
+    test!(scheme.open(url, 0).err().map(|err| err.errno) == Some(ENOENT));
+    test!(scheme.unlink(url).err().map(|err| err.errno) == Some(ENOENT));
+    succ!();
+}
+
+pub fn share() -> bool {
+    use core::slice;
+    use syscall::fs::{close, fmap, ftruncate, open};
+    use system::error::{EBUSY, EEXIST, EINVAL, ENOENT};
+    use system::syscall::{MAP_WRITE, O_CREAT, O_EXCL};
+
+    /// Map the segment behind both file descriptors, which must be empty
+    fn shared(first: usize, second: usize) -> bool {
+        // An empty segment can not be mapped
+        test!(fmap(first, 4096, MAP_WRITE).err().map(|err| err.errno) == Some(EINVAL));
+        test!(ftruncate(first, 5000).is_ok());
+        test!(fmap(first, 0, MAP_WRITE).err().map(|err| err.errno) == Some(EINVAL));
+        test!(fmap(first, 8193, MAP_WRITE).err().map(|err| err.errno) == Some(EINVAL));
+
+        // Both handles map the same pages
+        let first_address = match fmap(first, 5000, MAP_WRITE) {
+            Ok(address) => address,
+            Err(_) => fail!(),
+        };
+        let second_address = match fmap(second, 5000, 0) {
+            Ok(address) => address,
+            Err(_) => fail!(),
+        };
+        test!(first_address != second_address);
+        let first_mapping = unsafe { slice::from_raw_parts_mut(first_address as *mut u8, 5000) };
+        let second_mapping = unsafe { slice::from_raw_parts(second_address as *const u8, 5000) };
+        test!(second_mapping.iter().all(|&b| b == 0));
+        first_mapping[0] = b'a';
+        first_mapping[4999] = b'z';
+        test!(second_mapping[0] == b'a');
+        test!(second_mapping[4999] == b'z');
+
+        // The pages can not move while they are mapped
+        test!(ftruncate(second, 8192).err().map(|err| err.errno) == Some(EBUSY));
+        succ!();
+    }
+
+    let path = b"shm:test_share\0";
+    test!(open(path.as_ptr(), 0).err().map(|err| err.errno) == Some(ENOENT));
+    let first = match open(b"shm:test_share?create\0".as_ptr(), 0) {
+        Ok(fd) => fd,
+        Err(_) => fail!(),
+    };
+    test!(open(path.as_ptr(), O_CREAT | O_EXCL).err().map(|err| err.errno) == Some(EEXIST));
+    let second = match open(path.as_ptr(), 0) {
+        Ok(fd) => fd,
+        Err(_) => fail!(),
+    };
+
+    let result = shared(first, second);
+    test!(close(first).is_ok());
+    test!(close(second).is_ok());
+    test!(result);
+
+    // The segment is freed with its last handle, while the mappings keep the pages
+    test!(open(path.as_ptr(), 0).err().map(|err| err.errno) == Some(ENOENT));
+    succ!();
+}
@@ -0,0 +1,38 @@
+pub fn duration() -> bool {
+    use common::time::{Duration, NANOS_PER_MILLI};
+    use fs::{KScheme, Url};
+    use schemes::sleep::{parse_duration, SleepScheme};
+    use system::error::{EAGAIN, EINVAL};
+
+    test!(parse_duration("//500ms").ok() == Some(Duration::new(0, 500 * NANOS_PER_MILLI)));
+    test!(parse_duration("2s").ok() == Some(Duration::new(2, 0)));
+    test!(parse_duration("3").ok() == Some(Duration::new(3, 0)));
+    test!(parse_duration("1500ms").ok() == Some(Duration::new(1, 500 * NANOS_PER_MILLI)));
+    for bad in ["", "ms", "s", "-1", "1.5s", "10m", "soon"].iter() {
+        test!(parse_duration(bad).err().map(|err| err.errno) == Some(EINVAL));
+    }
+    test!(SleepScheme.open(Url::from_str("sleep:later").unwrap(), 0)
+                     .err()
+                     .map(|err| err.errno) == Some(EINVAL));
+
+    // A read waits for at least the duration
+    let mut resource = match SleepScheme.open(Url::from_str("sleep://20ms").unwrap(), 0) {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+    let start = Duration::monotonic();
+    test!(resource.read(&mut [0; 4]).ok() == Some(0));
+    test!(Duration::monotonic() - start >= Duration::new(0, 20 * NANOS_PER_MILLI));
+
+    let mut path = [0; 16];
+    test!(resource.path(&mut path).ok() == Some(10));
+    test!(&path[..10] == b"sleep:20ms");
+
+    // Unless it does not block
+    let mut resource = match SleepScheme.open(Url::from_str("sleep:10s?nonblock").unwrap(), 0) {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+    test!(resource.read(&mut [0; 4]).err().map(|err| err.errno) == Some(EAGAIN));
+    succ!();
+}
@@ -0,0 +1,1091 @@
+use schemes::test::helpers::{with_child, with_context};
+
+pub fn resupervise() -> bool {
+    use fs::SupervisorResource;
+
+    with_child("test_supervisor", |pid| {
+        let supervisor = match SupervisorResource::new(pid) {
+            Ok(supervisor) => supervisor,
+            Err(_) => fail!(),
+        };
+
+        // Only one supervisor at a time
+        test!(SupervisorResource::new(pid).is_err());
+
+        // Closing the supervisor detaches it
+        drop(supervisor);
+        test!(match unsafe { & *::env().contexts.get() }.find(pid) {
+            Ok(context) => !context.supervised && context.supervised_resource.is_none(),
+            Err(_) => false,
+        });
+
+        test!(SupervisorResource::new(pid).is_ok());
+        succ!();
+    })
+}
+
+pub fn errors() -> bool {
+    use fs::SupervisorResource;
+    use system::error::{EACCES, EPERM, ESRCH};
+
+    match SupervisorResource::new(usize::max_value()) {
+        Err(err) => test!(err.errno == ESRCH),
+        Ok(_) => fail!(),
+    }
+
+    // Not a child of the current context
+    test!(with_context("test_supervisor", usize::max_value(), |pid| {
+        match SupervisorResource::new(pid) {
+            Err(err) => err.errno == EACCES,
+            Ok(_) => false,
+        }
+    }));
+
+    with_child("test_supervisor", |pid| {
+        let supervisor = SupervisorResource::new(pid);
+        test!(supervisor.is_ok());
+
+        // Already supervised
+        match SupervisorResource::new(pid) {
+            Err(err) => test!(err.errno == EPERM),
+            Ok(_) => fail!(),
+        }
+        succ!();
+    })
+}
+
+pub fn supervisor_gone() -> bool {
+    use arch::regs::Regs;
+    use fs::SupervisorResource;
+    use system::scheme::Packet;
+
+    with_child("test_supervisor", |pid| {
+        let supervisor = match SupervisorResource::new(pid) {
+            Ok(supervisor) => supervisor,
+            Err(_) => fail!(),
+        };
+
+        // Keep the tracee end, as a stopped tracee would
+        let tracee = match unsafe { & *::env().contexts.get() }.find(pid) {
+            Ok(context) => match context.supervised_resource.clone() {
+                Some(tracee) => tracee,
+                None => fail!(),
+            },
+            Err(_) => fail!(),
+        };
+        test!(tracee.is_attached());
+
+        drop(supervisor);
+        test!(!tracee.is_attached());
+
+        // The tracee resumes, running the syscall, instead of waiting for a supervisor
+        let mut regs = Regs::default();
+        regs.flags = 0x100;
+        test!(!tracee.trap(Packet::default(), &mut regs));
+        test!(regs.flags & 0x100 == 0);
+        succ!();
+    })
+}
+
+pub fn tracee_gone() -> bool {
+    use core::{mem, slice};
+    use fs::{Resource, SupervisorResource};
+    use system::error::ESRCH;
+    use system::scheme::Packet;
+
+    with_child("test_supervisor", |pid| {
+        let mut supervisor = match SupervisorResource::new(pid) {
+            Ok(supervisor) => supervisor,
+            Err(_) => fail!(),
+        };
+
+        // The tracee exits, and its tracee end goes away
+        let tracee = match unsafe { &mut *::env().contexts.get() }.find_mut(pid) {
+            Ok(context) => match context.supervised_resource.take() {
+                Some(tracee) => tracee,
+                None => fail!(),
+            },
+            Err(_) => fail!(),
+        };
+        tracee.exit(0);
+        drop(tracee);
+
+        let mut packet = Packet::default();
+        let buf = unsafe {
+            slice::from_raw_parts_mut(&mut packet as *mut Packet as *mut u8, mem::size_of::<Packet>())
+        };
+
+        // The exit event is still read, after which reads fail instead of waiting forever
+        test!(supervisor.read(buf).ok() == Some(mem::size_of::<Packet>()));
+        test!(packet.id == pid);
+        test!(supervisor.read(buf).err().map(|err| err.errno) == Some(ESRCH));
+        succ!();
+    })
+}
+
+pub fn mask() -> bool {
+    use core::{mem, slice};
+    use fs::{Resource, SupervisorResource};
+    use system::error::EINVAL;
+    use system::scheme::Packet;
+    use system::syscall::{SUPERVISE_MASK, SUPERVISE_MASK_ALL, SUPERVISE_MASK_SYSCALL,
+                          SUPERVISE_MASK_SYSCALL_EXIT};
+
+    fn set_mask(supervisor: &mut SupervisorResource, mask: usize) -> ::system::error::Result<usize> {
+        let mut packet = Packet::default();
+        packet.id = SUPERVISE_MASK;
+        packet.a = mask;
+        supervisor.write(unsafe {
+            slice::from_raw_parts(&packet as *const Packet as *const u8, mem::size_of::<Packet>())
+        })
+    }
+
+    fn stops_for(pid: usize, class: usize) -> bool {
+        match unsafe { & *::env().contexts.get() }.find(pid) {
+            Ok(context) => context.supervised_resource.as_ref().map_or(false, |tracee| {
+                tracee.stops_for(class)
+            }),
+            Err(_) => false,
+        }
+    }
+
+    with_child("test_supervisor", |pid| {
+        let mut supervisor = match SupervisorResource::new(pid) {
+            Ok(supervisor) => supervisor,
+            Err(_) => fail!(),
+        };
+
+        // Every event stops the tracee by default
+        test!(stops_for(pid, SUPERVISE_MASK_ALL));
+
+        // The mask can be changed while the tracee is running
+        test!(set_mask(&mut supervisor, SUPERVISE_MASK_SYSCALL).is_ok());
+        test!(stops_for(pid, SUPERVISE_MASK_SYSCALL));
+        test!(!stops_for(pid, SUPERVISE_MASK_SYSCALL_EXIT));
+
+        match set_mask(&mut supervisor, SUPERVISE_MASK_ALL + 1) {
+            Err(err) => test!(err.errno == EINVAL),
+            Ok(_) => fail!(),
+        }
+        test!(stops_for(pid, SUPERVISE_MASK_SYSCALL));
+        succ!();
+    })
+}
+
+pub fn exit() -> bool {
+    use core::{mem, slice};
+    use fs::{Resource, SupervisorResource};
+    use system::scheme::{Packet, PACKET_MAGIC};
+    use system::syscall::SUPERVISE_EVENT_EXIT;
+
+    with_child("test_supervisor", |pid| {
+        let mut supervisor = match SupervisorResource::new(pid) {
+            Ok(supervisor) => supervisor,
+            Err(_) => fail!(),
+        };
+
+        let tracee = match unsafe { & *::env().contexts.get() }.find(pid) {
+            Ok(context) => match context.supervised_resource.clone() {
+                Some(tracee) => tracee,
+                None => fail!(),
+            },
+            Err(_) => fail!(),
+        };
+        tracee.exit(42);
+
+        let mut packet = Packet::default();
+        test!(supervisor.read(unsafe {
+            slice::from_raw_parts_mut(&mut packet as *mut Packet as *mut u8, mem::size_of::<Packet>())
+        }).ok() == Some(mem::size_of::<Packet>()));
+        test!(packet.id == pid);
+        test!(packet.a == SUPERVISE_EVENT_EXIT);
+        test!(packet.b == 42);
+        test!(packet.magic == PACKET_MAGIC);
+        succ!();
+    })
+}
+
+pub fn setargs() -> bool {
+    use arch::regs::Regs;
+    use core::{mem, slice};
+    use fs::{Resource, SupervisorResource};
+    use system::scheme::Packet;
+    use system::syscall::{SUPERVISE_CONTINUE, SUPERVISE_SETARGS, SYS_WRITE};
+
+    fn command(supervisor: &mut SupervisorResource, id: usize, a: usize, b: usize, c: usize) -> bool {
+        let mut packet = Packet::default();
+        packet.id = id;
+        packet.a = a;
+        packet.b = b;
+        packet.c = c;
+        supervisor.write(unsafe {
+            slice::from_raw_parts(&packet as *const Packet as *const u8, mem::size_of::<Packet>())
+        }).is_ok()
+    }
+
+    with_child("test_supervisor", |pid| {
+        let mut supervisor = match SupervisorResource::new(pid) {
+            Ok(supervisor) => supervisor,
+            Err(_) => fail!(),
+        };
+
+        // Queue the commands as if the tracee was already stopped at its syscall
+        let tracee = match unsafe { &mut *::env().contexts.get() }.find_mut(pid) {
+            Ok(context) => {
+                context.blocked_syscall = true;
+                match context.supervised_resource.clone() {
+                    Some(tracee) => tracee,
+                    None => fail!(),
+                }
+            },
+            Err(_) => fail!(),
+        };
+        test!(command(&mut supervisor, SUPERVISE_SETARGS, 4, 5, 6));
+        test!(command(&mut supervisor, SUPERVISE_CONTINUE, 0, 0, 0));
+
+        let mut regs = Regs::default();
+        regs.ax = SYS_WRITE;
+        regs.bx = 1;
+        regs.cx = 2;
+        regs.dx = 3;
+        test!(!tracee.trap(regs.into(), &mut regs));
+        test!(regs.ax == SYS_WRITE);
+        test!(regs.bx == 4 && regs.cx == 5 && regs.dx == 6);
+
+        // The stop carried the syscall and its original arguments
+        let mut packet = Packet::default();
+        test!(supervisor.read(unsafe {
+            slice::from_raw_parts_mut(&mut packet as *mut Packet as *mut u8, mem::size_of::<Packet>())
+        }).is_ok());
+        test!(packet.id == pid && packet.a == SYS_WRITE);
+        test!(packet.b == 1 && packet.c == 2 && packet.d == 3);
+        succ!();
+    })
+}
+
+pub fn filter() -> bool {
+    use core::{mem, slice};
+    use collections::Vec;
+    use fs::{Resource, SupervisorResource};
+    use system::error::{EACCES, EINVAL};
+    use system::scheme::Packet;
+    use system::syscall::{SUPERVISE_FILTER, SUPERVISE_FILTER_ALLOW, SUPERVISE_FILTER_DENY,
+                          SUPERVISE_FILTER_TRAP, SYS_OPEN, SYS_READ, SYS_WRITE, SuperviseRule};
+
+    fn set_filter(supervisor: &mut SupervisorResource, rules: &[SuperviseRule])
+                  -> ::system::error::Result<usize> {
+        let mut packet = Packet::default();
+        packet.id = SUPERVISE_FILTER;
+        packet.a = SUPERVISE_FILTER_TRAP;
+
+        let mut buf = Vec::new();
+        buf.extend_from_slice(unsafe {
+            slice::from_raw_parts(&packet as *const Packet as *const u8, mem::size_of::<Packet>())
+        });
+        buf.extend_from_slice(unsafe {
+            slice::from_raw_parts(rules.as_ptr() as *const u8,
+                                  rules.len() * mem::size_of::<SuperviseRule>())
+        });
+        supervisor.write(&buf)
+    }
+
+    fn action(pid: usize, syscall: usize) -> Option<(usize, usize)> {
+        match unsafe { & *::env().contexts.get() }.find(pid) {
+            Ok(context) => context.supervised_resource.as_ref().map(|tracee| {
+                let rule = tracee.rule(syscall);
+                (rule.action, rule.errno)
+            }),
+            Err(_) => None,
+        }
+    }
+
+    with_child("test_supervisor", |pid| {
+        let mut supervisor = match SupervisorResource::new(pid) {
+            Ok(supervisor) => supervisor,
+            Err(_) => fail!(),
+        };
+
+        // Without a filter, every syscall stops
+        test!(action(pid, SYS_WRITE) == Some((SUPERVISE_FILTER_TRAP, 0)));
+
+        let rules = [
+            SuperviseRule { syscall: SYS_WRITE, action: SUPERVISE_FILTER_ALLOW, errno: 0 },
+            SuperviseRule { syscall: SYS_OPEN, action: SUPERVISE_FILTER_DENY, errno: EACCES as usize },
+        ];
+        test!(set_filter(&mut supervisor, &rules).is_ok());
+        test!(action(pid, SYS_WRITE) == Some((SUPERVISE_FILTER_ALLOW, 0)));
+        test!(action(pid, SYS_OPEN) == Some((SUPERVISE_FILTER_DENY, EACCES as usize)));
+        test!(action(pid, SYS_READ) == Some((SUPERVISE_FILTER_TRAP, 0)));
+
+        // A deny needs an errno, and the old filter is kept
+        let rules = [SuperviseRule { syscall: SYS_READ, action: SUPERVISE_FILTER_DENY, errno: 0 }];
+        match set_filter(&mut supervisor, &rules) {
+            Err(err) => test!(err.errno == EINVAL),
+            Ok(_) => fail!(),
+        }
+        test!(action(pid, SYS_WRITE) == Some((SUPERVISE_FILTER_ALLOW, 0)));
+
+        // No rules removes the filter
+        test!(set_filter(&mut supervisor, &[]).is_ok());
+        test!(action(pid, SYS_WRITE) == Some((SUPERVISE_FILTER_TRAP, 0)));
+        succ!();
+    })
+}
+
+pub fn cycles() -> bool {
+    use fs::SupervisorResource;
+    use system::error::{EACCES, EINVAL};
+
+    let contexts = unsafe { & *::env().contexts.get() };
+    let (cur_pid, cur_ppid) = match contexts.current() {
+        Ok(context) => (context.pid, context.ppid),
+        Err(_) => fail!(),
+    };
+
+    // A context can not supervise itself
+    match SupervisorResource::new(cur_pid) {
+        Err(err) => test!(err.errno == EINVAL),
+        Ok(_) => fail!(),
+    }
+
+    // Nor its parent
+    if cur_ppid != cur_pid && contexts.find(cur_ppid).is_ok() {
+        match SupervisorResource::new(cur_ppid) {
+            Err(err) => test!(err.errno == EACCES),
+            Ok(_) => fail!(),
+        }
+    }
+    succ!();
+}
+
+pub fn observe() -> bool {
+    use core::{mem, slice};
+    use fs::{Resource, SupervisorResource};
+    use fs::supervisor_resource::notify_observers;
+    use system::error::{EACCES, EAGAIN};
+    use system::scheme::Packet;
+    use system::syscall::{F_SETFL, O_NONBLOCK};
+
+    fn read_packet(observer: &mut SupervisorResource) -> ::system::error::Result<Packet> {
+        let mut packet = Packet::default();
+        try!(observer.read(unsafe {
+            slice::from_raw_parts_mut(&mut packet as *mut Packet as *mut u8, mem::size_of::<Packet>())
+        }));
+        Ok(packet)
+    }
+
+    with_child("test_supervisor", |pid| {
+        let mut first = match SupervisorResource::observe(pid) {
+            Ok(observer) => observer,
+            Err(_) => fail!(),
+        };
+        let mut second = match SupervisorResource::observe(pid) {
+            Ok(observer) => observer,
+            Err(_) => fail!(),
+        };
+        test!(first.fcntl(F_SETFL, O_NONBLOCK).is_ok());
+        test!(second.fcntl(F_SETFL, O_NONBLOCK).is_ok());
+
+        // Observers do not count as supervisors
+        let supervisor = SupervisorResource::new(pid);
+        test!(supervisor.is_ok());
+
+        // Observers can not command the tracee
+        let packet = Packet::default();
+        test!(first.write(unsafe {
+            slice::from_raw_parts(&packet as *const Packet as *const u8, mem::size_of::<Packet>())
+        }).err().map(|err| err.errno) == Some(EACCES));
+        test!(first.write(&[0]).err().map(|err| err.errno) == Some(EACCES));
+
+        // Every observer reads a copy of each event
+        let mut event = Packet::default();
+        event.a = 20;
+        event.b = 1;
+        match unsafe { &mut *::env().contexts.get() }.find_mut(pid) {
+            Ok(context) => notify_observers(context, event),
+            Err(_) => fail!(),
+        }
+        for observer in [&mut first, &mut second].iter_mut() {
+            match read_packet(observer) {
+                Ok(packet) => test!(packet.id == pid && packet.a == 20 && packet.b == 1),
+                Err(_) => fail!(),
+            }
+            test!(read_packet(observer).err().map(|err| err.errno) == Some(EAGAIN));
+        }
+
+        // Closed observers are forgotten
+        drop(second);
+        match unsafe { &mut *::env().contexts.get() }.find_mut(pid) {
+            Ok(context) => {
+                notify_observers(context, event);
+                test!(context.observers.len() == 1);
+            },
+            Err(_) => fail!(),
+        }
+        succ!();
+    })
+}
+
+pub fn close() -> bool {
+    use fs::{Resource, SupervisorResource};
+
+    fn supervised(pid: usize) -> bool {
+        match unsafe { & *::env().contexts.get() }.find(pid) {
+            Ok(context) => context.supervised && context.supervised_resource.is_some(),
+            Err(_) => false,
+        }
+    }
+
+    with_child("test_supervisor", |pid| {
+        let mut supervisor = match SupervisorResource::new(pid) {
+            Ok(supervisor) => supervisor,
+            Err(_) => fail!(),
+        };
+
+        // Closing a duplicate keeps the tracee supervised
+        let mut dup = match supervisor.dup() {
+            Ok(dup) => dup,
+            Err(_) => fail!(),
+        };
+        test!(dup.close().is_ok());
+        drop(dup);
+        test!(supervised(pid));
+
+        // Closing the last one detaches the tracee before it is dropped
+        test!(supervisor.close().is_ok());
+        test!(!supervised(pid));
+        succ!();
+    })
+}
+
+pub fn control() -> bool {
+    use fs::{Resource, SupervisorResource};
+    use system::error::{EACCES, EINVAL};
+    use system::syscall::{F_GETFL, O_NONBLOCK, SUPERVISE_DETACH, SUPERVISE_MASK,
+                          SUPERVISE_MASK_ALL, SUPERVISE_NONBLOCK, SUPERVISE_SIGNAL};
+
+    with_child("test_supervisor", |pid| {
+        let mut supervisor = match SupervisorResource::new(pid) {
+            Ok(supervisor) => supervisor,
+            Err(_) => fail!(),
+        };
+
+        // Commands take the place of packets without a payload
+        test!(supervisor.control(SUPERVISE_NONBLOCK, 1).ok() == Some(0));
+        test!(supervisor.fcntl(F_GETFL, 0).ok() == Some(O_NONBLOCK));
+        test!(supervisor.control(SUPERVISE_MASK, SUPERVISE_MASK_ALL).ok() == Some(0));
+        test!(supervisor.control(SUPERVISE_MASK, !0).err().map(|err| err.errno) == Some(EINVAL));
+        test!(supervisor.control(SUPERVISE_SIGNAL, 0).err().map(|err| err.errno) == Some(EINVAL));
+        test!(supervisor.control(!0, 0).err().map(|err| err.errno) == Some(EINVAL));
+
+        // Observers can not command the tracee
+        let mut observer = match SupervisorResource::observe(pid) {
+            Ok(observer) => observer,
+            Err(_) => fail!(),
+        };
+        test!(observer.control(SUPERVISE_NONBLOCK, 1).err().map(|err| err.errno) == Some(EACCES));
+
+        test!(supervisor.control(SUPERVISE_DETACH, 0).ok() == Some(0));
+        test!(match unsafe { & *::env().contexts.get() }.find(pid) {
+            Ok(context) => !context.supervised,
+            Err(_) => false,
+        });
+        succ!();
+    })
+}
+
+pub fn legacy_write() -> bool {
+    use core::{mem, slice};
+    use fs::{Resource, SupervisorResource};
+    use system::error::{EBUSY, EINVAL};
+    use system::scheme::Packet;
+    use system::syscall::{SUPERVISE_LEGACY_WRITE, SUPERVISE_SETREGS};
+
+    with_child("test_supervisor", |pid| {
+        let mut supervisor = match SupervisorResource::new(pid) {
+            Ok(supervisor) => supervisor,
+            Err(_) => fail!(),
+        };
+
+        // Short writes are rejected, instead of being taken as a return value
+        test!(supervisor.write(&[1, 2]).err().map(|err| err.errno) == Some(EINVAL));
+
+        // Registers are only replaced whole
+        let mut packet = Packet::default();
+        packet.id = SUPERVISE_SETREGS;
+        let mut buf = unsafe {
+            slice::from_raw_parts(&packet as *const Packet as *const u8, mem::size_of::<Packet>())
+        }.to_vec();
+        buf.push(0);
+        test!(supervisor.write(&buf).err().map(|err| err.errno) == Some(EINVAL));
+
+        // Old supervisors can ask for the old behaviour, which needs a stopped tracee
+        test!(supervisor.control(SUPERVISE_LEGACY_WRITE, 1).is_ok());
+        test!(supervisor.write(&[1, 2]).err().map(|err| err.errno) == Some(EBUSY));
+        test!(supervisor.control(SUPERVISE_LEGACY_WRITE, 0).is_ok());
+        test!(supervisor.write(&[1, 2]).err().map(|err| err.errno) == Some(EINVAL));
+        succ!();
+    })
+}
+
+pub fn setregs() -> bool {
+    use arch::regs::Regs;
+    use core::{mem, slice};
+    use fs::{Resource, SupervisorResource};
+    use system::error::EPERM;
+    use system::scheme::Packet;
+    use system::syscall::{SUPERVISE_CONTINUE, SUPERVISE_SETREGS};
+
+    fn setregs(supervisor: &mut SupervisorResource, regs: &Regs) -> Option<usize> {
+        let mut packet = Packet::default();
+        packet.id = SUPERVISE_SETREGS;
+        let mut buf = unsafe {
+            slice::from_raw_parts(&packet as *const Packet as *const u8, mem::size_of::<Packet>())
+        }.to_vec();
+        buf.extend_from_slice(unsafe {
+            slice::from_raw_parts(regs as *const Regs as *const u8, mem::size_of::<Regs>())
+        });
+        supervisor.write(&buf).err().map(|err| err.errno)
+    }
+
+    with_child("test_supervisor", |pid| {
+        let mut supervisor = match SupervisorResource::new(pid) {
+            Ok(supervisor) => supervisor,
+            Err(_) => fail!(),
+        };
+
+        let mut regs = Regs::default();
+        regs.ax = 1;
+        regs.cs = 0x1B;
+        regs.ss = 0x23;
+        regs.flags = 0x202;
+
+        // Stop the tracee as if it was at a syscall
+        let tracee = match unsafe { &mut *::env().contexts.get() }.find_mut(pid) {
+            Ok(context) => {
+                context.blocked_syscall = true;
+                context.stopped_regs = Some(regs);
+                match context.supervised_resource.clone() {
+                    Some(tracee) => tracee,
+                    None => fail!(),
+                }
+            },
+            Err(_) => fail!(),
+        };
+
+        // Privileged flags and segment selectors can not be changed
+        let mut new_regs = regs;
+        new_regs.ax = 2;
+        new_regs.flags |= 0x3000;
+        test!(setregs(&mut supervisor, &new_regs) == Some(EPERM));
+        new_regs.flags = regs.flags & !0x200;
+        test!(setregs(&mut supervisor, &new_regs) == Some(EPERM));
+        new_regs.flags = regs.flags;
+        new_regs.cs = 0x8;
+        test!(setregs(&mut supervisor, &new_regs) == Some(EPERM));
+
+        // Arithmetic flags can
+        let mut new_regs = regs;
+        new_regs.ax = 3;
+        new_regs.flags |= 0x1;
+        test!(setregs(&mut supervisor, &new_regs) == None);
+
+        let mut packet = Packet::default();
+        packet.id = SUPERVISE_CONTINUE;
+        test!(supervisor.write(unsafe {
+            slice::from_raw_parts(&packet as *const Packet as *const u8, mem::size_of::<Packet>())
+        }).is_ok());
+
+        // Only the accepted registers were applied
+        let mut tracee_regs = regs;
+        test!(!tracee.trap(regs.into(), &mut tracee_regs));
+        test!(tracee_regs.ax == 3);
+        test!(tracee_regs.flags == 0x203);
+        test!(tracee_regs.cs == 0x1B && tracee_regs.ss == 0x23);
+        succ!();
+    })
+}
+
+pub fn interrupt() -> bool {
+    use arch::context::Context;
+    use core::{mem, slice};
+    use fs::{Resource, SupervisorResource};
+    use system::error::EINTR;
+    use system::scheme::Packet;
+    use system::syscall::SUPERVISE_INTERRUPT;
+
+    let cur_pid = match unsafe { & *::env().contexts.get() }.current() {
+        Ok(context) => context.pid,
+        Err(_) => fail!(),
+    };
+
+    with_child("test_supervisor", |pid| {
+        let mut supervisor = match SupervisorResource::new(pid) {
+            Ok(supervisor) => supervisor,
+            Err(_) => fail!(),
+        };
+        let mut canceler = match supervisor.dup() {
+            Ok(canceler) => canceler,
+            Err(_) => fail!(),
+        };
+
+        // Nothing is blocked yet, or blocked in another context
+        test!(canceler.control(SUPERVISE_INTERRUPT, 0).ok() == Some(0));
+
+        // The tracee never stops, so the read blocks until another thread cancels it
+        Context::spawn("test_interrupt".into(),
+                       box move || {
+                           let _ = canceler.control(SUPERVISE_INTERRUPT, cur_pid);
+                       });
+
+        let mut packet = Packet::default();
+        let buf = unsafe {
+            slice::from_raw_parts_mut(&mut packet as *mut Packet as *mut u8, mem::size_of::<Packet>())
+        };
+        test!(supervisor.read(buf).err().map(|err| err.errno) == Some(EINTR));
+        test!(supervisor.control(SUPERVISE_INTERRUPT, 0).ok() == Some(0));
+        succ!();
+    })
+}
+
+pub fn unaligned() -> bool {
+    use core::{mem, slice};
+    use fs::{Resource, SupervisorResource};
+    use system::scheme::Packet;
+    use system::syscall::{SUPERVISE_EVENT_EXIT, SUPERVISE_FILTER, SUPERVISE_FILTER_ALLOW,
+                          SUPERVISE_FILTER_DENY, SuperviseRule};
+
+    with_child("test_supervisor", |pid| {
+        let mut supervisor = match SupervisorResource::new(pid) {
+            Ok(supervisor) => supervisor,
+            Err(_) => fail!(),
+        };
+        let size = mem::size_of::<Packet>();
+        let rule_size = mem::size_of::<SuperviseRule>();
+
+        // Every buffer starts one byte after the start of `storage`
+        let mut storage = vec![0u8; 1 + size + rule_size];
+
+        // A filter written from a misaligned buffer
+        let mut packet = Packet::default();
+        packet.id = SUPERVISE_FILTER;
+        packet.a = SUPERVISE_FILTER_ALLOW;
+        let rule = SuperviseRule {
+            syscall: 42,
+            action: SUPERVISE_FILTER_DENY,
+            errno: 7,
+        };
+        storage[1..1 + size].copy_from_slice(&packet);
+        storage[1 + size..].copy_from_slice(unsafe {
+            slice::from_raw_parts(&rule as *const SuperviseRule as *const u8, rule_size)
+        });
+        test!(supervisor.write(&storage[1..]).ok() == Some(size + rule_size));
+
+        let tracee = match unsafe { & *::env().contexts.get() }.find(pid) {
+            Ok(context) => match context.supervised_resource.clone() {
+                Some(tracee) => tracee,
+                None => fail!(),
+            },
+            Err(_) => fail!(),
+        };
+        let (denied, allowed) = (tracee.rule(42), tracee.rule(43));
+        test!(denied.action == SUPERVISE_FILTER_DENY && denied.errno == 7);
+        test!(allowed.action == SUPERVISE_FILTER_ALLOW);
+
+        // An event read into a misaligned buffer
+        tracee.exit(5);
+        test!(supervisor.read(&mut storage[1..1 + size]).ok() == Some(size));
+        let mut event = Packet::default();
+        event.copy_from_slice(&storage[1..1 + size]);
+        test!(event.id == pid && event.a == SUPERVISE_EVENT_EXIT && event.b == 5);
+        succ!();
+    })
+}
+
+pub fn exit_policy() -> bool {
+    use arch::context::Context;
+    use arch::regs::Regs;
+    use core::{mem, slice};
+    use fs::{Resource, SupervisorResource};
+    use system::error::{EINVAL, EPERM};
+    use system::scheme::Packet;
+    use system::syscall::{SUPERVISE_CONTINUE, SUPERVISE_EVENT_EXIT, SUPERVISE_EXIT_POLICY,
+                          SUPERVISE_EXIT_REAP, SUPERVISE_GETREGS};
+
+    /// Spawn a child of the current context, which exits with `status` and `ax` once it runs
+    fn spawn(status: usize, ax: usize) -> Option<usize> {
+        let contexts = unsafe { &mut *::env().contexts.get() };
+        let cur_pid = match contexts.current() {
+            Ok(context) => context.pid,
+            Err(_) => return None,
+        };
+
+        let pid = Context::spawn("test_exit_policy".into(),
+                                 box move || {
+                                     let mut regs = Regs::default();
+                                     regs.ax = ax;
+                                     ::syscall::process::exit(status, &regs);
+                                 });
+        match contexts.find_mut(pid) {
+            Ok(context) => context.ppid = cur_pid,
+            Err(_) => return None,
+        }
+        Some(pid)
+    }
+
+    fn read_packet(supervisor: &mut SupervisorResource) -> Option<Packet> {
+        let mut packet = Packet::default();
+        match supervisor.read(&mut packet) {
+            Ok(count) if count == mem::size_of::<Packet>() => Some(packet),
+            _ => None,
+        }
+    }
+
+    /// Wait for the status the child `pid` exited with
+    fn wait(pid: usize) -> Option<usize> {
+        unsafe { & *::env().contexts.get() }.current().ok().map(|current| {
+            current.statuses.receive(&pid, "test exit_policy")
+        })
+    }
+
+    fn lingers(pid: usize) -> bool {
+        match unsafe { & *::env().contexts.get() }.find(pid) {
+            Ok(context) => !context.exited && context.supervised,
+            Err(_) => false,
+        }
+    }
+
+    // By default, the tracee stays a zombie, which can be inspected, until it is resumed
+    let pid = match spawn(7, 0x5A) {
+        Some(pid) => pid,
+        None => fail!(),
+    };
+    let mut supervisor = match SupervisorResource::new(pid) {
+        Ok(supervisor) => supervisor,
+        Err(_) => fail!(),
+    };
+    match read_packet(&mut supervisor) {
+        Some(packet) => {
+            test!(packet.id == pid && packet.a == SUPERVISE_EVENT_EXIT && packet.b == 7)
+        }
+        None => fail!(),
+    }
+    test!(lingers(pid));
+
+    let mut packet = Packet::default();
+    packet.id = SUPERVISE_GETREGS;
+    test!(supervisor.write(&packet).is_ok());
+    let mut regs = Regs::default();
+    test!(supervisor.read(unsafe {
+        slice::from_raw_parts_mut(&mut regs as *mut Regs as *mut u8, mem::size_of::<Regs>())
+    }).ok() == Some(mem::size_of::<Regs>()));
+    test!(regs.ax == 0x5A);
+
+    test!(supervisor.control(SUPERVISE_CONTINUE, 0).is_ok());
+    test!(wait(pid) == Some(7));
+    test!(!lingers(pid));
+    drop(supervisor);
+
+    // A reaped tracee is torn down at once, leaving only its exit event
+    let pid = match spawn(9, 0) {
+        Some(pid) => pid,
+        None => fail!(),
+    };
+    let mut supervisor = match SupervisorResource::new(pid) {
+        Ok(supervisor) => supervisor,
+        Err(_) => fail!(),
+    };
+    test!(supervisor.control(SUPERVISE_EXIT_POLICY, 2).err().map(|err| err.errno) == Some(EINVAL));
+    test!(supervisor.control(SUPERVISE_EXIT_POLICY, SUPERVISE_EXIT_REAP).is_ok());
+    test!(wait(pid) == Some(9));
+    match read_packet(&mut supervisor) {
+        Some(packet) => {
+            test!(packet.id == pid && packet.a == SUPERVISE_EVENT_EXIT && packet.b == 9)
+        }
+        None => fail!(),
+    }
+    test!(supervisor.control(SUPERVISE_CONTINUE, 0).err().map(|err| err.errno) == Some(EPERM));
+    succ!();
+}
+
+pub fn forcekill() -> bool {
+    use alloc::arc::Arc;
+    use arch::context::{context_switch, Context, ContextFile};
+    use core::mem;
+    use fs::{Resource, SupervisorResource};
+    use schemes::pipe::{PipeRead, PipeWrite};
+    use schemes::test::helpers::with_iopl;
+    use sync::WaitCondition;
+    use system::error::{EPERM, ESRCH};
+    use system::scheme::Packet;
+    use system::syscall::{SIGKILL, SUPERVISE_EVENT_EXIT, SUPERVISE_FORCEKILL};
+
+    fn set_ppid(pid: usize, ppid: usize) {
+        if let Ok(context) = unsafe { &mut *::env().contexts.get() }.find_mut(pid) {
+            context.ppid = ppid;
+        }
+    }
+
+    fn blocked(pid: usize) -> bool {
+        match unsafe { & *::env().contexts.get() }.find(pid) {
+            Ok(context) => context.blocked > 0,
+            Err(_) => false,
+        }
+    }
+
+    let contexts = unsafe { &mut *::env().contexts.get() };
+    let cur_pid = match contexts.current() {
+        Ok(context) => context.pid,
+        Err(_) => fail!(),
+    };
+
+    // The tracee waits in the kernel for a condition which is never notified, holding a pipe
+    let condition = Arc::new(WaitCondition::new());
+    let tracee_condition = condition.clone();
+    let pid = Context::spawn("test_forcekill".into(),
+                             box move || {
+                                 loop {
+                                     tracee_condition.wait("test forcekill");
+                                 }
+                             });
+    let mut pipe = PipeRead::new();
+    match contexts.find_mut(pid) {
+        Ok(context) => {
+            context.ppid = cur_pid;
+            unsafe { &mut *context.files.get() }.push(ContextFile {
+                fd: 0,
+                resource: box PipeWrite::new(&pipe),
+                cloexec: false,
+            });
+        },
+        Err(_) => fail!(),
+    }
+    let mut supervisor = match SupervisorResource::new(pid) {
+        Ok(supervisor) => supervisor,
+        Err(_) => fail!(),
+    };
+    for _ in 0..16 {
+        if blocked(pid) {
+            break;
+        }
+        unsafe { context_switch() };
+    }
+    test!(blocked(pid));
+    test!(!pipe.is_readable());
+
+    // Only the parent, or a privileged context, may force a kill
+    set_ppid(pid, 0);
+    let unprivileged = with_iopl(0, || {
+        supervisor.control(SUPERVISE_FORCEKILL, pid).err().map(|err| err.errno)
+    });
+    set_ppid(pid, cur_pid);
+    test!(unprivileged == Some(EPERM));
+    test!(supervisor.control(SUPERVISE_FORCEKILL, cur_pid).err().map(|err| err.errno) ==
+          Some(ESRCH));
+
+    test!(supervisor.control(SUPERVISE_FORCEKILL, pid).ok() == Some(0));
+
+    // The tracee is gone, its files are closed, and its exit is reported without it running
+    test!(contexts.find(pid).is_err());
+    test!(pipe.is_readable());
+    let mut buf = [0; 1];
+    test!(pipe.read(&mut buf).ok() == Some(0));
+    let mut packet = Packet::default();
+    test!(supervisor.read(&mut packet).ok() == Some(mem::size_of::<Packet>()));
+    test!(packet.id == pid && packet.a == SUPERVISE_EVENT_EXIT && packet.b == 128 + SIGKILL);
+    test!(contexts.current().ok().map(|current| {
+        current.statuses.receive(&pid, "test forcekill")
+    }) == Some(128 + SIGKILL));
+    test!(supervisor.read(&mut packet).err().map(|err| err.errno) == Some(ESRCH));
+    test!(supervisor.control(SUPERVISE_FORCEKILL, pid).err().map(|err| err.errno) ==
+          Some(ESRCH));
+
+    // The condition it waited on can still be notified
+    condition.notify("test forcekill");
+    succ!();
+}
+
+pub fn causes() -> bool {
+    use fs::SupervisorResource;
+    use system::error::{Error, EBUSY, EINVAL};
+
+    let cur_pid = match unsafe { & *::env().contexts.get() }.current() {
+        Ok(context) => context.pid,
+        Err(_) => fail!(),
+    };
+
+    // Supervisor errors carry why they happened, along with the errno
+    let error = match SupervisorResource::new(cur_pid) {
+        Err(error) => error,
+        Ok(_) => fail!(),
+    };
+    test!(error.errno == EINVAL && error.cause == Some("supervising itself"));
+
+    test!(SupervisorResource::observe(cur_pid).err().and_then(|err| err.cause) ==
+          Some("observing itself"));
+
+    // Only the errno reaches userspace, and only debugging shows the cause
+    let busy = Error::with_cause(EBUSY, "tracee not stopped");
+    test!(Error::mux(Err(Error::with_cause(EBUSY, "tracee not stopped"))) ==
+          Error::mux(Err(Error::new(EBUSY))));
+    test!(format!("{}", busy) == busy.text());
+    test!(format!("{:?}", busy) == format!("{}: tracee not stopped", busy.text()));
+    test!(format!("{:?}", Error::new(EBUSY)) == busy.text());
+    test!(Error::new(EBUSY).cause.is_none());
+    succ!();
+}
+
+pub fn fpregs() -> bool {
+    use arch::regs::{FpRegs, Regs};
+    use core::{mem, slice};
+    use fs::{Resource, SupervisorResource};
+    use system::error::EINVAL;
+    use system::scheme::Packet;
+    use system::syscall::{SUPERVISE_CONTINUE, SUPERVISE_GETFPREGS, SUPERVISE_SETFPREGS};
+
+    fn command(supervisor: &mut SupervisorResource, id: usize, payload: &[u8]) -> Option<isize> {
+        let mut packet = Packet::default();
+        packet.id = id;
+        let mut buf = unsafe {
+            slice::from_raw_parts(&packet as *const Packet as *const u8, mem::size_of::<Packet>())
+        }.to_vec();
+        buf.extend_from_slice(payload);
+        supervisor.write(&buf).err().map(|err| err.errno)
+    }
+
+    fn bytes(fp_regs: &FpRegs) -> &[u8] {
+        unsafe {
+            slice::from_raw_parts(fp_regs as *const FpRegs as *const u8, mem::size_of::<FpRegs>())
+        }
+    }
+
+    // The registers of this context, which the tracee below really is, to put back afterwards
+    let original = match unsafe { &mut *::env().contexts.get() }.current_mut() {
+        Ok(context) => {
+            unsafe { context.save_fx() };
+            context.fp_regs()
+        },
+        Err(_) => fail!(),
+    };
+
+    let result = with_child("test_supervisor", |pid| {
+        let mut supervisor = match SupervisorResource::new(pid) {
+            Ok(supervisor) => supervisor,
+            Err(_) => fail!(),
+        };
+
+        let regs = Regs::default();
+        let tracee = match unsafe { &mut *::env().contexts.get() }.find_mut(pid) {
+            Ok(context) => {
+                context.blocked_syscall = true;
+                context.stopped_regs = Some(regs);
+                match context.supervised_resource.clone() {
+                    Some(tracee) => tracee,
+                    None => fail!(),
+                }
+            },
+            Err(_) => fail!(),
+        };
+
+        // The payload must be exactly the save area, with supported MXCSR bits
+        let mut fp_regs = original;
+        fp_regs.xmm[0] = [0x5A; 16];
+        test!(mem::size_of::<FpRegs>() == 512);
+        test!(command(&mut supervisor, SUPERVISE_SETFPREGS, &bytes(&fp_regs)[..511]) ==
+              Some(EINVAL));
+        let mut bad = fp_regs;
+        bad.mxcsr |= 0x10000;
+        test!(command(&mut supervisor, SUPERVISE_SETFPREGS, bytes(&bad)) == Some(EINVAL));
+
+        test!(command(&mut supervisor, SUPERVISE_SETFPREGS, bytes(&fp_regs)) == None);
+        test!(command(&mut supervisor, SUPERVISE_GETFPREGS, &[]) == None);
+        test!(command(&mut supervisor, SUPERVISE_CONTINUE, &[]) == None);
+
+        let mut tracee_regs = regs;
+        test!(!tracee.trap(regs.into(), &mut tracee_regs));
+
+        // The registers read back are the ones set, loaded and saved again by the tracee
+        let mut buf = [0; 512];
+        test!(supervisor.read(&mut buf[..256]).err().map(|err| err.errno) == Some(EINVAL));
+        test!(supervisor.read(&mut buf).ok() == Some(512));
+        let mut read = FpRegs::default();
+        unsafe {
+            slice::from_raw_parts_mut(&mut read as *mut FpRegs as *mut u8, 512)
+        }.copy_from_slice(&buf);
+        test!(read.xmm[0] == [0x5A; 16]);
+        test!(read.fcw == original.fcw && read.mxcsr == original.mxcsr);
+        succ!();
+    });
+
+    if let Ok(context) = unsafe { &mut *::env().contexts.get() }.current_mut() {
+        context.set_fp_regs(&original);
+        unsafe { context.load_fx() };
+    }
+
+    result
+}
+
+pub fn round_robin() -> bool {
+    use core::{mem, slice};
+    use fs::{Resource, SupervisorResource};
+    use system::scheme::Packet;
+    use system::syscall::SUPERVISE_NONBLOCK;
+
+    /// Queue an exit event of the tracee `pid`, which does not stop it
+    fn queue(pid: usize, count: usize) -> bool {
+        if let Ok(context) = unsafe { & *::env().contexts.get() }.find(pid) {
+            if let Some(ref tracee) = context.supervised_resource {
+                for _ in 0..count {
+                    tracee.exit(0);
+                }
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Read one event, returning the PID of its tracee
+    fn read(supervisor: &mut SupervisorResource) -> Option<usize> {
+        let mut packet = Packet::default();
+        match supervisor.read(&mut packet) {
+            Ok(count) if count == mem::size_of::<Packet>() => Some(packet.id),
+            _ => None,
+        }
+    }
+
+    let name = "test_supervisor";
+    with_child(name, |first| with_child(name, |second| with_child(name, |third| {
+        let mut supervisor = match SupervisorResource::new(0) {
+            Ok(supervisor) => supervisor,
+            Err(_) => fail!(),
+        };
+        test!(supervisor.control(SUPERVISE_NONBLOCK, 1).is_ok());
+
+        // The first tracee queues many events before the others get to queue one
+        test!(queue(first, 8) && queue(second, 1) && queue(third, 1) && queue(first, 1));
+
+        // Every tracee is served within one round
+        let mut pids = [0; 3];
+        for pid in pids.iter_mut() {
+            *pid = read(&mut supervisor).unwrap_or(0);
+        }
+        test!(pids.contains(&first) && pids.contains(&second) && pids.contains(&third));
+
+        // The rest of the events of the first tracee follow
+        for _ in 0..8 {
+            test!(read(&mut supervisor) == Some(first));
+        }
+        test!(read(&mut supervisor) == None);
+
+        // A read of several events takes them in turn as well
+        test!(queue(first, 3) && queue(second, 1));
+        let mut packets = [Packet::default(); 3];
+        test!(supervisor.read(unsafe {
+            slice::from_raw_parts_mut(packets.as_mut_ptr() as *mut u8,
+                                      packets.len() * mem::size_of::<Packet>())
+        }).ok() == Some(packets.len() * mem::size_of::<Packet>()));
+        test!(packets[0].id != packets[1].id);
+        test!(packets.iter().filter(|packet| packet.id == second).count() == 1);
+        succ!();
+    })))
+}
@@ -0,0 +1,47 @@
+pub fn load() -> bool {
+    use schemes::sysinfo::{Load, LOAD_TICKS};
+
+    let mut load = Load::new();
+    test!(load.average() == 0);
+
+    load.push(1);
+    load.push(2);
+    test!(load.average() == 150);
+
+    // Only the last ticks count
+    for _ in 0..LOAD_TICKS {
+        load.push(3);
+    }
+    test!(load.average() == 300);
+    load.push(3 + LOAD_TICKS);
+    test!(load.average() == 400);
+    succ!();
+}
+
+pub fn read() -> bool {
+    use collections::Vec;
+    use core::str;
+    use fs::{KScheme, Url};
+    use schemes::sysinfo::SysInfoScheme;
+
+    let mut resource = match SysInfoScheme.open(Url::from_str("sysinfo:").unwrap(), 0) {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+
+    let mut data = Vec::new();
+    test!(resource.read_to_end(&mut data).is_ok());
+    let text = match str::from_utf8(&data) {
+        Ok(text) => text,
+        Err(_) => fail!(),
+    };
+    let names: Vec<&str> = text.lines().filter_map(|line| line.split(": ").next()).collect();
+    test!(names == ["Uptime", "Memory Total", "Memory Free", "Contexts", "Load"]);
+
+    let contexts = unsafe { & *::env().contexts.get() }.len();
+    test!(text.contains(&format!("\nContexts: {}\n", contexts)[..]));
+
+    // Read only
+    test!(resource.write(b"0").is_err());
+    succ!();
+}
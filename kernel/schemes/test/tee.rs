@@ -0,0 +1,92 @@
+pub fn both() -> bool {
+    use collections::Vec;
+    use collections::string::ToString;
+    use fs::{Resource, ResourceSeek, Tee, VecResource};
+
+    let mut tee = Tee::new(box VecResource::new("first:".to_string(), Vec::new()),
+                           box VecResource::new("second:".to_string(), b"xy".to_vec()));
+
+    test!(tee.write(b"abc").ok() == Some(3));
+    test!(tee.write(b"def").ok() == Some(3));
+    test!(&tee.first().data()[..] == b"abcdef");
+    test!(&tee.second().data()[..] == b"abcdef");
+
+    // Reads come from the first, and seeks move both
+    test!(tee.seek(ResourceSeek::Start(2)).ok() == Some(2));
+    let mut buf = [0; 8];
+    test!(tee.read(&mut buf).ok() == Some(4));
+    test!(&buf[..4] == b"cdef");
+    test!(tee.seek(ResourceSeek::Start(1)).ok() == Some(1));
+    test!(tee.write(b"B").ok() == Some(1));
+    test!(&tee.first().data()[..] == b"aBcdef");
+    test!(&tee.second().data()[..] == b"aBcdef");
+
+    let mut path = [0; 16];
+    test!(tee.path(&mut path).ok() == Some(6));
+    test!(&path[..6] == b"first:");
+
+    test!(tee.truncate(3).is_ok());
+    test!(&tee.first().data()[..] == b"aBc");
+    test!(&tee.second().data()[..] == b"aBc");
+    succ!();
+}
+
+pub fn errors() -> bool {
+    use collections::Vec;
+    use collections::string::ToString;
+    use fs::{Resource, Tee, VecResource};
+    use system::error::ENOSPC;
+
+    // The first fails, so nothing reaches the second
+    let mut tee = Tee::new(box VecResource::bounded("first:".to_string(), Vec::new(), 2),
+                           box VecResource::new("second:".to_string(), Vec::new()));
+    test!(tee.write(b"abc").err().map(|err| err.errno) == Some(ENOSPC));
+    test!(tee.first().data().is_empty());
+    test!(tee.second().data().is_empty());
+
+    // The second fails, so the first is not written to again on the retry
+    let mut tee = Tee::new(box VecResource::new("first:".to_string(), Vec::new()),
+                           box VecResource::bounded("second:".to_string(), Vec::new(), 2));
+    test!(tee.write(b"abc").err().map(|err| err.errno) == Some(ENOSPC));
+    test!(&tee.first().data()[..] == b"abc");
+    test!(tee.second().data().is_empty());
+    test!(tee.write(b"ab").ok() == Some(2));
+    test!(&tee.first().data()[..] == b"abc");
+    test!(&tee.second().data()[..] == b"ab");
+    succ!();
+}
+
+pub fn short() -> bool {
+    use collections::Vec;
+    use collections::string::ToString;
+    use fs::{RateLimited, Resource, Tee, VecResource};
+    use schemes::pipe::{PipeRead, PipeWrite};
+    use schemes::test::helpers::with_ticks;
+
+    with_ticks(|| {
+        let mut read = PipeRead::new();
+        let second = match RateLimited::new(box PipeWrite::new(&read), 4, true) {
+            Ok(second) => second,
+            Err(_) => fail!(),
+        };
+        let mut tee = Tee::new(box VecResource::new("first:".to_string(), Vec::new()), box second);
+
+        // The second takes 4 bytes, so the write is short, and retrying the rest completes both
+        test!(tee.write(b"abcdef").ok() == Some(4));
+        test!(&tee.first().data()[..] == b"abcdef");
+        unsafe { *::env().ticks.get() += 1 };
+        test!(tee.write(b"ef").ok() == Some(2));
+        test!(&tee.first().data()[..] == b"abcdef");
+
+        let mut buf = [0; 16];
+        test!(read.read(&mut buf).ok() == Some(6));
+        test!(&buf[..6] == b"abcdef");
+
+        // Once caught up, both are written to again
+        test!(tee.write(b"gh").ok() == Some(2));
+        test!(&tee.first().data()[..] == b"abcdefgh");
+        test!(read.read(&mut buf).ok() == Some(2));
+        test!(&buf[..2] == b"gh");
+        succ!();
+    })
+}
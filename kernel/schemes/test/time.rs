@@ -0,0 +1,122 @@
+pub fn access() -> bool {
+    use fs::{KScheme, Url};
+    use schemes::test::helpers::with_iopl;
+    use schemes::time::TimeScheme;
+    use system::error::EPERM;
+    use system::syscall::{O_RDONLY, O_WRONLY};
+
+    fn open(path: &str, flags: usize) -> Option<isize> {
+        ::env().open(Url::from_str(path).unwrap(), flags).err().map(|err| err.errno)
+    }
+
+    let (read, unprivileged, check) = with_iopl(0, || {
+        (open("time:realtime", O_RDONLY),
+         open("time:realtime", O_WRONLY),
+         TimeScheme.check_access(&Url::from_str("time:realtime").unwrap(), O_WRONLY)
+                   .err()
+                   .map(|err| err.errno))
+    });
+    let (privileged, monotonic) = with_iopl(3, || {
+        (open("time:realtime", O_WRONLY), open("time:monotonic", O_WRONLY))
+    });
+
+    test!(read == None);
+    test!(unprivileged == Some(EPERM));
+    test!(check == Some(EPERM));
+    test!(privileged == None);
+    test!(monotonic == Some(EPERM));
+    succ!();
+}
+
+pub fn monotonic() -> bool {
+    use core::{mem, slice};
+    use fs::{KScheme, Url};
+    use schemes::time::TimeScheme;
+    use system::syscall::TimeSpec;
+
+    let mut scheme = TimeScheme;
+    test!(scheme.open(Url::from_str("time:bogus").unwrap(), 0).is_err());
+
+    let mut resource = match scheme.open(Url::from_str("time://monotonic").unwrap(), 0) {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+
+    // The buffer must fit a TimeSpec
+    let mut small = [0; 4];
+    test!(resource.read(&mut small).is_err());
+
+    let mut first = TimeSpec::default();
+    let mut second = TimeSpec::default();
+    for time_spec in [&mut first, &mut second].iter_mut() {
+        let buf = unsafe {
+            slice::from_raw_parts_mut(*time_spec as *mut TimeSpec as *mut u8,
+                                      mem::size_of::<TimeSpec>())
+        };
+        test!(resource.read(buf).ok() == Some(mem::size_of::<TimeSpec>()));
+    }
+    test!((second.tv_sec, second.tv_nsec) >= (first.tv_sec, first.tv_nsec));
+    succ!();
+}
+
+pub fn set_realtime() -> bool {
+    use common::time::Duration;
+    use core::{mem, slice};
+    use fs::{KScheme, Url};
+    use schemes::test::helpers::with_iopl;
+    use schemes::time::TimeScheme;
+    use system::error::{EINVAL, EPERM};
+    use system::syscall::TimeSpec;
+
+    fn write(time_spec: &TimeSpec, path: &str) -> Option<isize> {
+        let mut resource = match TimeScheme.open(Url::from_str(path).unwrap(), 0) {
+            Ok(resource) => resource,
+            Err(err) => return Some(err.errno),
+        };
+        resource.write(unsafe {
+            slice::from_raw_parts(time_spec as *const TimeSpec as *const u8,
+                                  mem::size_of::<TimeSpec>())
+        }).err().map(|err| err.errno)
+    }
+
+    let realtime = Duration::realtime();
+    let monotonic = Duration::monotonic();
+
+    let mut time_spec = TimeSpec {
+        tv_sec: realtime.secs + 3600,
+        tv_nsec: realtime.nanos,
+    };
+
+    // Only privileged contexts may set the clock, and only the realtime one
+    test!(with_iopl(0, || write(&time_spec, "time:realtime")) == Some(EPERM));
+    test!(with_iopl(3, || {
+        let result = write(&time_spec, "time:realtime");
+        let after = Duration::realtime();
+        let moved = Duration::monotonic();
+        time_spec.tv_sec = realtime.secs;
+        let restored = write(&time_spec, "time:realtime");
+        test!(result == None);
+        test!(after.secs >= realtime.secs + 3600);
+        test!(moved.secs < monotonic.secs + 3600);
+        test!(restored == None);
+        test!(write(&time_spec, "time:monotonic") == Some(EPERM));
+
+        // Bogus times are rejected
+        time_spec.tv_nsec = -1;
+        test!(write(&time_spec, "time:realtime") == Some(EINVAL));
+        time_spec.tv_nsec = 1000000000;
+        test!(write(&time_spec, "time:realtime") == Some(EINVAL));
+        time_spec.tv_nsec = 0;
+        time_spec.tv_sec = -1;
+        test!(write(&time_spec, "time:realtime") == Some(EINVAL));
+        succ!();
+    }));
+
+    // Writes must be exactly a TimeSpec
+    let mut resource = match TimeScheme.open(Url::from_str("time:realtime").unwrap(), 0) {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+    test!(resource.write(&[0; 4]).err().map(|err| err.errno) == Some(EINVAL));
+    succ!();
+}
@@ -0,0 +1,51 @@
+pub fn frequency() -> bool {
+    use collections::string::{String, ToString};
+    use fs::{KScheme, Url};
+    use schemes::test::helpers::with_iopl;
+    use schemes::timer::TimerScheme;
+    use system::error::{EINVAL, EPERM};
+
+    fn write(hz: &str) -> Option<isize> {
+        match TimerScheme.open(Url::from_str("timer:frequency").unwrap(), 0) {
+            Ok(mut resource) => resource.write(hz.as_bytes()).err().map(|err| err.errno),
+            Err(err) => Some(err.errno),
+        }
+    }
+
+    fn read() -> Option<String> {
+        let mut resource = match TimerScheme.open(Url::from_str("timer://frequency").unwrap(), 0) {
+            Ok(resource) => resource,
+            Err(_) => return None,
+        };
+        let mut buf = [0; 32];
+        resource.read(&mut buf)
+                .ok()
+                .and_then(|count| String::from_utf8(buf[..count].to_vec()).ok())
+    }
+
+    test!(TimerScheme.open(Url::from_str("timer:bogus").unwrap(), 0).is_err());
+
+    let pit = unsafe { &mut *::env().pit.get() };
+    let divisor = pit.divisor();
+    let duration = pit.duration();
+
+    let invalid = with_iopl(3, || {
+        write("5") == Some(EINVAL) && write("100000") == Some(EINVAL) &&
+        write("fast") == Some(EINVAL)
+    });
+
+    let unprivileged = with_iopl(0, || write("250") == Some(EPERM));
+
+    let (set, frequency) = with_iopl(3, || (write("250\n").is_none(), read()));
+    let changed = pit.frequency() == 250 && pit.duration() != duration;
+
+    pit.set_divisor(divisor);
+
+    test!(invalid);
+    test!(unprivileged);
+    test!(set);
+    test!(frequency == Some("250\n".to_string()));
+    test!(changed);
+    test!(pit.duration() == duration);
+    succ!();
+}
@@ -0,0 +1,65 @@
+pub fn record() -> bool {
+    use collections::Vec;
+    use core::{mem, ptr};
+    use fs::{KScheme, Resource, Url};
+    use schemes::test::helpers::with_iopl;
+    use schemes::trace::{self, TraceScheme, TRACE_CAPACITY};
+    use system::error::{EINVAL, EPERM};
+    use system::syscall::{TraceRecord, TRACE_DROPPED, TRACE_SWITCH, TRACE_SYSCALL};
+
+    /// Read every record left, as (event, a, b)
+    fn drain(resource: &mut Resource) -> Vec<(usize, usize, usize)> {
+        let size = mem::size_of::<TraceRecord>();
+        let mut buf = vec![0; 64 * size];
+        let mut records = Vec::new();
+        while let Ok(count) = resource.read(&mut buf) {
+            if count == 0 {
+                break;
+            }
+            for chunk in buf[..count].chunks(size) {
+                let record = unsafe { ptr::read(chunk.as_ptr() as *const TraceRecord) };
+                records.push((record.event, record.a, record.b));
+            }
+        }
+        records
+    }
+
+    let unprivileged = with_iopl(0, || {
+        TraceScheme.open(Url::from_str("trace:").unwrap(), 0).err().map(|err| err.errno)
+    });
+    test!(unprivileged == Some(EPERM));
+
+    let mut resource = match TraceScheme.open(Url::from_str("trace:").unwrap(), 0) {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+    drain(&mut *resource);
+
+    // Only the classes in the mask are recorded
+    test!(resource.write(b"8").err().map(|err| err.errno) == Some(EINVAL));
+    test!(resource.write(b"all").err().map(|err| err.errno) == Some(EINVAL));
+    test!(resource.write(b"2\n").ok() == Some(2));
+    trace::record(TRACE_SWITCH, 1, 2);
+    trace::record(TRACE_SYSCALL, 42, 7);
+    test!(resource.write(b"0").is_ok());
+    trace::record(TRACE_SYSCALL, 43, 7);
+    test!(drain(&mut *resource) == [(TRACE_SYSCALL, 42, 7)]);
+
+    // Events are dropped while the records are full, and counted once there is space again
+    test!(resource.write(b"2").is_ok());
+    for i in 0..TRACE_CAPACITY + 3 {
+        trace::record(TRACE_SYSCALL, i, 0);
+    }
+    let size = mem::size_of::<TraceRecord>();
+    let mut buf = vec![0; 2 * size + 1];
+    test!(resource.read(&mut buf[..1]).err().map(|err| err.errno) == Some(EINVAL));
+    test!(resource.read(&mut buf).ok() == Some(2 * size));
+    trace::record(TRACE_SYSCALL, 0, 1);
+    test!(resource.write(b"0").is_ok());
+    let records = drain(&mut *resource);
+    test!(records.len() == TRACE_CAPACITY);
+    test!(records[TRACE_CAPACITY - 3] == (TRACE_SYSCALL, TRACE_CAPACITY - 1, 0));
+    test!(records[TRACE_CAPACITY - 2] == (TRACE_DROPPED, 3, 0));
+    test!(records[TRACE_CAPACITY - 1] == (TRACE_SYSCALL, 0, 1));
+    succ!();
+}
@@ -0,0 +1,53 @@
+pub fn read() -> bool {
+    use fs::{KScheme, Url};
+    use schemes::uptime::UptimeScheme;
+
+    let mut resource = match UptimeScheme.open(Url::from_str("uptime:").unwrap(), 0) {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+
+    let mut buf = [0; 128];
+    let count = match resource.read(&mut buf) {
+        Ok(count) => count,
+        Err(_) => fail!(),
+    };
+    test!(buf[..count].starts_with(b"Uptime: "));
+    test!(buf[..count].ends_with(b" s\n"));
+
+    // Read only
+    test!(resource.write(b"0").is_err());
+    succ!();
+}
+
+pub fn binary() -> bool {
+    use common::time::Duration;
+    use core::{mem, slice};
+    use fs::{KScheme, Url};
+    use schemes::uptime::UptimeScheme;
+    use system::syscall::UptimeInfo;
+
+    let url = Url::from_str("uptime:?format=binary").unwrap();
+    let mut resource = match UptimeScheme.open(url, 0) {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+
+    let before = Duration::monotonic();
+    let mut info = UptimeInfo::default();
+    let count = match resource.read(unsafe {
+        slice::from_raw_parts_mut(&mut info as *mut UptimeInfo as *mut u8,
+                                  mem::size_of::<UptimeInfo>())
+    }) {
+        Ok(count) => count,
+        Err(_) => fail!(),
+    };
+    test!(count == mem::size_of::<UptimeInfo>());
+    test!(info.uptime.tv_sec >= before.secs);
+    test!(info.uptime.tv_nsec >= 0 && info.uptime.tv_nsec < 1000000000);
+
+    // The whole struct was read
+    let mut buf = [0; 8];
+    test!(resource.read(&mut buf).ok() == Some(0));
+    succ!();
+}
@@ -0,0 +1,58 @@
+pub fn flags() -> bool {
+    use fs::Url;
+    use system::syscall::{F_GETFL, O_APPEND, O_CREAT, O_NONBLOCK, O_RDWR};
+
+    let url = Url::from_str("log://?nonblock&append").unwrap();
+    test!(url.path() == "//");
+    test!(url.query() == "nonblock&append");
+    test!(url.flags(O_RDWR).ok() == Some(O_RDWR | O_NONBLOCK | O_APPEND));
+
+    // Without a query, the flags are left alone
+    let url = Url::from_str("log://").unwrap();
+    test!(url.path() == "//" && url.query() == "");
+    test!(url.flags(O_CREAT).ok() == Some(O_CREAT));
+    test!(Url::from_str("log:?&").unwrap().flags(0).ok() == Some(0));
+
+    // Unknown flags are rejected
+    test!(Url::from_str("log:?nonblocking").unwrap().flags(0).is_err());
+
+    // Schemes consult them in open
+    let mut resource = match Url::from_str("log:?nonblock").unwrap().open() {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+    test!(resource.fcntl(F_GETFL, 0).ok() == Some(O_NONBLOCK));
+    test!(Url::from_str("log:?bogus").unwrap().open().is_err());
+    succ!();
+}
+
+pub fn format() -> bool {
+    use core::{mem, slice};
+    use fs::{Format, Url};
+    use system::syscall::MemoryInfo;
+
+    test!(Url::from_str("memory:").unwrap().format().ok() == Some(Format::Text));
+    test!(Url::from_str("memory:?format=text").unwrap().format().ok() == Some(Format::Text));
+    test!(Url::from_str("memory:?format=binary").unwrap().format().ok() == Some(Format::Binary));
+    test!(Url::from_str("memory:?nonblock&format=binary").unwrap().format().ok() ==
+          Some(Format::Binary));
+    test!(Url::from_str("memory:?format=xml").unwrap().format().is_err());
+
+    // Schemes consult it in open
+    let mut resource = match Url::from_str("memory:?format=binary").unwrap().open() {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+    let mut info = MemoryInfo::default();
+    let count = match resource.read(unsafe {
+        slice::from_raw_parts_mut(&mut info as *mut MemoryInfo as *mut u8,
+                                  mem::size_of::<MemoryInfo>())
+    }) {
+        Ok(count) => count,
+        Err(_) => fail!(),
+    };
+    test!(count == mem::size_of::<MemoryInfo>());
+    test!(info.total == info.used + info.free && info.total > 0);
+    test!(Url::from_str("memory:?format=xml").unwrap().open().is_err());
+    succ!();
+}
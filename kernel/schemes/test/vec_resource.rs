@@ -0,0 +1,531 @@
+pub fn read() -> bool {
+    use collections::Vec;
+    use collections::string::ToString;
+    use fs::{Resource, ResourceSeek, VecResource};
+
+    let mut resource = VecResource::new("test:".to_string(), b"abcdef".to_vec());
+
+    // Reading one byte at a time reconstructs the data
+    let mut data = Vec::new();
+    let mut buf = [0; 1];
+    while let Ok(1) = resource.read(&mut buf) {
+        data.push(buf[0]);
+    }
+    test!(&data[..] == b"abcdef");
+    test!(resource.read(&mut buf).ok() == Some(0));
+
+    // Rewinding reads the data again
+    test!(resource.seek(ResourceSeek::Start(0)).ok() == Some(0));
+    let mut buf = [0; 8];
+    test!(resource.read(&mut buf).ok() == Some(6));
+    test!(&buf[..6] == b"abcdef");
+    succ!();
+}
+
+pub fn seek() -> bool {
+    use collections::string::ToString;
+    use fs::{Resource, ResourceSeek, VecResource};
+
+    let mut resource = VecResource::new("test:".to_string(), b"abcdef".to_vec());
+
+    test!(resource.seek(ResourceSeek::Start(2)).ok() == Some(2));
+    test!(resource.seek(ResourceSeek::Current(2)).ok() == Some(4));
+    test!(resource.seek(ResourceSeek::Current(-3)).ok() == Some(1));
+    test!(resource.seek(ResourceSeek::End(-1)).ok() == Some(5));
+
+    let mut buf = [0; 4];
+    test!(resource.read(&mut buf).ok() == Some(1));
+    test!(buf[0] == b'f');
+
+    // Seeking is clamped to the data
+    test!(resource.seek(ResourceSeek::Start(10)).ok() == Some(6));
+    test!(resource.seek(ResourceSeek::Current(-10)).ok() == Some(0));
+    test!(resource.seek(ResourceSeek::End(4)).ok() == Some(6));
+    succ!();
+}
+
+pub fn tell() -> bool {
+    use collections::string::ToString;
+    use fs::{Resource, ResourceSeek, VecResource};
+
+    let mut resource = VecResource::new("test:".to_string(), b"abcdef".to_vec());
+
+    // Seeking by nothing reports the cursor without moving it
+    test!(resource.seek(ResourceSeek::Current(0)).ok() == Some(0));
+    let mut buf = [0; 4];
+    test!(resource.read(&mut buf).ok() == Some(4));
+    test!(resource.seek(ResourceSeek::Current(0)).ok() == Some(4));
+    test!(resource.seek(ResourceSeek::Current(0)).ok() == Some(4));
+
+    // Seeking to the end reports the size
+    test!(resource.seek(ResourceSeek::End(0)).ok() == Some(6));
+    test!(resource.seek(ResourceSeek::Current(0)).ok() == Some(6));
+    test!(resource.write(b"gh").ok() == Some(2));
+    test!(resource.seek(ResourceSeek::End(0)).ok() == Some(8));
+    test!(resource.read(&mut buf).ok() == Some(0));
+    succ!();
+}
+
+pub fn stat() -> bool {
+    use collections::string::ToString;
+    use fs::{Resource, VecResource};
+    use system::syscall::{MODE_FILE, Stat};
+
+    let resource = VecResource::new("test:".to_string(), b"abcdef".to_vec());
+
+    let mut stat = Stat::default();
+    test!(resource.stat(&mut stat).is_ok());
+    test!(stat.st_mode == MODE_FILE);
+    test!(stat.st_size == 6);
+    succ!();
+}
+
+pub fn path() -> bool {
+    use collections::string::ToString;
+    use collections::Vec;
+    use fs::{Resource, VecResource};
+
+    let resource = VecResource::new("test:path".to_string(), Vec::new());
+
+    let mut buf = [0; 16];
+    test!(resource.path(&mut buf).ok() == Some(9));
+    test!(&buf[..9] == b"test:path");
+
+    // The path is truncated to the buffer
+    let mut small = [0; 4];
+    test!(resource.path(&mut small).ok() == Some(4));
+    test!(&small == b"test");
+    succ!();
+}
+
+pub fn truncate() -> bool {
+    use collections::string::ToString;
+    use fs::{Resource, ResourceSeek, VecResource};
+
+    let mut resource = VecResource::new("test:".to_string(), b"abcdef".to_vec());
+
+    // Growing zero fills
+    test!(resource.truncate(8).is_ok());
+    test!(&resource.data()[..] == b"abcdef\0\0");
+
+    // Shrinking clamps the cursor
+    test!(resource.seek(ResourceSeek::End(0)).ok() == Some(8));
+    test!(resource.truncate(3).is_ok());
+    test!(&resource.data()[..] == b"abc");
+    test!(resource.seek(ResourceSeek::Current(0)).ok() == Some(3));
+
+    let mut buf = [0; 4];
+    test!(resource.read(&mut buf).ok() == Some(0));
+    succ!();
+}
+
+pub fn write() -> bool {
+    use collections::string::ToString;
+    use fs::{Resource, ResourceSeek, VecResource};
+
+    let mut resource = VecResource::new("test:".to_string(), b"abcdef".to_vec());
+
+    // Overwrite in place
+    test!(resource.write(b"AB").ok() == Some(2));
+    test!(&resource.data()[..] == b"ABcdef");
+
+    // Write after seek, extending past the end
+    test!(resource.seek(ResourceSeek::Start(4)).ok() == Some(4));
+    test!(resource.write(b"EFGH").ok() == Some(4));
+    test!(&resource.data()[..] == b"ABcdEFGH");
+    test!(resource.seek(ResourceSeek::Current(0)).ok() == Some(8));
+
+    // Append at the end
+    test!(resource.write(b"I").ok() == Some(1));
+    test!(&resource.data()[..] == b"ABcdEFGHI");
+    succ!();
+}
+
+pub fn append() -> bool {
+    use collections::string::ToString;
+    use fs::{Resource, ResourceSeek, VecResource};
+    use system::syscall::{F_GETFL, F_SETFL, O_APPEND};
+
+    let mut resource = VecResource::new("test:".to_string(), b"abc".to_vec());
+
+    test!(resource.fcntl(F_GETFL, 0).ok() == Some(0));
+    test!(resource.fcntl(F_SETFL, O_APPEND).is_ok());
+    test!(resource.fcntl(F_GETFL, 0).ok() == Some(O_APPEND));
+
+    // Seeking does not move where writes go
+    test!(resource.seek(ResourceSeek::Start(0)).ok() == Some(0));
+    test!(resource.write(b"de").ok() == Some(2));
+    test!(&resource.data()[..] == b"abcde");
+    test!(resource.seek(ResourceSeek::Current(0)).ok() == Some(5));
+
+    test!(resource.seek(ResourceSeek::Start(1)).ok() == Some(1));
+    let mut buf = [0; 2];
+    test!(resource.read(&mut buf).ok() == Some(2));
+    test!(&buf == b"bc");
+    test!(resource.write(b"f").ok() == Some(1));
+    test!(&resource.data()[..] == b"abcdef");
+
+    // Clearing the flag writes at the cursor again
+    test!(resource.fcntl(F_SETFL, 0).is_ok());
+    test!(resource.seek(ResourceSeek::Start(0)).ok() == Some(0));
+    test!(resource.write(b"A").ok() == Some(1));
+    test!(&resource.data()[..] == b"Abcdef");
+    succ!();
+}
+
+pub fn shared() -> bool {
+    use alloc::arc::Arc;
+    use collections::string::ToString;
+    use fs::{Resource, VecResource};
+
+    let data = Arc::new(b"banner".to_vec());
+    let mut first = VecResource::from_shared("test:".to_string(), data.clone());
+    let mut second = VecResource::from_shared("test:".to_string(), data.clone());
+    test!(Arc::strong_count(&data) == 3);
+
+    let mut buf = [0; 6];
+    test!(first.read(&mut buf).ok() == Some(6));
+    test!(&buf == b"banner");
+    test!(second.read(&mut buf).ok() == Some(6));
+    test!(&buf == b"banner");
+
+    // Writing copies the data, leaving the other resources alone
+    test!(first.write(b"!").ok() == Some(1));
+    test!(&first.data()[..] == b"banner!");
+    test!(&second.data()[..] == b"banner");
+    test!(&data[..] == b"banner");
+    test!(Arc::strong_count(&data) == 2);
+    succ!();
+}
+
+pub fn fork() -> bool {
+    use alloc::arc::Arc;
+    use arch::context::ContextFile;
+    use collections::Vec;
+    use collections::string::ToString;
+    use fs::{ResourceSeek, VecResource};
+
+    let data = Arc::new(b"parent".to_vec());
+    let mut parent = vec![ContextFile {
+        fd: 3,
+        resource: box VecResource::from_shared("test:".to_string(), data.clone()),
+        cloexec: false,
+    }];
+
+    // Forking shares the data of the files instead of copying it
+    let mut child: Vec<ContextFile> = parent.iter().filter_map(|file| file.dup().ok()).collect();
+    test!(child.len() == 1 && child[0].fd == 3);
+    test!(Arc::strong_count(&data) == 3);
+
+    // The first write of the child copies it, leaving the parent alone
+    test!(child[0].resource.write(b"CHILD!").ok() == Some(6));
+    test!(Arc::strong_count(&data) == 2);
+
+    let mut buf = [0; 8];
+    test!(parent[0].resource.read(&mut buf).ok() == Some(6));
+    test!(&buf[..6] == b"parent");
+    test!(child[0].resource.seek(ResourceSeek::Start(0)).ok() == Some(0));
+    test!(child[0].resource.read(&mut buf).ok() == Some(6));
+    test!(&buf[..6] == b"CHILD!");
+    test!(&data[..] == b"parent");
+    succ!();
+}
+
+pub fn cursors() -> bool {
+    use collections::string::ToString;
+    use fs::{Resource, ResourceSeek, VecResource};
+
+    let mut first = VecResource::new("test:".to_string(), b"abcdef".to_vec());
+    let mut buf = [0; 2];
+    test!(first.read(&mut buf).ok() == Some(2));
+
+    // A duplicate starts at the same offset, over the same data
+    let mut second = match first.dup() {
+        Ok(second) => second,
+        Err(_) => fail!(),
+    };
+    test!(second.read(&mut buf).ok() == Some(2));
+    test!(&buf == b"cd");
+    test!(second.read(&mut buf).ok() == Some(2));
+    test!(&buf == b"ef");
+
+    // Advancing one cursor leaves the other alone
+    test!(first.seek(ResourceSeek::Current(0)).ok() == Some(2));
+    test!(first.read(&mut buf).ok() == Some(2));
+    test!(&buf == b"cd");
+    test!(second.read(&mut buf).ok() == Some(0));
+    succ!();
+}
+
+pub fn read_line() -> bool {
+    use collections::string::ToString;
+    use fs::{Resource, VecResource};
+
+    let mut resource = VecResource::new("test:".to_string(), b"first\n\nthird line\nlast".to_vec());
+
+    let mut buf = [0; 32];
+    test!(resource.read_line(&mut buf).ok() == Some(6));
+    test!(&buf[..6] == b"first\n");
+
+    // An empty line is just its newline
+    test!(resource.read_line(&mut buf).ok() == Some(1));
+    test!(&buf[..1] == b"\n");
+
+    // A line longer than the buffer is read in parts
+    let mut small = [0; 5];
+    test!(resource.read_line(&mut small).ok() == Some(5));
+    test!(&small == b"third");
+    test!(resource.read_line(&mut buf).ok() == Some(6));
+    test!(&buf[..6] == b" line\n");
+
+    // The last line does not need a newline
+    test!(resource.read_line(&mut buf).ok() == Some(4));
+    test!(&buf[..4] == b"last");
+    test!(resource.read_line(&mut buf).ok() == Some(0));
+    succ!();
+}
+
+pub fn read_to_end() -> bool {
+    use collections::string::ToString;
+    use collections::Vec;
+    use fs::{Resource, VecResource};
+
+    let data: Vec<u8> = (0..2000).map(|i| i as u8).collect();
+    let mut resource = VecResource::new("test:".to_string(), data.clone());
+
+    // Reading starts at the cursor, and keeps what is already in the output
+    let mut buf = [0; 10];
+    test!(resource.read(&mut buf).ok() == Some(10));
+    let mut out = vec![0xFF];
+    test!(resource.read_to_end(&mut out).ok() == Some(1990));
+    test!(out.len() == 1991);
+    test!(out[0] == 0xFF);
+    test!(&out[1..] == &data[10..]);
+
+    // At the end, nothing is read
+    test!(resource.read_to_end(&mut out).ok() == Some(0));
+    test!(out.len() == 1991);
+    succ!();
+}
+
+pub fn readv() -> bool {
+    use collections::string::ToString;
+    use fs::{Resource, VecResource};
+
+    let mut resource = VecResource::new("test:".to_string(), b"headerbody".to_vec());
+
+    let mut header = [0; 6];
+    let mut body = [0; 8];
+    let mut rest = [0; 4];
+    test!(resource.readv(&mut [&mut header[..], &mut body[..], &mut rest[..]]).ok() == Some(10));
+    test!(&header == b"header");
+    test!(&body[..4] == b"body");
+
+    // The buffer after a short read is left alone
+    test!(rest == [0; 4]);
+    test!(resource.readv(&mut [&mut header[..]]).ok() == Some(0));
+    succ!();
+}
+
+pub fn writev() -> bool {
+    use collections::string::ToString;
+    use fs::{Resource, ResourceSeek, VecResource};
+
+    let mut resource = VecResource::new("test:".to_string(), b"0123456789".to_vec());
+
+    // Buffers are written in order at the cursor
+    test!(resource.seek(ResourceSeek::Start(2)).ok() == Some(2));
+    test!(resource.writev(&[&b"ab"[..], &b""[..], &b"cde"[..], &b"fghij"[..]]).ok() == Some(10));
+
+    let mut buf = [0; 16];
+    test!(resource.seek(ResourceSeek::Start(0)).ok() == Some(0));
+    test!(resource.read(&mut buf).ok() == Some(12));
+    test!(&buf[..12] == b"01abcdefghij");
+    succ!();
+}
+
+pub fn seek_data_hole() -> bool {
+    use collections::string::ToString;
+    use fs::{Resource, ResourceSeek, VecResource};
+    use system::error::ENXIO;
+
+    let mut resource = VecResource::new("test:".to_string(), b"0123456789".to_vec());
+
+    // All of the data is dense
+    test!(resource.seek(ResourceSeek::Data(0)).ok() == Some(0));
+    test!(resource.seek(ResourceSeek::Data(4)).ok() == Some(4));
+    test!(resource.seek(ResourceSeek::Hole(4)).ok() == Some(10));
+    test!(resource.seek(ResourceSeek::Hole(9)).ok() == Some(10));
+
+    // There is nothing at or past the end
+    test!(resource.seek(ResourceSeek::Data(10)).err().map(|err| err.errno) == Some(ENXIO));
+    test!(resource.seek(ResourceSeek::Hole(11)).err().map(|err| err.errno) == Some(ENXIO));
+    test!(resource.seek(ResourceSeek::Current(0)).ok() == Some(10));
+    succ!();
+}
+
+pub fn copy() -> bool {
+    use collections::Vec;
+    use collections::string::ToString;
+    use fs::{copy_resource, Resource, ResourceSeek, VecResource};
+
+    let data: Vec<u8> = (0..2000).map(|i| i as u8).collect();
+    let mut src = VecResource::new("src:".to_string(), data.clone());
+    let mut dst = VecResource::new("dst:".to_string(), Vec::new());
+
+    // Copying goes through the bounce buffer in several chunks, from the cursor of the source
+    test!(src.seek(ResourceSeek::Start(100)).ok() == Some(100));
+    test!(copy_resource(&mut src, &mut dst, 1500).ok() == Some(1500));
+    test!(&dst.data()[..] == &data[100..1600]);
+
+    // Reaching the end of the source stops early
+    test!(copy_resource(&mut src, &mut dst, 1000).ok() == Some(400));
+    test!(&dst.data()[..] == &data[100..]);
+    test!(copy_resource(&mut src, &mut dst, 1000).ok() == Some(0));
+
+    // Nothing is read for an empty copy
+    test!(src.seek(ResourceSeek::Start(0)).ok() == Some(0));
+    test!(copy_resource(&mut src, &mut dst, 0).ok() == Some(0));
+    test!(src.seek(ResourceSeek::Current(0)).ok() == Some(0));
+    succ!();
+}
+
+pub fn flags() -> bool {
+    use collections::string::ToString;
+    use fs::{Resource, VecResource};
+    use system::syscall::{F_GETFL, F_SETFL, O_APPEND};
+
+    let mut resource = VecResource::new("test:".to_string(), b"abc".to_vec());
+    test!(resource.flags() == 0);
+
+    // The flags follow F_SETFL, and agree with F_GETFL
+    test!(resource.fcntl(F_SETFL, O_APPEND).is_ok());
+    test!(resource.flags() == O_APPEND);
+    test!(resource.fcntl(F_GETFL, 0).ok() == Some(resource.flags()));
+
+    // Duplicates keep the flags
+    test!(resource.dup().map(|dup| dup.flags()).ok() == Some(O_APPEND));
+
+    test!(resource.fcntl(F_SETFL, 0).is_ok());
+    test!(resource.flags() == 0);
+    succ!();
+}
+
+pub fn mmap() -> bool {
+    use collections::string::ToString;
+    use core::slice;
+    use fs::{KScheme, Resource, Url, VecResource};
+    use schemes::null::NullScheme;
+    use system::error::{EINVAL, ENODEV};
+    use system::syscall::MAP_WRITE;
+
+    let mut resource = VecResource::new("test:".to_string(), b"abc".to_vec());
+
+    let address = match resource.mmap(4096, MAP_WRITE) {
+        Ok(address) => address,
+        Err(_) => fail!(),
+    };
+    let mapping = unsafe { slice::from_raw_parts_mut(address as *mut u8, 4096) };
+    test!(&mapping[..3] == b"abc");
+    test!(mapping[3..].iter().all(|&b| b == 0));
+
+    // The mapping is a private copy
+    mapping[0] = b'A';
+    test!(&resource.data()[..] == b"abc");
+
+    test!(resource.mmap(0, 0).err().map(|err| err.errno) == Some(EINVAL));
+
+    // Resources which keep no memory can not be mapped
+    let mut null = match NullScheme.open(Url::from_str("null:").unwrap(), 0) {
+        Ok(resource) => resource,
+        Err(_) => fail!(),
+    };
+    test!(null.mmap(4096, 0).err().map(|err| err.errno) == Some(ENODEV));
+    succ!();
+}
+
+pub fn fmap() -> bool {
+    use collections::string::ToString;
+    use core::slice;
+    use fs::VecResource;
+    use schemes::test::helpers::install;
+    use syscall::fs::{close, fmap, open};
+    use system::error::{EBADF, EINVAL, ENODEV};
+    use system::syscall::MAP_WRITE;
+
+    let fd = match install(box VecResource::new("test:".to_string(), b"abc".to_vec())) {
+        Some(fd) => fd,
+        None => fail!(),
+    };
+    let address = fmap(fd, 4096, MAP_WRITE);
+    let empty = fmap(fd, 0, 0).err().map(|err| err.errno);
+    test!(close(fd).is_ok());
+
+    // The mapping outlives the file
+    let address = match address {
+        Ok(address) => address,
+        Err(_) => fail!(),
+    };
+    let mapping = unsafe { slice::from_raw_parts_mut(address as *mut u8, 4096) };
+    test!(&mapping[..3] == b"abc");
+    test!(mapping[3..].iter().all(|&b| b == 0));
+    test!(empty == Some(EINVAL));
+    test!(fmap(fd, 4096, 0).err().map(|err| err.errno) == Some(EBADF));
+
+    // Resources which keep no memory can not be mapped
+    let fd = match open(b"null:\0".as_ptr(), 0) {
+        Ok(fd) => fd,
+        Err(_) => fail!(),
+    };
+    let null = fmap(fd, 4096, 0).err().map(|err| err.errno);
+    test!(close(fd).is_ok());
+    test!(null == Some(ENODEV));
+    succ!();
+}
+
+pub fn bounded() -> bool {
+    use collections::string::ToString;
+    use fs::{Resource, ResourceSeek, VecResource};
+    use system::error::ENOSPC;
+    use system::syscall::{F_SETFL, O_APPEND};
+
+    let mut resource = VecResource::bounded("test:".to_string(), b"abc".to_vec(), 8);
+
+    // Just below and at the cap
+    test!(resource.seek(ResourceSeek::End(0)).ok() == Some(3));
+    test!(resource.write(b"defg").ok() == Some(4));
+    test!(&resource.data()[..] == b"abcdefg");
+    test!(resource.write(b"h").ok() == Some(1));
+    test!(&resource.data()[..] == b"abcdefgh");
+
+    // Just above the cap, nothing is written
+    test!(resource.write(b"i").err().map(|err| err.errno) == Some(ENOSPC));
+    test!(&resource.data()[..] == b"abcdefgh");
+    test!(resource.seek(ResourceSeek::Current(0)).ok() == Some(8));
+    test!(resource.seek(ResourceSeek::Start(6)).ok() == Some(6));
+    test!(resource.write(b"GHI").err().map(|err| err.errno) == Some(ENOSPC));
+    test!(resource.write(b"GH").ok() == Some(2));
+    test!(&resource.data()[..] == b"abcdefGH");
+
+    // Appending and truncating are capped too, and duplicates keep the cap
+    test!(resource.fcntl(F_SETFL, O_APPEND).is_ok());
+    test!(resource.seek(ResourceSeek::Start(0)).ok() == Some(0));
+    test!(resource.write(b"i").err().map(|err| err.errno) == Some(ENOSPC));
+    test!(resource.truncate(9).err().map(|err| err.errno) == Some(ENOSPC));
+    test!(resource.truncate(4).is_ok());
+    test!(resource.truncate(8).is_ok());
+    test!(&resource.data()[..] == b"abcd\0\0\0\0");
+    let mut dup = match resource.dup() {
+        Ok(dup) => dup,
+        Err(_) => fail!(),
+    };
+    test!(dup.write(b"i").err().map(|err| err.errno) == Some(ENOSPC));
+
+    // Data already past the cap is kept, but does not grow
+    let mut long = VecResource::bounded("test:".to_string(), b"abcdef".to_vec(), 4);
+    test!(long.write(b"ABCDEF").ok() == Some(6));
+    test!(&long.data()[..] == b"ABCDEF");
+    test!(long.write(b"G").err().map(|err| err.errno) == Some(ENOSPC));
+    test!(long.truncate(2).is_ok());
+    succ!();
+}
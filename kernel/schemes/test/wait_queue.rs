@@ -0,0 +1,41 @@
+pub fn bounded() -> bool {
+    use sync::WaitQueue;
+
+    let queue = WaitQueue::new_bounded(2);
+
+    test!(queue.try_send(1, "test").is_ok());
+    test!(queue.try_send(2, "test").is_ok());
+    test!(queue.is_full());
+    test!(queue.try_send(3, "test") == Err(3));
+
+    // Receiving frees space
+    test!(queue.receive_nonblocking() == Some(1));
+    test!(!queue.is_full());
+    test!(queue.try_send(3, "test").is_ok());
+    test!(queue.receive("test") == 2);
+    test!(queue.receive("test") == 3);
+
+    // Unbounded queues are never full
+    let queue = WaitQueue::new();
+    for i in 0..64 {
+        queue.send(i, "test");
+    }
+    test!(!queue.is_full());
+    succ!();
+}
+
+pub fn receive_all() -> bool {
+    use sync::WaitQueue;
+
+    let queue = WaitQueue::new();
+
+    // Nothing is queued, so this does not block
+    test!(queue.receive_all().is_empty());
+
+    for i in 0..4 {
+        queue.send(i, "test");
+    }
+    test!(queue.receive_all() == vec![0, 1, 2, 3]);
+    test!(queue.receive_nonblocking().is_none());
+    succ!();
+}
@@ -0,0 +1,50 @@
+pub fn expire() -> bool {
+    use common::time::Duration;
+    use fs::Resource;
+    use schemes::watchdog::{self, WatchdogAction, WatchdogResource};
+
+    let mut resource = WatchdogResource::new(0, Duration::new(1, 0), WatchdogAction::Log);
+
+    // The watchdog only expires once its timeout has passed
+    let now = Duration::monotonic();
+    resource.check(now);
+    test!(!resource.expired());
+    resource.check(now + Duration::new(2, 0));
+    test!(resource.expired());
+
+    // Petting restarts the countdown
+    test!(resource.write(b"").ok() == Some(0));
+    test!(resource.expired());
+    test!(resource.write(b"!").ok() == Some(1));
+    test!(!resource.expired());
+    resource.check(Duration::monotonic());
+    test!(!resource.expired());
+
+    // Closing disarms the watchdog
+    let watchdogs = unsafe { (*::env().watchdogs.get()).len() };
+    drop(resource);
+    watchdog::check(Duration::monotonic());
+    test!(unsafe { (*::env().watchdogs.get()).len() } == watchdogs - 1);
+    succ!();
+}
+
+pub fn options() -> bool {
+    use common::time::NANOS_PER_MILLI;
+    use schemes::watchdog::{parse_options, WatchdogAction};
+    use system::error::EINVAL;
+
+    fn parse(reference: &str) -> Option<(i64, i32, WatchdogAction)> {
+        parse_options(reference).ok().map(|(timeout, action)| (timeout.secs, timeout.nanos, action))
+    }
+
+    test!(parse("//5s") == Some((5, 0, WatchdogAction::Log)));
+    test!(parse("5") == Some((5, 0, WatchdogAction::Log)));
+    test!(parse("1500ms") == Some((1, 500 * NANOS_PER_MILLI, WatchdogAction::Log)));
+    test!(parse("5s&kill") == Some((5, 0, WatchdogAction::Kill)));
+    test!(parse("5s&log") == Some((5, 0, WatchdogAction::Log)));
+
+    for reference in ["", "0s", "0ms", "s", "5ss", "-1s", "5m", "5s&bogus"].iter() {
+        test!(parse_options(reference).err().map(|err| err.errno) == Some(EINVAL));
+    }
+    succ!();
+}
@@ -0,0 +1,89 @@
+use alloc::boxed::Box;
+
+use core::cmp;
+
+use common::random::Csprng;
+
+use fs::{KScheme, Resource, Url};
+
+use system::error::{Error, Result, EINVAL};
+
+/// Parse the seed of a test-random URL reference
+///
+/// An empty reference is the seed 0. Returns `EINVAL` if the reference is not a number.
+pub fn parse_seed(reference: &str) -> Result<u64> {
+    let reference = reference.trim_left_matches('/');
+    if reference.is_empty() {
+        Ok(0)
+    } else {
+        reference.parse::<u64>().or(Err(Error::new(EINVAL)))
+    }
+}
+
+/// Create the generator for `seed`, positioned `pos` bytes into its stream
+fn generator(seed: u64, pos: usize) -> Csprng {
+    let key = [seed as u32, (seed >> 32) as u32, 0, 0, 0, 0, 0, 0];
+    let mut csprng = Csprng::from_parts(key, (pos / 64) as u64, [0; 2]);
+    csprng.next_block(&mut [0; 64][..pos % 64]);
+    csprng
+}
+
+/// A deterministic random resource
+///
+/// The output only depends on the seed, and never touches the kernel entropy pool. It is NOT
+/// secret, and must never be used for anything which needs to be unpredictable.
+pub struct TestRandomResource {
+    seed: u64,
+    csprng: Csprng,
+    /// The number of bytes read so far
+    pos: usize,
+}
+
+impl Resource for TestRandomResource {
+    /// Duplicate the resource, continuing from the same position in the stream
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box TestRandomResource {
+            seed: self.seed,
+            csprng: generator(self.seed, self.pos),
+            pos: self.pos,
+        })
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = format!("test-random:{}", self.seed);
+
+        for (b, p) in buf.iter_mut().zip(path.bytes()) {
+            *b = p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    /// Fill `buf` with the next bytes of the stream of the seed
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        self.csprng.next_block(buf);
+        self.pos += buf.len();
+        Ok(buf.len())
+    }
+}
+
+/// The deterministic random scheme, for reproducible tests
+///
+/// `test-random:SEED` reads the same stream every time it is opened with the same seed. Use
+/// `random:` or `urandom:` for anything else.
+pub struct TestRandomScheme;
+
+impl KScheme for TestRandomScheme {
+    fn scheme(&self) -> &str {
+        "test-random"
+    }
+
+    fn open(&mut self, url: Url, _: usize) -> Result<Box<Resource>> {
+        let seed = try!(parse_seed(url.reference()));
+        Ok(box TestRandomResource {
+            seed: seed,
+            csprng: generator(seed, 0),
+            pos: 0,
+        })
+    }
+}
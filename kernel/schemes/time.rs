@@ -0,0 +1,137 @@
+use alloc::boxed::Box;
+
+use core::{cmp, mem, slice};
+
+use common::time::{Duration, NANOS_PER_SEC};
+
+use fs::{check_privileged, is_write, KScheme, Resource, Url};
+
+use system::error::{Error, Result, EINVAL, ENOENT, EPERM};
+use system::syscall::{CLOCK_MONOTONIC, CLOCK_REALTIME, TimeSpec};
+
+/// A clock resource
+///
+/// Every read produces the current time of the clock as a `TimeSpec`. Writing a `TimeSpec` to the
+/// realtime clock sets it.
+pub struct TimeResource {
+    /// `CLOCK_MONOTONIC` or `CLOCK_REALTIME`
+    clock: usize,
+}
+
+impl Resource for TimeResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box TimeResource { clock: self.clock })
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path: &[u8] = if self.clock == CLOCK_REALTIME {
+            b"time:realtime"
+        } else {
+            b"time:monotonic"
+        };
+
+        for (b, p) in buf.iter_mut().zip(path.iter()) {
+            *b = *p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    /// Write the current time of the clock to `buf`
+    ///
+    /// Returns `EINVAL` if `buf` is smaller than a `TimeSpec`.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if buf.len() < mem::size_of::<TimeSpec>() {
+            return Err(Error::new(EINVAL));
+        }
+
+        let time = if self.clock == CLOCK_REALTIME {
+            Duration::realtime()
+        } else {
+            Duration::monotonic()
+        };
+
+        let time_spec = TimeSpec {
+            tv_sec: time.secs,
+            tv_nsec: time.nanos,
+        };
+
+        let data = unsafe {
+            slice::from_raw_parts(&time_spec as *const TimeSpec as *const u8,
+                                  mem::size_of::<TimeSpec>())
+        };
+        for (b, d) in buf.iter_mut().zip(data.iter()) {
+            *b = *d;
+        }
+
+        Ok(data.len())
+    }
+
+    /// Set the realtime clock to the `TimeSpec` in `buf`
+    ///
+    /// The monotonic clock is left alone. Returns `EINVAL` if `buf` is not the size of a
+    /// `TimeSpec`, or the time is before the epoch or has nanoseconds out of range, and `EPERM` if
+    /// the current context has no I/O privilege, or the clock is monotonic.
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if self.clock != CLOCK_REALTIME {
+            return Err(Error::new(EPERM));
+        }
+        if buf.len() != mem::size_of::<TimeSpec>() {
+            return Err(Error::new(EINVAL));
+        }
+
+        let mut time_spec = TimeSpec::default();
+        unsafe {
+            slice::from_raw_parts_mut(&mut time_spec as *mut TimeSpec as *mut u8,
+                                      mem::size_of::<TimeSpec>())
+        }.clone_from_slice(buf);
+        if time_spec.tv_sec < 0 || time_spec.tv_nsec < 0 || time_spec.tv_nsec >= NANOS_PER_SEC {
+            return Err(Error::new(EINVAL));
+        }
+
+        if try!(unsafe { & *::env().contexts.get() }.current()).iopl != 3 {
+            return Err(Error::new(EPERM));
+        }
+
+        let time = Duration::new(time_spec.tv_sec, time_spec.tv_nsec);
+        unsafe { *::env().clock_realtime.get() = time };
+
+        Ok(buf.len())
+    }
+}
+
+/// The clock scheme
+///
+/// `time:monotonic` reads the time since boot, and `time:realtime` reads the time since the epoch,
+/// which privileged contexts can also set.
+pub struct TimeScheme;
+
+impl KScheme for TimeScheme {
+    fn scheme(&self) -> &str {
+        "time"
+    }
+
+    /// Only privileged contexts may open the realtime clock for writing, and no context the
+    /// monotonic one
+    fn check_access(&self, url: &Url, flags: usize) -> Result<()> {
+        if !is_write(flags) {
+            return Ok(());
+        }
+
+        match url.reference().trim_matches('/') {
+            "realtime" => check_privileged(),
+            "" | "monotonic" => Err(Error::new(EPERM)),
+            _ => Ok(()),
+        }
+    }
+
+    fn open(&mut self, url: Url, _: usize) -> Result<Box<Resource>> {
+        let clock = match url.reference().trim_matches('/') {
+            "" | "monotonic" => CLOCK_MONOTONIC,
+            "realtime" => CLOCK_REALTIME,
+            _ => return Err(Error::new(ENOENT)),
+        };
+
+        Ok(box TimeResource { clock: clock })
+    }
+}
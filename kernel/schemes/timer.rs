@@ -0,0 +1,92 @@
+use alloc::boxed::Box;
+
+use core::{cmp, str};
+
+use fs::{KScheme, Resource, ResourceSeek, Url};
+
+use system::error::{Error, Result, EINVAL, ENOENT, EPERM};
+
+/// The tick frequency of the PIT, read and written as a decimal number of Hz
+pub struct FrequencyResource {
+    pos: usize,
+}
+
+impl Resource for FrequencyResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box FrequencyResource { pos: self.pos })
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = b"timer:frequency";
+
+        for (b, p) in buf.iter_mut().zip(path.iter()) {
+            *b = *p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    /// Read the frequency, followed by a newline
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let data = format!("{}\n", unsafe { & *::env().pit.get() }.frequency());
+
+        let mut i = 0;
+        for (b, d) in buf.iter_mut().zip(data.bytes().skip(self.pos)) {
+            *b = d;
+            i += 1;
+        }
+        self.pos += i;
+
+        Ok(i)
+    }
+
+    /// Reprogram the PIT to the frequency written as a decimal number
+    ///
+    /// Returns `EINVAL` if it is not a number between `PIT_HZ_MIN` and `PIT_HZ_MAX`, and `EPERM`
+    /// if the current context has no I/O privilege.
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let hz = try!(str::from_utf8(buf)
+                          .ok()
+                          .and_then(|hz| hz.trim().parse::<usize>().ok())
+                          .ok_or(Error::new(EINVAL)));
+
+        if try!(unsafe { & *::env().contexts.get() }.current()).iopl != 3 {
+            return Err(Error::new(EPERM));
+        }
+
+        try!(unsafe { &mut *::env().pit.get() }.set_frequency(hz));
+        Ok(buf.len())
+    }
+
+    /// Only seeking from the start is supported
+    fn seek(&mut self, pos: ResourceSeek) -> Result<usize> {
+        match pos {
+            ResourceSeek::Start(offset) => self.pos = offset,
+            _ => return Err(Error::new(EINVAL)),
+        }
+        Ok(self.pos)
+    }
+
+    fn sync(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The timer scheme
+///
+/// `timer:frequency` reads the tick rate of the PIT in Hz, which privileged contexts can also set.
+/// The clocks and timeouts follow the new rate from the next tick on.
+pub struct TimerScheme;
+
+impl KScheme for TimerScheme {
+    fn scheme(&self) -> &str {
+        "timer"
+    }
+
+    fn open(&mut self, url: Url, _: usize) -> Result<Box<Resource>> {
+        match url.reference().trim_matches('/') {
+            "" | "frequency" => Ok(box FrequencyResource { pos: 0 }),
+            _ => Err(Error::new(ENOENT)),
+        }
+    }
+}
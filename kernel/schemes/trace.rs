@@ -0,0 +1,171 @@
+use alloc::boxed::Box;
+
+use collections::vec_deque::VecDeque;
+
+use core::{cmp, mem, slice, str};
+
+use common::random::rdtsc;
+
+use fs::{KScheme, Resource, Url};
+
+use system::error::{Error, Result, EINVAL, EPERM};
+use system::syscall::{TraceRecord, TRACE_ALL, TRACE_DROPPED};
+
+/// The number of records kept before new events are dropped
+pub const TRACE_CAPACITY: usize = 4096;
+
+/// The recorded kernel events
+pub struct Trace {
+    /// The classes of events which are recorded
+    mask: usize,
+    records: VecDeque<TraceRecord>,
+    /// The number of events dropped since the last record
+    dropped: usize,
+}
+
+impl Trace {
+    pub fn new() -> Trace {
+        Trace {
+            mask: 0,
+            records: VecDeque::new(),
+            dropped: 0,
+        }
+    }
+
+    /// Record the classes of events in `mask` from now on, or stop recording if it is 0
+    ///
+    /// Returns `EINVAL` if `mask` has unknown classes.
+    pub fn set_mask(&mut self, mask: usize) -> Result<()> {
+        if mask & !TRACE_ALL != 0 {
+            return Err(Error::new(EINVAL));
+        }
+
+        // Allocate the records up front, so that recording never allocates
+        if self.records.capacity() < TRACE_CAPACITY {
+            self.records.reserve_exact(TRACE_CAPACITY);
+        }
+        self.mask = mask;
+        Ok(())
+    }
+
+    /// Record an event of the class `event`, if it is recorded
+    ///
+    /// The event is dropped if `TRACE_CAPACITY` records are unread. Once there is space again, a
+    /// `TRACE_DROPPED` record of the number of dropped events comes first.
+    pub fn record(&mut self, event: usize, a: usize, b: usize) {
+        if self.mask & event != event {
+            return;
+        }
+
+        let time = rdtsc();
+        let needed = if self.dropped > 0 { 2 } else { 1 };
+        if self.records.len() + needed > TRACE_CAPACITY {
+            self.dropped += 1;
+            return;
+        }
+
+        if self.dropped > 0 {
+            self.records.push_back(TraceRecord {
+                time: time,
+                event: TRACE_DROPPED,
+                a: self.dropped,
+                b: 0,
+            });
+            self.dropped = 0;
+        }
+        self.records.push_back(TraceRecord {
+            time: time,
+            event: event,
+            a: a,
+            b: b,
+        });
+    }
+}
+
+/// Record a kernel event of the class `event`, one of `TRACE_*`, if it is recorded
+pub fn record(event: usize, a: usize, b: usize) {
+    unsafe { (*::env().trace.get()).record(event, a, b) };
+}
+
+/// A handle to the recorded kernel events
+pub struct TraceResource;
+
+impl Resource for TraceResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box TraceResource)
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = b"trace:";
+
+        for (b, p) in buf.iter_mut().zip(path.iter()) {
+            *b = *p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    /// Remove as many whole records as fit in `buf`
+    ///
+    /// Returns 0 if no records are left, and `EINVAL` if `buf` is too small for one.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let trace = unsafe { &mut *::env().trace.get() };
+        let size = mem::size_of::<TraceRecord>();
+        if buf.len() < size && !trace.records.is_empty() {
+            return Err(Error::new(EINVAL));
+        }
+
+        let mut i = 0;
+        while i + size <= buf.len() {
+            match trace.records.pop_front() {
+                Some(record) => {
+                    buf[i..i + size].copy_from_slice(unsafe {
+                        slice::from_raw_parts(&record as *const TraceRecord as *const u8, size)
+                    });
+                    i += size;
+                },
+                None => break,
+            }
+        }
+        Ok(i)
+    }
+
+    /// Set the classes of events which are recorded, from a decimal mask of `TRACE_*`
+    ///
+    /// Returns `EINVAL` if the mask is not a number, or has unknown classes.
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let mask = try!(str::from_utf8(buf)
+                            .ok()
+                            .and_then(|mask| mask.trim().parse::<usize>().ok())
+                            .ok_or(Error::new(EINVAL)));
+
+        try!(unsafe { &mut *::env().trace.get() }.set_mask(mask));
+        Ok(buf.len())
+    }
+
+    fn sync(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The kernel event tracing scheme
+///
+/// Records context switches, syscalls and IRQs, see `TraceRecord`. Every handle shares the same
+/// records, so each record is read once. Only contexts with an IOPL of 3 may open it, as the events
+/// of every context are recorded.
+pub struct TraceScheme;
+
+impl KScheme for TraceScheme {
+    fn scheme(&self) -> &str {
+        "trace"
+    }
+
+    fn open(&mut self, _: Url, _: usize) -> Result<Box<Resource>> {
+        let contexts = unsafe { & *::env().contexts.get() };
+        if try!(contexts.current()).iopl != 3 {
+            return Err(Error::new(EPERM));
+        }
+
+        Ok(box TraceResource)
+    }
+}
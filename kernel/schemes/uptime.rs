@@ -0,0 +1,125 @@
+use alloc::boxed::Box;
+
+use collections::Vec;
+
+use core::{cmp, mem, slice};
+
+use common::time::{Duration, NANOS_PER_MILLI};
+
+use fs::{Format, KScheme, Resource, ResourceSeek, Url};
+
+use system::error::{Error, Result, EACCES, EINVAL};
+use system::syscall::{TimeSpec, UptimeInfo};
+
+/// An uptime resource
+///
+/// The uptime is measured at every read, so rewinding and reading again reflects the time since.
+pub struct UptimeResource {
+    pos: usize,
+    format: Format,
+}
+
+impl UptimeResource {
+    /// The time since boot, from the monotonic clock, and the real time at boot
+    ///
+    /// As text, these are in seconds, and as an `UptimeInfo`, in `TimeSpec`s.
+    fn summary(&self) -> Vec<u8> {
+        let uptime = Duration::monotonic();
+        let boot = Duration::realtime() - uptime;
+        match self.format {
+            Format::Text => {
+                format!("Uptime: {}.{:03} s\nBoot Time: {} s\n",
+                        uptime.secs,
+                        uptime.nanos / NANOS_PER_MILLI,
+                        boot.secs)
+                    .into_bytes()
+            }
+            Format::Binary => {
+                let info = UptimeInfo {
+                    uptime: TimeSpec {
+                        tv_sec: uptime.secs,
+                        tv_nsec: uptime.nanos,
+                    },
+                    boot: TimeSpec {
+                        tv_sec: boot.secs,
+                        tv_nsec: boot.nanos,
+                    },
+                };
+                unsafe {
+                    slice::from_raw_parts(&info as *const UptimeInfo as *const u8,
+                                          mem::size_of::<UptimeInfo>())
+                }.to_vec()
+            }
+        }
+    }
+}
+
+impl Resource for UptimeResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box UptimeResource {
+            pos: self.pos,
+            format: self.format,
+        })
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = b"uptime:";
+
+        for (b, p) in buf.iter_mut().zip(path.iter()) {
+            *b = *p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let summary = self.summary();
+
+        let mut i = 0;
+        for (b, s) in buf.iter_mut().zip(summary.iter().skip(self.pos)) {
+            *b = *s;
+            i += 1;
+        }
+        self.pos += i;
+
+        Ok(i)
+    }
+
+    /// The uptime is read only
+    fn write(&mut self, _: &[u8]) -> Result<usize> {
+        Err(Error::new(EACCES))
+    }
+
+    fn seek(&mut self, pos: ResourceSeek) -> Result<usize> {
+        let len = self.summary().len();
+        self.pos = match pos {
+            ResourceSeek::Start(offset) => cmp::min(len, offset),
+            ResourceSeek::Current(offset) =>
+                cmp::max(0, cmp::min(len as isize, self.pos as isize + offset)) as usize,
+            ResourceSeek::End(offset) =>
+                cmp::max(0, cmp::min(len as isize, len as isize + offset)) as usize,
+            ResourceSeek::Data(_) | ResourceSeek::Hole(_) => return Err(Error::new(EINVAL)),
+        };
+        Ok(self.pos)
+    }
+}
+
+/// The uptime scheme
+///
+/// Reads the time since boot, in seconds with millisecond precision, and the real time at which
+/// the kernel booted, in seconds since the epoch. There is no idle time, as it is not tracked.
+/// `uptime:?format=binary` reads them as an `UptimeInfo` instead.
+pub struct UptimeScheme;
+
+impl KScheme for UptimeScheme {
+    fn scheme(&self) -> &str {
+        "uptime"
+    }
+
+    fn open(&mut self, url: Url, _: usize) -> Result<Box<Resource>> {
+        Ok(box UptimeResource {
+            pos: 0,
+            format: try!(url.format()),
+        })
+    }
+}
@@ -0,0 +1,196 @@
+use alloc::arc::Arc;
+use alloc::boxed::Box;
+
+use core::cell::UnsafeCell;
+use core::cmp;
+
+use common::time::{Duration, NANOS_PER_MILLI};
+
+use fs::{KScheme, Resource, Url};
+
+use system::error::{Error, Result, EINVAL};
+use system::syscall::SIGKILL;
+
+/// What a watchdog does when it expires
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WatchdogAction {
+    /// Log a warning
+    Log,
+    /// Send `SIGKILL` to the context which opened the watchdog
+    Kill,
+}
+
+/// Parse the options of a watchdog URL reference
+///
+/// The reference is the timeout, which may start with `//`, followed by `&log` or `&kill` for the
+/// action, which defaults to logging. The timeout is a number of seconds, with an optional `s`
+/// suffix, or milliseconds with an `ms` suffix. Returns `EINVAL` if the timeout is missing, zero
+/// or not a number, or if the action is unknown.
+pub fn parse_options(reference: &str) -> Result<(Duration, WatchdogAction)> {
+    let mut options = reference.trim_left_matches('/').split('&');
+
+    let timeout = options.next().unwrap_or("");
+    let millis = if timeout.ends_with("ms") {
+        try!(timeout[..timeout.len() - 2].parse::<u32>().or(Err(Error::new(EINVAL)))) as i64
+    } else {
+        let secs = if timeout.ends_with('s') {
+            &timeout[..timeout.len() - 1]
+        } else {
+            timeout
+        };
+        try!(secs.parse::<u32>().or(Err(Error::new(EINVAL)))) as i64 * 1000
+    };
+    if millis == 0 {
+        return Err(Error::new(EINVAL));
+    }
+    let timeout = Duration::new(millis / 1000, (millis % 1000) as i32 * NANOS_PER_MILLI);
+
+    let mut action = WatchdogAction::Log;
+    for option in options {
+        action = match option {
+            "log" => WatchdogAction::Log,
+            "kill" => WatchdogAction::Kill,
+            _ => return Err(Error::new(EINVAL)),
+        };
+    }
+
+    Ok((timeout, action))
+}
+
+/// An armed watchdog
+pub struct Watchdog {
+    /// The context which opened the watchdog
+    pid: usize,
+    timeout: Duration,
+    action: WatchdogAction,
+    /// When the watchdog expires, unless it is pet before
+    deadline: Duration,
+    /// Whether the action was taken since the watchdog was last pet
+    expired: bool,
+}
+
+impl Watchdog {
+    /// Take the action of the watchdog, once, if it expired at `now`
+    fn check(&mut self, now: Duration) {
+        if self.expired || now < self.deadline {
+            return;
+        }
+        self.expired = true;
+
+        syslog_warning!("watchdog: context {} was not pet for {}.{:03} s",
+                        self.pid,
+                        self.timeout.secs,
+                        self.timeout.nanos / NANOS_PER_MILLI);
+
+        if self.action == WatchdogAction::Kill {
+            if let Ok(context) = unsafe { &mut *::env().contexts.get() }.find_mut(self.pid) {
+                context.signal(SIGKILL);
+            }
+        }
+    }
+}
+
+/// Take the action of every watchdog which expired at `now`, and forget those which were closed
+///
+/// This is called on every tick of the PIT. Like any signal, the `SIGKILL` of an expired watchdog
+/// is taken when its context next returns from a syscall.
+pub fn check(now: Duration) {
+    unsafe { &mut *::env().watchdogs.get() }.retain(|watchdog| {
+        if let Some(watchdog) = watchdog.upgrade() {
+            unsafe { &mut *watchdog.get() }.check(now);
+            true
+        } else {
+            false
+        }
+    });
+}
+
+/// A watchdog resource
+///
+/// The watchdog is armed until the last duplicate of the resource is closed.
+pub struct WatchdogResource {
+    watchdog: Arc<UnsafeCell<Watchdog>>,
+}
+
+impl WatchdogResource {
+    /// Arm a watchdog for the context `pid`, which expires after `timeout` unless it is pet
+    pub fn new(pid: usize, timeout: Duration, action: WatchdogAction) -> WatchdogResource {
+        let watchdog = Arc::new(UnsafeCell::new(Watchdog {
+            pid: pid,
+            timeout: timeout,
+            action: action,
+            deadline: Duration::monotonic() + timeout,
+            expired: false,
+        }));
+        unsafe { &mut *::env().watchdogs.get() }.push(Arc::downgrade(&watchdog));
+
+        WatchdogResource { watchdog: watchdog }
+    }
+
+    /// Take the action of the watchdog if it expired at `now`, like `check` does for all of them
+    pub fn check(&self, now: Duration) {
+        unsafe { &mut *self.watchdog.get() }.check(now);
+    }
+
+    /// Whether the action was taken since the watchdog was last pet
+    pub fn expired(&self) -> bool {
+        unsafe { &*self.watchdog.get() }.expired
+    }
+}
+
+impl Resource for WatchdogResource {
+    /// Duplicates pet the same watchdog
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box WatchdogResource { watchdog: self.watchdog.clone() })
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let watchdog = unsafe { &*self.watchdog.get() };
+        let path = format!("watchdog:{}ms{}",
+                           watchdog.timeout.secs * 1000 +
+                           (watchdog.timeout.nanos / NANOS_PER_MILLI) as i64,
+                           if watchdog.action == WatchdogAction::Kill {
+                               "&kill"
+                           } else {
+                               ""
+                           });
+
+        for (b, p) in buf.iter_mut().zip(path.bytes()) {
+            *b = p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    /// Pet the watchdog, restarting its countdown, if `buf` is not empty
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if !buf.is_empty() {
+            let watchdog = unsafe { &mut *self.watchdog.get() };
+            watchdog.deadline = Duration::monotonic() + watchdog.timeout;
+            watchdog.expired = false;
+        }
+        Ok(buf.len())
+    }
+
+    fn sync(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The watchdog scheme
+///
+/// Opening `watchdog://5s` arms a watchdog for the current context, which writes to the resource
+/// to show that it is still alive.
+pub struct WatchdogScheme;
+
+impl KScheme for WatchdogScheme {
+    fn scheme(&self) -> &str {
+        "watchdog"
+    }
+
+    fn open(&mut self, url: Url, _: usize) -> Result<Box<Resource>> {
+        let (timeout, action) = try!(parse_options(url.reference()));
+        let pid = try!(unsafe { & *::env().contexts.get() }.current()).pid;
+        Ok(box WatchdogResource::new(pid, timeout, action))
+    }
+}
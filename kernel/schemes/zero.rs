@@ -0,0 +1,57 @@
+use alloc::boxed::Box;
+
+use core::cmp;
+
+use fs::{KScheme, Resource, Url};
+
+use system::error::Result;
+
+/// A resource which reads as an endless stream of zeros
+pub struct ZeroResource;
+
+impl Resource for ZeroResource {
+    fn dup(&self) -> Result<Box<Resource>> {
+        Ok(box ZeroResource)
+    }
+
+    fn path(&self, buf: &mut [u8]) -> Result<usize> {
+        let path = b"zero:";
+
+        for (b, p) in buf.iter_mut().zip(path.iter()) {
+            *b = *p;
+        }
+
+        Ok(cmp::min(buf.len(), path.len()))
+    }
+
+    /// Fill `buf` with zeros
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        for b in buf.iter_mut() {
+            *b = 0;
+        }
+        Ok(buf.len())
+    }
+
+    /// Discard `buf`
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        Ok(buf.len())
+    }
+
+    /// Nothing is buffered, so there is nothing to sync
+    fn sync(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A scheme of zeros
+pub struct ZeroScheme;
+
+impl KScheme for ZeroScheme {
+    fn scheme(&self) -> &str {
+        "zero"
+    }
+
+    fn open(&mut self, _: Url, _: usize) -> Result<Box<Resource>> {
+        Ok(box ZeroResource)
+    }
+}
@@ -0,0 +1,116 @@
+use collections::vec_deque::VecDeque;
+
+use core::cell::UnsafeCell;
+
+use super::WaitCondition;
+
+/// A value received from a `BroadcastQueue`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Broadcast<T> {
+    /// The next value for the reader
+    Value(T),
+    /// The reader fell behind, and this many values were dropped from the ring before it read them
+    Lagged(usize),
+}
+
+/// The position of one reader of a `BroadcastQueue`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BroadcastReader {
+    /// The sequence number of the next value to read
+    next: usize,
+}
+
+/// A ring of values sent by one producer to many readers
+///
+/// Every reader has its own cursor, and sees every value sent after it was created. Sending never
+/// blocks: once the ring is full, the oldest value is dropped, and readers which had not read it
+/// yet are told how many values they missed.
+pub struct BroadcastQueue<T> {
+    inner: UnsafeCell<VecDeque<T>>,
+    /// The sequence number of the first value in the ring
+    start: UnsafeCell<usize>,
+    capacity: usize,
+    /// Notified when a value is sent
+    pub condition: WaitCondition,
+}
+
+impl<T: Clone> BroadcastQueue<T> {
+    /// Create a queue holding the last `capacity` values
+    pub fn new(capacity: usize) -> BroadcastQueue<T> {
+        BroadcastQueue {
+            inner: UnsafeCell::new(VecDeque::new()),
+            start: UnsafeCell::new(0),
+            capacity: capacity,
+            condition: WaitCondition::new(),
+        }
+    }
+
+    unsafe fn inner<'a>(&'a self) -> &'a mut VecDeque<T> {
+        &mut *self.inner.get()
+    }
+
+    unsafe fn start<'a>(&'a self) -> &'a mut usize {
+        &mut *self.start.get()
+    }
+
+    /// The sequence number of the next value to be sent
+    fn end(&self) -> usize {
+        unsafe { *self.start() + self.inner().len() }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Create a reader, which receives the values sent from now on
+    pub fn reader(&self) -> BroadcastReader {
+        BroadcastReader {
+            next: self.end(),
+        }
+    }
+
+    /// Send a value to every reader, dropping the oldest value if the ring is full
+    pub fn send(&self, value: T, reason: &str) {
+        let inner = unsafe { self.inner() };
+        inner.push_back(value);
+        while inner.len() > self.capacity {
+            inner.pop_front();
+            unsafe { *self.start() += 1 };
+        }
+        self.condition.notify(reason);
+    }
+
+    /// Check if `reader` has a value or a lag to receive
+    pub fn peek(&self, reader: &BroadcastReader) -> bool {
+        reader.next < self.end()
+    }
+
+    /// Receive the next value for `reader`, without blocking
+    ///
+    /// Returns `Broadcast::Lagged` once if values were dropped before `reader` read them, after
+    /// which `reader` continues from the oldest value left.
+    pub fn receive_nonblocking(&self, reader: &mut BroadcastReader) -> Option<Broadcast<T>> {
+        let start = unsafe { *self.start() };
+        if reader.next < start {
+            let lagged = start - reader.next;
+            reader.next = start;
+            return Some(Broadcast::Lagged(lagged));
+        }
+
+        let value = unsafe { self.inner() }.get(reader.next - start).cloned();
+        if value.is_some() {
+            reader.next += 1;
+        }
+        value.map(Broadcast::Value)
+    }
+
+    /// Receive the next value for `reader`, waiting for one to be sent
+    pub fn receive(&self, reader: &mut BroadcastReader, reason: &str) -> Broadcast<T> {
+        loop {
+            if let Some(value) = self.receive_nonblocking(reader) {
+                return value;
+            }
+            self.condition.wait(reason);
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use collections::vec_deque::VecDeque;
+
+use core::cell::UnsafeCell;
+
+use super::WaitCondition;
+
+/// A bounded queue of bytes, for stream resources
+pub struct ByteQueue {
+    pub inner: UnsafeCell<VecDeque<u8>>,
+    capacity: usize,
+    /// Notified when bytes are written
+    pub readable: WaitCondition,
+    /// Notified when bytes are read
+    pub writable: WaitCondition,
+}
+
+impl ByteQueue {
+    /// Create a queue holding at most `capacity` bytes
+    pub fn new(capacity: usize) -> ByteQueue {
+        ByteQueue {
+            inner: UnsafeCell::new(VecDeque::new()),
+            capacity: capacity,
+            readable: WaitCondition::new(),
+            writable: WaitCondition::new(),
+        }
+    }
+
+    pub unsafe fn inner<'a>(&'a self) -> &'a mut VecDeque<u8> {
+        &mut *self.inner.get()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        unsafe { self.inner() }.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        unsafe { self.inner() }.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() >= self.capacity
+    }
+
+    /// Write as much of `buf` as fits, without blocking
+    ///
+    /// Returns the number of bytes written.
+    pub fn try_write_bytes(&self, buf: &[u8]) -> usize {
+        let inner = unsafe { self.inner() };
+
+        let mut i = 0;
+        while i < buf.len() && inner.len() < self.capacity {
+            inner.push_back(buf[i]);
+            i += 1;
+        }
+
+        if i > 0 {
+            self.readable.notify("ByteQueue::try_write_bytes");
+        }
+
+        i
+    }
+
+    /// Write all of `buf`, blocking while the queue is full
+    pub fn write_bytes(&self, buf: &[u8], reason: &str) -> usize {
+        let mut i = self.try_write_bytes(buf);
+        while i < buf.len() {
+            self.writable.wait(reason);
+            i += self.try_write_bytes(&buf[i..]);
+        }
+        i
+    }
+
+    /// Read as many bytes as are queued into `buf`, without blocking
+    ///
+    /// Returns the number of bytes read.
+    pub fn try_read_bytes(&self, buf: &mut [u8]) -> usize {
+        let inner = unsafe { self.inner() };
+
+        let mut i = 0;
+        while i < buf.len() {
+            match inner.pop_front() {
+                Some(b) => {
+                    buf[i] = b;
+                    i += 1;
+                },
+                None => break,
+            }
+        }
+
+        if i > 0 {
+            self.writable.notify("ByteQueue::try_read_bytes");
+        }
+
+        i
+    }
+
+    /// Read into `buf`, blocking until at least one byte is queued
+    pub fn read_bytes(&self, buf: &mut [u8], reason: &str) -> usize {
+        if buf.is_empty() {
+            return 0;
+        }
+
+        loop {
+            let i = self.try_read_bytes(buf);
+            if i > 0 {
+                return i;
+            }
+            self.readable.wait(reason);
+        }
+    }
+}
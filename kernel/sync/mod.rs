@@ -1,7 +1,11 @@
+pub use self::broadcast_queue::{Broadcast, BroadcastQueue, BroadcastReader};
+pub use self::byte_queue::ByteQueue;
 pub use self::wait_condition::WaitCondition;
 pub use self::wait_queue::WaitQueue;
 pub use self::wait_map::WaitMap;
 
+pub mod broadcast_queue;
+pub mod byte_queue;
 pub mod wait_condition;
 pub mod wait_queue;
 pub mod wait_map;
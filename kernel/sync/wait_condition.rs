@@ -9,13 +9,16 @@ use core::ops::DerefMut;
 use common::time::Duration;
 
 pub struct WaitCondition {
-    contexts: UnsafeCell<Vec<*mut Context>>
+    contexts: UnsafeCell<Vec<*mut Context>>,
+    /// Contexts woken by `interrupt`, which have not returned from `wait_interruptible` yet
+    interrupted: UnsafeCell<Vec<*mut Context>>,
 }
 
 impl WaitCondition {
     pub fn new() -> WaitCondition {
         WaitCondition {
-            contexts: UnsafeCell::new(Vec::new())
+            contexts: UnsafeCell::new(Vec::new()),
+            interrupted: UnsafeCell::new(Vec::new()),
         }
     }
 
@@ -31,22 +34,117 @@ impl WaitCondition {
         let mut contexts = Vec::new();
         mem::swap(unsafe { &mut *self.contexts.get() }, &mut contexts);
         for &context in contexts.iter() {
-            unsafe { (*context).unblock(reason) }
+            unsafe {
+                (*context).waiting = None;
+                (*context).unblock(reason);
+            }
         }
     }
 
     pub fn wait(&self, reason: &str) {
-        {
-            // debugln!("  WaitCondition::wait {:X} {}", self as *const _ as usize, reason);
-            if let Ok(mut context) = unsafe { &mut *::env().contexts.get() }.current_mut() {
-                let mut contexts = unsafe { &mut *self.contexts.get() };
-                contexts.push(context.deref_mut() as *mut Context);
-                (*context).block(reason);
-            } else {
-                // debugln!("    NOT FOUND {}/{}", unsafe { & *::env().contexts.get() }.i, unsafe { & *::env().contexts.get() }.len());
+        self.block(reason);
+        unsafe { context_switch(); }
+        self.finish();
+    }
+
+    /// Add the current context to the waiting contexts and block it, returning it
+    fn block(&self, reason: &str) -> Option<*mut Context> {
+        // debugln!("  WaitCondition::wait {:X} {}", self as *const _ as usize, reason);
+        if let Ok(mut context) = unsafe { &mut *::env().contexts.get() }.current_mut() {
+            let ptr = context.deref_mut() as *mut Context;
+            unsafe { &mut *self.contexts.get() }.push(ptr);
+            (*context).waiting = Some(self as *const WaitCondition);
+            (*context).block(reason);
+            Some(ptr)
+        } else {
+            // debugln!("    NOT FOUND {}/{}", unsafe { & *::env().contexts.get() }.i, unsafe { & *::env().contexts.get() }.len());
+            None
+        }
+    }
+
+    /// End the wait of the current context, which may have been woken by something other than
+    /// this condition, so it is not left in the list to be unblocked by a later `notify`
+    ///
+    /// `notify`, `remove` and dropping the condition clear the `waiting` of the context, and the
+    /// condition may be freed once it is woken, so the condition is only touched if the context
+    /// still waits on it. Returns whether it did.
+    fn finish(&self) -> bool {
+        if let Ok(mut context) = unsafe { &mut *::env().contexts.get() }.current_mut() {
+            if (*context).waiting == Some(self as *const WaitCondition) {
+                let ptr = context.deref_mut() as *mut Context;
+                unsafe { &mut *self.contexts.get() }.retain(|&waiting| waiting != ptr);
+                (*context).waiting = None;
+                return true;
             }
         }
+        false
+    }
+
+    /// Stop `context` waiting, without waking it
+    ///
+    /// Returns whether it was waiting. The caller unblocks it if it should run again.
+    pub fn remove(&self, context: *mut Context) -> bool {
+        let contexts = unsafe { &mut *self.contexts.get() };
+        let len = contexts.len();
+        contexts.retain(|&waiting| waiting != context);
+        if contexts.len() == len {
+            return false;
+        }
+        unsafe { (*context).waiting = None };
+        true
+    }
+
+    /// Remove every pointer to `context`, which is about to be freed
+    pub fn forget(&self, context: *mut Context) {
+        self.remove(context);
+        unsafe { &mut *self.interrupted.get() }.retain(|&interrupted| interrupted != context);
+        unsafe { (*context).waiting = None };
+    }
+
+    /// Wake only the waiting context `pid`, or every waiting context if `pid` is 0, so that their
+    /// `wait_interruptible` returns false
+    ///
+    /// This is meant for conditions which are waited on with `wait_interruptible`. Returns the
+    /// number of contexts woken.
+    pub fn interrupt(&self, pid: usize, reason: &str) -> usize {
+        let interrupted = unsafe { &mut *self.interrupted.get() };
+        let mut count = 0;
+        unsafe { &mut *self.contexts.get() }.retain(|&context| {
+            if pid == 0 || unsafe { (*context).pid } == pid {
+                unsafe { (*context).unblock(reason) };
+                interrupted.push(context);
+                count += 1;
+                false
+            } else {
+                true
+            }
+        });
+        count
+    }
+
+    /// Wait like `wait`, returning false if the wait was ended by `interrupt`
+    pub fn wait_interruptible(&self, reason: &str) -> bool {
+        let ptr = match self.block(reason) {
+            Some(ptr) => ptr,
+            None => return true,
+        };
+
         unsafe { context_switch(); }
+
+        // `interrupt` leaves `waiting` set, so a context which is no longer waiting on the
+        // condition was woken by something else
+        if !self.finish() {
+            return true;
+        }
+
+        let interrupted = unsafe { &mut *self.interrupted.get() };
+        match interrupted.iter().position(|&context| context == ptr) {
+            Some(i) => {
+                interrupted.remove(i);
+                false
+            },
+            None => true,
+        }
     }
 
     pub fn wait_for(&self, reason: &str, time: Duration) -> bool {
@@ -56,6 +154,7 @@ impl WaitCondition {
             if let Ok(mut context) = unsafe { &mut *::env().contexts.get() }.current_mut() {
                 let mut contexts = unsafe { &mut *self.contexts.get() };
                 contexts.push(context.deref_mut() as *mut Context);
+                (*context).waiting = Some(self as *const WaitCondition);
                 (*context).wake = Some(Duration::monotonic() + time);
                 (*context).block(reason);
             } else {
@@ -66,18 +165,23 @@ impl WaitCondition {
         {
             if let Ok(mut context) = unsafe { &mut *::env().contexts.get() }.current_mut() {
                 if (*context).wake.is_none() {
+                    // Timed out
                     ret = false;
                 } else {
                     (*context).wake = None;
                 }
             }
         }
+        self.finish();
         ret
     }
 }
 
 impl Drop for WaitCondition {
     fn drop(&mut self){
+        for &context in unsafe { & *self.interrupted.get() }.iter() {
+            unsafe { (*context).waiting = None };
+        }
         self.notify("WaitCondition::drop");
     }
 }
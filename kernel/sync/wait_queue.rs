@@ -1,23 +1,39 @@
+use collections::Vec;
 use collections::vec_deque::VecDeque;
 
 use core::cell::UnsafeCell;
-use core::mem;
-use core::ops::DerefMut;
+use core::{cmp, i64};
 
-use common::time::Duration;
+use common::time::{Duration, NANOS_PER_SEC};
 
 use super::WaitCondition;
 
 pub struct WaitQueue<T> {
     pub inner: UnsafeCell<VecDeque<T>>,
     pub condition: WaitCondition,
+    /// Notified when a value is received
+    pub space: WaitCondition,
+    /// The number of values the queue holds before `send` blocks, if it is bounded
+    capacity: Option<usize>,
 }
 
 impl<T> WaitQueue<T> {
     pub fn new() -> WaitQueue<T> {
         WaitQueue {
             inner: UnsafeCell::new(VecDeque::new()),
-            condition: WaitCondition::new()
+            condition: WaitCondition::new(),
+            space: WaitCondition::new(),
+            capacity: None,
+        }
+    }
+
+    /// Create a queue holding at most `capacity` values
+    pub fn new_bounded(capacity: usize) -> WaitQueue<T> {
+        WaitQueue {
+            inner: UnsafeCell::new(VecDeque::new()),
+            condition: WaitCondition::new(),
+            space: WaitCondition::new(),
+            capacity: Some(capacity),
         }
     }
 
@@ -28,46 +44,119 @@ impl<T> WaitQueue<T> {
     pub fn clone(&self) -> WaitQueue<T> where T: Clone {
         WaitQueue {
             inner: UnsafeCell::new(unsafe { self.inner() }.clone()),
-            condition: WaitCondition::new()
+            condition: WaitCondition::new(),
+            space: WaitCondition::new(),
+            capacity: self.capacity,
+        }
+    }
+
+    /// Check if the queue is bounded and holds as many values as it can
+    pub fn is_full(&self) -> bool {
+        match self.capacity {
+            Some(capacity) => unsafe { self.inner() }.len() >= capacity,
+            None => false,
         }
     }
 
+    /// Remove the next value, waking any senders waiting for space
+    fn pop(&self) -> Option<T> {
+        let value = unsafe { self.inner() }.pop_front();
+        if value.is_some() && self.capacity.is_some() {
+            self.space.notify("WaitQueue::pop");
+        }
+        value
+    }
+
     pub fn receive(&self, reason: &str) -> T {
         loop {
-            if let Some(value) = unsafe { self.inner() }.pop_front() {
+            if let Some(value) = self.pop() {
                 return value;
             }
             self.condition.wait(reason);
         }
     }
 
+    /// Check if a value is queued, without removing it
+    ///
+    /// Kernel code runs with interrupts disabled, so a value seen here is still queued for a
+    /// following `receive` from the same context, unless that context blocks in between.
+    pub fn peek(&self) -> bool {
+        ! unsafe { self.inner() }.is_empty()
+    }
+
+    /// Clone the next value, if one is queued, without removing it
+    pub fn peek_clone(&self) -> Option<T> where T: Clone {
+        unsafe { self.inner() }.front().cloned()
+    }
+
+    /// Receive a value if one is queued, without blocking
+    pub fn receive_nonblocking(&self) -> Option<T> {
+        self.pop()
+    }
+
     pub fn receive_for(&self, reason: &str, time: Duration) -> Option<T> {
+        let deadline = Duration::monotonic() + time;
         loop {
-            if let Some(value) = unsafe { self.inner() }.pop_front() {
+            if let Some(value) = self.pop() {
                 return Some(value);
             }
-            if ! self.condition.wait_for(reason, time) {
+
+            let now = Duration::monotonic();
+            if now >= deadline || ! self.condition.wait_for(reason, deadline - now) {
                 return None;
             }
         }
     }
 
-    pub fn receive_all(&self, reason: &str) -> VecDeque<T> {
-        loop {
-            {
-                let mut inner = unsafe { self.inner() };
-                if ! inner.is_empty() {
-                    let mut swap_inner = VecDeque::new();
-                    mem::swap(inner.deref_mut(), &mut swap_inner);
-                    return swap_inner;
-                }
-            }
-            self.condition.wait(reason);
+    /// Receive a value, waiting for at most `ticks` ticks of the PIT, at its current frequency
+    ///
+    /// Returns `None` if no value arrived in time.
+    pub fn receive_timeout(&self, ticks: u64) -> Option<T> {
+        let tick = unsafe { & *::env().pit.get() }.duration();
+        let tick_nanos = tick.secs * NANOS_PER_SEC as i64 + tick.nanos as i64;
+        let nanos = (cmp::min(ticks, i64::MAX as u64) as i64).saturating_mul(tick_nanos);
+        let time = Duration::new(nanos / NANOS_PER_SEC as i64,
+                                 (nanos % NANOS_PER_SEC as i64) as i32);
+
+        self.receive_for("WaitQueue::receive_timeout", time)
+    }
+
+    /// Wake the context `pid` waiting for a value, or every waiting context if `pid` is 0, see
+    /// `WaitCondition::interrupt`
+    pub fn interrupt(&self, pid: usize, reason: &str) -> usize {
+        self.condition.interrupt(pid, reason)
+    }
+
+    /// Remove and return every queued value
+    ///
+    /// Unlike `receive`, this never blocks: the result is empty if nothing is queued.
+    pub fn receive_all(&self) -> Vec<T> {
+        let values: Vec<T> = unsafe { self.inner() }.drain(..).collect();
+        if !values.is_empty() && self.capacity.is_some() {
+            self.space.notify("WaitQueue::receive_all");
         }
+        values
     }
 
+    /// Send a value, blocking while a bounded queue is full
     pub fn send(&self, value: T, reason: &str) {
+        while self.is_full() {
+            self.space.wait(reason);
+        }
         unsafe { self.inner() }.push_back(value);
         self.condition.notify(reason);
     }
+
+    /// Send a value if there is space for it, without blocking
+    ///
+    /// Returns the value if the queue is full.
+    pub fn try_send(&self, value: T, reason: &str) -> Result<(), T> {
+        if self.is_full() {
+            Err(value)
+        } else {
+            unsafe { self.inner() }.push_back(value);
+            self.condition.notify(reason);
+            Ok(())
+        }
+    }
 }
@@ -20,8 +20,10 @@ use core::ops::DerefMut;
 use core::{mem, ptr, slice, str};
 
 use fs::Url;
+use fs::supervisor_resource;
 
 use system::error::{Error, Result, ENOEXEC, ENOMEM};
+use system::syscall::{SUPERVISE_EVENT_EXEC, SUPERVISE_MASK_EXEC};
 
 pub fn execute_thread(context_ptr: *mut Context, entry: usize, mut args: Vec<String>) -> ! {
     Context::spawn("kexec".into(),
@@ -63,6 +65,7 @@ pub fn execute_thread(context_ptr: *mut Context, entry: usize, mut args: Vec<Str
             virtual_size: CONTEXT_STACK_SIZE,
             writeable: true,
             allocated: true,
+            shared: None,
         });
 
         let user_sp = if let Some(ref stack) = context.stack {
@@ -176,8 +179,10 @@ pub fn execute(mut args: Vec<String>) -> Result<usize> {
 
                 if entry > 0 && ! segments.is_empty() {
                     unsafe { current.unmap() };
+                    current.close_on_exec();
 
                     current.name = url.to_string().into();
+                    current.args = args.clone();
                     current.cwd = Arc::new(UnsafeCell::new(unsafe { (*current.cwd.get()).clone() }));
 
                     current.image = Arc::new(UnsafeCell::new(ContextZone::new(CONTEXT_IMAGE_ADDR, CONTEXT_IMAGE_SIZE)));
@@ -206,6 +211,7 @@ pub fn execute(mut args: Vec<String>) -> Result<usize> {
                                 virtual_size: virtual_size + offset,
                                 writeable: true,
                                 allocated: true,
+                                shared: None,
                             };
 
                             unsafe { memory.map() };
@@ -229,6 +235,11 @@ pub fn execute(mut args: Vec<String>) -> Result<usize> {
 
                     unsafe { current.map() };
 
+                    // There are no registers to change yet, as the executable has not started
+                    let mut regs = Regs::default();
+                    regs.ip = entry;
+                    supervisor_resource::stop(SUPERVISE_MASK_EXEC, SUPERVISE_EVENT_EXEC, entry, 0, &mut regs);
+
                     execute_thread(current.deref_mut(), entry, args);
                 } else {
                     Err(Error::new(ENOEXEC))
@@ -8,9 +8,10 @@ use schemes::pipe::{PipeRead, PipeWrite};
 
 use system::c_string_to_str;
 
-use syscall::{Stat, SEEK_CUR, SEEK_END, SEEK_SET};
+use syscall::{Stat, CONTROL_ISATTY, FD_CLOEXEC, F_GETFD, F_SETFD, O_CLOEXEC, SEEK_CUR, SEEK_DATA,
+              SEEK_END, SEEK_HOLE, SEEK_SET};
 
-use system::error::{Error, Result, EBADF, EFAULT, EINVAL};
+use system::error::{Error, Result, EBADF, EFAULT, EINVAL, ENXIO};
 
 /** <!-- @MANSTART{sys_chdir} -->
 NAME
@@ -68,6 +69,9 @@ SYNOPSIS
 DESCRIPTION
     sys_close closes a file descriptor, so that it no longer refers to any file and may be reused.
 
+    The resource is torn down before it is dropped. If that fails, the error is returned, but the
+    file descriptor is closed regardless.
+
 RETURN VALUE
     On success, Ok(0) is returned. On error, Err(err) is returned where err is one of the following
     errors
@@ -96,9 +100,11 @@ pub fn close(fd: usize) -> Result<usize> {
 
         if remove {
             if i < unsafe { (*current.files.get()).len() } {
-                drop(unsafe { (*current.files.get()).remove(i) });
+                let mut file = unsafe { (*current.files.get()).remove(i) };
+                let result = file.resource.close();
+                drop(file);
 
-                return Ok(0);
+                return result.and(Ok(0));
             }
         }
     }
@@ -106,6 +112,42 @@ pub fn close(fd: usize) -> Result<usize> {
     Err(Error::new(EBADF))
 }
 
+/** <!-- @MANSTART{sys_control} -->
+NAME
+    sys_control - run a resource specific command on a file descriptor
+
+SYNOPSIS
+    sys_control(fd: usize, cmd: usize, arg: usize) -> Result<usize>;
+
+DESCRIPTION
+    sys_control runs the command cmd, with the argument arg, on the resource referenced by fd.
+    Commands are out-of-band, and are defined by each resource, except for CONTROL_ISATTY, which
+    returns 1 if fd is a terminal, or 0 if it is not, for any resource
+
+RETURN VALUE
+    On success, the result of cmd is returned in Ok(result). On error, Err(err) is returned where
+    err is one of the following errors, or an error of cmd
+
+ERRORS
+    EBADF
+        fd is not a valid open file decriptor
+
+    EINVAL
+        cmd is not supported by fd
+
+    ESRCH
+        Currently not running in a process context (rare, would only happen during kernel init)
+<!-- @MANEND --> */
+pub fn control(fd: usize, cmd: usize, arg: usize) -> Result<usize> {
+    let contexts = unsafe { &mut *::env().contexts.get() };
+    let mut current = try!(contexts.current_mut());
+    let mut resource = try!(current.get_file_mut(fd));
+    if cmd == CONTROL_ISATTY {
+        return Ok(resource.is_terminal() as usize);
+    }
+    resource.control(cmd, arg)
+}
+
 /** <!-- @MANSTART{sys_dup} -->
 NAME
     sys_dup - duplicate a file descriptor
@@ -124,6 +166,9 @@ ERRORS
     EBADF
         fd is not a valid open file decriptor
 
+    EMFILE
+        The process already has as many open files as its limit allows
+
     ESRCH
         Currently not running in a process context (rare, would only happen during kernel init)
 <!-- @MANEND --> */
@@ -131,6 +176,7 @@ pub fn dup(fd: usize) -> Result<usize> {
     let contexts = unsafe { & *::env().contexts.get() };
     let current = try!(contexts.current());
     let resource = try!(current.get_file(fd));
+    try!(current.reserve_files(1));
     let new_resource = try!(resource.dup());
     let new_fd = current.next_fd();
 
@@ -138,11 +184,109 @@ pub fn dup(fd: usize) -> Result<usize> {
         (*current.files.get()).push(ContextFile {
             fd: new_fd,
             resource: new_resource,
+            cloexec: false,
         });
     }
     Ok(new_fd)
 }
 
+/** <!-- @MANSTART{sys_fcntl} -->
+NAME
+    sys_fcntl - manipulate a file descriptor
+
+SYNOPSIS
+    sys_fcntl(fd: usize, cmd: usize, arg: usize) -> Result<usize>;
+
+DESCRIPTION
+    sys_fcntl performs the operation cmd on the file referenced by fd
+
+    F_GETFD: 1
+        The file descriptor flags, FD_CLOEXEC, are returned
+
+    F_SETFD: 2
+        The file descriptor flags are set to arg. With FD_CLOEXEC, fd is closed when a new image
+        is executed
+
+    F_GETFL: 3
+        The file status flags, such as O_NONBLOCK, are returned
+
+    F_SETFL: 4
+        The file status flags are set to arg
+
+RETURN VALUE
+    On success, the result of cmd is returned in Ok(result). On error, Err(err) is returned where
+    err is one of the following errors
+
+ERRORS
+    EBADF
+        fd is not a valid open file decriptor
+
+    EINVAL
+        cmd is not supported by fd
+
+    ESRCH
+        Currently not running in a process context (rare, would only happen during kernel init)
+<!-- @MANEND --> */
+pub fn fcntl(fd: usize, cmd: usize, arg: usize) -> Result<usize> {
+    let contexts = unsafe { &mut *::env().contexts.get() };
+    let mut current = try!(contexts.current_mut());
+    match cmd {
+        F_GETFD => Ok(if try!(current.get_context_file_mut(fd)).cloexec {
+            FD_CLOEXEC
+        } else {
+            0
+        }),
+        F_SETFD => {
+            try!(current.get_context_file_mut(fd)).cloexec = arg & FD_CLOEXEC == FD_CLOEXEC;
+            Ok(0)
+        }
+        _ => {
+            let mut resource = try!(current.get_file_mut(fd));
+            resource.fcntl(cmd, arg)
+        }
+    }
+}
+
+/** <!-- @MANSTART{sys_fmap} -->
+NAME
+    sys_fmap - map a file into memory
+
+SYNOPSIS
+    sys_fmap(fd: usize, len: usize, flags: usize) -> Result<usize>;
+
+DESCRIPTION
+    sys_fmap maps the first len bytes of the resource referenced by fd into the address space of
+    the calling process, rounded up to whole pages. The mapping is writeable if flags contains
+    MAP_WRITE. What a write to the mapping changes depends on the resource: a shared memory
+    segment is shared with every other process mapping it, while an in-memory file is copied
+
+RETURN VALUE
+    On success, the address of the mapping is returned in Ok(address). On error, Err(err) is
+    returned where err is one of the following errors
+
+ERRORS
+    EBADF
+        fd is not a valid open file decriptor
+
+    EINVAL
+        len is 0, or larger than a shared memory segment
+
+    ENODEV
+        fd can not be mapped
+
+    ENOMEM
+        There is no memory, or no space left to map it
+
+    ESRCH
+        Currently not running in a process context (rare, would only happen during kernel init)
+<!-- @MANEND --> */
+pub fn fmap(fd: usize, len: usize, flags: usize) -> Result<usize> {
+    let contexts = unsafe { &mut *::env().contexts.get() };
+    let mut current = try!(contexts.current_mut());
+    let mut resource = try!(current.get_file_mut(fd));
+    resource.mmap(len, flags)
+}
+
 pub fn fpath(fd: usize, buf: *mut u8, count: usize) -> Result<usize> {
     let contexts = unsafe { & *::env().contexts.get() };
     let current = contexts.current()?;
@@ -254,6 +398,13 @@ DESCRIPTION
     SEEK_END: 2
         The offset is set to the size of the file plus offset bytes
 
+    SEEK_DATA: 3
+        The offset is set to the next location at or after offset which contains data
+
+    SEEK_HOLE: 4
+        The offset is set to the next hole at or after offset, which is the size of the file if
+        there is no other hole
+
 RETURN VALUE
     On success, Ok(new_offset) is returned, where new_offset is the resulting offset location. On
     error, Err(err) is returned where err is one of the following errors
@@ -265,6 +416,9 @@ ERRORS
     EINVAL
         whence or the offset is not valid
 
+    ENXIO
+        whence is SEEK_DATA or SEEK_HOLE, and offset is negative, or not before the end of the file
+
     ESPIPE
         fd does not support seeking
 
@@ -279,6 +433,9 @@ pub fn lseek(fd: usize, offset: isize, whence: usize) -> Result<usize> {
         SEEK_SET => resource.seek(ResourceSeek::Start(offset as usize)),
         SEEK_CUR => resource.seek(ResourceSeek::Current(offset)),
         SEEK_END => resource.seek(ResourceSeek::End(offset)),
+        SEEK_DATA | SEEK_HOLE if offset < 0 => Err(Error::new(ENXIO)),
+        SEEK_DATA => resource.seek(ResourceSeek::Data(offset as usize)),
+        SEEK_HOLE => resource.seek(ResourceSeek::Hole(offset as usize)),
         _ => Err(Error::new(EINVAL)),
     }
 }
@@ -337,6 +494,9 @@ SYNOPSIS
 DESCRIPTION
     sys_open returns a file descriptor referencing path, creating path if O_CREAT is provided
 
+    With O_CLOEXEC, the file descriptor is closed when a new image is executed, as if FD_CLOEXEC
+    had been set with sys_fcntl
+
     TODO: Open is very complicated, and has a lot of flags
 
 RETURN VALUE
@@ -358,6 +518,9 @@ ERRORS
     EISDIR
         path refers to a directory and O_DIRECTORY was not provided
 
+    EMFILE
+        The process already has as many open files as its limit allows
+
     ENOENT
         A directory component in path does not exist
 
@@ -382,6 +545,7 @@ ERRORS
 pub fn open(path_c: *const u8, flags: usize) -> Result<usize> {
     let contexts = unsafe { & *::env().contexts.get() };
     let current = try!(contexts.current());
+    try!(current.reserve_files(1));
     let path = current.canonicalize(c_string_to_str(path_c));
     let url = try!(Url::from_str(&path));
     let resource = try!(::env().open(url, flags));
@@ -390,15 +554,17 @@ pub fn open(path_c: *const u8, flags: usize) -> Result<usize> {
         (*current.files.get()).push(ContextFile {
             fd: fd,
             resource: resource,
+            cloexec: flags & O_CLOEXEC == O_CLOEXEC,
         });
     }
     Ok(fd)
 }
 
-pub fn pipe2(fds: *mut usize, _flags: usize) -> Result<usize> {
+pub fn pipe2(fds: *mut usize, flags: usize) -> Result<usize> {
     let contexts = unsafe { & *::env().contexts.get() };
     let current = try!(contexts.current());
     if fds as usize > 0 {
+        try!(current.reserve_files(2));
         let read = box PipeRead::new();
         let write = box PipeWrite::new(&read);
 
@@ -407,12 +573,14 @@ pub fn pipe2(fds: *mut usize, _flags: usize) -> Result<usize> {
             (*current.files.get()).push(ContextFile {
                 fd: *fds.offset(0),
                 resource: read,
+                cloexec: flags & O_CLOEXEC == O_CLOEXEC,
             });
 
             *fds.offset(1) = current.next_fd();
             (*current.files.get()).push(ContextFile {
                 fd: *fds.offset(1),
                 resource: write,
+                cloexec: flags & O_CLOEXEC == O_CLOEXEC,
             });
         }
 
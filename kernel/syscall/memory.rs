@@ -34,7 +34,8 @@ pub fn brk(addr: usize) -> Result<usize> {
                     virtual_address: ret,
                     virtual_size: size,
                     writeable: true,
-                    allocated: true
+                    allocated: true,
+                    shared: None,
                 };
                 ret = mem.virtual_address + mem.virtual_size;
 
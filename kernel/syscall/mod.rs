@@ -4,16 +4,24 @@ pub use system::syscall::*;
 use arch::regs::Regs;
 use arch::context::context_switch;
 
+use fs::supervisor_resource;
+
+use schemes::trace;
+
 pub mod execute;
 pub mod fs;
 pub mod memory;
 pub mod process;
+pub mod random;
 pub mod time;
 
 pub fn name(number: usize) -> &'static str {
     match number {
         // Redox
+        SYS_CONTROL => "control",
+        SYS_FMAP => "fmap",
         SYS_SUPERVISE => "supervise",
+        SYS_SUPERVISE_OBSERVE => "supervise_observe",
 
         // Unix
         SYS_BRK => "brk",
@@ -24,11 +32,13 @@ pub fn name(number: usize) -> &'static str {
         SYS_DUP => "dup",
         SYS_EXECVE => "execve",
         SYS_EXIT => "exit",
+        SYS_FCNTL => "fcntl",
         SYS_FPATH => "fpath",
         SYS_FSTAT => "fstat",
         SYS_FSYNC => "fsync",
         SYS_FTRUNCATE => "ftruncate",
         SYS_GETPID => "getpid",
+        SYS_GETRANDOM => "getrandom",
         SYS_IOPL => "iopl",
         // TODO: link
         SYS_LSEEK => "lseek",
@@ -59,25 +69,46 @@ pub fn handle(regs: &mut Regs) {
         let contexts = unsafe { &mut *::env().contexts.get() };
         if let Ok(cur) = contexts.current_mut() {
             cur.current_syscall = Some((regs.ip, regs.ax, regs.bx, regs.cx, regs.dx));
+            trace::record(TRACE_SYSCALL, cur.pid, regs.ax);
             // debugln!("PID {}: {} @ {:X}: {} {} {:X} {:X} {:X}", cur.pid, cur.name, regs.ip, regs.ax, name(regs.ax), regs.bx, regs.cx, regs.dx);
+            supervisor_resource::notify_observers(cur, (*regs).into());
+
             if cur.supervised {
-                // Block the process.
-                cur.blocked_syscall = true;
-                cur.block("syscall::handle Supervise");
-                // Clear the timer.
-                cur.wake = None;
-
-                loop {
-                    if cur.blocked > 0 {
-                        unsafe { context_switch() };
-                    } else {
+                // Wait for the supervisor to attach.
+                while cur.supervised_resource.is_none() {
+                    unsafe { context_switch() };
+                }
+
+                if let Some(resource) = cur.supervised_resource.clone() {
+                    let rule = resource.rule(regs.ax);
+                    if rule.action == SUPERVISE_FILTER_DENY {
+                        regs.ax = Error::mux(Err(Error::new(rule.errno as isize)));
+                        cur.current_syscall = None;
+                        process::deliver_signals(regs);
                         return;
                     }
+
+                    if rule.action == SUPERVISE_FILTER_TRAP && resource.stops_for(SUPERVISE_MASK_SYSCALL) {
+                        cur.blocked_syscall = true;
+                        // Clear the timer.
+                        cur.wake = None;
+
+                        let handled = resource.trap((*regs).into(), regs);
+
+                        cur.blocked_syscall = false;
+
+                        if handled {
+                            cur.current_syscall = None;
+                            process::deliver_signals(regs);
+                            return;
+                        }
+                    }
                 }
             }
         }
     }
 
+    let number = regs.ax;
     let result = match regs.ax {
         // These are arranged in such a way that the most frequent syscalls preceeds less frequent
         // once, to acheive the best performance.
@@ -96,11 +127,13 @@ pub fn handle(regs: &mut Regs) {
         SYS_FSYNC => fs::fsync(regs.bx),
         SYS_FTRUNCATE => fs::ftruncate(regs.bx, regs.cx),
         SYS_DUP => fs::dup(regs.bx),
+        SYS_FCNTL => fs::fcntl(regs.bx, regs.cx, regs.dx),
         SYS_IOPL => process::iopl(regs),
         SYS_CLOCK_GETTIME => time::clock_gettime(regs.bx, regs.cx as *mut TimeSpec),
         SYS_EXECVE => process::execve(regs.bx as *const u8, regs.cx as *const *const u8),
-        SYS_EXIT => process::exit(regs.bx),
+        SYS_EXIT => process::exit(regs.bx, regs),
         SYS_GETPID => process::getpid(),
+        SYS_GETRANDOM => random::getrandom(regs.bx as *mut u8, regs.cx, regs.dx),
         // TODO: link
         SYS_PIPE2 => fs::pipe2(regs.bx as *mut usize, regs.cx),
         SYS_RMDIR => fs::rmdir(regs.bx as *const u8),
@@ -110,6 +143,9 @@ pub fn handle(regs: &mut Regs) {
         SYS_BRK => memory::brk(regs.bx),
         SYS_CHDIR => fs::chdir(regs.bx as *const u8),
         SYS_SUPERVISE => process::supervise(regs.bx),
+        SYS_CONTROL => fs::control(regs.bx, regs.cx, regs.dx),
+        SYS_FMAP => fs::fmap(regs.bx, regs.cx, regs.dx),
+        SYS_SUPERVISE_OBSERVE => process::supervise_observe(regs.bx),
         _ => Err(Error::new(ENOSYS)),
     };
 
@@ -118,8 +154,22 @@ pub fn handle(regs: &mut Regs) {
         if let Ok(cur) = contexts.current_mut() {
             // debugln!("PID {}: {} @ {:X}: {} {} {:X} {:X} {:X} = {:?}", cur.pid, cur.name, regs.ip, regs.ax, name(regs.ax), regs.bx, regs.cx, regs.dx, result);
             cur.current_syscall = None;
+
+            // The cause of an error is dropped once it becomes an errno, so debug builds log it
+            if cfg!(debug_assertions) {
+                if let Err(ref err) = result {
+                    if err.cause.is_some() {
+                        syslog_debug!("PID {}: {}: {:?}", cur.pid, name(number), err);
+                    }
+                }
+            }
         }
     }
 
     regs.ax = Error::mux(result);
+
+    let value = regs.ax;
+    supervisor_resource::stop(SUPERVISE_MASK_SYSCALL_EXIT, SUPERVISE_EVENT_SYSCALL_EXIT, number, value, regs);
+
+    process::deliver_signals(regs);
 }
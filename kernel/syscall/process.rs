@@ -1,6 +1,6 @@
 //! System calls related to process managment.
 
-use arch::context::{context_clone, context_switch, ContextFile};
+use arch::context::{context_clone, context_switch, Context, ContextFile};
 use arch::regs::Regs;
 
 use collections::{BTreeMap, Vec};
@@ -10,12 +10,16 @@ use core::mem;
 use core::ops::DerefMut;
 
 use system::{c_array_to_slice, c_string_to_str};
+use system::scheme::{Packet, PACKET_MAGIC};
+use system::syscall::{NSIG, SIGCHLD, SIGCONT, SIGSTOP, SIGTSTP, SIGTTIN, SIGTTOU, SIGURG, SIGWINCH,
+                      SUPERVISE_EVENT_EXIT, SUPERVISE_EVENT_SIGNAL, SUPERVISE_MASK_SIGNAL};
 
-use system::error::{Error, Result, ECHILD, EINVAL, EACCES};
+use system::error::{Error, Result, ECHILD, EINVAL, ESRCH};
 
 use super::execute::execute;
 
 use fs::SupervisorResource;
+use fs::supervisor_resource;
 
 pub fn clone(regs: &Regs) -> Result<usize> {
     unsafe { context_clone(regs) }
@@ -32,13 +36,29 @@ pub fn execve(path: *const u8, args: *const *const u8) -> Result<usize> {
 }
 
 /// Exit context
-pub fn exit(status: usize) -> ! {
+///
+/// `regs` are the registers the context exits with, which its supervisor can read while it lingers
+/// as a zombie, see `supervisor_resource::exit`.
+pub fn exit(status: usize, regs: &Regs) -> ! {
     {
         let contexts = unsafe { &mut *::env().contexts.get() };
 
         let mut statuses = BTreeMap::new();
         let (pid, ppid) = {
             if let Ok(mut current) = contexts.current_mut() {
+                supervisor_resource::notify_observers(current, Packet {
+                    id: 0,
+                    a: SUPERVISE_EVENT_EXIT,
+                    b: status,
+                    c: 0,
+                    d: 0,
+                    magic: PACKET_MAGIC,
+                });
+                current.observers.clear();
+
+                // Tell the supervisor before letting go of it
+                supervisor_resource::exit(current, status, regs);
+
                 mem::swap(&mut statuses, &mut unsafe { current.statuses.inner() }.deref_mut());
                 current.exit();
                 (current.pid, current.ppid)
@@ -47,25 +67,88 @@ pub fn exit(status: usize) -> ! {
             }
         };
 
-        for mut context in contexts.iter_mut() {
-            // Add exit status to parent
-            if context.pid == ppid {
-                context.statuses.send(pid, status, "exit parent status");
-                for (pid, status) in statuses.iter() {
-                    context.statuses.send(*pid, *status, "exit child status");
-                }
-            }
+        orphan(pid, ppid, status, &statuses);
+    }
 
-            // Move children to parent
-            if context.ppid == pid {
-                context.ppid = ppid;
+    loop {
+        unsafe { context_switch() };
+    }
+}
+
+/// Give the parent `ppid` of the exited context `pid` its status, and the unread statuses of its
+/// children, and move its children to that parent
+fn orphan(pid: usize, ppid: usize, status: usize, statuses: &BTreeMap<usize, usize>) {
+    let contexts = unsafe { &mut *::env().contexts.get() };
+    for mut context in contexts.iter_mut() {
+        // Add exit status to parent
+        if context.pid == ppid {
+            context.statuses.send(pid, status, "exit parent status");
+            for (pid, status) in statuses.iter() {
+                context.statuses.send(*pid, *status, "exit child status");
             }
         }
+
+        // Move children to parent
+        if context.ppid == pid {
+            context.ppid = ppid;
+        }
     }
+}
 
-    loop {
-        unsafe { context_switch() };
+/// Tear down the context `pid` at once, exiting with `status`, whatever it is doing
+///
+/// Unlike a signal, this does not wait for the context to run again, so a context blocked in the
+/// kernel forever is still ended, see `Context::kill`. Its supervisor, if any, is sent its exit
+/// event without the context stopping, and must have space for it. Returns `EINVAL` for the
+/// current context, and `ESRCH` if there is no such context. The context is freed at once, as
+/// `Context::kill` removes it from the wait condition it is blocked on.
+pub fn force_exit(pid: usize, status: usize) -> Result<()> {
+    let contexts = unsafe { &mut *::env().contexts.get() };
+    if try!(contexts.current()).pid == pid {
+        return Err(Error::new(EINVAL));
+    }
+
+    let i = try!(contexts.inner
+                         .iter()
+                         .position(|context| context.pid == pid)
+                         .ok_or(Error::new(ESRCH)));
+    let mut context = contexts.inner.remove(i);
+    if i < contexts.i {
+        contexts.i -= 1;
+    }
+
+    supervisor_resource::notify_observers(&mut context, Packet {
+        id: 0,
+        a: SUPERVISE_EVENT_EXIT,
+        b: status,
+        c: 0,
+        d: 0,
+        magic: PACKET_MAGIC,
+    });
+    context.observers.clear();
+
+    context.supervised = false;
+    if let Some(resource) = context.supervised_resource.take() {
+        resource.exit(status);
+    }
+
+    let mut statuses = BTreeMap::new();
+    mem::swap(&mut statuses, &mut unsafe { context.statuses.inner() }.deref_mut());
+    let ppid = context.ppid;
+    unsafe { context.kill() };
+
+    // A vfork child must not unblock its parent once it is freed
+    let ptr = context.deref_mut() as *mut Context;
+    for child in contexts.iter_mut() {
+        if child.vfork == Some(ptr) {
+            child.vfork = None;
+        }
     }
+    drop(context);
+
+    orphan(pid, ppid, status, &statuses);
+
+    Ok(())
 }
 
 pub fn getpid() -> Result<usize> {
@@ -108,6 +191,39 @@ pub fn iopl(regs: &mut Regs) -> Result<usize> {
     }
 }
 
+/// Act on the pending signals of the current context
+///
+/// There are no signal handlers yet, so every signal takes its default action. SIGCHLD, SIGCONT,
+/// SIGURG, and SIGWINCH are ignored, as are the stop signals, as there is no job control. Any other
+/// signal exits the context with a status of 128 plus the signal number. Blocked signals are left
+/// pending, except for SIGKILL and SIGSTOP.
+pub fn deliver_signals(regs: &mut Regs) {
+    let pending = {
+        let contexts = unsafe { &mut *::env().contexts.get() };
+        if let Ok(mut current) = contexts.current_mut() {
+            let pending = current.deliverable_signals();
+            current.pending_signals &= !pending;
+            pending
+        } else {
+            0
+        }
+    };
+
+    for signal in 1..NSIG {
+        if pending & 1 << signal == 1 << signal {
+            // The supervisor may discard the signal
+            if supervisor_resource::stop(SUPERVISE_MASK_SIGNAL, SUPERVISE_EVENT_SIGNAL, signal, 0, regs) {
+                continue;
+            }
+
+            match signal {
+                SIGCHLD | SIGCONT | SIGURG | SIGWINCH | SIGSTOP | SIGTSTP | SIGTTIN | SIGTTOU => (),
+                _ => exit(128 + signal, regs),
+            }
+        }
+    }
+}
+
 //TODO: Finish implementation, add more functions to WaitMap so that matching any or using WNOHANG works
 pub fn waitpid(pid: isize, status_ptr: *mut usize, _options: usize) -> Result<usize> {
     let contexts = unsafe { &mut *::env().contexts.get() };
@@ -135,33 +251,30 @@ pub fn sched_yield() -> Result<usize> {
 
 /// Supervise a child process of the current context.
 ///
-/// This will make all syscalls the given process makes mark the process as blocked, until it is
-/// handled by the supervisor (parrent process) through the returned handle (for details, see the
-/// docs in the `system` crate).
+/// This will make all syscalls the given process makes stop the process, until it is handled by
+/// the supervisor (parrent process) through the returned handle (for details, see the docs in the
+/// `system` crate).
 ///
-/// This routine is done by having a field defining whether the process is blocked by a syscall.
-/// When the syscall is read from the file handle, this field is set to false, but the process is
-/// still stopped (because it is marked as `blocked`), until the new value of the EAX register is
-/// written to the file handle.
+/// The supervisor and the tracee are connected by a pair of wait queues, see `SupervisorResource`.
 pub fn supervise(pid: usize) -> Result<usize> {
-    let contexts = unsafe { &mut *::env().contexts.get() };
-    let cur_pid = try!(contexts.current_mut()).pid;
-
-    let procc;
-
-    {
-        let jailed = try!(contexts.find_mut(pid));
-
-        // Make sure that this is actually a child process of the invoker.
-        if jailed.ppid != cur_pid {
-            return Err(Error::new(EACCES));
-        }
-
-        jailed.supervised = true;
+    try!(try!(unsafe { & *::env().contexts.get() }.current()).reserve_files(1));
+    let resource = try!(SupervisorResource::new(pid));
+    add_supervisor(resource)
+}
 
-        procc = &mut **jailed as *mut _;
-    }
+/// Observe a child process of the current context.
+///
+/// The returned handle reads the same events as a supervisor, without stopping the process, and
+/// cannot be written to. See `SupervisorResource::observe`.
+pub fn supervise_observe(pid: usize) -> Result<usize> {
+    try!(try!(unsafe { & *::env().contexts.get() }.current()).reserve_files(1));
+    let resource = try!(SupervisorResource::observe(pid));
+    add_supervisor(resource)
+}
 
+/// Add a supervisor resource to the files of the current context
+fn add_supervisor(resource: SupervisorResource) -> Result<usize> {
+    let contexts = unsafe { &mut *::env().contexts.get() };
     let current = try!(contexts.current_mut());
 
     let fd = current.next_fd();
@@ -169,7 +282,8 @@ pub fn supervise(pid: usize) -> Result<usize> {
     unsafe {
         (*current.files.get()).push(ContextFile {
             fd: fd,
-            resource: box try!(SupervisorResource::new(procc)),
+            resource: box resource,
+            cloexec: false,
         });
     }
 
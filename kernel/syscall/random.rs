@@ -0,0 +1,69 @@
+//! System calls related to random numbers.
+
+use common::random;
+
+use syscall::{GRND_NONBLOCK, GRND_RANDOM};
+
+use system::error::{Error, Result, EAGAIN, EINVAL};
+
+/** <!-- @MANSTART{getrandom} -->
+NAME
+    getrandom - fill a buffer with random bytes
+
+SYNOPSIS
+    getrandom(buf: *mut u8, count: usize, flags: usize) -> Result<usize>
+
+DESCRIPTION
+    getrandom fills count bytes at buf from the kernel entropy pool, the same pool which backs
+    random: and urandom:
+
+    Until the pool has gathered enough entropy, getrandom blocks like random: does
+
+    The following flags are supported
+
+    GRND_NONBLOCK
+        Return EAGAIN instead of blocking if the pool has not gathered enough entropy
+
+    GRND_RANDOM
+        Accepted for compatibility, there is only one pool
+
+RETURN VALUE
+    On success, Ok(count) is returned. On error, Err(err) is returned where err is one of the
+    following errors
+
+ERRORS
+    EAGAIN
+        GRND_NONBLOCK was given, and the pool has not gathered enough entropy
+
+    EFAULT
+        buf does not point to count bytes of writable memory
+
+    EINVAL
+        flags contains an unknown flag
+
+    ESRCH
+        Currently not running in a process context (rare, would only happen during kernel init)
+<!-- @MANEND --> */
+pub fn getrandom(buf: *mut u8, count: usize, flags: usize) -> Result<usize> {
+    if flags & !(GRND_NONBLOCK | GRND_RANDOM) != 0 {
+        return Err(Error::new(EINVAL));
+    }
+
+    if count == 0 {
+        return Ok(0);
+    }
+
+    let contexts = unsafe { & *::env().contexts.get() };
+    let current = contexts.current()?;
+    let buf_safe = current.get_slice_mut(buf, count)?;
+
+    while random::entropy() < random::ENTROPY_THRESHOLD {
+        if flags & GRND_NONBLOCK == GRND_NONBLOCK {
+            return Err(Error::new(EAGAIN));
+        }
+        ::env().tick.wait("getrandom");
+    }
+
+    random::fill(buf_safe);
+    Ok(count)
+}
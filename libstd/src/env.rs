@@ -184,7 +184,7 @@ pub fn var_os<K: AsRef<OsStr>>(key: K) -> Option<OsString> {
 /// Sets the environment variable `key` to the value `value` for the current process
 pub fn set_var<K: AsRef<OsStr>, V: AsRef<OsStr>>(key: K, value: V) {
     if let (Some(key_str), Some(value_str)) = (key.as_ref().to_str(), value.as_ref().to_str()) {
-        if let Ok(mut file) = File::open(&("env:".to_owned() + key_str)) {
+        if let Ok(mut file) = File::create(&("env:".to_owned() + key_str)) {
             let _ = file.write_all(value_str.as_bytes());
         }
     }